@@ -4,6 +4,7 @@ use std::future::Future;
 use std::net::IpAddr;
 use std::pin::Pin;
 
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::cloud_init;
@@ -173,16 +174,19 @@ impl InstanceRequestBuilder {
 }
 
 /// Handle returned by a backend once an instance has been created.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct InstanceHandle {
     /// Provider specific identifier for the instance.
     pub id: String,
     /// Zone in which the instance was created.
     pub zone: String,
+    /// Run identifier recorded in the instance's provenance tags, when the
+    /// backend tags resources with run provenance.
+    pub run_id: Option<String>,
 }
 
 /// Connection details for reaching an instance once it is ready.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct InstanceNetworking {
     /// Public IPv4 address assigned by the provider.
     pub public_ip: IpAddr,
@@ -190,6 +194,17 @@ pub struct InstanceNetworking {
     pub ssh_port: u16,
 }
 
+/// An instance that has been created and is reachable over SSH.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProvisionedInstance {
+    /// Backend handle used for teardown.
+    pub handle: InstanceHandle,
+    /// Connection details for SSH and rsync.
+    pub networking: InstanceNetworking,
+    /// Request the instance was created from.
+    pub request: InstanceRequest,
+}
+
 /// Errors raised by backends.
 #[derive(Debug, Error, Eq, PartialEq)]
 pub enum BackendError {
@@ -198,13 +213,24 @@ pub enum BackendError {
     Validation(String),
 }
 
+/// Classifies backend errors for retries.
+///
+/// Each provider decides which of its errors are worth retrying, such as a
+/// server error or exhausted capacity, as opposed to an invalid request.
+pub trait TransientError {
+    /// Returns `true` when the failed operation may succeed if retried.
+    fn is_transient(&self) -> bool {
+        false
+    }
+}
+
 /// Future returned by backend operations.
 pub type BackendFuture<'a, T, E> = Pin<Box<dyn Future<Output = Result<T, E>> + Send + 'a>>;
 
 /// Minimal interface implemented by cloud backends.
 pub trait Backend {
     /// Provider specific error type returned by the backend.
-    type Error: std::error::Error + Send + Sync + 'static;
+    type Error: std::error::Error + TransientError + Send + Sync + 'static;
 
     /// Creates a new instance and returns a handle used for subsequent calls.
     fn create<'a>(
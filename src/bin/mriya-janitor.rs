@@ -3,10 +3,23 @@
 //! This binary deletes any Scaleway resources tagged with
 //! `mriya-test-run-<MRIYA_TEST_RUN_ID>` and then verifies the set is empty.
 
-use clap::Parser;
-use mriya::janitor::{DEFAULT_SCW_BIN, Janitor, JanitorConfig, TEST_RUN_ID_ENV};
+use clap::{Parser, ValueEnum};
+use mriya::janitor::{
+    DEFAULT_SCW_BIN, Janitor, JanitorConfig, JanitorError, ResourceType, SweepAction, SweepSummary,
+    TEST_RUN_ID_ENV,
+};
 use std::io::Write as _;
 
+/// Output format for the sweep report.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+enum OutputFormat {
+    /// One line per resource followed by a summary line.
+    #[default]
+    Text,
+    /// A JSON document with per-resource results.
+    Json,
+}
+
 #[derive(Debug, Parser)]
 #[command(
     name = "mriya-janitor",
@@ -22,20 +35,88 @@ struct Cli {
     /// Path to the Scaleway CLI binary.
     #[arg(long, default_value = DEFAULT_SCW_BIN)]
     scw_bin: String,
+    /// List the tagged resources that would be deleted without deleting them.
+    #[arg(long)]
+    dry_run: bool,
+    /// Keep deleting the remaining resources when one deletion fails.
+    #[arg(long)]
+    keep_going: bool,
+    /// Output format for the sweep report.
+    #[arg(long, value_enum, default_value_t)]
+    format: OutputFormat,
+}
+
+fn render(summary: &SweepSummary, format: OutputFormat) -> Result<String, String> {
+    if format == OutputFormat::Json {
+        return serde_json::to_string_pretty(summary).map_err(|err| err.to_string());
+    }
+
+    let mut lines = summary
+        .resources
+        .iter()
+        .map(|res| {
+            let run = res
+                .run_id
+                .as_deref()
+                .map(|id| format!(" (run {id})"))
+                .unwrap_or_default();
+            let error = res
+                .error
+                .as_deref()
+                .map(|err| format!(": {err}"))
+                .unwrap_or_default();
+            format!(
+                "{} {}{run} {}{error}",
+                res.resource_type.as_str(),
+                res.label(),
+                res.action.as_str()
+            )
+        })
+        .collect::<Vec<_>>();
+    let (verb, action) = if summary.dry_run {
+        ("dry run", SweepAction::WouldDelete)
+    } else {
+        ("sweep", SweepAction::Deleted)
+    };
+    let counts = ResourceType::ALL
+        .iter()
+        .map(|&resource_type| {
+            format!(
+                "{}_{}={}",
+                action.as_str(),
+                resource_type.as_str(),
+                summary.count(resource_type, action)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    lines.push(format!("janitor {verb} complete: {counts}"));
+    Ok(lines.join("\n"))
 }
 
 fn main() -> Result<(), String> {
     let cli = Cli::parse();
     let config = JanitorConfig::new(cli.project_id, cli.test_run_id, cli.scw_bin)
-        .map_err(|err| err.to_string())?;
+        .map_err(|err| err.to_string())?
+        .with_dry_run(cli.dry_run)
+        .with_keep_going(cli.keep_going);
     let janitor = Janitor::with_process_runner(config);
-    let summary = janitor.sweep().map_err(|err| err.to_string())?;
-    writeln!(
-        std::io::stdout(),
-        "janitor sweep complete: deleted_servers={}, deleted_volumes={}",
-        summary.deleted_servers,
-        summary.deleted_volumes
-    )
-    .map_err(|err| err.to_string())?;
-    Ok(())
+    // Build the runtime explicitly so construction errors are reported
+    // instead of panicking inside the `#[tokio::main]` expansion.
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|err| format!("failed to start async runtime: {err}"))?;
+    let (summary, failure) = match runtime.block_on(janitor.sweep()) {
+        Ok(summary) => (summary, None),
+        Err(err) => match &err {
+            // Still render the per-resource report so CI can see which
+            // deletions failed before the process exits non-zero.
+            JanitorError::NotClean { report, .. } => (report.clone(), Some(err.to_string())),
+            _ => return Err(err.to_string()),
+        },
+    };
+    writeln!(std::io::stdout(), "{}", render(&summary, cli.format)?)
+        .map_err(|err| err.to_string())?;
+    failure.map_or(Ok(()), Err)
 }
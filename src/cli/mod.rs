@@ -19,6 +19,32 @@ pub(crate) enum Cli {
     /// Prepare a cache volume for this project.
     #[command(name = "init", about = "Prepare a cache volume for this project")]
     Init(InitCommand),
+    /// Manage a warm pool of ready instances.
+    #[command(
+        subcommand,
+        name = "pool",
+        about = "Manage a warm pool of ready instances"
+    )]
+    Pool(PoolCommand),
+    /// Keep an instance running across several commands.
+    #[command(
+        subcommand,
+        name = "session",
+        about = "Keep an instance running across several commands"
+    )]
+    Session(SessionCommand),
+    /// Report what a sync would send without provisioning an instance.
+    #[command(
+        name = "sync",
+        about = "Report what a sync would send without provisioning an instance"
+    )]
+    Sync(SyncCommand),
+    /// Re-run a command on a kept-alive instance whenever the workspace changes.
+    #[command(
+        name = "watch",
+        about = "Re-run a command on a kept-alive instance whenever files change"
+    )]
+    Watch(WatchCommand),
 }
 
 /// Arguments for the `mriya run` subcommand.
@@ -47,6 +73,118 @@ pub(crate) struct RunCommand {
     /// Provide cloud-init user-data from a local file for this run.
     #[arg(long, value_name = "PATH", conflicts_with = "cloud_init")]
     pub(crate) cloud_init_file: Option<String>,
+    /// Split the run across this many instances executing concurrently.
+    ///
+    /// Each shard receives `MRIYA_SHARD_INDEX` (1-based) and
+    /// `MRIYA_SHARD_COUNT` in its environment, and its output is prefixed with
+    /// `[shard i/n]`. The run exits non-zero when any shard fails, and every
+    /// instance is torn down regardless.
+    #[arg(long, value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..))]
+    pub(crate) shards: u16,
+    /// Run the command once per matrix cell, in parallel.
+    ///
+    /// A cell is a comma-separated list of `arch=`, `image=`, and `type=`
+    /// overrides (for example `arch=arm64,type=COPARM1-2C-8G`). Repeat the flag
+    /// to add cells; pass it without a value to use the cells configured under
+    /// `[matrix]`. Prints a summary of exit codes and durations per cell.
+    #[arg(long, value_name = "CELL", num_args = 0..=1, conflicts_with = "shards")]
+    pub(crate) matrix: Option<Vec<String>>,
+    /// Always provision a new instance instead of claiming one from a warm
+    /// pool started with `mriya pool start`.
+    #[arg(long)]
+    pub(crate) no_pool: bool,
+    /// Choose how the workspace is selected for sync, overriding
+    /// `[sync] sync_mode`.
+    ///
+    /// `rsync-filter` sends the working tree minus `.gitignore` matches,
+    /// `git-files` sends only the files `git ls-files` reports,
+    /// `git-commit` sends a snapshot of `HEAD`, and `git-fetch` has the
+    /// instance fetch `HEAD` from the git remote and sends only uncommitted
+    /// changes.
+    #[arg(long, value_name = "MODE", value_parser = ["rsync-filter", "git-files", "git-commit", "git-fetch"])]
+    pub(crate) sync_mode: Option<String>,
+    /// Report what the sync would send before provisioning.
+    ///
+    /// Lists the file count, total size, largest files, and top directories on
+    /// stderr.
+    #[arg(long)]
+    pub(crate) sync_stats: bool,
+    /// Fail instead of warning when the sync would send likely secrets.
+    #[arg(long)]
+    pub(crate) strict_secrets: bool,
+    /// Set a variable for the remote command, as `KEY=VALUE` or as `KEY` to
+    /// copy its local value. Repeatable.
+    ///
+    /// Values travel over `ssh`'s standard input into a file only the SSH
+    /// user can read, never on a command line. Adds to `[run] env` and
+    /// `pass_env`, overriding entries with the same name.
+    #[arg(long = "env", value_name = "KEY[=VALUE]")]
+    pub(crate) env: Vec<String>,
+    /// Allocate a remote pseudo-terminal for the command.
+    ///
+    /// Keeps colours and progress bars and makes interactive programs such as
+    /// a REPL or `gdb` usable: local input and window-size changes are
+    /// forwarded. Output goes straight to the terminal, so stdout and stderr
+    /// arrive merged. Defaults to on when stdin and stdout are both terminals;
+    /// pass `--tty=false` to turn it off.
+    #[arg(
+        long,
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        conflicts_with_all = ["shards", "matrix"]
+    )]
+    pub(crate) tty: Option<bool>,
+    /// Run a local script on the remote host instead of a command.
+    ///
+    /// The script is uploaded to a private file and executed from the remote
+    /// working directory with the usual cache routing, so its shebang picks
+    /// the interpreter (`sh` without one). Trailing arguments are passed to
+    /// the script.
+    #[arg(long, value_name = "PATH")]
+    pub(crate) script: Option<String>,
+    /// Exit with this code for every failure of Mriya itself, such as a
+    /// provider error or a failed sync, instead of the code for each kind
+    /// of failure (240-249).
+    ///
+    /// Lets CI retry infrastructure failures without mistaking them for the
+    /// command's own exit codes.
+    #[arg(long, value_name = "CODE", value_parser = clap::value_parser!(u8).range(1..))]
+    pub(crate) infra_exit_code: Option<u8>,
+    /// Command to execute on the remote host (use -- to separate flags), or
+    /// the arguments of `--script`.
+    #[arg(required_unless_present = "script", trailing_var_arg = true)]
+    pub(crate) command: Vec<String>,
+}
+
+/// Arguments for the `mriya sync` subcommand.
+#[derive(Debug, Parser)]
+pub(crate) struct SyncCommand {
+    /// Report what would be sent without transferring anything.
+    ///
+    /// Required: a real sync needs an instance, which `mriya run`,
+    /// `mriya session`, and `mriya watch` provide. Exits non-zero when the
+    /// sync would exceed `max_sync_bytes`.
+    #[arg(long, required = true)]
+    pub(crate) dry_run: bool,
+    /// Choose how the workspace is selected, overriding `[sync] sync_mode`.
+    #[arg(long, value_name = "MODE", value_parser = ["rsync-filter", "git-files", "git-commit", "git-fetch"])]
+    pub(crate) sync_mode: Option<String>,
+    /// Fail instead of warning when the sync would send likely secrets.
+    #[arg(long)]
+    pub(crate) strict_secrets: bool,
+}
+
+/// Arguments for the `mriya watch` subcommand.
+#[derive(Debug, Parser)]
+pub(crate) struct WatchCommand {
+    /// Milliseconds the workspace must stay unchanged before re-running.
+    #[arg(long, value_name = "MILLISECONDS", default_value_t = 300)]
+    pub(crate) debounce_ms: u64,
+    /// Fail instead of warning when the sync would send likely secrets.
+    #[arg(long)]
+    pub(crate) strict_secrets: bool,
     /// Command to execute on the remote host (use -- to separate flags).
     #[arg(required = true, trailing_var_arg = true)]
     pub(crate) command: Vec<String>,
@@ -59,3 +197,81 @@ pub(crate) struct InitCommand {
     #[arg(long)]
     pub(crate) force: bool,
 }
+
+/// Subcommands of `mriya pool`.
+#[derive(Debug, clap::Subcommand)]
+pub(crate) enum PoolCommand {
+    /// Keep ready instances for the current configuration until idle.
+    #[command(name = "start", about = "Keep ready instances until the pool is idle")]
+    Start(PoolStartCommand),
+    /// Destroy ready instances and ask a running pool to stop.
+    #[command(name = "stop", about = "Destroy ready instances and stop the pool")]
+    Stop,
+}
+
+/// Arguments for the `mriya pool start` subcommand.
+#[derive(Debug, Parser)]
+pub(crate) struct PoolStartCommand {
+    /// Number of ready instances to keep.
+    #[arg(long, value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..))]
+    pub(crate) size: u16,
+    /// Drain the pool and exit after this many seconds without a claim.
+    #[arg(long, value_name = "SECONDS", default_value_t = 1800)]
+    pub(crate) idle_timeout: u64,
+    /// Seconds between reconciliation passes.
+    #[arg(long, value_name = "SECONDS", default_value_t = 5, value_parser = clap::value_parser!(u64).range(1..))]
+    pub(crate) poll_interval: u64,
+}
+
+/// Subcommands of `mriya session`.
+#[derive(Debug, clap::Subcommand)]
+pub(crate) enum SessionCommand {
+    /// Provision an instance and leave it running.
+    #[command(name = "start", about = "Provision an instance and leave it running")]
+    Start(SessionStartCommand),
+    /// Sync the workspace and run a command on the session's instance.
+    #[command(
+        name = "exec",
+        about = "Sync the workspace and run a command on the session's instance"
+    )]
+    Exec(SessionExecCommand),
+    /// Destroy the session's instance.
+    #[command(name = "stop", about = "Destroy the session's instance")]
+    Stop(SessionStopCommand),
+}
+
+/// Arguments for the `mriya session start` subcommand.
+#[derive(Debug, Parser)]
+pub(crate) struct SessionStartCommand {
+    /// Name of the session, for running several sessions side by side.
+    #[arg(long, value_name = "NAME", default_value = "default")]
+    pub(crate) name: String,
+    /// Override the Scaleway instance type (commercial type) for this session.
+    #[arg(long, value_name = "TYPE")]
+    pub(crate) instance_type: Option<String>,
+    /// Override the image label for this session.
+    #[arg(long, value_name = "IMAGE")]
+    pub(crate) image: Option<String>,
+    /// Fail instead of warning when the sync would send likely secrets.
+    #[arg(long)]
+    pub(crate) strict_secrets: bool,
+}
+
+/// Arguments for the `mriya session exec` subcommand.
+#[derive(Debug, Parser)]
+pub(crate) struct SessionExecCommand {
+    /// Name of the session to run the command in.
+    #[arg(long, value_name = "NAME", default_value = "default")]
+    pub(crate) name: String,
+    /// Command to execute on the remote host (use -- to separate flags).
+    #[arg(required = true, trailing_var_arg = true)]
+    pub(crate) command: Vec<String>,
+}
+
+/// Arguments for the `mriya session stop` subcommand.
+#[derive(Debug, Parser)]
+pub(crate) struct SessionStopCommand {
+    /// Name of the session to stop.
+    #[arg(long, value_name = "NAME", default_value = "default")]
+    pub(crate) name: String,
+}
@@ -0,0 +1,130 @@
+//! Exit codes for Mriya's own failures.
+//!
+//! A successful `mriya run` exits with the remote command's status, so
+//! Mriya's own failures use a reserved range, [`INFRA_EXIT_CODES`], that
+//! commands rarely return and that stays clear of the shell's 126, 127, and
+//! 128 + signal codes and of `ssh`'s 255. Each [`Failure`] has its own code
+//! so CI can tell a flaky API from a broken configuration; `--infra-exit-code`
+//! collapses them onto one code instead.
+
+use std::ops::RangeInclusive;
+
+use mriya::{PoolError, RunError};
+
+use crate::CliError;
+
+/// Exit codes reserved for Mriya's own failures.
+pub(crate) const INFRA_EXIT_CODES: RangeInclusive<i32> = 240..=249;
+
+/// Step of a run that failed, independent of the remote command.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Failure {
+    /// Invalid configuration, arguments, or local files (240).
+    Config,
+    /// The provider could not create or look up resources (241).
+    Provision,
+    /// The instance never became reachable over SSH (242).
+    Readiness,
+    /// Cloud-init failed or did not finish in time (243).
+    CloudInit,
+    /// The workspace could not be synced or failed a sync check (244).
+    Sync,
+    /// The remote command could not be started (245).
+    RemoteStart,
+    /// A `before` or `after` hook failed (246).
+    Hook,
+    /// The instance could not be destroyed (247).
+    Teardown,
+    /// The remote command ended without an exit status, usually because a
+    /// signal killed it (248).
+    Signal,
+    /// Any other failure, such as shards that failed at different steps or
+    /// unreadable local state (249).
+    Other,
+}
+
+impl Failure {
+    /// Returns the code in [`INFRA_EXIT_CODES`] for this failure.
+    pub(crate) const fn exit_code(self) -> i32 {
+        let offset = match self {
+            Self::Config => 0,
+            Self::Provision => 1,
+            Self::Readiness => 2,
+            Self::CloudInit => 3,
+            Self::Sync => 4,
+            Self::RemoteStart => 5,
+            Self::Hook => 6,
+            Self::Teardown => 7,
+            Self::Signal => 8,
+            Self::Other => 9,
+        };
+        *INFRA_EXIT_CODES.start() + offset
+    }
+
+    /// Classifies a failed run.
+    pub(crate) const fn of_run<E: std::error::Error>(err: &RunError<E>) -> Self {
+        match err {
+            RunError::Provision(_) => Self::Provision,
+            RunError::Wait { .. } => Self::Readiness,
+            RunError::Provisioning { .. } | RunError::ProvisioningTimeout { .. } => Self::CloudInit,
+            RunError::Sync { .. } => Self::Sync,
+            RunError::Remote { .. } => Self::RemoteStart,
+            RunError::BeforeHook(_)
+            | RunError::AfterHook(_)
+            | RunError::LocalBeforeHook(_)
+            | RunError::LocalAfterHook(_) => Self::Hook,
+            RunError::Teardown { .. } => Self::Teardown,
+        }
+    }
+
+    /// Classifies an error reported by the CLI.
+    pub(crate) const fn of(err: &CliError) -> Self {
+        match err {
+            CliError::Config(_)
+            | CliError::Matrix(_)
+            | CliError::InvalidCommand(_)
+            | CliError::InvalidOverride { .. }
+            | CliError::InvalidCloudInit(_)
+            | CliError::Script { .. }
+            | CliError::Pool(PoolError::SharedVolume { .. }) => Self::Config,
+            CliError::Backend(_) | CliError::Init(_) => Self::Provision,
+            CliError::Sync(_) => Self::Sync,
+            CliError::MissingExitCode => Self::Signal,
+            CliError::Run(run) => Self::of_run(run),
+            CliError::RunsFailed {
+                cause: Some(cause), ..
+            } => *cause,
+            CliError::RunsFailed { cause: None, .. }
+            | CliError::Pool(_)
+            | CliError::Session(_)
+            | CliError::Watch(_) => Self::Other,
+        }
+    }
+}
+
+/// Returns the failure shared by every item of `failures`, or `None` when
+/// they differ or there are none.
+pub(crate) fn shared_failure(failures: impl IntoIterator<Item = Failure>) -> Option<Failure> {
+    let mut rest = failures.into_iter();
+    let first = rest.next()?;
+    rest.all(|failure| failure == first).then_some(first)
+}
+
+/// Prints a command-line parsing error and returns its exit code: 0 for
+/// `--help`, which clap reports as an error, and the [`Failure::Config`] code
+/// for invalid arguments, so they cannot be mistaken for the command's own
+/// exit code.
+pub(crate) fn parse_error_exit_code(err: &clap::Error) -> i32 {
+    err.print().ok();
+    if err.use_stderr() {
+        Failure::Config.exit_code()
+    } else {
+        0
+    }
+}
+
+/// Returns the exit code for `err`: `infra_exit_code` when given, otherwise
+/// the code of its [`Failure`].
+pub(crate) fn exit_code_for(err: &CliError, infra_exit_code: Option<u8>) -> i32 {
+    infra_exit_code.map_or_else(|| Failure::of(err).exit_code(), i32::from)
+}
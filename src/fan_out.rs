@@ -0,0 +1,160 @@
+//! Parallel execution for `mriya run --shards N` and `mriya run --matrix`.
+//!
+//! Every shard or matrix cell provisions its own instance through a regular
+//! [`RunOrchestrator`]. Output from each run is prefixed with its label so
+//! interleaved lines remain attributable. Shards additionally receive their
+//! index and count in the remote environment; matrix cells vary the instance
+//! request and finish with a summary table.
+
+use std::io::{self, Write};
+
+use camino::Utf8Path;
+use mriya::matrix::{expand, summary_table};
+use mriya::parallel::shard_command;
+use mriya::{
+    Hooks, InstanceRequest, ParallelRun, PrefixedCommandRunner, Redactor, RemoteEnv, RetryPolicy,
+    RunOrchestrator, RunOutcome, ScalewayBackend, ScalewayBackendError, SyncConfig, Syncer,
+    aggregate_exit_code, run_parallel,
+};
+
+use crate::exit_code::{Failure, shared_failure};
+use crate::{CliError, warn_on_retry};
+
+/// Inputs shared by every run in the batch.
+pub(crate) struct FanOutPlan {
+    /// Backend cloned into each run; provenance is shared across runs.
+    pub(crate) backend: ScalewayBackend,
+    /// Instance request used as the template for every run.
+    pub(crate) request: InstanceRequest,
+    /// Sync configuration applied to each run.
+    pub(crate) sync_config: SyncConfig,
+    /// Environment forwarded to each run's command.
+    pub(crate) env: RemoteEnv,
+    /// Secrets masked in each run's output.
+    pub(crate) redactor: Redactor,
+    /// Remote hooks run around each run's command.
+    pub(crate) hooks: Hooks,
+    /// Provisioning retries of each run.
+    pub(crate) retry: RetryPolicy,
+    /// Script uploaded for each run by `--script`.
+    pub(crate) script: Option<Vec<u8>>,
+    /// Rendered remote command.
+    pub(crate) remote_command: String,
+}
+
+/// How a run is split across instances.
+pub(crate) enum FanOut {
+    /// Identical instances, each told its 1-based index out of the count.
+    Shards(usize),
+    /// One instance per matrix cell.
+    Matrix {
+        /// Cell specifications.
+        cells: Vec<String>,
+        /// Per-architecture cache volume mappings.
+        cache_volumes: Vec<String>,
+    },
+}
+
+/// Runs the batch concurrently and aggregates the exit codes.
+///
+/// Errors from individual runs are reported on stderr with the run label.
+/// When every failed run failed at the same step, the batch exits with that
+/// step's code.
+/// Infrastructure failures take precedence over remote exit codes so a run
+/// that never executed its command is never mistaken for a passing one.
+pub(crate) async fn run_fan_out(
+    plan: FanOutPlan,
+    fan_out: FanOut,
+    source: &Utf8Path,
+) -> Result<i32, CliError> {
+    let show_summary = matches!(fan_out, FanOut::Matrix { .. });
+    let runs = build_runs(&plan, fan_out)?;
+    let total = runs.len();
+    let outcomes = run_parallel(runs, source).await;
+
+    if show_summary {
+        write!(io::stdout(), "{}", summary_table(&outcomes)).ok();
+    }
+    let failed = report_failures(&outcomes);
+    if failed > 0 {
+        let cause = shared_failure(
+            outcomes
+                .iter()
+                .filter_map(|outcome| outcome.result.as_ref().err())
+                .map(Failure::of_run),
+        );
+        return Err(CliError::RunsFailed {
+            failed,
+            total,
+            cause,
+        });
+    }
+    aggregate_exit_code(&outcomes).ok_or(CliError::MissingExitCode)
+}
+
+fn build_runs(
+    plan: &FanOutPlan,
+    fan_out: FanOut,
+) -> Result<Vec<ParallelRun<ScalewayBackend, PrefixedCommandRunner>>, CliError> {
+    let runs = match fan_out {
+        FanOut::Shards(count) => (1..=count)
+            .map(|index| {
+                // A block volume attaches to a single instance, so only the
+                // first shard mounts the cache; the others build cold.
+                let mut request = plan.request.clone();
+                if index > 1 {
+                    request.volume_id = None;
+                }
+                let command = shard_command(index, count, &plan.remote_command);
+                (format!("shard {index}/{count}"), request, command)
+            })
+            .collect::<Vec<_>>(),
+        FanOut::Matrix {
+            cells,
+            cache_volumes,
+        } => expand(&plan.request, &cells, &cache_volumes)?
+            .into_iter()
+            .map(|(label, request)| (label, request, plan.remote_command.clone()))
+            .collect(),
+    };
+
+    runs.into_iter()
+        .map(|(label, request, remote_command)| {
+            let mut syncer = Syncer::new(
+                plan.sync_config.clone(),
+                PrefixedCommandRunner::new(format!("[{label}] "))
+                    .with_redactor(plan.redactor.clone()),
+            )
+            .map_err(|err| CliError::Sync(err.to_string()))?
+            .with_env(plan.env.clone());
+            if let Some(script) = &plan.script {
+                syncer = syncer.with_script(script.clone());
+            }
+            let retry = warn_on_retry(
+                plan.retry.clone(),
+                format!("[{label}] "),
+                plan.redactor.clone(),
+            );
+            Ok(ParallelRun {
+                label,
+                orchestrator: RunOrchestrator::new(plan.backend.clone(), syncer)
+                    .with_hooks(plan.hooks.clone())
+                    .with_retry(retry),
+                request,
+                remote_command,
+            })
+        })
+        .collect()
+}
+
+fn report_failures(outcomes: &[RunOutcome<ScalewayBackendError>]) -> usize {
+    let mut stderr = io::stderr().lock();
+    let mut failed = 0;
+    for outcome in outcomes {
+        if let Err(err) = &outcome.result {
+            failed += 1;
+            writeln!(stderr, "[{}] remote run failed: {err}", outcome.label).ok();
+        }
+    }
+    failed
+}
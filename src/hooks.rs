@@ -0,0 +1,226 @@
+//! Commands run around the remote command of `mriya run`.
+//!
+//! Remote hooks run on the instance through [`RunOrchestrator`]: `before`
+//! hooks after the sync and before the command, `after` hooks once the
+//! command has finished, whatever its exit code, or once the sync or a
+//! `before` hook has failed. Local hooks run on this machine through
+//! `sh -c`, before provisioning and after teardown. `before` hooks stop at
+//! the first failure; `after` hooks all run, and the first failure is
+//! reported.
+//!
+//! [`RunOrchestrator`]: crate::RunOrchestrator
+
+use std::ffi::OsString;
+use std::future::Future;
+
+use thiserror::Error;
+
+use crate::sync::{AsyncCommandRunner, Redactor, SyncError};
+
+/// Commands run before and after a step.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Hooks {
+    /// Commands run before the step; the first failure aborts it.
+    pub before: Vec<String>,
+    /// Commands run after the step, whether or not it succeeded.
+    pub after: Vec<String>,
+}
+
+impl Hooks {
+    /// Returns `true` when there are no hooks.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.before.is_empty() && self.after.is_empty()
+    }
+
+    /// Runs the `before` hooks locally, stopping at the first failure.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first [`HookFailure`].
+    pub async fn run_local_before<R: AsyncCommandRunner>(
+        &self,
+        runner: &R,
+    ) -> Result<(), HookFailure> {
+        run_sequence(&self.before, Order::StopAtFailure, |command| {
+            run_local(runner, command)
+        })
+        .await
+    }
+
+    /// Runs every `after` hook locally.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first [`HookFailure`].
+    pub async fn run_local_after<R: AsyncCommandRunner>(
+        &self,
+        runner: &R,
+    ) -> Result<(), HookFailure> {
+        run_sequence(&self.after, Order::RunAll, |command| {
+            run_local(runner, command)
+        })
+        .await
+    }
+}
+
+/// A hook that failed to start or exited unsuccessfully.
+#[derive(Clone, Debug, Error, Eq, PartialEq)]
+#[error("`{command}` {reason}")]
+pub struct HookFailure {
+    /// Hook command as configured.
+    pub command: String,
+    /// What went wrong, such as its exit status.
+    pub reason: String,
+}
+
+impl HookFailure {
+    /// Masks the secrets of `redactor` in the command and reason.
+    #[must_use]
+    pub fn redact(self, redactor: &Redactor) -> Self {
+        Self {
+            command: redactor.redact(&self.command),
+            reason: redactor.redact(&self.reason),
+        }
+    }
+
+    /// Appends `note` to the reason.
+    pub(crate) fn with_note(mut self, note: impl FnOnce(String) -> String) -> Self {
+        self.reason = note(self.reason);
+        self
+    }
+}
+
+/// Whether a sequence continues past a failed hook.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Order {
+    /// Stops at the first failure.
+    StopAtFailure,
+    /// Runs every hook.
+    RunAll,
+}
+
+/// Runs `commands` through `run`, which returns the exit code of a command,
+/// and reports the first failure.
+pub(crate) async fn run_sequence<F, Fut>(
+    commands: &[String],
+    order: Order,
+    run: F,
+) -> Result<(), HookFailure>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Result<Option<i32>, SyncError>>,
+{
+    let mut first_failure = None;
+    for command in commands {
+        let reason = match run(command.clone()).await {
+            Ok(Some(0)) => continue,
+            Ok(Some(code)) => format!("exited with status {code}"),
+            Ok(None) => String::from("terminated without an exit status"),
+            Err(err) => format!("failed to run: {err}"),
+        };
+        let failure = HookFailure {
+            command: command.clone(),
+            reason,
+        };
+        if order == Order::StopAtFailure {
+            return Err(failure);
+        }
+        first_failure.get_or_insert(failure);
+    }
+    first_failure.map_or(Ok(()), Err)
+}
+
+async fn run_local<R: AsyncCommandRunner>(
+    runner: &R,
+    command: String,
+) -> Result<Option<i32>, SyncError> {
+    let args = [OsString::from("-c"), OsString::from(command)];
+    runner.run("sh", &args).await.map(|output| output.code)
+}
+
+#[cfg(test)]
+mod tests {
+    //! Unit tests for hook sequencing and local hooks.
+    use super::*;
+    use crate::test_support::{CommandInvocation, ScriptedRunner};
+    use rstest::rstest;
+
+    fn hooks(before: &[&str], after: &[&str]) -> Hooks {
+        Hooks {
+            before: before.iter().map(|command| (*command).to_owned()).collect(),
+            after: after.iter().map(|command| (*command).to_owned()).collect(),
+        }
+    }
+
+    fn commands_run(runner: &ScriptedRunner) -> Vec<String> {
+        runner
+            .invocations()
+            .iter()
+            .map(CommandInvocation::command_string)
+            .collect()
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn local_before_hooks_stop_at_the_first_failure() {
+        let runner = ScriptedRunner::new();
+        runner.push_success();
+        runner.push_failure(2);
+
+        let err = hooks(&["make assets", "false", "never"], &[])
+            .run_local_before(&runner)
+            .await
+            .expect_err("second hook fails");
+
+        assert_eq!(err.to_string(), "`false` exited with status 2");
+        assert_eq!(commands_run(&runner), ["sh -c make assets", "sh -c false"]);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn local_after_hooks_all_run_and_report_the_first_failure() {
+        let runner = ScriptedRunner::new();
+        runner.push_missing_exit_code();
+        runner.push_failure(1);
+        runner.push_success();
+
+        let err = hooks(&[], &["collect", "false", "notify"])
+            .run_local_after(&runner)
+            .await
+            .expect_err("hooks fail");
+
+        assert_eq!(
+            err.to_string(),
+            "`collect` terminated without an exit status"
+        );
+        assert_eq!(runner.invocations().len(), 3);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn hooks_that_cannot_start_are_failures() {
+        let runner = ScriptedRunner::new();
+        runner.fail_next_spawn("sh", "no shell");
+
+        let err = hooks(&["make"], &[])
+            .run_local_before(&runner)
+            .await
+            .expect_err("spawn fails");
+
+        assert_eq!(err.command, "make");
+        assert!(err.reason.starts_with("failed to run: "), "{err}");
+    }
+
+    #[rstest]
+    fn failures_mask_secrets() {
+        let failure = HookFailure {
+            command: String::from("curl -H 'token: s3cr3t-value'"),
+            reason: String::from("exited with status 1"),
+        };
+
+        let masked = failure.redact(&Redactor::new(["s3cr3t-value"]));
+
+        assert_eq!(masked.command, "curl -H 'token: ***'");
+    }
+}
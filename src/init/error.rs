@@ -4,7 +4,7 @@ use thiserror::Error;
 
 use crate::config::ConfigError;
 use crate::config_store::ConfigStoreError;
-use crate::sync::SyncError;
+use crate::sync::{Redactor, SyncError};
 
 /// Errors raised while loading init configuration.
 #[derive(Debug, Error, Eq, PartialEq)]
@@ -83,3 +83,30 @@ where
     #[error("failed to destroy formatter instance: {0}")]
     Teardown(#[source] BackendError),
 }
+
+impl<BackendError> InitError<BackendError>
+where
+    BackendError: std::error::Error + 'static,
+{
+    /// Masks the secrets of `redactor` in the message and underlying sync
+    /// error. Backend errors are left as they are.
+    #[must_use]
+    pub fn redact(self, redactor: &Redactor) -> Self {
+        match self {
+            Self::Wait { message, source } => Self::Wait {
+                message: redactor.redact(&message),
+                source,
+            },
+            Self::Format { message, source } => Self::Format {
+                message: redactor.redact(&message),
+                source: source.map(|err| err.redact(redactor)),
+            },
+            Self::Detach { message, source } => Self::Detach {
+                message: redactor.redact(&message),
+                source,
+            },
+            other
+            @ (Self::Config(_) | Self::Volume(_) | Self::Provision(_) | Self::Teardown(_)) => other,
+        }
+    }
+}
@@ -16,14 +16,20 @@ pub(super) fn volume_size_bytes(size_gb: u32) -> Option<u64> {
 }
 
 pub(super) fn volume_name_for_project(project_name: &str) -> String {
+    project_slug(project_name).map_or_else(
+        || String::from("mriya-cache"),
+        |slug| format!("mriya-{slug}-cache"),
+    )
+}
+
+/// Returns the slug used to name per-project resources, or `None` when the
+/// project name contains no usable characters.
+pub(crate) fn project_slug(project_name: &str) -> Option<String> {
     let slug = slugify(project_name);
-    if slug.is_empty() {
-        return String::from("mriya-cache");
-    }
-    format!("mriya-{slug}-cache")
+    (!slug.is_empty()).then_some(slug)
 }
 
-pub(super) fn slugify(value: &str) -> String {
+pub(crate) fn slugify(value: &str) -> String {
     let mut slug = String::new();
     let mut last_dash = false;
     for ch in value.chars() {
@@ -10,12 +10,12 @@ use serde::Deserialize;
 use crate::backend::{Backend, InstanceHandle, InstanceNetworking, InstanceRequest};
 use crate::config::ScalewayConfig;
 use crate::config_store::{ConfigStoreError, ConfigWriter};
-use crate::sync::{CommandRunner, RemoteCommandOutput, SyncError, Syncer};
+use crate::sync::{AsyncCommandRunner, RemoteCommandOutput, SyncError, Syncer};
 use crate::volume::{VolumeBackend, VolumeHandle, VolumeRequest};
 use helpers::{format_command, volume_name_for_project, volume_size_bytes};
 
 mod error;
-mod helpers;
+pub(crate) mod helpers;
 
 pub use error::{InitConfigError, InitError, InitRequestError};
 
@@ -132,7 +132,7 @@ pub struct InitOutcome {
 
 /// Coordinates volume creation, formatting, and configuration updates.
 #[derive(Debug)]
-pub struct InitOrchestrator<B, R: CommandRunner, W> {
+pub struct InitOrchestrator<B, R: AsyncCommandRunner, W> {
     backend: B,
     syncer: Syncer<R>,
     config_writer: W,
@@ -142,7 +142,7 @@ impl<B, R, W> InitOrchestrator<B, R, W>
 where
     B: Backend + VolumeBackend,
     B::Error: Display + Send + Sync + std::error::Error + 'static,
-    R: CommandRunner,
+    R: AsyncCommandRunner,
     W: ConfigWriter,
 {
     /// Creates a new init orchestrator.
@@ -171,7 +171,7 @@ where
             .map_err(InitError::Volume)?;
 
         let (handle, networking) = self.prepare_instance(request, &volume).await?;
-        let format_result = self.format_volume(&networking, &volume.id);
+        let format_result = self.format_volume(&networking, &volume.id).await;
         self.handle_failure_or_destroy(&handle, format_result, |message, failure| {
             InitError::Format {
                 message,
@@ -268,7 +268,7 @@ where
         }
     }
 
-    fn format_volume(
+    async fn format_volume(
         &self,
         networking: &InstanceNetworking,
         volume_id: &str,
@@ -276,6 +276,7 @@ where
         let output = self
             .syncer
             .run_remote_raw(networking, &format_command(volume_id))
+            .await
             .map_err(|err| FormatFailure {
                 message: String::from("failed to execute format command"),
                 source: Some(err),
@@ -5,15 +5,16 @@
 //! unique tag (`mriya-test-run-<id>`) and deletes them, failing if anything
 //! remains afterwards.
 
-use std::ffi::OsString;
-
-use serde_json::Value;
 use thiserror::Error;
 
-use crate::sync::{CommandOutput, CommandRunner, ProcessCommandRunner, SyncError};
+use crate::provenance::RunProvenance;
+use crate::sync::{AsyncCommandRunner, ProcessCommandRunner, SyncError};
 
+mod report;
+mod scw;
 pub mod types;
-pub use types::{ScwServer, ScwVolume};
+pub use report::{ResourceType, SweepAction, SweepSummary, SweptResource};
+pub use types::{ScwResource, ScwServer, ScwVolume};
 
 /// Environment variable used by test harnesses to identify a test run.
 pub const TEST_RUN_ID_ENV: &str = "MRIYA_TEST_RUN_ID";
@@ -24,24 +25,24 @@ pub const TEST_RUN_TAG_PREFIX: &str = "mriya-test-run-";
 /// Default Scaleway CLI binary name.
 pub const DEFAULT_SCW_BIN: &str = "scw";
 
-/// Scaleway resource type for janitor operations.
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
-enum ResourceType {
-    /// Instance servers.
-    Servers,
-    /// Block storage volumes.
-    Volumes,
-}
-
-impl ResourceType {
-    /// Returns the resource name used in scw CLI commands and JSON parsing.
-    const fn as_str(self) -> &'static str {
-        match self {
-            Self::Servers => "servers",
-            Self::Volumes => "volumes",
-        }
-    }
-}
+/// Order in which resource types are swept.
+///
+/// Resources are deleted in dependency order: servers first so their IPs,
+/// volumes, and security groups are released, images before the snapshots
+/// backing them, and snapshots before the volumes they were taken from.
+/// Security groups go last because Scaleway refuses to delete a group that a
+/// server still references.
+const SWEEP_ORDER: [ResourceType; 6] = [
+    ResourceType::Servers,
+    ResourceType::Ips,
+    ResourceType::Images,
+    ResourceType::Snapshots,
+    ResourceType::Volumes,
+    ResourceType::SecurityGroups,
+];
+
+/// Maximum number of resource labels shown per category in error messages.
+const MAX_ITEMS_TO_SHOW: usize = 5;
 
 /// Configuration for a janitor sweep.
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -52,6 +53,10 @@ pub struct JanitorConfig {
     pub test_run_id: String,
     /// Path to the `scw` CLI binary.
     pub scw_bin: String,
+    /// List tagged resources without deleting them.
+    pub dry_run: bool,
+    /// Keep deleting remaining resources when one deletion fails.
+    pub keep_going: bool,
 }
 
 impl JanitorConfig {
@@ -91,9 +96,28 @@ impl JanitorConfig {
             project_id: trimmed_project_id,
             test_run_id: trimmed_test_run_id,
             scw_bin: trimmed_scw_bin,
+            dry_run: false,
+            keep_going: false,
         })
     }
 
+    /// Enables or disables dry-run mode, where nothing is deleted.
+    #[must_use]
+    pub const fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Enables or disables continuing past failed deletions.
+    ///
+    /// Failures are recorded in the [`SweepSummary`] and reported together
+    /// through [`JanitorError::NotClean`] once every deletion was attempted.
+    #[must_use]
+    pub const fn with_keep_going(mut self, keep_going: bool) -> Self {
+        self.keep_going = keep_going;
+        self
+    }
+
     /// Returns the full tag used for this test run.
     #[must_use]
     pub fn test_run_tag(&self) -> String {
@@ -101,15 +125,6 @@ impl JanitorConfig {
     }
 }
 
-/// Summary of janitor work.
-#[derive(Clone, Debug, Eq, PartialEq)]
-pub struct SweepSummary {
-    /// Number of servers deleted during the sweep.
-    pub deleted_servers: usize,
-    /// Number of Block Storage volumes deleted during the sweep.
-    pub deleted_volumes: usize,
-}
-
 /// Errors returned by the janitor.
 #[derive(Clone, Debug, Error, Eq, PartialEq)]
 pub enum JanitorError {
@@ -142,8 +157,10 @@ pub enum JanitorError {
     /// Raised when resources remain after the sweep.
     #[error("resources remain after janitor sweep: {message}")]
     NotClean {
-        /// Human-readable description of what remains.
+        /// Human-readable description of what remains or failed.
         message: String,
+        /// Per-resource results gathered before the sweep was found unclean.
+        report: SweepSummary,
     },
     /// Raised when command execution fails.
     #[error(transparent)]
@@ -152,7 +169,7 @@ pub enum JanitorError {
 
 /// Deletes test-run-tagged Scaleway resources by shelling out to `scw`.
 #[derive(Clone, Debug)]
-pub struct Janitor<R: CommandRunner> {
+pub struct Janitor<R: AsyncCommandRunner> {
     config: JanitorConfig,
     runner: R,
 }
@@ -165,232 +182,139 @@ impl Janitor<ProcessCommandRunner> {
     }
 }
 
-impl<R: CommandRunner> Janitor<R> {
+impl<R: AsyncCommandRunner> Janitor<R> {
     /// Creates a new janitor using the provided configuration and runner.
     #[must_use]
     pub const fn new(config: JanitorConfig, runner: R) -> Self {
         Self { config, runner }
     }
 
-    /// Performs a sweep and returns how many resources were deleted.
+    /// Performs a sweep and returns a per-resource report.
     ///
-    /// The sweep is ordered: servers are deleted first (waiting for deletion),
-    /// then tagged volumes are deleted. The command fails if any tagged
-    /// resources remain at the end.
+    /// The sweep follows [`SWEEP_ORDER`]: servers are deleted first (waiting
+    /// for deletion), then IPs, images, snapshots, volumes, and security
+    /// groups. The command fails if any tagged resources remain at the end.
+    /// In dry-run mode the tagged resources are only listed and recorded as
+    /// [`SweepAction::WouldDelete`].
     ///
     /// # Errors
     ///
     /// Returns [`JanitorError`] when `scw` fails, output cannot be parsed, or
-    /// resources remain after deletion attempts.
-    pub fn sweep(&self) -> Result<SweepSummary, JanitorError> {
+    /// resources remain after deletion attempts. With
+    /// [`JanitorConfig::keep_going`] set, failed deletions are collected and
+    /// reported through [`JanitorError::NotClean`] instead of aborting.
+    pub async fn sweep(&self) -> Result<SweepSummary, JanitorError> {
         let tag = self.config.test_run_tag();
+        let mut summary = SweepSummary {
+            dry_run: self.config.dry_run,
+            resources: Vec::new(),
+        };
 
-        let deleted_servers = self.delete_tagged_servers(&tag)?;
-        let deleted_volumes = self.delete_tagged_volumes(&tag)?;
-        self.ensure_no_remaining(&tag)?;
-
-        Ok(SweepSummary {
-            deleted_servers,
-            deleted_volumes,
-        })
+        for resource in SWEEP_ORDER {
+            self.sweep_resource(resource, &tag, &mut summary).await?;
+        }
+        if self.config.dry_run {
+            return Ok(summary);
+        }
+        self.ensure_no_remaining(&tag, summary).await
     }
 
-    fn delete_tagged<T>(
+    async fn sweep_resource(
         &self,
+        resource: ResourceType,
         tag: &str,
-        list: fn(&Self, &str) -> Result<Vec<T>, JanitorError>,
-        delete: fn(&Self, &T) -> Result<CommandOutput, JanitorError>,
-    ) -> Result<usize, JanitorError> {
-        let items = list(self, tag)?;
-        for item in &items {
-            delete(self, item)?;
+        summary: &mut SweepSummary,
+    ) -> Result<(), JanitorError> {
+        for item in self.list_tagged(resource, tag).await? {
+            let (action, error) = self.attempt_delete(resource, &item).await?;
+            let run_id = RunProvenance::run_id_from_tags(&item.tags).map(str::to_owned);
+            summary.resources.push(SweptResource {
+                id: item.id,
+                zone: item.zone,
+                resource_type: resource,
+                run_id,
+                action,
+                error,
+            });
         }
-        Ok(items.len())
-    }
-
-    fn delete_tagged_servers(&self, tag: &str) -> Result<usize, JanitorError> {
-        self.delete_tagged(tag, Self::list_tagged_servers, Self::delete_server)
+        Ok(())
     }
 
-    fn delete_tagged_volumes(&self, tag: &str) -> Result<usize, JanitorError> {
-        self.delete_tagged(tag, Self::list_tagged_volumes, Self::delete_volume)
-    }
-
-    fn ensure_no_remaining(&self, tag: &str) -> Result<(), JanitorError> {
-        const MAX_ITEMS_TO_SHOW: usize = 5;
-
-        let remaining_servers = self.list_tagged_servers(tag)?;
-        let remaining_volumes = self.list_tagged_volumes(tag)?;
-
-        if remaining_servers.is_empty() && remaining_volumes.is_empty() {
-            return Ok(());
-        }
-
-        let remaining_server_ids = remaining_servers
-            .iter()
-            .take(MAX_ITEMS_TO_SHOW)
-            .map(|srv| format!("{}@{}", srv.id, srv.zone))
-            .collect::<Vec<_>>()
-            .join(", ");
-        let remaining_volume_ids = remaining_volumes
-            .iter()
-            .take(MAX_ITEMS_TO_SHOW)
-            .map(|vol| format!("{}@{}", vol.id, vol.zone))
-            .collect::<Vec<_>>()
-            .join(", ");
-
-        let message = format!(
-            "servers remaining: {} [{}], volumes remaining: {} [{}] (showing up to {} of each)",
-            remaining_servers.len(),
-            remaining_server_ids,
-            remaining_volumes.len(),
-            remaining_volume_ids,
-            MAX_ITEMS_TO_SHOW
-        );
-        Err(JanitorError::NotClean { message })
-    }
-
-    fn has_tag(tags: &[String], tag: &str) -> bool {
-        tags.iter().any(|existing| existing == tag)
-    }
-
-    fn list_tagged_servers(&self, tag: &str) -> Result<Vec<ScwServer>, JanitorError> {
-        Ok(self
-            .list_servers()?
-            .into_iter()
-            .filter(|srv| Self::has_tag(&srv.tags, tag))
-            .collect())
-    }
-
-    fn list_tagged_volumes(&self, tag: &str) -> Result<Vec<ScwVolume>, JanitorError> {
-        Ok(self
-            .list_volumes()?
-            .into_iter()
-            .filter(|vol| Self::has_tag(&vol.tags, tag))
-            .collect())
-    }
-
-    fn run_scw(
+    /// Deletes a single resource, honouring dry-run and keep-going modes.
+    async fn attempt_delete(
         &self,
-        args: &[&str],
         resource: ResourceType,
-    ) -> Result<CommandOutput, JanitorError> {
-        let os_args = args.iter().map(OsString::from).collect::<Vec<_>>();
-        let output = self.runner.run(&self.config.scw_bin, &os_args)?;
-
-        if output.is_success() {
-            return Ok(output);
+        item: &ScwResource,
+    ) -> Result<(SweepAction, Option<String>), JanitorError> {
+        if self.config.dry_run {
+            return Ok((SweepAction::WouldDelete, None));
+        }
+        match self.delete_resource(resource, item).await {
+            Ok(_) => Ok((SweepAction::Deleted, None)),
+            Err(err) if self.config.keep_going => Ok((
+                SweepAction::Failed,
+                Some(err.to_string().trim_end().to_owned()),
+            )),
+            Err(err) => Err(err),
         }
-
-        let status_text = output
-            .code
-            .map_or_else(|| String::from("unknown"), |code| code.to_string());
-        Err(JanitorError::CommandFailure {
-            program: self.config.scw_bin.clone(),
-            status: output.code,
-            status_text,
-            stderr: format!("{}: {}", resource.as_str(), output.stderr),
-        })
-    }
-
-    fn run_scw_json(&self, args: &[&str], resource: ResourceType) -> Result<String, JanitorError> {
-        self.run_scw(args, resource).map(|out| out.stdout)
-    }
-
-    fn parse_scw_list<T>(stdout: &str, resource: ResourceType) -> Result<Vec<T>, JanitorError>
-    where
-        T: serde::de::DeserializeOwned,
-    {
-        let resource_name = resource.as_str();
-        let payload = serde_json::from_str::<Value>(stdout).map_err(|err| JanitorError::Parse {
-            resource: resource_name.to_owned(),
-            message: err.to_string(),
-        })?;
-
-        let items = Self::unwrap_scw_list_items(payload, resource_name)?;
-
-        serde_json::from_value::<Vec<T>>(items).map_err(|err| JanitorError::Parse {
-            resource: resource_name.to_owned(),
-            message: err.to_string(),
-        })
     }
 
-    /// Lists Scaleway resources of a specific type using the scw CLI.
-    fn list_scw_resources<T>(
+    async fn ensure_no_remaining(
         &self,
-        subcommand_path: &[&str],
-        resource: ResourceType,
-    ) -> Result<Vec<T>, JanitorError>
-    where
-        T: serde::de::DeserializeOwned,
-    {
-        let project_arg = format!("project-id={}", self.config.project_id);
-
-        // Capacity: `subcommand_path` + ["list", project_arg, "zone=all", "-o", "json"].
-        let mut args = Vec::with_capacity(subcommand_path.len() + 5);
-        args.extend_from_slice(subcommand_path);
-        args.extend_from_slice(&["list", project_arg.as_str(), "zone=all", "-o", "json"]);
-
-        let stdout = self.run_scw_json(&args, resource)?;
-        Self::parse_scw_list(&stdout, resource)
-    }
+        tag: &str,
+        summary: SweepSummary,
+    ) -> Result<SweepSummary, JanitorError> {
+        let mut remaining = Vec::new();
+        for resource in SWEEP_ORDER {
+            let items = self.list_tagged(resource, tag).await?;
+            if !items.is_empty() {
+                remaining.push((resource, items));
+            }
+        }
+        let failures = summary.failures().collect::<Vec<_>>();
 
-    fn unwrap_scw_list_items(payload: Value, resource_name: &str) -> Result<Value, JanitorError> {
-        match payload {
-            Value::Array(items) => Ok(Value::Array(items)),
-            Value::Object(map) => Self::extract_scw_list_field(map, resource_name),
-            other => Err(JanitorError::Parse {
-                resource: resource_name.to_owned(),
-                message: format!("unexpected JSON shape: {other}"),
-            }),
+        if remaining.is_empty() && failures.is_empty() {
+            return Ok(summary);
         }
-    }
 
-    fn extract_scw_list_field(
-        mut map: serde_json::Map<String, Value>,
-        resource_name: &str,
-    ) -> Result<Value, JanitorError> {
-        map.remove(resource_name)
-            .ok_or_else(|| JanitorError::Parse {
-                resource: resource_name.to_owned(),
-                message: format!("missing '{resource_name}' field"),
+        let remaining_text = remaining
+            .iter()
+            .map(|(resource, items)| {
+                format!(
+                    "{} remaining: {} [{}]",
+                    resource.as_str(),
+                    items.len(),
+                    Self::labels(items.iter().map(ScwResource::label))
+                )
             })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut parts = Vec::new();
+        if !remaining_text.is_empty() {
+            parts.push(format!(
+                "{remaining_text} (showing up to {MAX_ITEMS_TO_SHOW} of each)"
+            ));
+        }
+        if !failures.is_empty() {
+            parts.push(format!(
+                "failed deletions: {} [{}]",
+                failures.len(),
+                failures
+                    .iter()
+                    .map(|res| format!("{}: {}", res.label(), res.error.as_deref().unwrap_or("")))
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            ));
+        }
+        let message = parts.join("; ");
+        Err(JanitorError::NotClean {
+            message,
+            report: summary,
+        })
     }
 
-    fn list_servers(&self) -> Result<Vec<ScwServer>, JanitorError> {
-        self.list_scw_resources(&["instance", "server"], ResourceType::Servers)
-    }
-
-    fn delete_server(&self, server: &ScwServer) -> Result<CommandOutput, JanitorError> {
-        let zone_arg = format!("zone={}", server.zone);
-        let args = [
-            "instance",
-            "server",
-            "delete",
-            server.id.as_str(),
-            zone_arg.as_str(),
-            "with-ip=true",
-            "with-volumes=none",
-            "force-shutdown=true",
-            "--wait",
-        ];
-        self.run_scw(&args, ResourceType::Servers)
-    }
-
-    fn list_volumes(&self) -> Result<Vec<ScwVolume>, JanitorError> {
-        self.list_scw_resources(&["block", "volume"], ResourceType::Volumes)
-    }
-
-    fn delete_volume(&self, volume: &ScwVolume) -> Result<CommandOutput, JanitorError> {
-        let zone_arg = format!("zone={}", volume.zone);
-        let args = [
-            "block",
-            "volume",
-            "delete",
-            volume.id.as_str(),
-            zone_arg.as_str(),
-        ];
-        self.run_scw(&args, ResourceType::Volumes)
+    fn labels(items: impl Iterator<Item = String>) -> String {
+        items.take(MAX_ITEMS_TO_SHOW).collect::<Vec<_>>().join(", ")
     }
 }
 
@@ -0,0 +1,141 @@
+//! Sweep reporting types for the Scaleway janitor.
+//!
+//! A sweep records one entry per tagged resource it touched so callers can
+//! render a per-resource report (for example as JSON in CI) instead of a
+//! single line of counts.
+
+use serde::Serialize;
+
+/// Scaleway resource type handled by the janitor.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResourceType {
+    /// Instance servers.
+    Servers,
+    /// Flexible public IP addresses.
+    Ips,
+    /// Instance images.
+    Images,
+    /// Block Storage snapshots.
+    Snapshots,
+    /// Block Storage volumes.
+    Volumes,
+    /// Instance security groups.
+    SecurityGroups,
+}
+
+impl ResourceType {
+    /// Every resource type, in declaration order.
+    pub const ALL: [Self; 6] = [
+        Self::Servers,
+        Self::Ips,
+        Self::Images,
+        Self::Snapshots,
+        Self::Volumes,
+        Self::SecurityGroups,
+    ];
+
+    /// Returns the resource name used in scw CLI commands and JSON parsing.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Servers => "servers",
+            Self::Ips => "ips",
+            Self::Images => "images",
+            Self::Snapshots => "snapshots",
+            Self::Volumes => "volumes",
+            Self::SecurityGroups => "security_groups",
+        }
+    }
+}
+
+/// Action recorded for a single resource during a sweep.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SweepAction {
+    /// The resource was deleted.
+    Deleted,
+    /// The resource would have been deleted, but the sweep was a dry run.
+    WouldDelete,
+    /// Deleting the resource failed; see [`SweptResource::error`].
+    Failed,
+}
+
+impl SweepAction {
+    /// Returns the action name used in reports.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Deleted => "deleted",
+            Self::WouldDelete => "would_delete",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+/// Outcome of the janitor for a single tagged resource.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct SweptResource {
+    /// Provider identifier of the resource.
+    pub id: String,
+    /// Zone the resource lives in.
+    pub zone: String,
+    /// Kind of resource.
+    #[serde(rename = "type")]
+    pub resource_type: ResourceType,
+    /// Run ID from the resource's provenance tags, when present.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+    /// What the janitor did with the resource.
+    pub action: SweepAction,
+    /// Error message when [`SweepAction::Failed`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl SweptResource {
+    /// Renders the resource as `<id>@<zone>` for human-readable messages.
+    #[must_use]
+    pub fn label(&self) -> String {
+        format!("{}@{}", self.id, self.zone)
+    }
+}
+
+/// Summary of janitor work.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct SweepSummary {
+    /// Whether the sweep only listed resources without deleting them.
+    pub dry_run: bool,
+    /// Every tagged resource the sweep touched, in sweep order.
+    pub resources: Vec<SweptResource>,
+}
+
+impl SweepSummary {
+    /// Counts resources of `resource_type` that ended with `action`.
+    #[must_use]
+    pub fn count(&self, resource_type: ResourceType, action: SweepAction) -> usize {
+        self.resources
+            .iter()
+            .filter(|res| res.resource_type == resource_type && res.action == action)
+            .count()
+    }
+
+    /// Number of servers deleted during the sweep.
+    #[must_use]
+    pub fn deleted_servers(&self) -> usize {
+        self.count(ResourceType::Servers, SweepAction::Deleted)
+    }
+
+    /// Number of Block Storage volumes deleted during the sweep.
+    #[must_use]
+    pub fn deleted_volumes(&self) -> usize {
+        self.count(ResourceType::Volumes, SweepAction::Deleted)
+    }
+
+    /// Returns the resources whose deletion failed.
+    pub fn failures(&self) -> impl Iterator<Item = &SweptResource> {
+        self.resources
+            .iter()
+            .filter(|res| res.action == SweepAction::Failed)
+    }
+}
@@ -0,0 +1,153 @@
+//! `scw` CLI invocation and output parsing for the janitor.
+
+use std::ffi::OsString;
+
+use serde_json::Value;
+
+use crate::sync::{AsyncCommandRunner, CommandOutput};
+
+use super::{Janitor, JanitorError, ResourceType, ScwResource};
+
+impl ResourceType {
+    /// Returns the `scw` subcommand path used to list and delete the resource.
+    const fn subcommand_path(self) -> &'static [&'static str] {
+        match self {
+            Self::Servers => &["instance", "server"],
+            Self::Ips => &["instance", "ip"],
+            Self::Images => &["instance", "image"],
+            Self::Snapshots => &["block", "snapshot"],
+            Self::Volumes => &["block", "volume"],
+            Self::SecurityGroups => &["instance", "security-group"],
+        }
+    }
+
+    /// Returns extra arguments appended to the delete command.
+    const fn delete_flags(self) -> &'static [&'static str] {
+        match self {
+            // Persistent cache volumes are user-managed; never delete them
+            // alongside the server, and wait so dependent deletes succeed.
+            Self::Servers => &[
+                "with-ip=true",
+                "with-volumes=none",
+                "force-shutdown=true",
+                "--wait",
+            ],
+            // Snapshots are swept separately so they are reported per
+            // resource rather than disappearing with their image.
+            Self::Images => &["with-snapshots=false"],
+            Self::Ips | Self::Snapshots | Self::Volumes | Self::SecurityGroups => &[],
+        }
+    }
+}
+
+impl<R: AsyncCommandRunner> Janitor<R> {
+    pub(super) async fn list_tagged(
+        &self,
+        resource: ResourceType,
+        tag: &str,
+    ) -> Result<Vec<ScwResource>, JanitorError> {
+        Ok(self
+            .list_scw_resources(resource)
+            .await?
+            .into_iter()
+            .filter(|item| item.tags.iter().any(|existing| existing == tag))
+            .collect())
+    }
+
+    pub(super) async fn delete_resource(
+        &self,
+        resource: ResourceType,
+        item: &ScwResource,
+    ) -> Result<CommandOutput, JanitorError> {
+        let zone_arg = format!("zone={}", item.zone);
+        let subcommand_path = resource.subcommand_path();
+        let flags = resource.delete_flags();
+
+        // Capacity: `subcommand_path` + ["delete", id, zone_arg] + `flags`.
+        let mut args = Vec::with_capacity(subcommand_path.len() + 3 + flags.len());
+        args.extend_from_slice(subcommand_path);
+        args.extend_from_slice(&["delete", item.id.as_str(), zone_arg.as_str()]);
+        args.extend_from_slice(flags);
+        self.run_scw(&args, resource).await
+    }
+
+    async fn run_scw(
+        &self,
+        args: &[&str],
+        resource: ResourceType,
+    ) -> Result<CommandOutput, JanitorError> {
+        let os_args = args.iter().map(OsString::from).collect::<Vec<_>>();
+        let output = self.runner.run(&self.config.scw_bin, &os_args).await?;
+
+        if output.is_success() {
+            return Ok(output);
+        }
+
+        let status_text = output
+            .code
+            .map_or_else(|| String::from("unknown"), |code| code.to_string());
+        Err(JanitorError::CommandFailure {
+            program: self.config.scw_bin.clone(),
+            status: output.code,
+            status_text,
+            stderr: format!("{}: {}", resource.as_str(), output.stderr),
+        })
+    }
+
+    fn parse_scw_list(
+        stdout: &str,
+        resource: ResourceType,
+    ) -> Result<Vec<ScwResource>, JanitorError> {
+        let resource_name = resource.as_str();
+        let payload = serde_json::from_str::<Value>(stdout).map_err(|err| JanitorError::Parse {
+            resource: resource_name.to_owned(),
+            message: err.to_string(),
+        })?;
+
+        let items = Self::unwrap_scw_list_items(payload, resource_name)?;
+
+        serde_json::from_value::<Vec<ScwResource>>(items).map_err(|err| JanitorError::Parse {
+            resource: resource_name.to_owned(),
+            message: err.to_string(),
+        })
+    }
+
+    /// Lists Scaleway resources of a specific type using the scw CLI.
+    async fn list_scw_resources(
+        &self,
+        resource: ResourceType,
+    ) -> Result<Vec<ScwResource>, JanitorError> {
+        let project_arg = format!("project-id={}", self.config.project_id);
+        let subcommand_path = resource.subcommand_path();
+
+        // Capacity: `subcommand_path` + ["list", project_arg, "zone=all", "-o", "json"].
+        let mut args = Vec::with_capacity(subcommand_path.len() + 5);
+        args.extend_from_slice(subcommand_path);
+        args.extend_from_slice(&["list", project_arg.as_str(), "zone=all", "-o", "json"]);
+
+        let stdout = self.run_scw(&args, resource).await?.stdout;
+        Self::parse_scw_list(&stdout, resource)
+    }
+
+    fn unwrap_scw_list_items(payload: Value, resource_name: &str) -> Result<Value, JanitorError> {
+        match payload {
+            Value::Array(items) => Ok(Value::Array(items)),
+            Value::Object(map) => Self::extract_scw_list_field(map, resource_name),
+            other => Err(JanitorError::Parse {
+                resource: resource_name.to_owned(),
+                message: format!("unexpected JSON shape: {other}"),
+            }),
+        }
+    }
+
+    fn extract_scw_list_field(
+        mut map: serde_json::Map<String, Value>,
+        resource_name: &str,
+    ) -> Result<Value, JanitorError> {
+        map.remove(resource_name)
+            .ok_or_else(|| JanitorError::Parse {
+                resource: resource_name.to_owned(),
+                message: format!("missing '{resource_name}' field"),
+            })
+    }
+}
@@ -1,9 +1,23 @@
 //! Unit tests for the janitor module.
 
 use super::*;
-use crate::test_support::{ScriptedRunner, json_servers, json_volumes};
+use crate::test_support::{ScriptedRunner, json_resources, json_servers, json_volumes};
 use rstest::rstest;
 
+/// Resource types swept between servers and volumes.
+const BETWEEN_SERVERS_AND_VOLUMES: [ResourceType; 3] = [
+    ResourceType::Ips,
+    ResourceType::Images,
+    ResourceType::Snapshots,
+];
+
+/// Queues an empty `scw ... list` response for each resource type.
+fn push_empty_lists(runner: &ScriptedRunner, resources: &[ResourceType]) {
+    for resource in resources {
+        runner.push_output(Some(0), json_resources(resource.as_str(), &[]), "");
+    }
+}
+
 #[rstest]
 fn janitor_config_builds_test_run_tag() {
     let cfg = JanitorConfig::new("proj", "abc", DEFAULT_SCW_BIN).expect("config should build");
@@ -31,7 +45,8 @@ fn janitor_config_rejects_blank_fields(
 }
 
 #[rstest]
-fn sweep_deletes_only_tagged_resources() {
+#[tokio::test]
+async fn sweep_deletes_only_tagged_resources() {
     let cfg = JanitorConfig::new("project", "run-1", DEFAULT_SCW_BIN).expect("config");
     let runner = ScriptedRunner::new();
 
@@ -50,6 +65,7 @@ fn sweep_deletes_only_tagged_resources() {
     );
     // delete server srv-a
     runner.push_success();
+    push_empty_lists(&runner, &BETWEEN_SERVERS_AND_VOLUMES);
     // list volumes (pre)
     runner.push_output(
         Some(0),
@@ -61,24 +77,28 @@ fn sweep_deletes_only_tagged_resources() {
     );
     // delete volume vol-a
     runner.push_success();
+    push_empty_lists(&runner, &[ResourceType::SecurityGroups]);
     // list servers (post)
     runner.push_output(
         Some(0),
         json_servers(&[("srv-b", "fr-par-1", &["mriya", "ephemeral"])]),
         "",
     );
+    push_empty_lists(&runner, &BETWEEN_SERVERS_AND_VOLUMES);
     // list volumes (post)
     runner.push_output(Some(0), json_volumes(&[("vol-b", "fr-par-1", &[])]), "");
+    push_empty_lists(&runner, &[ResourceType::SecurityGroups]);
 
     let janitor = Janitor::new(cfg, runner.clone());
-    let summary = janitor.sweep().expect("sweep should succeed");
-    assert_eq!(
-        summary,
-        SweepSummary {
-            deleted_servers: 1,
-            deleted_volumes: 1
-        }
-    );
+    let summary = janitor.sweep().await.expect("sweep should succeed");
+    assert_eq!(summary.deleted_servers(), 1);
+    assert_eq!(summary.deleted_volumes(), 1);
+    let labels = summary
+        .resources
+        .iter()
+        .map(SweptResource::label)
+        .collect::<Vec<_>>();
+    assert_eq!(labels, ["srv-a@fr-par-1", "vol-a@fr-par-1"]);
 
     let invocations = runner.invocations();
     let delete_calls = invocations
@@ -97,7 +117,8 @@ fn sweep_deletes_only_tagged_resources() {
 }
 
 #[rstest]
-fn sweep_errors_when_tagged_resources_remain() {
+#[tokio::test]
+async fn sweep_errors_when_tagged_resources_remain() {
     let cfg = JanitorConfig::new("project", "run-1", DEFAULT_SCW_BIN).expect("config");
     let runner = ScriptedRunner::new();
 
@@ -113,8 +134,10 @@ fn sweep_errors_when_tagged_resources_remain() {
     );
     // delete server srv-a fails to remove (but command succeeds)
     runner.push_success();
+    push_empty_lists(&runner, &BETWEEN_SERVERS_AND_VOLUMES);
     // list volumes (pre)
     runner.push_output(Some(0), json_volumes(&[]), "");
+    push_empty_lists(&runner, &[ResourceType::SecurityGroups]);
     // list servers (post): still present
     runner.push_output(
         Some(0),
@@ -125,12 +148,14 @@ fn sweep_errors_when_tagged_resources_remain() {
         )]),
         "",
     );
+    push_empty_lists(&runner, &BETWEEN_SERVERS_AND_VOLUMES);
     // list volumes (post)
     runner.push_output(Some(0), json_volumes(&[]), "");
+    push_empty_lists(&runner, &[ResourceType::SecurityGroups]);
 
     let janitor = Janitor::new(cfg, runner);
-    let err = janitor.sweep().expect_err("sweep should fail");
-    let JanitorError::NotClean { message } = err else {
+    let err = janitor.sweep().await.expect_err("sweep should fail");
+    let JanitorError::NotClean { message, .. } = err else {
         panic!("expected NotClean, got {err:?}");
     };
     assert!(
@@ -140,14 +165,15 @@ fn sweep_errors_when_tagged_resources_remain() {
 }
 
 #[rstest]
-fn sweep_surfaces_scw_command_failures() {
+#[tokio::test]
+async fn sweep_surfaces_scw_command_failures() {
     let cfg = JanitorConfig::new("project", "run-1", DEFAULT_SCW_BIN).expect("config");
     let runner = ScriptedRunner::new();
 
     runner.push_output(Some(2), "", "permission denied");
 
     let janitor = Janitor::new(cfg, runner);
-    let err = janitor.sweep().expect_err("sweep should fail");
+    let err = janitor.sweep().await.expect_err("sweep should fail");
     assert!(matches!(err, JanitorError::CommandFailure { .. }));
 }
 
@@ -164,7 +190,8 @@ fn sweep_surfaces_scw_command_failures() {
     None
 )]
 #[case("unexpected JSON shape", "true", Some("unexpected JSON shape"))]
-fn sweep_surfaces_parse_failures(
+#[tokio::test]
+async fn sweep_surfaces_parse_failures(
     #[case] scenario: &str,
     #[case] json_output: &str,
     #[case] expected_message_fragment: Option<&str>,
@@ -177,6 +204,7 @@ fn sweep_surfaces_parse_failures(
     let janitor = Janitor::new(cfg, runner);
     let err = janitor
         .sweep()
+        .await
         .expect_err(&format!("sweep should fail for {scenario}"));
 
     let JanitorError::Parse { message, .. } = err else {
@@ -191,13 +219,222 @@ fn sweep_surfaces_parse_failures(
 }
 
 #[rstest]
-fn sweep_surfaces_runner_failures() {
+#[tokio::test]
+async fn sweep_surfaces_runner_failures() {
     let cfg = JanitorConfig::new("project", "run-1", DEFAULT_SCW_BIN).expect("config");
     // ScriptedRunner with no outputs will return an error when sweep attempts to run a command,
     // simulating a runner-level failure.
     let runner = ScriptedRunner::new();
 
     let janitor = Janitor::new(cfg, runner);
-    let err = janitor.sweep().expect_err("sweep should fail");
+    let err = janitor.sweep().await.expect_err("sweep should fail");
     assert!(matches!(err, JanitorError::Runner(_)));
 }
+
+#[rstest]
+#[tokio::test]
+async fn dry_run_lists_without_deleting() {
+    let cfg = JanitorConfig::new("project", "run-1", DEFAULT_SCW_BIN)
+        .expect("config")
+        .with_dry_run(true);
+    let runner = ScriptedRunner::new();
+
+    runner.push_output(
+        Some(0),
+        json_servers(&[(
+            "srv-a",
+            "fr-par-1",
+            &["mriya-test-run-run-1", "mriya-run-abc123"],
+        )]),
+        "",
+    );
+    push_empty_lists(&runner, &BETWEEN_SERVERS_AND_VOLUMES);
+    runner.push_output(
+        Some(0),
+        json_volumes(&[("vol-a", "nl-ams-1", &["mriya-test-run-run-1"])]),
+        "",
+    );
+    push_empty_lists(&runner, &[ResourceType::SecurityGroups]);
+
+    let janitor = Janitor::new(cfg, runner.clone());
+    let summary = janitor.sweep().await.expect("dry run should succeed");
+
+    assert!(summary.dry_run);
+    assert_eq!(
+        summary.count(ResourceType::Servers, SweepAction::WouldDelete),
+        1
+    );
+    assert_eq!(
+        summary.count(ResourceType::Volumes, SweepAction::WouldDelete),
+        1
+    );
+    assert_eq!(summary.deleted_servers(), 0);
+    let run_ids = summary
+        .resources
+        .iter()
+        .map(|res| res.run_id.as_deref())
+        .collect::<Vec<_>>();
+    assert_eq!(run_ids, [Some("abc123"), None]);
+    assert_eq!(
+        runner.invocations().len(),
+        6,
+        "dry run should only list resources"
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn keep_going_attempts_every_delete_and_reports_failures() {
+    let cfg = JanitorConfig::new("project", "run-1", DEFAULT_SCW_BIN)
+        .expect("config")
+        .with_keep_going(true);
+    let runner = ScriptedRunner::new();
+
+    runner.push_output(
+        Some(0),
+        json_servers(&[
+            ("srv-a", "fr-par-1", &["mriya-test-run-run-1"]),
+            ("srv-b", "fr-par-1", &["mriya-test-run-run-1"]),
+        ]),
+        "",
+    );
+    // delete srv-a fails, delete srv-b succeeds
+    runner.push_output(Some(1), "", "server is locked");
+    runner.push_success();
+    push_empty_lists(&runner, &BETWEEN_SERVERS_AND_VOLUMES);
+    runner.push_output(
+        Some(0),
+        json_volumes(&[("vol-a", "fr-par-1", &["mriya-test-run-run-1"])]),
+        "",
+    );
+    runner.push_success();
+    push_empty_lists(&runner, &[ResourceType::SecurityGroups]);
+    // post: the locked server remains
+    runner.push_output(
+        Some(0),
+        json_servers(&[("srv-a", "fr-par-1", &["mriya-test-run-run-1"])]),
+        "",
+    );
+    push_empty_lists(&runner, &BETWEEN_SERVERS_AND_VOLUMES);
+    runner.push_output(Some(0), json_volumes(&[]), "");
+    push_empty_lists(&runner, &[ResourceType::SecurityGroups]);
+
+    let janitor = Janitor::new(cfg, runner);
+    let err = janitor
+        .sweep()
+        .await
+        .expect_err("sweep should report failures");
+    let JanitorError::NotClean { message, report } = err else {
+        panic!("expected NotClean, got {err:?}");
+    };
+    assert!(
+        message.contains("failed deletions: 1") && message.contains("server is locked"),
+        "expected failure details, got: {message}"
+    );
+    assert_eq!(report.deleted_servers(), 1);
+    assert_eq!(report.deleted_volumes(), 1);
+    let failures = report
+        .failures()
+        .map(SweptResource::label)
+        .collect::<Vec<_>>();
+    assert_eq!(failures, ["srv-a@fr-par-1"]);
+}
+
+#[rstest]
+fn summary_serializes_per_resource_results() {
+    let summary = SweepSummary {
+        dry_run: false,
+        resources: vec![
+            SweptResource {
+                id: String::from("srv-a"),
+                zone: String::from("fr-par-1"),
+                resource_type: ResourceType::Servers,
+                run_id: Some(String::from("abc123")),
+                action: SweepAction::Deleted,
+                error: None,
+            },
+            SweptResource {
+                id: String::from("vol-a"),
+                zone: String::from("fr-par-1"),
+                resource_type: ResourceType::Volumes,
+                run_id: None,
+                action: SweepAction::Failed,
+                error: Some(String::from("boom")),
+            },
+        ],
+    };
+
+    let json = serde_json::to_value(&summary).expect("serialize summary");
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "dry_run": false,
+            "resources": [
+                {
+                    "id": "srv-a",
+                    "zone": "fr-par-1",
+                    "type": "servers",
+                    "run_id": "abc123",
+                    "action": "deleted"
+                },
+                {
+                    "id": "vol-a",
+                    "zone": "fr-par-1",
+                    "type": "volumes",
+                    "action": "failed",
+                    "error": "boom"
+                }
+            ]
+        })
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn sweep_deletes_every_resource_type_in_dependency_order() {
+    let cfg = JanitorConfig::new("project", "run-1", DEFAULT_SCW_BIN).expect("config");
+    let runner = ScriptedRunner::new();
+
+    for resource in SWEEP_ORDER {
+        let id = format!("{}-a", resource.as_str());
+        runner.push_output(
+            Some(0),
+            json_resources(
+                resource.as_str(),
+                &[(id.as_str(), "fr-par-1", &["mriya-test-run-run-1"])],
+            ),
+            "",
+        );
+        runner.push_success();
+    }
+    push_empty_lists(&runner, &SWEEP_ORDER);
+
+    let janitor = Janitor::new(cfg, runner.clone());
+    let summary = janitor.sweep().await.expect("sweep should succeed");
+    assert_eq!(summary.resources.len(), SWEEP_ORDER.len());
+
+    let deleted = runner
+        .invocations()
+        .iter()
+        .filter(|call| call.args.iter().any(|arg| arg == "delete"))
+        .map(|call| {
+            call.args
+                .iter()
+                .take(2)
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        deleted,
+        [
+            "instance server",
+            "instance ip",
+            "instance image",
+            "block snapshot",
+            "block volume",
+            "instance security-group",
+        ]
+    );
+}
@@ -2,20 +2,27 @@
 
 use serde::Deserialize;
 
-/// Scaleway instance server representation returned by `scw instance server list`.
+/// Scaleway resource representation returned by `scw ... list -o json`.
+///
+/// Every resource type the janitor sweeps exposes the same identifying
+/// fields, so a single shape covers servers, volumes, and their siblings.
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
-pub struct ScwServer {
+pub struct ScwResource {
     pub(super) id: String,
     pub(super) zone: String,
     #[serde(default)]
     pub(super) tags: Vec<String>,
 }
 
-/// Scaleway Block Storage volume representation returned by `scw block volume list`.
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
-pub struct ScwVolume {
-    pub(super) id: String,
-    pub(super) zone: String,
-    #[serde(default)]
-    pub(super) tags: Vec<String>,
+impl ScwResource {
+    /// Renders the resource as `<id>@<zone>` for human-readable messages.
+    pub(super) fn label(&self) -> String {
+        format!("{}@{}", self.id, self.zone)
+    }
 }
+
+/// Scaleway instance server representation returned by `scw instance server list`.
+pub type ScwServer = ScwResource;
+
+/// Scaleway Block Storage volume representation returned by `scw block volume list`.
+pub type ScwVolume = ScwResource;
@@ -8,29 +8,50 @@ pub mod backend;
 pub mod cloud_init;
 pub mod config;
 pub mod config_store;
+pub mod hooks;
 pub mod init;
 pub mod janitor;
+pub mod matrix;
+pub mod parallel;
+pub mod pool;
+pub mod provenance;
+pub mod retry;
 pub mod run;
+pub mod run_config;
 pub mod scaleway;
+pub mod session;
 pub mod sync;
 #[cfg(test)]
 pub mod test_helpers;
 pub mod test_support;
 pub mod volume;
+pub mod watch;
 
 pub use backend::{
     Backend, InstanceHandle, InstanceNetworking, InstanceRequest, InstanceRequestBuilder,
+    ProvisionedInstance, TransientError,
 };
 pub use config::ScalewayConfig;
 pub use config_store::{ConfigStore, ConfigStoreError, ConfigWriter};
+pub use hooks::{HookFailure, Hooks};
 pub use init::{InitConfig, InitError, InitOrchestrator, InitOutcome, InitRequest};
 pub use janitor::{
-    Janitor, JanitorConfig, JanitorError, SweepSummary, TEST_RUN_ID_ENV, TEST_RUN_TAG_PREFIX,
+    Janitor, JanitorConfig, JanitorError, ResourceType, SweepAction, SweepSummary, SweptResource,
+    TEST_RUN_ID_ENV, TEST_RUN_TAG_PREFIX,
 };
+pub use matrix::{MatrixCell, MatrixConfig, MatrixError};
+pub use parallel::{ParallelRun, RunOutcome, aggregate_exit_code, run_parallel};
+pub use pool::{PoolError, PoolManager, PoolStore};
+pub use provenance::RunProvenance;
+pub use retry::{RetryNotice, RetryPolicy, RetryReporter};
 pub use run::{RunError, RunOrchestrator};
+pub use run_config::{RunConfig, RunConfigError};
 pub use scaleway::{ScalewayBackend, ScalewayBackendError};
+pub use session::{Session, SessionError, SessionStore};
 pub use sync::{
-    CommandOutput, DEFAULT_REMOTE_PATH, ProcessCommandRunner, RemoteCommandOutput,
-    StreamingCommandRunner, SyncConfig, SyncConfigLoadError, SyncDestination, SyncError, Syncer,
+    Capture, CommandOutput, DEFAULT_REMOTE_PATH, OutputSink, OutputStream, PrefixedCommandRunner,
+    ProcessCommandRunner, Redactor, RemoteCommandOutput, RemoteEnv, StreamingCommandRunner,
+    SyncConfig, SyncConfigLoadError, SyncDestination, SyncError, SyncMode, SyncStats, Syncer,
 };
 pub use volume::{VolumeBackend, VolumeHandle, VolumeRequest};
+pub use watch::{WatchError, WatchLoop, WatchRun, WorkspaceFilter, watch_workspace};
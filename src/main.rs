@@ -3,12 +3,16 @@
 //! This binary provisions a short-lived Scaleway instance, synchronizes the
 //! local workspace via `rsync`, executes a user-supplied command over SSH, and
 //! tears the instance down. The `run` subcommand preserves remote exit codes
-//! locally and reports errors on stderr with meaningful exit statuses.
+//! locally and reports errors on stderr with meaningful exit statuses, and the
+//! `session` and `watch` subcommands keep one instance alive across several
+//! commands. `sync --dry-run` reports what a sync would send without
+//! provisioning anything.
 
 #[cfg(any(test, feature = "test-backdoors"))]
 use std::env;
-use std::io::{self, Write};
+use std::io::{self, IsTerminal, Write};
 use std::process;
+use std::sync::Arc;
 #[cfg(test)]
 use std::{future::Future, pin::Pin};
 #[cfg(test)]
@@ -20,13 +24,28 @@ use shell_escape::unix::escape;
 use thiserror::Error;
 
 mod cli;
+mod exit_code;
+mod fan_out;
+mod pool_command;
+mod session_command;
+mod sync_command;
+mod watch_command;
 
 use cli::{Cli, InitCommand, RunCommand};
+use exit_code::{Failure, exit_code_for, parse_error_exit_code};
+use fan_out::{FanOut, FanOutPlan, run_fan_out};
+use mriya::sync::script_command;
 use mriya::{
-    ConfigStore, InitConfig, InitError, InitOrchestrator, InitRequest, InstanceRequest, RunError,
-    RunOrchestrator, ScalewayBackend, ScalewayBackendError, ScalewayConfig, StreamingCommandRunner,
-    SyncConfig, Syncer,
+    ConfigStore, Hooks, InitConfig, InitError, InitOrchestrator, InitRequest, InstanceRequest,
+    MatrixConfig, MatrixError, PoolError, Redactor, RemoteEnv, RetryNotice, RetryPolicy, RunConfig,
+    RunError, RunOrchestrator, RunProvenance, ScalewayBackend, ScalewayBackendError,
+    ScalewayConfig, SessionError, StreamingCommandRunner, SyncConfig, SyncConfigLoadError, Syncer,
+    WatchError,
 };
+use pool_command::{exec_pool, execute_pooled};
+use session_command::exec_session;
+use sync_command::{Preflight, exec_sync, preflight};
+use watch_command::exec_watch;
 
 #[cfg(test)]
 mod main_tests;
@@ -41,6 +60,15 @@ enum CliError {
     Sync(String),
     #[error("remote command terminated without an exit status")]
     MissingExitCode,
+    #[error("{failed} of {total} parallel runs failed")]
+    RunsFailed {
+        failed: usize,
+        total: usize,
+        /// Step at which every failed run failed, when they agree.
+        cause: Option<Failure>,
+    },
+    #[error("matrix error: {0}")]
+    Matrix(#[from] MatrixError),
     #[error("remote run failed: {0}")]
     Run(#[from] RunError<ScalewayBackendError>),
     #[error("invalid command argument: {0}")]
@@ -52,11 +80,50 @@ enum CliError {
     },
     #[error("invalid cloud-init configuration: {0}")]
     InvalidCloudInit(String),
+    #[error("failed to read script {path}: {message}")]
+    Script { path: String, message: String },
+    #[error("pool error: {0}")]
+    Pool(#[from] PoolError),
+    #[error("session error: {0}")]
+    Session(#[from] SessionError),
+    #[error("watch error: {0}")]
+    Watch(#[from] WatchError),
     #[error("init failed: {0}")]
     Init(#[from] InitError<ScalewayBackendError>),
 }
 
+impl CliError {
+    /// Masks the secrets of `redactor` in the error's message.
+    fn redact(self, redactor: &Redactor) -> Self {
+        match self {
+            Self::Config(message) => Self::Config(redactor.redact(&message)),
+            Self::Backend(message) => Self::Backend(redactor.redact(&message)),
+            Self::Sync(message) => Self::Sync(redactor.redact(&message)),
+            Self::Run(err) => Self::Run(err.redact(redactor)),
+            Self::Pool(err) => Self::Pool(err.redact(redactor)),
+            Self::Session(err) => Self::Session(err.redact(redactor)),
+            Self::Watch(err) => Self::Watch(err.redact(redactor)),
+            Self::Init(err) => Self::Init(err.redact(redactor)),
+            Self::InvalidOverride { field, message } => Self::InvalidOverride {
+                field,
+                message: redactor.redact(&message),
+            },
+            Self::Script { path, message } => Self::Script {
+                path,
+                message: redactor.redact(&message),
+            },
+            Self::InvalidCommand(message) => Self::InvalidCommand(redactor.redact(&message)),
+            Self::InvalidCloudInit(message) => Self::InvalidCloudInit(redactor.redact(&message)),
+            other @ (Self::MissingExitCode | Self::RunsFailed { .. } | Self::Matrix(_)) => other,
+        }
+    }
+}
+
 fn main() {
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(err) => process::exit(parse_error_exit_code(&err)),
+    };
     // Build the runtime explicitly so runtime construction errors are
     // reported through the usual error path instead of panicking inside the
     // `#[tokio::main]` expansion.
@@ -64,7 +131,7 @@ fn main() {
         .enable_all()
         .build()
     {
-        Ok(runtime) => runtime.block_on(async_main()),
+        Ok(runtime) => runtime.block_on(async_main(cli)),
         Err(err) => {
             writeln!(io::stderr(), "failed to start async runtime: {err}").ok();
             1
@@ -74,18 +141,36 @@ fn main() {
     process::exit(exit_code);
 }
 
-async fn async_main() -> i32 {
-    let cli = Cli::parse();
+async fn async_main(cli: Cli) -> i32 {
+    let infra_exit_code = match &cli {
+        Cli::Run(command) => command.infra_exit_code,
+        _ => None,
+    };
     match cli {
         Cli::Run(command) => exec_run(command).await,
         Cli::Init(command) => exec_init(command).await,
+        Cli::Pool(command) => exec_pool(command).await,
+        Cli::Session(command) => exec_session(command).await,
+        Cli::Sync(command) => exec_sync(&command).await,
+        Cli::Watch(command) => exec_watch(command).await,
     }
+    // `mriya run` has already masked its forwarded secrets; every subcommand
+    // masks the Scaleway secret key here.
+    .map_err(|err| err.redact(&config_redactor()))
     .unwrap_or_else(|err| {
         report_error(&err);
-        1
+        exit_code_for(&err, infra_exit_code)
     })
 }
 
+/// Masks the configured Scaleway secret key, or nothing when configuration
+/// cannot be loaded.
+fn config_redactor() -> Redactor {
+    ScalewayConfig::load_without_cli_args()
+        .map(|config| Redactor::new([config.secret_key]))
+        .unwrap_or_default()
+}
+
 async fn exec_run(command: RunCommand) -> Result<i32, CliError> {
     #[cfg(test)]
     if let Some(hook) = RUN_COMMAND_HOOK.lock().await.as_ref() {
@@ -113,23 +198,127 @@ async fn run_command(args: RunCommand) -> Result<i32, CliError> {
         }
     }
 
+    let run_config =
+        RunConfig::load_without_cli_args().map_err(|err| CliError::Config(err.to_string()))?;
+    let env = run_config
+        .remote_env(&args.env)
+        .map_err(|err| CliError::Config(err.to_string()))?;
     let (backend, request) = build_backend_and_request(&args)?;
+    let redactor = Redactor::new(
+        env.secret_values(&run_config.secret_env)
+            .into_iter()
+            .chain(backend.secret_values()),
+    );
+    let setup = RunSetup {
+        backend,
+        request,
+        env,
+        redactor: redactor.clone(),
+        hooks: run_config.remote_hooks(),
+        retry: run_config.retry_policy(),
+    };
+    run_between_local_hooks(&args, setup, &run_config.local_hooks())
+        .await
+        .map_err(|err| err.redact(&redactor))
+}
 
-    let sync_config =
-        SyncConfig::load_without_cli_args().map_err(|err| CliError::Config(err.to_string()))?;
-    let syncer = Syncer::new(sync_config, StreamingCommandRunner)
-        .map_err(|err| CliError::Sync(err.to_string()))?;
+/// Resolved inputs of `mriya run`.
+struct RunSetup {
+    backend: ScalewayBackend,
+    request: InstanceRequest,
+    env: RemoteEnv,
+    redactor: Redactor,
+    hooks: Hooks,
+    retry: RetryPolicy,
+}
 
-    let cwd = std::env::current_dir().map_err(|err| CliError::Config(err.to_string()))?;
-    let source = Utf8PathBuf::from_path_buf(cwd)
-        .map_err(|path| CliError::Config(path.display().to_string()))?;
+/// Runs the local `before` hooks, then the run, then the local `after`
+/// hooks whatever the outcome, including a failed `before` hook. The first
+/// error wins over a failed `after` hook.
+async fn run_between_local_hooks(
+    args: &RunCommand,
+    setup: RunSetup,
+    hooks: &Hooks,
+) -> Result<i32, CliError> {
+    let runner = StreamingCommandRunner::default().with_redactor(setup.redactor.clone());
+    let result = match hooks.run_local_before(&runner).await {
+        Ok(()) => execute_run(args, setup).await,
+        Err(failure) => Err(RunError::<ScalewayBackendError>::LocalBeforeHook(failure).into()),
+    };
+    let after = hooks
+        .run_local_after(&runner)
+        .await
+        .map_err(RunError::<ScalewayBackendError>::LocalAfterHook);
+    let code = result?;
+    after?;
+    Ok(code)
+}
+
+async fn execute_run(args: &RunCommand, setup: RunSetup) -> Result<i32, CliError> {
+    let mut sync_config =
+        SyncConfig::load_without_cli_args().map_err(|err| CliError::Config(err.to_string()))?;
+    apply_sync_mode(&mut sync_config, args.sync_mode.as_deref())?;
+    let source = current_dir()?;
 
-    let orchestrator = RunOrchestrator::new(backend, syncer);
     validate_command_args(&args.command)?;
-    let remote_command = render_remote_command(&args.command);
-    let output = orchestrator
-        .execute(&request, &source, &remote_command)
-        .await?;
+    let script = read_script(args.script.as_deref())?;
+    let remote_command = if script.is_some() {
+        script_command(&render_remote_command(&args.command))
+    } else {
+        render_remote_command(&args.command)
+    };
+    let runner = StreamingCommandRunner::default().with_redactor(setup.redactor.clone());
+    let mut syncer = Syncer::new(sync_config, runner)
+        .map_err(|err| CliError::Sync(err.to_string()))?
+        .with_env(setup.env.clone());
+    if let Some(contents) = &script {
+        syncer = syncer.with_script(contents.clone());
+    }
+    preflight(
+        &syncer,
+        Preflight {
+            sync_stats: args.sync_stats,
+            strict_secrets: args.strict_secrets,
+        },
+    )
+    .await?;
+    let backend = setup
+        .backend
+        .with_provenance(RunProvenance::detect(&source));
+    if let Some(fan_out) = fan_out_for(args)? {
+        let plan = FanOutPlan {
+            backend,
+            request: setup.request,
+            sync_config: syncer.config().clone(),
+            env: setup.env,
+            redactor: setup.redactor,
+            hooks: setup.hooks,
+            retry: setup.retry,
+            script,
+            remote_command,
+        };
+        return run_fan_out(plan, fan_out, &source).await;
+    }
+
+    let tty = wants_tty(args, &setup.redactor);
+    if tty && !setup.redactor.is_empty() {
+        writeln!(
+            io::stderr(),
+            "warning: --tty output goes straight to the terminal, so secrets in it are not masked"
+        )
+        .ok();
+    }
+    let retry = warn_on_retry(setup.retry, String::new(), setup.redactor.clone());
+    let orchestrator = RunOrchestrator::new(backend, syncer.with_tty(tty))
+        .with_hooks(setup.hooks)
+        .with_retry(retry);
+    let output = if args.no_pool {
+        orchestrator
+            .execute(&setup.request, &source, &remote_command)
+            .await?
+    } else {
+        execute_pooled(&orchestrator, &setup.request, &source, &remote_command).await?
+    };
 
     output.exit_code.ok_or(CliError::MissingExitCode)
 }
@@ -142,14 +331,13 @@ async fn init_command(args: InitCommand) -> Result<i32, CliError> {
     let sync_config =
         SyncConfig::load_without_cli_args().map_err(|err| CliError::Config(err.to_string()))?;
 
+    let cwd_path = current_dir()?;
+
     let backend = ScalewayBackend::new(scaleway_config.clone())
-        .map_err(|err| CliError::Backend(err.to_string()))?;
-    let syncer = Syncer::new(sync_config, StreamingCommandRunner)
+        .map_err(|err| CliError::Backend(err.to_string()))?
+        .with_provenance(RunProvenance::detect(&cwd_path));
+    let syncer = Syncer::new(sync_config, StreamingCommandRunner::default())
         .map_err(|err| CliError::Sync(err.to_string()))?;
-
-    let cwd = std::env::current_dir().map_err(|err| CliError::Config(err.to_string()))?;
-    let cwd_path = Utf8PathBuf::from_path_buf(cwd)
-        .map_err(|path| CliError::Config(path.display().to_string()))?;
     let project_name = cwd_path.file_name().unwrap_or("mriya");
 
     let request =
@@ -170,20 +358,71 @@ async fn init_command(args: InitCommand) -> Result<i32, CliError> {
     Ok(0)
 }
 
+/// Reports each retried provisioning attempt on stderr, prefixed with
+/// `prefix` and with the secrets of `redactor` masked.
+fn warn_on_retry(policy: RetryPolicy, prefix: String, redactor: Redactor) -> RetryPolicy {
+    policy.with_reporter(Arc::new(move |notice: &RetryNotice<'_>| {
+        let reason = redactor.redact(&notice.error.to_string());
+        writeln!(
+            io::stderr(),
+            "{prefix}warning: provisioning attempt {} of {} failed, retrying in {}s: {reason}",
+            notice.attempt,
+            notice.attempts,
+            notice.delay.as_secs(),
+        )
+        .ok();
+    }))
+}
+
+/// Resolves `--tty`, defaulting to a pseudo-terminal when both stdin and
+/// stdout are terminals. Terminal output bypasses redaction, so the default
+/// stays off while there are secrets to mask.
+fn wants_tty(args: &RunCommand, redactor: &Redactor) -> bool {
+    args.tty.unwrap_or_else(|| {
+        redactor.is_empty() && io::stdin().is_terminal() && io::stdout().is_terminal()
+    })
+}
+
+fn fan_out_for(args: &RunCommand) -> Result<Option<FanOut>, CliError> {
+    if let Some(cli_cells) = &args.matrix {
+        let config = MatrixConfig::load_without_cli_args()?;
+        let cells = if cli_cells.is_empty() {
+            config.cells
+        } else {
+            cli_cells.clone()
+        };
+        return Ok(Some(FanOut::Matrix {
+            cells,
+            cache_volumes: config.cache_volumes,
+        }));
+    }
+    Ok((args.shards > 1).then(|| FanOut::Shards(usize::from(args.shards))))
+}
+
 fn build_backend_and_request(
     args: &RunCommand,
 ) -> Result<(ScalewayBackend, InstanceRequest), CliError> {
+    let (backend, mut request) = default_backend_and_request()?;
+    apply_instance_overrides(&mut request, args)?;
+    Ok((backend, request))
+}
+
+fn default_backend_and_request() -> Result<(ScalewayBackend, InstanceRequest), CliError> {
     let scaleway_config =
         ScalewayConfig::load_without_cli_args().map_err(|err| CliError::Config(err.to_string()))?;
     let backend =
         ScalewayBackend::new(scaleway_config).map_err(|err| CliError::Backend(err.to_string()))?;
-    let mut request = backend
+    let request = backend
         .default_request()
         .map_err(|err| CliError::Backend(err.to_string()))?;
-    apply_instance_overrides(&mut request, args)?;
     Ok((backend, request))
 }
 
+fn current_dir() -> Result<Utf8PathBuf, CliError> {
+    let cwd = std::env::current_dir().map_err(|err| CliError::Config(err.to_string()))?;
+    Utf8PathBuf::from_path_buf(cwd).map_err(|path| CliError::Config(path.display().to_string()))
+}
+
 fn apply_instance_overrides(
     request: &mut InstanceRequest,
     args: &RunCommand,
@@ -229,6 +468,16 @@ fn resolve_cloud_init_for_run(args: &RunCommand) -> Result<Option<String>, CliEr
     })
 }
 
+/// Applies a `--sync-mode` override to `config`.
+fn apply_sync_mode(config: &mut SyncConfig, mode: Option<&str>) -> Result<(), CliError> {
+    if let Some(name) = mode {
+        config.sync_mode = name
+            .parse()
+            .map_err(|err: SyncConfigLoadError| CliError::Config(err.to_string()))?;
+    }
+    Ok(())
+}
+
 fn parse_override(field: &'static str, value: &str) -> Result<String, CliError> {
     let trimmed = value.trim();
     if trimmed.is_empty() {
@@ -258,6 +507,17 @@ fn render_remote_command(args: &[String]) -> String {
     result
 }
 
+/// Reads the file given to `--script`, if any.
+fn read_script(path: Option<&str>) -> Result<Option<Vec<u8>>, CliError> {
+    path.map(|script| {
+        std::fs::read(script).map_err(|err| CliError::Script {
+            path: script.to_owned(),
+            message: err.to_string(),
+        })
+    })
+    .transpose()
+}
+
 fn validate_command_args(args: &[String]) -> Result<(), CliError> {
     for arg in args {
         if arg
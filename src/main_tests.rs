@@ -4,7 +4,9 @@
 //! and within the repository's file size limits.
 
 use super::*;
+use exit_code::{Failure, INFRA_EXIT_CODES};
 use mriya::test_support::EnvGuard;
+use mriya::{HookFailure, SyncError, SyncMode};
 use rstest::rstest;
 
 async fn dispatch_with_hook<F, Fut>(hook: F) -> Result<i32, CliError>
@@ -18,6 +20,16 @@ where
         image: None,
         cloud_init: None,
         cloud_init_file: None,
+        shards: 1,
+        matrix: None,
+        no_pool: false,
+        sync_mode: None,
+        sync_stats: false,
+        strict_secrets: false,
+        env: Vec::new(),
+        tty: None,
+        script: None,
+        infra_exit_code: None,
         command: vec![String::from("echo")],
     })
     .await;
@@ -74,6 +86,16 @@ async fn run_command_prefail_variants(
         image: None,
         cloud_init: None,
         cloud_init_file: None,
+        shards: 1,
+        matrix: None,
+        no_pool: false,
+        sync_mode: None,
+        sync_stats: false,
+        strict_secrets: false,
+        env: Vec::new(),
+        tty: None,
+        script: None,
+        infra_exit_code: None,
         command: vec![String::from("echo")],
     })
     .await;
@@ -96,6 +118,16 @@ async fn run_command_missing_exit_code_from_fake_mode() {
         image: None,
         cloud_init: None,
         cloud_init_file: None,
+        shards: 1,
+        matrix: None,
+        no_pool: false,
+        sync_mode: None,
+        sync_stats: false,
+        strict_secrets: false,
+        env: Vec::new(),
+        tty: None,
+        script: None,
+        infra_exit_code: None,
         command: vec![String::from("echo")],
     })
     .await;
@@ -150,6 +182,16 @@ fn apply_instance_overrides_updates_request() {
         image: Some(String::from("  ubuntu-22-04  ")),
         cloud_init: None,
         cloud_init_file: None,
+        shards: 1,
+        matrix: None,
+        no_pool: false,
+        sync_mode: None,
+        sync_stats: false,
+        strict_secrets: false,
+        env: Vec::new(),
+        tty: None,
+        script: None,
+        infra_exit_code: None,
         command: vec![String::from("echo"), String::from("ok")],
     };
 
@@ -170,3 +212,370 @@ fn write_error_writes_cli_error() {
         "rendered: {rendered}"
     );
 }
+
+#[rstest]
+#[case(&["mriya", "run", "--matrix", "--", "true"], Some(vec![]))]
+#[case(
+    &["mriya", "run", "--matrix", "arch=arm64", "--matrix", "image=jammy", "--", "true"],
+    Some(vec![String::from("arch=arm64"), String::from("image=jammy")])
+)]
+#[case(&["mriya", "run", "--", "true"], None)]
+fn matrix_flag_collects_cells(#[case] argv: &[&str], #[case] expected: Option<Vec<String>>) {
+    let Cli::Run(args) = Cli::try_parse_from(argv).expect("arguments should parse") else {
+        panic!("expected run subcommand");
+    };
+    assert_eq!(args.matrix, expected);
+    assert_eq!(args.command, vec![String::from("true")]);
+}
+
+#[rstest]
+#[case(&["mriya", "session", "exec", "--", "cargo", "test"], "default")]
+#[case(&["mriya", "session", "exec", "--name", "dev", "--", "cargo", "test"], "dev")]
+fn session_exec_parses_name_and_command(#[case] argv: &[&str], #[case] expected_name: &str) {
+    let Cli::Session(cli::SessionCommand::Exec(args)) =
+        Cli::try_parse_from(argv).expect("arguments should parse")
+    else {
+        panic!("expected session exec subcommand");
+    };
+    assert_eq!(args.name, expected_name);
+    assert_eq!(
+        args.command,
+        vec![String::from("cargo"), String::from("test")]
+    );
+}
+
+#[rstest]
+fn watch_parses_debounce_and_command() {
+    let Cli::Watch(args) = Cli::try_parse_from([
+        "mriya",
+        "watch",
+        "--debounce-ms",
+        "50",
+        "--",
+        "cargo",
+        "test",
+    ])
+    .expect("arguments should parse") else {
+        panic!("expected watch subcommand");
+    };
+    assert_eq!(args.debounce_ms, 50);
+    assert_eq!(
+        args.command,
+        vec![String::from("cargo"), String::from("test")]
+    );
+}
+
+#[rstest]
+#[case("rsync-filter", SyncMode::RsyncFilter)]
+#[case("git-files", SyncMode::GitFiles)]
+#[case("git-commit", SyncMode::GitCommit)]
+#[case("git-fetch", SyncMode::GitFetch)]
+fn sync_mode_flag_parses_into_sync_mode(#[case] flag: &str, #[case] expected: SyncMode) {
+    let Cli::Run(args) = Cli::try_parse_from(["mriya", "run", "--sync-mode", flag, "--", "true"])
+        .expect("arguments should parse")
+    else {
+        panic!("expected run subcommand");
+    };
+    let mode: SyncMode = args
+        .sync_mode
+        .expect("sync mode should be set")
+        .parse()
+        .expect("mode should parse");
+    assert_eq!(mode, expected);
+}
+
+#[rstest]
+fn sync_mode_flag_rejects_unknown_modes() {
+    let result = Cli::try_parse_from(["mriya", "run", "--sync-mode", "tarball", "--", "true"]);
+    assert!(result.is_err(), "unknown sync mode should be rejected");
+}
+
+#[rstest]
+fn script_flag_takes_trailing_arguments_for_the_script() {
+    let Cli::Run(args) = Cli::try_parse_from([
+        "mriya",
+        "run",
+        "--script",
+        "./ci/remote.sh",
+        "--",
+        "--fast",
+        "x",
+    ])
+    .expect("arguments should parse") else {
+        panic!("expected run subcommand");
+    };
+    assert_eq!(args.script.as_deref(), Some("./ci/remote.sh"));
+    assert_eq!(args.command, ["--fast", "x"]);
+}
+
+#[rstest]
+fn run_requires_a_command_or_a_script() {
+    assert!(Cli::try_parse_from(["mriya", "run", "--script", "./ci/remote.sh"]).is_ok());
+    assert!(Cli::try_parse_from(["mriya", "run"]).is_err());
+}
+
+#[rstest]
+fn missing_script_reports_its_path() {
+    let err = read_script(Some("/nonexistent/remote.sh")).expect_err("missing file");
+    assert!(
+        err.to_string()
+            .starts_with("failed to read script /nonexistent/remote.sh:"),
+        "{err}"
+    );
+}
+
+#[rstest]
+fn env_flag_collects_repeated_entries() {
+    let Cli::Run(args) = Cli::try_parse_from([
+        "mriya",
+        "run",
+        "--env",
+        "RUST_LOG=debug",
+        "--env",
+        "TOKEN",
+        "--",
+        "true",
+    ])
+    .expect("arguments should parse") else {
+        panic!("expected run subcommand");
+    };
+    assert_eq!(args.env, ["RUST_LOG=debug", "TOKEN"]);
+}
+
+#[rstest]
+#[case(&["mriya", "run", "--", "true"][..], None)]
+#[case(&["mriya", "run", "--tty", "--", "true"][..], Some(true))]
+#[case(&["mriya", "run", "--tty=false", "--", "true"][..], Some(false))]
+fn tty_flag_parses_into_an_override(#[case] argv: &[&str], #[case] expected: Option<bool>) {
+    let Cli::Run(args) = Cli::try_parse_from(argv).expect("arguments should parse") else {
+        panic!("expected run subcommand");
+    };
+    assert_eq!(args.tty, expected);
+}
+
+#[rstest]
+#[case(&["mriya", "run", "--tty", "--shards", "2", "--", "true"][..])]
+#[case(&["mriya", "run", "--tty", "--matrix", "--", "true"][..])]
+fn tty_flag_conflicts_with_fan_out(#[case] argv: &[&str]) {
+    assert!(
+        Cli::try_parse_from(argv).is_err(),
+        "fan-out runs cannot share a terminal"
+    );
+}
+
+#[rstest]
+fn sync_subcommand_requires_dry_run() {
+    let Cli::Sync(args) =
+        Cli::try_parse_from(["mriya", "sync", "--dry-run", "--sync-mode", "git-files"])
+            .expect("arguments should parse")
+    else {
+        panic!("expected sync subcommand");
+    };
+    assert!(args.dry_run);
+    assert_eq!(args.sync_mode.as_deref(), Some("git-files"));
+
+    let result = Cli::try_parse_from(["mriya", "sync"]);
+    assert!(result.is_err(), "sync without --dry-run should be rejected");
+}
+
+#[rstest]
+fn sync_stats_flag_parses_on_run() {
+    let Cli::Run(args) = Cli::try_parse_from(["mriya", "run", "--sync-stats", "--", "true"])
+        .expect("arguments should parse")
+    else {
+        panic!("expected run subcommand");
+    };
+    assert!(args.sync_stats);
+}
+
+#[rstest]
+#[case(0, "0 B")]
+#[case(1023, "1023 B")]
+#[case(1536, "1.5 KiB")]
+#[case(5 << 20, "5.0 MiB")]
+#[case(2_147_483_648, "2.0 GiB")]
+fn format_bytes_uses_binary_units(#[case] bytes: u64, #[case] expected: &str) {
+    assert_eq!(sync_command::format_bytes(bytes), expected);
+}
+
+#[rstest]
+fn sync_report_lists_largest_files_and_directories() {
+    let stats = mriya::SyncStats::parse(
+        ">f+++++++++ 2,147,483,648 data/big.parquet\n>f+++++++++ 1,024 Cargo.toml\n",
+    );
+    let mut report = Vec::new();
+
+    sync_command::write_report(&mut report, &stats).expect("report should write");
+
+    let text = String::from_utf8(report).expect("report should be UTF-8");
+    assert!(
+        text.starts_with("sync would send 2 files, 2.0 GiB\n"),
+        "{text}"
+    );
+    assert!(
+        text.contains("largest files:\n     2.0 GiB  data/big.parquet\n"),
+        "{text}"
+    );
+    assert!(
+        text.contains("top directories:\n     2.0 GiB  data (1 files)\n"),
+        "{text}"
+    );
+}
+
+#[rstest]
+#[case(&["mriya", "run", "--strict-secrets", "--", "true"][..])]
+#[case(&["mriya", "sync", "--dry-run", "--strict-secrets"][..])]
+#[case(&["mriya", "session", "start", "--strict-secrets"][..])]
+#[case(&["mriya", "watch", "--strict-secrets", "--", "true"][..])]
+fn strict_secrets_flag_is_accepted(#[case] args: &[&str]) {
+    let strict = match Cli::try_parse_from(args).expect("arguments should parse") {
+        Cli::Run(command) => command.strict_secrets,
+        Cli::Sync(command) => command.strict_secrets,
+        Cli::Session(cli::SessionCommand::Start(command)) => command.strict_secrets,
+        Cli::Watch(command) => command.strict_secrets,
+        other => panic!("unexpected subcommand: {other:?}"),
+    };
+    assert!(strict);
+}
+
+#[rstest]
+fn secret_warnings_name_each_file_and_the_allowlist() {
+    let findings = [mriya::sync::SecretFinding {
+        path: mriya::sync::Utf8PathBuf::from(".env"),
+        reason: mriya::sync::SecretMatch::Name(String::from(".env")),
+    }];
+    let mut warnings = Vec::new();
+
+    sync_command::write_secret_warnings(&mut warnings, &findings).expect("warnings should write");
+
+    let text = String::from_utf8(warnings).expect("warnings should be UTF-8");
+    assert!(
+        text.starts_with("warning: .env looks like a secret (name matches `.env`)\n"),
+        "{text}"
+    );
+    assert!(text.contains("[sync] secret_allowlist"), "{text}");
+}
+
+#[rstest]
+fn run_errors_are_redacted_before_reporting() {
+    let redactor = mriya::Redactor::new(["tok-5f3a9c"]);
+    let source = mriya::SyncError::CommandFailure {
+        program: String::from("ssh"),
+        status: Some(255),
+        status_text: String::from("255"),
+        stderr: String::from("bad key tok-5f3a9c"),
+    };
+    let err = CliError::Run(RunError::Remote {
+        message: source.to_string(),
+        source,
+    })
+    .redact(&redactor);
+
+    let mut buffer = Vec::new();
+    write_error(&mut buffer, &err);
+    let text = String::from_utf8(buffer).expect("utf-8 output");
+    assert!(!text.contains("tok-5f3a9c"), "{text}");
+    assert!(text.contains("bad key ***"), "{text}");
+}
+
+#[rstest]
+#[case(CliError::Init(InitError::Format {
+    message: String::from("mkfs saw tok-5f3a9c"),
+    source: None,
+}))]
+#[case(CliError::Session(SessionError::Parse {
+    path: Utf8PathBuf::from("dev.json"),
+    message: String::from("unexpected tok-5f3a9c"),
+}))]
+#[case(CliError::Pool(PoolError::Io {
+    path: Utf8PathBuf::from("pool.json"),
+    message: String::from("denied tok-5f3a9c"),
+}))]
+#[case(CliError::Watch(WatchError::Watch {
+    path: Utf8PathBuf::from("src"),
+    message: String::from("watch tok-5f3a9c"),
+}))]
+fn subcommand_errors_are_redacted(#[case] err: CliError) {
+    let masked = err.redact(&mriya::Redactor::new(["tok-5f3a9c"]));
+
+    let text = masked.to_string();
+    assert!(!text.contains("tok-5f3a9c"), "{text}");
+    assert!(text.contains("***"), "{text}");
+}
+
+fn run_error(kind: &str) -> RunError<ScalewayBackendError> {
+    let sync = || SyncError::Staging {
+        message: String::from("fake"),
+    };
+    let hook = HookFailure {
+        command: String::from("cargo fetch"),
+        reason: String::from("exited with status 1"),
+    };
+    let backend = || ScalewayBackendError::Config(String::from("fake"));
+    match kind {
+        "provision" => RunError::Provision(backend()),
+        "wait" => RunError::Wait {
+            message: String::from("fake"),
+            source: backend(),
+        },
+        "cloud-init" => RunError::ProvisioningTimeout {
+            message: String::from("fake"),
+        },
+        "sync" => RunError::Sync {
+            message: String::from("fake"),
+            source: sync(),
+        },
+        "remote" => RunError::Remote {
+            message: String::from("fake"),
+            source: sync(),
+        },
+        "local-hook" => RunError::LocalAfterHook(hook),
+        _ => RunError::Teardown {
+            message: String::from("fake"),
+            source: backend(),
+        },
+    }
+}
+
+#[rstest]
+#[case("provision", 241)]
+#[case("wait", 242)]
+#[case("cloud-init", 243)]
+#[case("sync", 244)]
+#[case("remote", 245)]
+#[case("local-hook", 246)]
+#[case("teardown", 247)]
+fn run_errors_exit_with_their_step_code(#[case] kind: &str, #[case] code: i32) {
+    let err = CliError::Run(run_error(kind));
+
+    assert_eq!(exit_code_for(&err, None), code);
+    assert_eq!(exit_code_for(&err, Some(125)), 125);
+}
+
+#[rstest]
+#[case(CliError::InvalidCommand(String::from("tab")), Failure::Config)]
+#[case(CliError::MissingExitCode, Failure::Signal)]
+#[case(CliError::RunsFailed { failed: 1, total: 2, cause: None }, Failure::Other)]
+#[case(
+    CliError::RunsFailed { failed: 2, total: 3, cause: Some(Failure::Provision) },
+    Failure::Provision
+)]
+#[case(
+    CliError::Pool(mriya::PoolError::SharedVolume { volume_id: String::from("vol") }),
+    Failure::Config
+)]
+fn cli_errors_map_onto_failures(#[case] err: CliError, #[case] failure: Failure) {
+    assert_eq!(Failure::of(&err), failure);
+    assert!(INFRA_EXIT_CODES.contains(&failure.exit_code()));
+}
+
+#[rstest]
+#[case(&[Failure::Provision, Failure::Provision], Some(Failure::Provision))]
+#[case(&[Failure::Provision, Failure::Sync], None)]
+#[case(&[], None)]
+fn shared_failure_requires_agreement(
+    #[case] failures: &[Failure],
+    #[case] shared: Option<Failure>,
+) {
+    assert_eq!(exit_code::shared_failure(failures.iter().copied()), shared);
+}
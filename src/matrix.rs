@@ -0,0 +1,371 @@
+//! Run matrices that expand one command across several instance shapes.
+//!
+//! A matrix is a list of cells. Each cell overrides some of `architecture`,
+//! `image_label`, and `instance_type` on the base [`InstanceRequest`], and the
+//! resulting requests run side by side through [`crate::parallel`]. Cells are
+//! written as comma-separated `key=value` pairs, for example
+//! `arch=arm64,image=Ubuntu 24.04 Noble Numbat,type=COPARM1-2C-8G`, both in the
+//! `[matrix]` configuration section and on the command line.
+
+use std::collections::BTreeSet;
+use std::ffi::OsString;
+use std::fmt::Write as _;
+use std::time::Duration;
+
+use ortho_config::OrthoConfig;
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::backend::InstanceRequest;
+use crate::parallel::RunOutcome;
+
+/// Matrix configuration layered via `OrthoConfig`.
+#[derive(Clone, Debug, Default, Deserialize, OrthoConfig, PartialEq, Eq)]
+#[ortho_config(
+    prefix = "MRIYA_MATRIX",
+    discovery(
+        app_name = "mriya",
+        env_var = "MRIYA_CONFIG_PATH",
+        config_file_name = "mriya.toml",
+        dotfile_name = ".mriya.toml",
+        project_file_name = "mriya.toml"
+    )
+)]
+pub struct MatrixConfig {
+    /// Cell specifications run by `mriya run --matrix`.
+    #[serde(default)]
+    pub cells: Vec<String>,
+    /// Cache volume for each architecture, written as `architecture=volume-id`.
+    #[serde(default)]
+    pub cache_volumes: Vec<String>,
+}
+
+impl MatrixConfig {
+    /// Loads matrix configuration without parsing CLI arguments.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MatrixError::Parse`] when merging sources fails.
+    pub fn load_without_cli_args() -> Result<Self, MatrixError> {
+        Self::load_from_iter([OsString::from("mriya")])
+            .map_err(|err| MatrixError::Parse(err.to_string()))
+    }
+}
+
+/// Errors raised while building a run matrix.
+#[derive(Clone, Debug, Error, Eq, PartialEq)]
+pub enum MatrixError {
+    /// Raised when configuration sources cannot be merged.
+    #[error("failed to load matrix configuration: {0}")]
+    Parse(String),
+    /// Raised when no cells are configured or supplied.
+    #[error("matrix has no cells: pass --matrix or add cells to [matrix] in mriya.toml")]
+    Empty,
+    /// Raised when a cell specification cannot be parsed.
+    #[error("invalid matrix cell '{spec}': {message}")]
+    InvalidCell {
+        /// Specification as written by the user.
+        spec: String,
+        /// Reason the specification was rejected.
+        message: String,
+    },
+    /// Raised when a cache volume mapping cannot be parsed.
+    #[error("invalid matrix cache volume '{entry}': expected architecture=volume-id")]
+    InvalidCacheVolume {
+        /// Entry as written by the user.
+        entry: String,
+    },
+}
+
+/// A single cell of a run matrix.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MatrixCell {
+    /// Label used in output prefixes and the summary table.
+    pub label: String,
+    /// Architecture override.
+    pub architecture: Option<String>,
+    /// Image label override.
+    pub image_label: Option<String>,
+    /// Instance type override.
+    pub instance_type: Option<String>,
+}
+
+impl MatrixCell {
+    /// Parses a cell specification of comma-separated `key=value` pairs.
+    ///
+    /// Recognised keys are `arch`, `image`, and `type`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MatrixError::InvalidCell`] for unknown or repeated keys,
+    /// empty values, or a specification without any override.
+    pub fn parse(spec: &str) -> Result<Self, MatrixError> {
+        let invalid = |message: &str| MatrixError::InvalidCell {
+            spec: spec.to_owned(),
+            message: message.to_owned(),
+        };
+        let mut cell = Self {
+            label: spec.trim().to_owned(),
+            ..Self::default()
+        };
+        for pair in spec.split(',').filter(|pair| !pair.trim().is_empty()) {
+            let (key, raw_value) = pair
+                .split_once('=')
+                .ok_or_else(|| invalid("expected key=value pairs"))?;
+            let value = raw_value.trim();
+            if value.is_empty() {
+                return Err(invalid("values must not be empty"));
+            }
+            let slot = match key.trim() {
+                "arch" => &mut cell.architecture,
+                "image" => &mut cell.image_label,
+                "type" => &mut cell.instance_type,
+                _ => return Err(invalid("keys must be one of arch, image, type")),
+            };
+            if slot.replace(value.to_owned()).is_some() {
+                return Err(invalid("keys must not repeat"));
+            }
+        }
+        if cell.architecture.is_none() && cell.image_label.is_none() && cell.instance_type.is_none()
+        {
+            return Err(invalid("at least one of arch, image, type is required"));
+        }
+        Ok(cell)
+    }
+
+    /// Applies the cell overrides to `base`.
+    #[must_use]
+    pub fn apply(&self, base: &InstanceRequest) -> InstanceRequest {
+        let mut request = base.clone();
+        if let Some(architecture) = &self.architecture {
+            request.architecture.clone_from(architecture);
+        }
+        if let Some(image_label) = &self.image_label {
+            request.image_label.clone_from(image_label);
+        }
+        if let Some(instance_type) = &self.instance_type {
+            request.instance_type.clone_from(instance_type);
+        }
+        request
+    }
+}
+
+/// Expands `cells` into one instance request per cell.
+///
+/// Cache volumes are assigned per architecture: a cell uses the volume mapped
+/// to its architecture in `cache_volumes`, falling back to the base request's
+/// volume only when it shares the base architecture. A block volume attaches
+/// to a single instance, so each volume goes to the first cell that claims it
+/// and later cells run without a cache.
+///
+/// # Errors
+///
+/// Returns [`MatrixError::Empty`] when `cells` is empty, and
+/// [`MatrixError::InvalidCell`] or [`MatrixError::InvalidCacheVolume`] when a
+/// specification cannot be parsed.
+pub fn expand(
+    base: &InstanceRequest,
+    cells: &[String],
+    cache_volumes: &[String],
+) -> Result<Vec<(String, InstanceRequest)>, MatrixError> {
+    if cells.is_empty() {
+        return Err(MatrixError::Empty);
+    }
+    let volumes = parse_cache_volumes(cache_volumes)?;
+    let mut claimed = BTreeSet::new();
+    cells
+        .iter()
+        .map(|spec| {
+            let cell = MatrixCell::parse(spec)?;
+            let mut request = cell.apply(base);
+            let candidate = volumes
+                .iter()
+                .find(|(arch, _)| *arch == request.architecture)
+                .map(|(_, volume)| volume.clone())
+                .or_else(|| {
+                    (request.architecture == base.architecture)
+                        .then(|| base.volume_id.clone())
+                        .flatten()
+                });
+            request.volume_id = candidate.filter(|volume| claimed.insert(volume.clone()));
+            Ok((cell.label, request))
+        })
+        .collect()
+}
+
+fn parse_cache_volumes(entries: &[String]) -> Result<Vec<(String, String)>, MatrixError> {
+    entries
+        .iter()
+        .map(|entry| {
+            entry
+                .split_once('=')
+                .map(|(arch, volume)| (arch.trim().to_owned(), volume.trim().to_owned()))
+                .filter(|(arch, volume)| !arch.is_empty() && !volume.is_empty())
+                .ok_or_else(|| MatrixError::InvalidCacheVolume {
+                    entry: entry.clone(),
+                })
+        })
+        .collect()
+}
+
+/// Renders a summary table with the exit code and duration of each run.
+///
+/// Runs that failed before their command completed show `error` in place of
+/// an exit code.
+#[must_use]
+pub fn summary_table<E>(outcomes: &[RunOutcome<E>]) -> String
+where
+    E: std::error::Error + 'static,
+{
+    let width = outcomes
+        .iter()
+        .map(|outcome| outcome.label.len())
+        .chain([CELL_HEADING.len()])
+        .max()
+        .unwrap_or(0);
+    let mut table = format!("{CELL_HEADING:<width$}  exit   duration\n");
+    for outcome in outcomes {
+        let exit = match (&outcome.result, outcome.exit_code()) {
+            (Err(_), _) => String::from("error"),
+            (Ok(_), Some(code)) => code.to_string(),
+            (Ok(_), None) => String::from("-"),
+        };
+        writeln!(
+            table,
+            "{label:<width$}  {exit:<5}  {duration}",
+            label = outcome.label,
+            duration = format_duration(outcome.duration),
+        )
+        .ok();
+    }
+    table
+}
+
+const CELL_HEADING: &str = "cell";
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 60 {
+        format!("{}m{:02}s", secs.div_euclid(60), secs.rem_euclid(60))
+    } else {
+        format!("{:.1}s", duration.as_secs_f64())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Unit tests for matrix expansion and summaries.
+    use super::*;
+    use crate::run::RunError;
+    use crate::sync::RemoteCommandOutput;
+    use rstest::{fixture, rstest};
+
+    #[fixture]
+    fn base() -> InstanceRequest {
+        InstanceRequest::builder()
+            .image_label("Ubuntu 24.04 Noble Numbat")
+            .instance_type("DEV1-S")
+            .zone("fr-par-1")
+            .project_id("project")
+            .architecture("x86_64")
+            .volume_id(Some(String::from("vol-x86")))
+            .build()
+            .expect("base request should be valid")
+    }
+
+    #[rstest]
+    fn parse_reads_every_override() {
+        let cell = MatrixCell::parse("arch=arm64, image=Ubuntu 22.04 ,type=COPARM1-2C-8G")
+            .expect("valid cell");
+        assert_eq!(cell.architecture.as_deref(), Some("arm64"));
+        assert_eq!(cell.image_label.as_deref(), Some("Ubuntu 22.04"));
+        assert_eq!(cell.instance_type.as_deref(), Some("COPARM1-2C-8G"));
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("arch")]
+    #[case("arch=")]
+    #[case("zone=fr-par-2")]
+    #[case("arch=arm64,arch=x86_64")]
+    fn parse_rejects_invalid_specs(#[case] spec: &str) {
+        assert!(matches!(
+            MatrixCell::parse(spec),
+            Err(MatrixError::InvalidCell { .. })
+        ));
+    }
+
+    #[rstest]
+    fn expand_assigns_cache_volumes_per_architecture(base: InstanceRequest) {
+        let cells = [
+            "image=Ubuntu 22.04 Jammy Jellyfish",
+            "image=Ubuntu 24.04 Noble Numbat",
+            "arch=arm64,type=COPARM1-2C-8G",
+            "arch=riscv64",
+        ]
+        .map(String::from);
+        let volumes = [String::from("arm64=vol-arm")];
+
+        let expanded = expand(&base, &cells, &volumes).expect("valid matrix");
+
+        let assigned = expanded
+            .iter()
+            .map(|(_, request)| request.volume_id.as_deref())
+            .collect::<Vec<_>>();
+        assert_eq!(assigned, vec![Some("vol-x86"), None, Some("vol-arm"), None]);
+        assert_eq!(
+            expanded
+                .get(2)
+                .map(|(_, request)| request.instance_type.as_str()),
+            Some("COPARM1-2C-8G")
+        );
+    }
+
+    #[rstest]
+    fn expand_rejects_empty_matrix(base: InstanceRequest) {
+        assert_eq!(expand(&base, &[], &[]), Err(MatrixError::Empty));
+    }
+
+    #[rstest]
+    fn expand_rejects_malformed_cache_volume(base: InstanceRequest) {
+        let cells = [String::from("arch=arm64")];
+        let volumes = [String::from("vol-arm")];
+        assert!(matches!(
+            expand(&base, &cells, &volumes),
+            Err(MatrixError::InvalidCacheVolume { .. })
+        ));
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("fake")]
+    struct FakeError;
+
+    #[rstest]
+    fn summary_table_lists_exit_codes_and_durations() {
+        let outcomes = vec![
+            RunOutcome {
+                label: String::from("arch=x86_64"),
+                result: Ok(RemoteCommandOutput {
+                    exit_code: Some(0),
+                    stdout: String::new(),
+                    stderr: String::new(),
+                }),
+                duration: Duration::from_millis(12_340),
+            },
+            RunOutcome {
+                label: String::from("arch=arm64"),
+                result: Err(RunError::Provision(FakeError)),
+                duration: Duration::from_secs(75),
+            },
+        ];
+
+        assert_eq!(
+            summary_table(&outcomes),
+            concat!(
+                "cell         exit   duration\n",
+                "arch=x86_64  0      12.3s\n",
+                "arch=arm64   error  1m15s\n",
+            )
+        );
+    }
+}
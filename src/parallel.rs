@@ -0,0 +1,208 @@
+//! Concurrent execution of several remote runs.
+//!
+//! Sharded runs fan a single command out across several instances. Each run
+//! is an ordinary [`RunOrchestrator`] execution, so provisioning, sync,
+//! teardown, and error reporting behave exactly as for a single `mriya run`;
+//! this module only schedules them side by side and gathers their outcomes.
+
+use std::time::{Duration, Instant};
+
+use camino::{Utf8Path, Utf8PathBuf};
+
+use crate::backend::{Backend, InstanceRequest};
+use crate::run::{RunError, RunOrchestrator};
+use crate::sync::{AsyncCommandRunner, RemoteCommandOutput};
+
+/// Environment variable exported with the 1-based shard index.
+pub const SHARD_INDEX_ENV: &str = "MRIYA_SHARD_INDEX";
+
+/// Environment variable exported with the total number of shards.
+pub const SHARD_COUNT_ENV: &str = "MRIYA_SHARD_COUNT";
+
+/// A single run scheduled alongside others.
+#[derive(Debug)]
+pub struct ParallelRun<B, R: AsyncCommandRunner> {
+    /// Label identifying the run in output and results (for example
+    /// `shard 2/4`).
+    pub label: String,
+    /// Orchestrator that performs the run.
+    pub orchestrator: RunOrchestrator<B, R>,
+    /// Instance request for this run.
+    pub request: InstanceRequest,
+    /// Remote command to execute.
+    pub remote_command: String,
+}
+
+/// Result of one run in a parallel batch.
+#[derive(Debug)]
+pub struct RunOutcome<E>
+where
+    E: std::error::Error + 'static,
+{
+    /// Label copied from the corresponding [`ParallelRun`].
+    pub label: String,
+    /// Output of the remote command, or the error that stopped the run.
+    pub result: Result<RemoteCommandOutput, RunError<E>>,
+    /// Wall-clock time from provisioning to teardown.
+    pub duration: Duration,
+}
+
+impl<E> RunOutcome<E>
+where
+    E: std::error::Error + 'static,
+{
+    /// Returns the exit code this run contributes to the aggregate status.
+    ///
+    /// Runs that failed before or during remote execution, or whose remote
+    /// command ended without a status, contribute `None`.
+    #[must_use]
+    pub fn exit_code(&self) -> Option<i32> {
+        self.result
+            .as_ref()
+            .ok()
+            .and_then(|output| output.exit_code)
+    }
+}
+
+/// Renders `remote_command` with the shard environment exported.
+///
+/// `index` is 1-based so it can be passed straight to tools such as
+/// `cargo nextest run --partition count:$MRIYA_SHARD_INDEX/$MRIYA_SHARD_COUNT`.
+/// The export is chained with `&&`, so the command stays behind the `cd`
+/// guard that [`Syncer`](crate::Syncer) wraps around it.
+#[must_use]
+pub fn shard_command(index: usize, count: usize, remote_command: &str) -> String {
+    format!("export {SHARD_INDEX_ENV}={index} {SHARD_COUNT_ENV}={count} && {remote_command}")
+}
+
+/// Aggregates exit codes across runs.
+///
+/// Returns `Some(0)` when every run completed with status zero, the first
+/// non-zero status (in run order) otherwise, and `None` when a run failed or
+/// terminated without a status and no run reported a non-zero status.
+#[must_use]
+pub fn aggregate_exit_code<E>(outcomes: &[RunOutcome<E>]) -> Option<i32>
+where
+    E: std::error::Error + 'static,
+{
+    let mut all_succeeded = true;
+    for outcome in outcomes {
+        match outcome.exit_code() {
+            Some(0) => {}
+            Some(code) => return Some(code),
+            None => all_succeeded = false,
+        }
+    }
+    all_succeeded.then_some(0)
+}
+
+/// Executes every run concurrently and returns their outcomes in input order.
+///
+/// Each run executes as its own task, so the runs' `rsync` and `ssh`
+/// processes proceed side by side. Every run tears its own instance down, so
+/// a failing run never prevents the others from cleaning up.
+///
+/// # Panics
+///
+/// Propagates a panic raised inside any run.
+pub async fn run_parallel<B, R>(
+    runs: Vec<ParallelRun<B, R>>,
+    source: &Utf8Path,
+) -> Vec<RunOutcome<B::Error>>
+where
+    B: Backend + Send + Sync + 'static,
+    B::Error: std::fmt::Display + Send + Sync + std::error::Error + 'static,
+    R: AsyncCommandRunner + 'static,
+{
+    let tasks = runs
+        .into_iter()
+        .map(|run| {
+            let run_source = source.to_path_buf();
+            tokio::spawn(async move { execute_run(run, &run_source).await })
+        })
+        .collect::<Vec<_>>();
+
+    let mut outcomes = Vec::with_capacity(tasks.len());
+    for task in tasks {
+        match task.await {
+            Ok(outcome) => outcomes.push(outcome),
+            Err(err) => std::panic::resume_unwind(err.into_panic()),
+        }
+    }
+    outcomes
+}
+
+async fn execute_run<B, R>(run: ParallelRun<B, R>, source: &Utf8PathBuf) -> RunOutcome<B::Error>
+where
+    B: Backend,
+    B::Error: std::fmt::Display + Send + Sync + std::error::Error + 'static,
+    R: AsyncCommandRunner,
+{
+    let started = Instant::now();
+    let result = run
+        .orchestrator
+        .execute(&run.request, source, &run.remote_command)
+        .await;
+    RunOutcome {
+        label: run.label,
+        result,
+        duration: started.elapsed(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Unit tests for shard command rendering and exit code aggregation.
+    use super::*;
+    use rstest::rstest;
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("fake")]
+    struct FakeError;
+
+    fn outcome(exit_code: Option<i32>) -> RunOutcome<FakeError> {
+        RunOutcome {
+            label: String::from("cell"),
+            result: Ok(RemoteCommandOutput {
+                exit_code,
+                stdout: String::new(),
+                stderr: String::new(),
+            }),
+            duration: Duration::ZERO,
+        }
+    }
+
+    #[rstest]
+    fn shard_command_exports_index_and_count() {
+        assert_eq!(
+            shard_command(2, 4, "cargo test"),
+            "export MRIYA_SHARD_INDEX=2 MRIYA_SHARD_COUNT=4 && cargo test"
+        );
+    }
+
+    #[rstest]
+    #[case(&[Some(0), Some(0)], Some(0))]
+    #[case(&[Some(0), Some(3), Some(5)], Some(3))]
+    #[case(&[None, Some(0)], None)]
+    #[case(&[None, Some(9)], Some(9))]
+    fn aggregate_exit_code_prefers_first_failure(
+        #[case] codes: &[Option<i32>],
+        #[case] expected: Option<i32>,
+    ) {
+        let outcomes = codes.iter().copied().map(outcome).collect::<Vec<_>>();
+        assert_eq!(aggregate_exit_code(&outcomes), expected);
+    }
+
+    #[rstest]
+    fn aggregate_exit_code_treats_run_errors_as_failures() {
+        let outcomes = vec![
+            outcome(Some(0)),
+            RunOutcome {
+                label: String::from("broken"),
+                result: Err(RunError::Provision(FakeError)),
+                duration: Duration::ZERO,
+            },
+        ];
+        assert_eq!(aggregate_exit_code(&outcomes), None);
+    }
+}
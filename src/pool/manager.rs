@@ -0,0 +1,180 @@
+//! Keeps a warm pool topped up and drains it when idle.
+
+use std::time::Duration;
+
+use super::{PoolEntry, PoolError, PoolStore};
+use crate::backend::{Backend, InstanceHandle, InstanceRequest};
+
+/// Default time without claims after which the pool drains itself.
+const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// What a single reconciliation pass did.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TickReport {
+    /// Instances created and added to the pool.
+    pub created: Vec<String>,
+    /// Instances destroyed because they were idle, abandoned, or drained.
+    pub destroyed: Vec<String>,
+    /// Human-readable descriptions of backend failures.
+    pub failures: Vec<String>,
+    /// `true` once the pool has drained and the manager should exit.
+    pub finished: bool,
+}
+
+/// Maintains a warm pool of instances for one profile.
+#[derive(Debug)]
+pub struct PoolManager<B> {
+    backend: B,
+    store: PoolStore,
+    request: InstanceRequest,
+    size: usize,
+    idle_timeout: Duration,
+}
+
+impl<B> PoolManager<B>
+where
+    B: Backend,
+    B::Error: std::fmt::Display,
+{
+    /// Creates a manager that keeps `size` ready instances matching `request`.
+    #[must_use]
+    pub const fn new(backend: B, store: PoolStore, request: InstanceRequest, size: usize) -> Self {
+        Self {
+            backend,
+            store,
+            request,
+            size,
+            idle_timeout: DEFAULT_IDLE_TIMEOUT,
+        }
+    }
+
+    /// Overrides how long the pool may go without claims before draining.
+    #[must_use]
+    pub const fn with_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    /// Marks the pool active at `now` and clears any pending stop request.
+    ///
+    /// Ready instances left by a previous manager are adopted.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PoolError::SharedVolume`] when the request names a cache
+    /// volume and the pool holds more than one instance, or another
+    /// [`PoolError`] when the state cannot be updated.
+    pub fn start(&self, now: u64) -> Result<(), PoolError> {
+        if let Some(volume_id) = &self.request.volume_id
+            && self.size > 1
+        {
+            return Err(PoolError::SharedVolume {
+                volume_id: volume_id.clone(),
+            });
+        }
+        self.store.update(|state| {
+            state.last_activity_at = now;
+            state.stop_requested = false;
+        })
+    }
+
+    /// Reconciles the pool once.
+    ///
+    /// Abandoned claims are destroyed first. When a stop has been requested
+    /// or the pool has been idle for longer than the timeout, every ready
+    /// instance is destroyed and the report is marked finished; otherwise the
+    /// pool is topped up to its target size.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PoolError`] when the state cannot be updated. Backend
+    /// failures are recorded in the report instead.
+    pub async fn tick(&self, now: u64) -> Result<TickReport, PoolError> {
+        let idle_timeout = self.idle_timeout.as_secs();
+        let size = self.size;
+        let (to_destroy, deficit, finished) = self.store.update(|state| {
+            let mut to_destroy = state.take_abandoned();
+            let idle = now.saturating_sub(state.last_activity_at) >= idle_timeout;
+            let finished = state.stop_requested || idle;
+            if finished {
+                to_destroy.extend(state.take_ready());
+            }
+            let deficit = size.saturating_sub(state.ready_count());
+            (to_destroy, deficit, finished)
+        })?;
+
+        let mut report = TickReport {
+            finished,
+            ..TickReport::default()
+        };
+        self.destroy_all(
+            to_destroy.iter().map(PoolEntry::handle).collect(),
+            &mut report,
+        )
+        .await;
+        if !finished {
+            for _ in 0..deficit {
+                self.provision_one(now, &mut report).await?;
+            }
+        }
+        Ok(report)
+    }
+
+    /// Requests a stop and destroys every ready instance.
+    ///
+    /// Claimed instances are left to the runs that own them.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PoolError`] when the state cannot be updated.
+    pub async fn drain(&self) -> Result<TickReport, PoolError> {
+        let ready = self.store.update(|state| {
+            state.stop_requested = true;
+            state.take_ready()
+        })?;
+        let mut report = TickReport {
+            finished: true,
+            ..TickReport::default()
+        };
+        self.destroy_all(ready.iter().map(PoolEntry::handle).collect(), &mut report)
+            .await;
+        Ok(report)
+    }
+
+    async fn provision_one(&self, now: u64, report: &mut TickReport) -> Result<(), PoolError> {
+        let handle = match self.backend.create(&self.request).await {
+            Ok(handle) => handle,
+            Err(err) => {
+                report.failures.push(format!("create failed: {err}"));
+                return Ok(());
+            }
+        };
+        match self.backend.wait_for_ready(&handle).await {
+            Ok(networking) => {
+                let entry = PoolEntry::ready(&handle, &networking, now);
+                self.store.update(|state| state.entries.push(entry))?;
+                report.created.push(handle.id);
+            }
+            Err(err) => {
+                report.failures.push(format!(
+                    "instance {} did not become ready: {err}",
+                    handle.id
+                ));
+                self.destroy_all(vec![handle], report).await;
+            }
+        }
+        Ok(())
+    }
+
+    async fn destroy_all(&self, handles: Vec<InstanceHandle>, report: &mut TickReport) {
+        for handle in handles {
+            let id = handle.id.clone();
+            match self.backend.destroy(handle).await {
+                Ok(()) => report.destroyed.push(id),
+                Err(err) => report
+                    .failures
+                    .push(format!("failed to destroy instance {id}: {err}")),
+            }
+        }
+    }
+}
@@ -0,0 +1,399 @@
+//! Warm pool of ready instances.
+//!
+//! Provisioning on Scaleway takes tens of seconds even with a custom image. A
+//! warm pool keeps a number of instances created and reachable ahead of time
+//! so `mriya run` can claim one instead of waiting for [`Backend::create`].
+//!
+//! Pool state lives in a JSON file per instance profile under the local state
+//! directory. Every read-modify-write cycle holds an exclusive lock on a
+//! sibling lock file and replaces the state file atomically, so concurrent
+//! runs never claim the same instance and a crash never leaves a partially
+//! written file behind. Claimed instances record the claiming process ID; the
+//! pool manager destroys instances whose claimant has exited without
+//! releasing them.
+//!
+//! [`Backend::create`]: crate::backend::Backend::create
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::net::IpAddr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::backend::{InstanceHandle, InstanceNetworking, InstanceRequest, ProvisionedInstance};
+use crate::init::helpers::slugify;
+use crate::sync::Redactor;
+
+mod manager;
+
+pub use manager::{PoolManager, TickReport};
+
+/// Environment variable overriding the local state directory.
+pub const STATE_DIR_ENV: &str = "MRIYA_STATE_DIR";
+
+/// Subdirectory of the state directory holding pool files.
+const POOL_DIR: &str = "pool";
+
+/// Errors raised while reading or updating pool state.
+#[derive(Debug, Error)]
+pub enum PoolError {
+    /// Raised when no state directory can be determined.
+    #[error("cannot determine state directory: set {STATE_DIR_ENV}, XDG_STATE_HOME, or HOME")]
+    StateDirUnavailable,
+    /// Raised when file system operations fail.
+    #[error("failed to access {path}: {message}")]
+    Io {
+        /// Path that could not be accessed.
+        path: Utf8PathBuf,
+        /// Human-readable error message.
+        message: String,
+    },
+    /// Raised when the state file cannot be parsed.
+    #[error("failed to parse {path}: {message}")]
+    Parse {
+        /// Path that could not be parsed.
+        path: Utf8PathBuf,
+        /// Human-readable error message.
+        message: String,
+    },
+    /// Raised when more than one pooled instance would need the same cache
+    /// volume, which attaches to one instance at a time.
+    #[error("cache volume {volume_id} attaches to one instance at a time; use a pool size of 1")]
+    SharedVolume {
+        /// Cache volume named by the request.
+        volume_id: String,
+    },
+}
+
+impl PoolError {
+    /// Masks the secrets of `redactor` in the error's message.
+    #[must_use]
+    pub fn redact(mut self, redactor: &Redactor) -> Self {
+        if let Self::Io { message, .. } | Self::Parse { message, .. } = &mut self {
+            *message = redactor.redact(message);
+        }
+        self
+    }
+}
+
+/// A pooled instance recorded in the state file.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PoolEntry {
+    /// Provider instance identifier.
+    pub id: String,
+    /// Availability zone of the instance.
+    pub zone: String,
+    /// Public IP address used for SSH.
+    pub public_ip: IpAddr,
+    /// SSH port.
+    pub ssh_port: u16,
+    /// Time the instance became ready, in seconds since the Unix epoch.
+    pub created_at: u64,
+    /// Process ID of the run that claimed the instance, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub claimed_by: Option<u32>,
+}
+
+impl PoolEntry {
+    /// Builds an entry for a freshly provisioned instance.
+    #[must_use]
+    pub fn ready(handle: &InstanceHandle, networking: &InstanceNetworking, now: u64) -> Self {
+        Self {
+            id: handle.id.clone(),
+            zone: handle.zone.clone(),
+            public_ip: networking.public_ip,
+            ssh_port: networking.ssh_port,
+            created_at: now,
+            claimed_by: None,
+        }
+    }
+
+    /// Returns the backend handle for the entry.
+    #[must_use]
+    pub fn handle(&self) -> InstanceHandle {
+        InstanceHandle {
+            id: self.id.clone(),
+            zone: self.zone.clone(),
+            run_id: None,
+        }
+    }
+
+    /// Returns `true` when the entry is claimed by a process that has exited.
+    #[must_use]
+    pub fn is_abandoned(&self) -> bool {
+        self.claimed_by.is_some_and(|pid| !process_alive(pid))
+    }
+}
+
+/// Contents of a pool state file.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PoolState {
+    /// Pooled instances, ready or claimed.
+    #[serde(default)]
+    pub entries: Vec<PoolEntry>,
+    /// Last time the pool was started or an instance was claimed, in seconds
+    /// since the Unix epoch. Drives the idle timeout.
+    #[serde(default)]
+    pub last_activity_at: u64,
+    /// Set by `mriya pool stop` to ask a running pool manager to drain.
+    #[serde(default)]
+    pub stop_requested: bool,
+}
+
+impl PoolState {
+    /// Number of instances ready to be claimed.
+    #[must_use]
+    pub fn ready_count(&self) -> usize {
+        self.entries
+            .iter()
+            .filter(|entry| entry.claimed_by.is_none())
+            .count()
+    }
+
+    /// Removes and returns every unclaimed entry.
+    pub fn take_ready(&mut self) -> Vec<PoolEntry> {
+        let (ready, claimed) = std::mem::take(&mut self.entries)
+            .into_iter()
+            .partition(|entry| entry.claimed_by.is_none());
+        self.entries = claimed;
+        ready
+    }
+
+    /// Removes and returns every entry whose claimant has exited.
+    pub fn take_abandoned(&mut self) -> Vec<PoolEntry> {
+        let (abandoned, kept) = std::mem::take(&mut self.entries)
+            .into_iter()
+            .partition(PoolEntry::is_abandoned);
+        self.entries = kept;
+        abandoned
+    }
+}
+
+/// Locked access to the state file of one instance profile.
+#[derive(Clone, Debug)]
+pub struct PoolStore {
+    dir: Utf8PathBuf,
+    profile: String,
+}
+
+impl PoolStore {
+    /// Creates a store for the profile of `request` under `state_dir`.
+    #[must_use]
+    pub fn new(state_dir: &Utf8Path, request: &InstanceRequest) -> Self {
+        Self {
+            dir: state_dir.join(POOL_DIR),
+            profile: profile_name(request),
+        }
+    }
+
+    /// Resolves the state directory from `MRIYA_STATE_DIR`, then
+    /// `$XDG_STATE_HOME/mriya`, then `$HOME/.local/state/mriya`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PoolError::StateDirUnavailable`] when none are set.
+    pub fn default_state_dir() -> Result<Utf8PathBuf, PoolError> {
+        let non_empty = |key: &str| std::env::var(key).ok().filter(|val| !val.is_empty());
+        non_empty(STATE_DIR_ENV)
+            .map(Utf8PathBuf::from)
+            .or_else(|| non_empty("XDG_STATE_HOME").map(|dir| Utf8PathBuf::from(dir).join("mriya")))
+            .or_else(|| {
+                non_empty("HOME").map(|home| Utf8PathBuf::from(home).join(".local/state/mriya"))
+            })
+            .ok_or(PoolError::StateDirUnavailable)
+    }
+
+    /// Name of the instance profile served by this store.
+    #[must_use]
+    pub fn profile(&self) -> &str {
+        &self.profile
+    }
+
+    /// Path of the state file.
+    #[must_use]
+    pub fn state_path(&self) -> Utf8PathBuf {
+        self.dir.join(format!("{}.json", self.profile))
+    }
+
+    /// Reads the current state without locking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PoolError`] when the file exists but cannot be read or parsed.
+    pub fn load(&self) -> Result<PoolState, PoolError> {
+        let path = self.state_path();
+        match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).map_err(|err| PoolError::Parse {
+                path,
+                message: err.to_string(),
+            }),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(PoolState::default()),
+            Err(err) => Err(io_error(&path, &err)),
+        }
+    }
+
+    /// Applies `change` to the state under an exclusive lock and persists it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PoolError`] when locking, reading, or writing fails.
+    pub fn update<T>(&self, change: impl FnOnce(&mut PoolState) -> T) -> Result<T, PoolError> {
+        fs::create_dir_all(&self.dir).map_err(|err| io_error(&self.dir, &err))?;
+        let lock = self.lock()?;
+        let mut state = self.load()?;
+        let result = change(&mut state);
+        self.save(&state)?;
+        lock.unlock()
+            .map_err(|err| io_error(&self.lock_path(), &err))?;
+        Ok(result)
+    }
+
+    /// Claims a ready instance for the current process.
+    ///
+    /// Returns `None` without touching the file system when the profile has
+    /// no pool.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PoolError`] when the state cannot be updated.
+    pub fn claim(
+        &self,
+        request: &InstanceRequest,
+    ) -> Result<Option<ProvisionedInstance>, PoolError> {
+        if !self.state_path().exists() {
+            return Ok(None);
+        }
+        let pid = std::process::id();
+        let now = unix_now();
+        let claimed = self.update(|state| {
+            let entry = state
+                .entries
+                .iter_mut()
+                .find(|entry| entry.claimed_by.is_none())?;
+            entry.claimed_by = Some(pid);
+            let claimed = entry.clone();
+            state.last_activity_at = now;
+            Some(claimed)
+        })?;
+        Ok(claimed.map(|entry| ProvisionedInstance {
+            handle: entry.handle(),
+            networking: InstanceNetworking {
+                public_ip: entry.public_ip,
+                ssh_port: entry.ssh_port,
+            },
+            request: request.clone(),
+        }))
+    }
+
+    /// Forgets a claimed instance once the run has torn it down.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PoolError`] when the state cannot be updated.
+    pub fn release(&self, instance_id: &str) -> Result<(), PoolError> {
+        self.update(|state| state.entries.retain(|entry| entry.id != instance_id))
+    }
+
+    fn lock_path(&self) -> Utf8PathBuf {
+        self.dir.join(format!("{}.lock", self.profile))
+    }
+
+    fn lock(&self) -> Result<File, PoolError> {
+        let path = self.lock_path();
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .map_err(|err| io_error(&path, &err))?;
+        file.lock().map_err(|err| io_error(&path, &err))?;
+        Ok(file)
+    }
+
+    fn save(&self, state: &PoolState) -> Result<(), PoolError> {
+        let path = self.state_path();
+        let tmp_path = self.dir.join(format!("{}.json.tmp", self.profile));
+        let contents = serde_json::to_vec_pretty(state).map_err(|err| PoolError::Parse {
+            path: path.clone(),
+            message: err.to_string(),
+        })?;
+        let mut tmp = File::create(&tmp_path).map_err(|err| io_error(&tmp_path, &err))?;
+        tmp.write_all(&contents)
+            .and_then(|()| tmp.sync_all())
+            .map_err(|err| io_error(&tmp_path, &err))?;
+        fs::rename(&tmp_path, &path).map_err(|err| io_error(&path, &err))
+    }
+}
+
+/// Derives a stable profile name from the fields that shape an instance.
+///
+/// Requests that differ in any field get separate pools, so a claimed
+/// instance always matches the request that claims it.
+#[must_use]
+pub fn profile_name(request: &InstanceRequest) -> String {
+    let fields = [
+        request.image_label.as_str(),
+        request.instance_type.as_str(),
+        request.zone.as_str(),
+        request.project_id.as_str(),
+        request.organisation_id.as_deref().unwrap_or_default(),
+        request.architecture.as_str(),
+        request.volume_id.as_deref().unwrap_or_default(),
+        request.cloud_init_user_data.as_deref().unwrap_or_default(),
+    ];
+    let prefix = slugify(&format!(
+        "{}-{}-{}",
+        request.zone, request.instance_type, request.architecture
+    ));
+    format!("{prefix}-{:016x}", fnv1a(&fields))
+}
+
+/// Current time in seconds since the Unix epoch.
+#[must_use]
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+fn fnv1a(fields: &[&str]) -> u64 {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+    fields
+        .iter()
+        .flat_map(|field| field.bytes().chain([0]))
+        .fold(OFFSET, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+        })
+}
+
+#[cfg(target_os = "linux")]
+fn process_alive(pid: u32) -> bool {
+    Utf8Path::new("/proc").join(pid.to_string()).exists()
+}
+
+/// Probes the process with `kill -0`, which checks that it exists without
+/// signalling it. A probe that cannot run counts as alive, so a claimed
+/// instance is never destroyed on a guess.
+#[cfg(not(target_os = "linux"))]
+fn process_alive(pid: u32) -> bool {
+    std::process::Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .map_or(true, |status| status.success())
+}
+
+fn io_error(path: &Utf8Path, err: &io::Error) -> PoolError {
+    PoolError::Io {
+        path: path.to_path_buf(),
+        message: err.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,132 @@
+//! Unit tests for warm pool state handling.
+
+use std::net::{IpAddr, Ipv4Addr};
+
+use camino::Utf8PathBuf;
+use rstest::{fixture, rstest};
+use tempfile::TempDir;
+
+use super::*;
+
+struct StoreFixture {
+    store: PoolStore,
+    request: InstanceRequest,
+    _dir: TempDir,
+}
+
+#[fixture]
+fn request() -> InstanceRequest {
+    InstanceRequest::builder()
+        .image_label("Ubuntu 24.04 Noble Numbat")
+        .instance_type("DEV1-S")
+        .zone("fr-par-1")
+        .project_id("project")
+        .architecture("x86_64")
+        .build()
+        .expect("request should be valid")
+}
+
+#[fixture]
+fn fixture(request: InstanceRequest) -> StoreFixture {
+    let dir = TempDir::new().expect("temp dir");
+    let root = Utf8PathBuf::from_path_buf(dir.path().to_path_buf()).expect("utf-8 path");
+    StoreFixture {
+        store: PoolStore::new(&root, &request),
+        request,
+        _dir: dir,
+    }
+}
+
+fn entry(id: &str, claimed_by: Option<u32>) -> PoolEntry {
+    PoolEntry {
+        id: id.to_owned(),
+        zone: String::from("fr-par-1"),
+        public_ip: IpAddr::V4(Ipv4Addr::LOCALHOST),
+        ssh_port: 22,
+        created_at: 1,
+        claimed_by,
+    }
+}
+
+#[rstest]
+fn profile_name_changes_with_request_shape(request: InstanceRequest) {
+    let other = InstanceRequest {
+        image_label: String::from("Ubuntu 22.04 Jammy Jellyfish"),
+        ..request.clone()
+    };
+    let name = profile_name(&request);
+    assert!(name.starts_with("fr-par-1-dev1-s-x86-64-"), "name: {name}");
+    assert_eq!(name, profile_name(&request.clone()));
+    assert_ne!(name, profile_name(&other));
+}
+
+#[rstest]
+fn claim_without_pool_leaves_file_system_untouched(fixture: StoreFixture) {
+    let claimed = fixture.store.claim(&fixture.request).expect("claim");
+    assert_eq!(claimed, None);
+    assert!(!fixture.store.state_path().exists());
+}
+
+#[rstest]
+fn claim_marks_entry_and_release_removes_it(fixture: StoreFixture) {
+    fixture
+        .store
+        .update(|state| {
+            state.entries.push(entry("claimed", Some(u32::MAX)));
+            state.entries.push(entry("ready", None));
+        })
+        .expect("seed state");
+
+    let claimed = fixture
+        .store
+        .claim(&fixture.request)
+        .expect("claim")
+        .expect("a ready instance");
+    assert_eq!(claimed.handle.id, "ready");
+    assert_eq!(claimed.request, fixture.request);
+
+    let state = fixture.store.load().expect("load");
+    assert_eq!(state.ready_count(), 0);
+    assert!(state.last_activity_at > 0);
+    assert_eq!(
+        fixture.store.claim(&fixture.request).expect("claim"),
+        None,
+        "an instance is never claimed twice"
+    );
+
+    fixture.store.release("ready").expect("release");
+    let ids = fixture
+        .store
+        .load()
+        .expect("load")
+        .entries
+        .into_iter()
+        .map(|pooled| pooled.id)
+        .collect::<Vec<_>>();
+    assert_eq!(ids, vec![String::from("claimed")]);
+}
+
+#[rstest]
+fn take_abandoned_keeps_live_claims() {
+    let mut state = PoolState {
+        entries: vec![
+            entry("ready", None),
+            entry("live", Some(std::process::id())),
+            entry("dead", Some(u32::MAX)),
+        ],
+        ..PoolState::default()
+    };
+
+    let abandoned = state.take_abandoned();
+
+    assert_eq!(abandoned, vec![entry("dead", Some(u32::MAX))]);
+    assert_eq!(state.entries.len(), 2);
+    assert_eq!(state.take_ready(), vec![entry("ready", None)]);
+}
+
+#[rstest]
+fn load_reports_corrupt_state(fixture: StoreFixture) {
+    fixture.store.update(|_| ()).expect("create state");
+    fs::write(fixture.store.state_path(), "not json").expect("corrupt state");
+    assert!(matches!(fixture.store.load(), Err(PoolError::Parse { .. })));
+}
@@ -0,0 +1,139 @@
+//! `mriya pool` subcommands and warm pool claiming for `mriya run`.
+//!
+//! `mriya pool start` runs in the foreground, topping the pool up until it has
+//! been idle for the configured timeout or receives `SIGINT`/`SIGTERM`, and
+//! drains every ready instance before exiting. `mriya run` claims a ready
+//! instance whose profile matches its request and falls back to on-demand
+//! provisioning when none is available.
+
+use std::io::{self, Write};
+use std::time::Duration;
+
+use camino::Utf8Path;
+use mriya::pool::{TickReport, unix_now};
+use mriya::sync::AsyncCommandRunner;
+use mriya::{
+    InstanceRequest, PoolManager, PoolStore, RemoteCommandOutput, RunOrchestrator, RunProvenance,
+    ScalewayBackend,
+};
+use tokio::signal::unix::{SignalKind, signal};
+use tokio::time::sleep;
+
+use crate::CliError;
+use crate::cli::{PoolCommand, PoolStartCommand};
+
+/// Executes a `mriya pool` subcommand.
+pub(crate) async fn exec_pool(command: PoolCommand) -> Result<i32, CliError> {
+    let (base_backend, request) = crate::default_backend_and_request()?;
+    let backend = base_backend.with_provenance(RunProvenance::detect(&crate::current_dir()?));
+    let store = PoolStore::new(&PoolStore::default_state_dir()?, &request);
+    match command {
+        PoolCommand::Start(args) => start_pool(backend, store, request, &args).await,
+        PoolCommand::Stop => {
+            let manager = PoolManager::new(backend, store, request, 0);
+            write_report(&manager.drain().await?);
+            Ok(0)
+        }
+    }
+}
+
+#[expect(
+    clippy::integer_division_remainder_used,
+    reason = "tokio::select! expands to a modulo when picking a branch"
+)]
+async fn start_pool(
+    backend: ScalewayBackend,
+    store: PoolStore,
+    request: InstanceRequest,
+    args: &PoolStartCommand,
+) -> Result<i32, CliError> {
+    let signal_error = |err: io::Error| CliError::Config(format!("signal handler: {err}"));
+    let mut interrupt = signal(SignalKind::interrupt()).map_err(signal_error)?;
+    let mut terminate = signal(SignalKind::terminate()).map_err(signal_error)?;
+
+    writeln!(
+        io::stderr(),
+        "pool: keeping {} ready instance(s) for profile {}",
+        args.size,
+        store.profile()
+    )
+    .ok();
+    let manager = PoolManager::new(backend, store, request, usize::from(args.size))
+        .with_idle_timeout(Duration::from_secs(args.idle_timeout));
+    manager.start(unix_now())?;
+
+    loop {
+        let report = manager.tick(unix_now()).await?;
+        write_report(&report);
+        if report.finished {
+            return Ok(0);
+        }
+        tokio::select! {
+            _ = interrupt.recv() => break,
+            _ = terminate.recv() => break,
+            () = sleep(Duration::from_secs(args.poll_interval)) => {}
+        }
+    }
+
+    write_report(&manager.drain().await?);
+    Ok(0)
+}
+
+fn write_report(report: &TickReport) {
+    let mut stderr = io::stderr().lock();
+    for id in &report.created {
+        writeln!(stderr, "pool: instance {id} ready").ok();
+    }
+    for id in &report.destroyed {
+        writeln!(stderr, "pool: instance {id} destroyed").ok();
+    }
+    for failure in &report.failures {
+        writeln!(stderr, "pool: {failure}").ok();
+    }
+    if report.finished {
+        writeln!(stderr, "pool: drained").ok();
+    }
+}
+
+/// Runs the command on a pooled instance when one is available and still
+/// answers over SSH, otherwise provisions a new instance.
+///
+/// The pool is an optimization, so pool state errors are reported as warnings
+/// and the run falls back to on-demand provisioning.
+pub(crate) async fn execute_pooled<R: AsyncCommandRunner>(
+    orchestrator: &RunOrchestrator<ScalewayBackend, R>,
+    request: &InstanceRequest,
+    source: &Utf8Path,
+    remote_command: &str,
+) -> Result<RemoteCommandOutput, CliError> {
+    let Some((store, instance)) = claim(request) else {
+        return Ok(orchestrator
+            .execute(request, source, remote_command)
+            .await?);
+    };
+
+    let instance_id = instance.handle.id.clone();
+    let result = orchestrator
+        .execute_claimed(instance, source, remote_command)
+        .await;
+    if let Err(err) = store.release(&instance_id) {
+        writeln!(
+            io::stderr(),
+            "warning: failed to release pooled instance: {err}"
+        )
+        .ok();
+    }
+    Ok(result?)
+}
+
+fn claim(request: &InstanceRequest) -> Option<(PoolStore, mriya::ProvisionedInstance)> {
+    // Without a state directory there can be no pool to claim from.
+    let store = PoolStore::new(&PoolStore::default_state_dir().ok()?, request);
+    match store.claim(request) {
+        Ok(instance) => instance.map(|claimed| (store, claimed)),
+        Err(err) => {
+            writeln!(io::stderr(), "warning: warm pool unavailable: {err}").ok();
+            None
+        }
+    }
+}
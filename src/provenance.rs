@@ -0,0 +1,210 @@
+//! Run-level provenance recorded on every provider resource.
+//!
+//! Each `mriya` invocation generates a run ID and captures who started it,
+//! from which machine and project, and at which commit. The backend turns
+//! this into tags so resources in the provider console can be traced back to
+//! the run that created them, and `mriya-janitor` can report the run ID of
+//! each resource it sweeps.
+
+use std::fs;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use camino::Utf8Path;
+use uuid::Uuid;
+
+use crate::init::helpers::{project_slug, slugify};
+
+/// Prefix of the tag carrying the run ID.
+pub const RUN_TAG_PREFIX: &str = "mriya-run-";
+/// Prefix of the tag carrying the local username.
+pub const USER_TAG_PREFIX: &str = "mriya-user-";
+/// Prefix of the tag carrying the local hostname.
+pub const HOST_TAG_PREFIX: &str = "mriya-host-";
+/// Prefix of the tag carrying the project slug.
+pub const PROJECT_TAG_PREFIX: &str = "mriya-project-";
+/// Prefix of the tag carrying the git commit of the synced tree.
+pub const COMMIT_TAG_PREFIX: &str = "mriya-commit-";
+/// Prefix of the tag carrying the creation time in seconds since the epoch.
+pub const CREATED_TAG_PREFIX: &str = "mriya-created-";
+
+/// Fallback used when a provenance value cannot be determined.
+const UNKNOWN: &str = "unknown";
+
+/// Who created a run, from where, and when.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RunProvenance {
+    /// Identifier generated for the run.
+    pub run_id: String,
+    /// Local username that started the run.
+    pub user: String,
+    /// Local hostname that started the run.
+    pub hostname: String,
+    /// Slug derived from the project directory name.
+    pub project: String,
+    /// Commit checked out in the project directory, when it is a git work tree.
+    pub git_commit: Option<String>,
+    /// Creation time in seconds since the Unix epoch.
+    pub created_at: u64,
+}
+
+impl RunProvenance {
+    /// Captures provenance for a run of the project rooted at `project_dir`.
+    ///
+    /// Values that cannot be determined (for example when `USER` is unset)
+    /// fall back to `unknown`; the git commit is omitted outside a git work
+    /// tree.
+    #[must_use]
+    pub fn detect(project_dir: &Utf8Path) -> Self {
+        let project = project_dir
+            .file_name()
+            .and_then(project_slug)
+            .unwrap_or_else(|| String::from(UNKNOWN));
+        Self {
+            run_id: Uuid::new_v4().simple().to_string(),
+            user: Self::detect_user(),
+            hostname: Self::detect_hostname(),
+            project,
+            git_commit: Self::detect_git_commit(project_dir),
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs()),
+        }
+    }
+
+    /// Renders the provenance as provider tags.
+    #[must_use]
+    pub fn tags(&self) -> Vec<String> {
+        let mut tags = vec![
+            format!("{RUN_TAG_PREFIX}{}", self.run_id),
+            format!("{USER_TAG_PREFIX}{}", Self::tag_value(&self.user)),
+            format!("{HOST_TAG_PREFIX}{}", Self::tag_value(&self.hostname)),
+            format!("{PROJECT_TAG_PREFIX}{}", Self::tag_value(&self.project)),
+        ];
+        if let Some(commit) = self.git_commit.as_deref() {
+            tags.push(format!("{COMMIT_TAG_PREFIX}{commit}"));
+        }
+        tags.push(format!("{CREATED_TAG_PREFIX}{}", self.created_at));
+        tags
+    }
+
+    /// Extracts the run ID from a resource's tags, if present.
+    #[must_use]
+    pub fn run_id_from_tags(tags: &[String]) -> Option<&str> {
+        tags.iter()
+            .find_map(|tag| tag.strip_prefix(RUN_TAG_PREFIX))
+            .filter(|id| !id.is_empty())
+    }
+
+    fn tag_value(value: &str) -> String {
+        let slug = slugify(value);
+        if slug.is_empty() {
+            String::from(UNKNOWN)
+        } else {
+            slug
+        }
+    }
+
+    fn detect_user() -> String {
+        ["USER", "USERNAME", "LOGNAME"]
+            .into_iter()
+            .find_map(|key| std::env::var(key).ok().filter(|val| !val.trim().is_empty()))
+            .unwrap_or_else(|| String::from(UNKNOWN))
+    }
+
+    fn detect_hostname() -> String {
+        std::env::var("HOSTNAME")
+            .ok()
+            .or_else(|| fs::read_to_string("/etc/hostname").ok())
+            .map(|name| name.trim().to_owned())
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| String::from(UNKNOWN))
+    }
+
+    fn detect_git_commit(project_dir: &Utf8Path) -> Option<String> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(project_dir.as_str())
+            .args(["rev-parse", "HEAD"])
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        let commit = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+        (!commit.is_empty()).then_some(commit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Unit tests for run provenance tags.
+    use super::*;
+    use camino::Utf8PathBuf;
+    use rstest::rstest;
+
+    fn sample() -> RunProvenance {
+        RunProvenance {
+            run_id: String::from("abc123"),
+            user: String::from("Ada Lovelace"),
+            hostname: String::from("build-box.local"),
+            project: String::from("mriya"),
+            git_commit: Some(String::from("0123456789abcdef")),
+            created_at: 1_700_000_000,
+        }
+    }
+
+    #[rstest]
+    fn tags_cover_every_provenance_field() {
+        assert_eq!(
+            sample().tags(),
+            vec![
+                String::from("mriya-run-abc123"),
+                String::from("mriya-user-ada-lovelace"),
+                String::from("mriya-host-build-box-local"),
+                String::from("mriya-project-mriya"),
+                String::from("mriya-commit-0123456789abcdef"),
+                String::from("mriya-created-1700000000"),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn tags_omit_commit_outside_git() {
+        let provenance = RunProvenance {
+            git_commit: None,
+            ..sample()
+        };
+        assert!(
+            provenance
+                .tags()
+                .iter()
+                .all(|tag| !tag.starts_with(COMMIT_TAG_PREFIX))
+        );
+    }
+
+    #[rstest]
+    #[case(&["mriya", "mriya-run-abc123"], Some("abc123"))]
+    #[case(&["mriya", "ephemeral"], None)]
+    #[case(&["mriya-run-"], None)]
+    fn run_id_from_tags_finds_run_tag(#[case] tags: &[&str], #[case] expected: Option<&str>) {
+        let owned = tags.iter().map(|tag| (*tag).to_owned()).collect::<Vec<_>>();
+        assert_eq!(RunProvenance::run_id_from_tags(&owned), expected);
+    }
+
+    #[rstest]
+    fn detect_uses_directory_slug_and_skips_commit_outside_git() {
+        let dir = tempfile::Builder::new()
+            .prefix("My Project ")
+            .tempdir()
+            .expect("temp dir");
+        let path = Utf8PathBuf::from_path_buf(dir.path().to_path_buf()).expect("utf-8 path");
+
+        let provenance = RunProvenance::detect(&path);
+
+        assert!(provenance.project.starts_with("my-project"));
+        assert_eq!(provenance.git_commit, None);
+        assert_eq!(provenance.run_id.len(), 32);
+        assert!(provenance.created_at > 0);
+    }
+}
@@ -0,0 +1,151 @@
+//! Retries of transient provisioning failures.
+//!
+//! [`RunOrchestrator`](crate::RunOrchestrator) retries creating an instance
+//! and waiting for it when the backend classifies the failure as transient
+//! (see [`TransientError`](crate::TransientError)), destroying the failed
+//! instance first. Delays double after each attempt up to a cap. Nothing is
+//! retried once the user command has started.
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Provisioning attempts made by [`RetryPolicy::default`].
+pub const DEFAULT_ATTEMPTS: u32 = 3;
+
+/// Delay before the first retry made by [`RetryPolicy::default`].
+pub const DEFAULT_BACKOFF: Duration = Duration::from_secs(5);
+
+/// Longest delay between attempts made by [`RetryPolicy::default`].
+pub const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// A failed attempt that is about to be retried.
+#[derive(Debug)]
+pub struct RetryNotice<'a> {
+    /// Number of the attempt that failed, starting at 1.
+    pub attempt: u32,
+    /// Total attempts allowed.
+    pub attempts: u32,
+    /// Delay before the next attempt.
+    pub delay: Duration,
+    /// Why the attempt failed.
+    pub error: &'a (dyn std::error::Error + 'static),
+}
+
+/// Callback told about each attempt that is retried.
+pub type RetryReporter = Arc<dyn Fn(&RetryNotice<'_>) + Send + Sync>;
+
+/// How often, and how patiently, provisioning is retried.
+#[derive(Clone)]
+pub struct RetryPolicy {
+    attempts: u32,
+    backoff: Duration,
+    max_backoff: Duration,
+    reporter: Option<RetryReporter>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new(DEFAULT_ATTEMPTS, DEFAULT_BACKOFF)
+    }
+}
+
+impl RetryPolicy {
+    /// Creates a policy making at most `attempts` attempts, waiting `backoff`
+    /// before the first retry and twice as long before each later one, up to
+    /// [`DEFAULT_MAX_BACKOFF`]. Zero attempts is treated as one.
+    #[must_use]
+    pub fn new(attempts: u32, backoff: Duration) -> Self {
+        Self {
+            attempts: attempts.max(1),
+            backoff,
+            max_backoff: DEFAULT_MAX_BACKOFF.max(backoff),
+            reporter: None,
+        }
+    }
+
+    /// Creates a policy that never retries.
+    #[must_use]
+    pub fn never() -> Self {
+        Self::new(1, Duration::ZERO)
+    }
+
+    /// Caps the delay between attempts.
+    #[must_use]
+    pub const fn with_max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Sets the callback told about each retried attempt.
+    #[must_use]
+    pub fn with_reporter(mut self, reporter: RetryReporter) -> Self {
+        self.reporter = Some(reporter);
+        self
+    }
+
+    /// Returns the total number of attempts.
+    #[must_use]
+    pub const fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    /// Returns the delay after the failed `attempt`, starting at 1.
+    #[must_use]
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let doublings = attempt.saturating_sub(1).min(u32::BITS - 1);
+        self.backoff
+            .saturating_mul(1_u32 << doublings)
+            .min(self.max_backoff)
+    }
+
+    /// Reports a retried attempt to the callback, if any.
+    pub(crate) fn report(&self, notice: &RetryNotice<'_>) {
+        if let Some(reporter) = &self.reporter {
+            reporter(notice);
+        }
+    }
+}
+
+impl fmt::Debug for RetryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("attempts", &self.attempts)
+            .field("backoff", &self.backoff)
+            .field("max_backoff", &self.max_backoff)
+            .field("reporter", &self.reporter.as_ref().map(|_| ".."))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Unit tests for retry delays.
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(1, 5)]
+    #[case(2, 10)]
+    #[case(3, 20)]
+    #[case(5, 60)]
+    #[case(u32::MAX, 60)]
+    fn backoff_doubles_up_to_the_cap(#[case] attempt: u32, #[case] seconds: u64) {
+        let policy = RetryPolicy::default();
+
+        assert_eq!(policy.backoff(attempt), Duration::from_secs(seconds));
+    }
+
+    #[rstest]
+    fn zero_attempts_still_makes_one() {
+        assert_eq!(RetryPolicy::new(0, Duration::ZERO).attempts(), 1);
+        assert_eq!(RetryPolicy::never().attempts(), 1);
+    }
+
+    #[rstest]
+    fn a_long_first_backoff_raises_the_cap() {
+        let policy = RetryPolicy::new(3, Duration::from_secs(90));
+
+        assert_eq!(policy.backoff(2), Duration::from_secs(90));
+    }
+}
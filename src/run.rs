@@ -3,7 +3,9 @@
 //! The run workflow provisions an instance via a backend, waits for SSH
 //! readiness, synchronizes the local workspace, executes a remote command
 //! using the system `ssh` client, and tears the instance down. Remote exit
-//! codes are preserved so callers observe the same status locally.
+//! codes are preserved so callers observe the same status locally. The same
+//! steps are exposed separately for [`Session`]s that keep an instance alive
+//! across several commands.
 
 use std::fmt::Display;
 use std::time::{Duration, Instant};
@@ -13,9 +15,15 @@ use shell_escape::unix::escape;
 use thiserror::Error;
 use tokio::time::sleep;
 
-use crate::backend::{Backend, InstanceHandle, InstanceNetworking, InstanceRequest};
+use crate::backend::{
+    Backend, InstanceNetworking, InstanceRequest, ProvisionedInstance, TransientError,
+};
+use crate::hooks::{HookFailure, Hooks, Order, run_sequence};
+use crate::retry::{RetryNotice, RetryPolicy};
+use crate::session::Session;
 use crate::sync::{
-    CommandRunner, RemoteCommandOutput, SyncError, Syncer, create_cache_directories_command,
+    AsyncCommandRunner, Redactor, RemoteCommandOutput, SyncError, Syncer,
+    create_cache_directories_command,
 };
 
 const CLOUD_INIT_POLL_INTERVAL: Duration = Duration::from_secs(2);
@@ -72,16 +80,77 @@ where
         #[source]
         source: SyncError,
     },
+    /// Raised when a remote `before` hook fails; the command does not run.
+    #[error("before hook failed: {0}")]
+    BeforeHook(#[source] HookFailure),
+    /// Raised when a remote `after` hook fails.
+    #[error("after hook failed: {0}")]
+    AfterHook(#[source] HookFailure),
+    /// Raised when a local hook fails before provisioning.
+    #[error("local before hook failed: {0}")]
+    LocalBeforeHook(#[source] HookFailure),
+    /// Raised when a local hook fails after teardown.
+    #[error("local after hook failed: {0}")]
+    LocalAfterHook(#[source] HookFailure),
     /// Raised when teardown fails after the primary operation succeeded.
-    #[error("failed to destroy instance: {0}")]
-    Teardown(#[source] BackendError),
+    #[error("failed to destroy instance: {message}")]
+    Teardown {
+        /// Human-readable description of the failure, naming the run.
+        message: String,
+        /// Provider-specific error.
+        #[source]
+        source: BackendError,
+    },
+}
+
+impl<BackendError> RunError<BackendError>
+where
+    BackendError: std::error::Error + 'static,
+{
+    /// Masks the secrets of `redactor` in the message and underlying sync
+    /// error. Backend errors are left as they are.
+    #[must_use]
+    pub fn redact(self, redactor: &Redactor) -> Self {
+        match self {
+            Self::Wait { message, source } => Self::Wait {
+                message: redactor.redact(&message),
+                source,
+            },
+            Self::Provisioning { message, source } => Self::Provisioning {
+                message: redactor.redact(&message),
+                source: source.redact(redactor),
+            },
+            Self::ProvisioningTimeout { message } => Self::ProvisioningTimeout {
+                message: redactor.redact(&message),
+            },
+            Self::Sync { message, source } => Self::Sync {
+                message: redactor.redact(&message),
+                source: source.redact(redactor),
+            },
+            Self::Remote { message, source } => Self::Remote {
+                message: redactor.redact(&message),
+                source: source.redact(redactor),
+            },
+            Self::BeforeHook(failure) => Self::BeforeHook(failure.redact(redactor)),
+            Self::AfterHook(failure) => Self::AfterHook(failure.redact(redactor)),
+            Self::LocalBeforeHook(failure) => Self::LocalBeforeHook(failure.redact(redactor)),
+            Self::LocalAfterHook(failure) => Self::LocalAfterHook(failure.redact(redactor)),
+            Self::Teardown { message, source } => Self::Teardown {
+                message: redactor.redact(&message),
+                source,
+            },
+            other @ Self::Provision(_) => other,
+        }
+    }
 }
 
 /// Executes the remote run flow using the provided backend and syncer.
 #[derive(Debug)]
-pub struct RunOrchestrator<B, R: CommandRunner> {
+pub struct RunOrchestrator<B, R: AsyncCommandRunner> {
     backend: B,
     syncer: Syncer<R>,
+    hooks: Hooks,
+    retry: RetryPolicy,
     cloud_init_poll_interval: Duration,
     cloud_init_wait_timeout: Duration,
 }
@@ -90,19 +159,39 @@ impl<B, R> RunOrchestrator<B, R>
 where
     B: Backend,
     B::Error: Display + Send + Sync + std::error::Error + 'static,
-    R: CommandRunner,
+    R: AsyncCommandRunner,
 {
-    /// Creates a new orchestrator.
+    /// Creates a new orchestrator that retries transient provisioning
+    /// failures with [`RetryPolicy::default`].
     #[must_use]
-    pub const fn new(backend: B, syncer: Syncer<R>) -> Self {
+    pub fn new(backend: B, syncer: Syncer<R>) -> Self {
         Self {
             backend,
             syncer,
+            hooks: Hooks {
+                before: Vec::new(),
+                after: Vec::new(),
+            },
+            retry: RetryPolicy::default(),
             cloud_init_poll_interval: CLOUD_INIT_POLL_INTERVAL,
             cloud_init_wait_timeout: CLOUD_INIT_WAIT_TIMEOUT,
         }
     }
 
+    /// Sets the remote hooks run around each command by [`Self::exec`].
+    #[must_use]
+    pub fn with_hooks(mut self, hooks: Hooks) -> Self {
+        self.hooks = hooks;
+        self
+    }
+
+    /// Sets how transient provisioning failures are retried.
+    #[must_use]
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
     /// Overrides the cloud-init polling interval.
     ///
     /// This is primarily used by tests to keep timeout scenarios fast.
@@ -121,11 +210,19 @@ where
         self
     }
 
+    /// Returns the syncer used for workspace sync and remote commands.
+    #[must_use]
+    pub const fn syncer(&self) -> &Syncer<R> {
+        &self.syncer
+    }
+
     /// Runs the end-to-end workflow and returns the remote command output.
     ///
-    /// The remote exit code is returned even when non-zero. Teardown is
-    /// always attempted; when teardown fails the error is surfaced even if
-    /// the remote command succeeded.
+    /// This is a session that runs a single command: [`RunOrchestrator::start`],
+    /// one [`RunOrchestrator::exec`], then [`RunOrchestrator::stop`]. The
+    /// remote exit code is returned even when non-zero. Teardown is always
+    /// attempted; when teardown fails the error is surfaced even if the remote
+    /// command succeeded.
     ///
     /// # Errors
     ///
@@ -137,92 +234,281 @@ where
         source: &Utf8Path,
         remote_command: &str,
     ) -> Result<RemoteCommandOutput, RunError<B::Error>> {
-        let handle = self
-            .backend
-            .create(request)
-            .await
-            .map_err(RunError::Provision)?;
-        let networking = self.wait_for_ready_or_destroy(&handle).await?;
-
-        self.mount_volume_if_needed(&handle, &networking, request)
-            .await?;
+        let session = self.start(request).await?;
+        self.exec_once(session, source, remote_command).await
+    }
 
-        let dest = self.syncer.destination_for(&networking);
-        self.sync_or_destroy(&handle, source, &dest).await?;
+    /// Runs the workflow on an instance that is already provisioned and
+    /// reachable, such as one claimed from a warm pool.
+    ///
+    /// The instance is destroyed afterwards exactly as in
+    /// [`RunOrchestrator::execute`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RunError`] when synchronization, remote execution, or
+    /// teardown fail.
+    pub async fn execute_on(
+        &self,
+        instance: ProvisionedInstance,
+        source: &Utf8Path,
+        remote_command: &str,
+    ) -> Result<RemoteCommandOutput, RunError<B::Error>> {
+        let session = self.attach(instance).await?;
+        self.exec_once(session, source, remote_command).await
+    }
 
-        if request.cloud_init_user_data.is_some() {
-            self.wait_for_cloud_init(&handle, &networking).await?;
+    /// Runs the workflow on an instance claimed from a warm pool, falling
+    /// back to [`RunOrchestrator::execute`] when it no longer answers.
+    ///
+    /// A pooled instance may have been reaped or stopped by the provider
+    /// since it became ready, so it is probed over SSH first. An instance
+    /// that fails the probe is destroyed before a new one is provisioned.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RunError::Teardown`] when the unreachable instance cannot
+    /// be destroyed, and otherwise the errors of
+    /// [`RunOrchestrator::execute_on`] or [`RunOrchestrator::execute`].
+    pub async fn execute_claimed(
+        &self,
+        instance: ProvisionedInstance,
+        source: &Utf8Path,
+        remote_command: &str,
+    ) -> Result<RemoteCommandOutput, RunError<B::Error>> {
+        let probe = self
+            .syncer
+            .run_remote_raw(&instance.networking, "true")
+            .await;
+        if probe.is_ok_and(|output| output.exit_code == Some(0)) {
+            return self.execute_on(instance, source, remote_command).await;
         }
+        let request = instance.request.clone();
+        // Provisioning a replacement while this one may still be running
+        // could leak an instance per run.
+        self.stop(Session::new(instance)).await?;
+        self.execute(&request, source, remote_command).await
+    }
 
-        let output = self
-            .run_remote_or_destroy(&handle, &networking, remote_command)
-            .await?;
-
-        self.backend
-            .destroy(handle)
-            .await
-            .map_err(RunError::Teardown)?;
-
-        Ok(output)
+    /// Provisions an instance and returns a session for running commands on
+    /// it.
+    ///
+    /// The instance is destroyed when it does not become ready or the cache
+    /// volume cannot be mounted. Creating the instance and waiting for it are
+    /// retried under the [`RetryPolicy`] while the backend reports transient
+    /// errors and the failed instance was destroyed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RunError`] when provisioning, readiness checks, or mounting
+    /// the cache volume fail.
+    pub async fn start(&self, request: &InstanceRequest) -> Result<Session, RunError<B::Error>> {
+        let mut attempt = 1;
+        let instance = loop {
+            let failure = match self.provision(request).await {
+                Ok(instance) => break instance,
+                Err(failure) => failure,
+            };
+            if !failure.retryable || attempt >= self.retry.attempts() {
+                return Err(failure.error);
+            }
+            let delay = self.retry.backoff(attempt);
+            self.retry.report(&RetryNotice {
+                attempt,
+                attempts: self.retry.attempts(),
+                delay,
+                error: &failure.error,
+            });
+            sleep(delay).await;
+            attempt += 1;
+        };
+        self.attach(instance).await
     }
 
-    async fn wait_for_ready_or_destroy(
+    /// Turns an instance that is already reachable into a session, mounting
+    /// the cache volume when the request names one.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RunError::Sync`] when the mount command cannot be executed;
+    /// the instance is destroyed in that case.
+    pub async fn attach(
         &self,
-        handle: &InstanceHandle,
-    ) -> Result<InstanceNetworking, RunError<B::Error>> {
-        match self.backend.wait_for_ready(handle).await {
-            Ok(net) => Ok(net),
-            Err(err) => {
-                let message = self.destroy_with_note(handle, &err).await;
-                Err(RunError::Wait {
-                    message,
-                    source: err,
-                })
-            }
+        instance: ProvisionedInstance,
+    ) -> Result<Session, RunError<B::Error>> {
+        let mount = if instance.request.volume_id.is_some() {
+            self.mount_cache_volume(&instance.networking).await
+        } else {
+            Ok(())
+        };
+        let session = Session::new(instance);
+        match mount {
+            Ok(()) => Ok(session),
+            Err(err) => Err(self.destroy_after(session, err).await),
         }
     }
 
-    async fn mount_volume_if_needed(
+    /// Syncs `source` to the session's instance and runs `remote_command`
+    /// between the `before` and `after` hooks.
+    ///
+    /// rsync transfers only what changed since the previous sync, so repeated
+    /// calls stay cheap. On the first call after provisioning with cloud-init
+    /// user data, the command waits for cloud-init to finish. The `after`
+    /// hooks run whatever the command's exit code, and also when the sync or
+    /// a `before` hook fails or the command fails to start. Failures leave
+    /// the instance running; callers decide whether to retry or stop.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RunError`] when synchronization, the cloud-init check, a
+    /// hook, or starting the remote command fail. A failed sync, `before`
+    /// hook, or start of the command takes precedence over a failed `after`
+    /// hook.
+    pub async fn exec(
+        &self,
+        session: &mut Session,
+        source: &Utf8Path,
+        remote_command: &str,
+    ) -> Result<RemoteCommandOutput, RunError<B::Error>> {
+        let output = match self.sync_session(session, source).await {
+            Ok(()) => self.run_command(&session.networking, remote_command).await,
+            Err(err) => Err(err),
+        };
+        let after = self
+            .run_hooks(&session.networking, &self.hooks.after, Order::RunAll)
+            .await;
+        let finished = output?;
+        after.map_err(RunError::AfterHook)?;
+        Ok(finished)
+    }
+
+    /// Runs the `before` hooks and, when they all succeed, the command.
+    async fn run_command(
         &self,
-        handle: &InstanceHandle,
         networking: &InstanceNetworking,
-        request: &InstanceRequest,
-    ) -> Result<(), RunError<B::Error>> {
-        if request.volume_id.is_some() {
-            self.mount_cache_volume(handle, networking).await?;
-        }
-        Ok(())
+        remote_command: &str,
+    ) -> Result<RemoteCommandOutput, RunError<B::Error>> {
+        self.run_hooks(networking, &self.hooks.before, Order::StopAtFailure)
+            .await
+            .map_err(RunError::BeforeHook)?;
+        self.syncer
+            .run_remote(networking, remote_command)
+            .await
+            .map_err(|err| classify_sync_and_run_error(err, &self.syncer.config().ssh_bin))
     }
 
-    async fn sync_or_destroy(
+    /// Syncs `source` to the session's instance, waiting for cloud-init on
+    /// the first call that needs it.
+    async fn sync_session(
         &self,
-        handle: &InstanceHandle,
+        session: &mut Session,
         source: &Utf8Path,
-        dest: &crate::sync::SyncDestination,
     ) -> Result<(), RunError<B::Error>> {
-        if let Err(err) = self.syncer.sync(source, dest) {
-            let message = self.destroy_with_note(handle, &err).await;
-            return Err(RunError::Sync {
-                message,
-                source: err,
-            });
+        let dest = self.syncer.destination_for(&session.networking);
+        let synced = self.syncer.sync(source, &dest).await;
+        if !session.awaiting_cloud_init {
+            return synced
+                .map_err(|err| classify_sync_and_run_error(err, &self.syncer.config().ssh_bin));
         }
+        synced.map_err(|err| RunError::Sync {
+            message: err.to_string(),
+            source: err,
+        })?;
+        self.wait_for_cloud_init(&session.networking).await?;
+        session.awaiting_cloud_init = false;
         Ok(())
     }
 
-    async fn run_remote_or_destroy(
+    async fn run_hooks(
         &self,
-        handle: &InstanceHandle,
         networking: &InstanceNetworking,
+        commands: &[String],
+        order: Order,
+    ) -> Result<(), HookFailure> {
+        run_sequence(commands, order, |command| async move {
+            self.syncer
+                .run_hook(networking, &command)
+                .await
+                .map(|output| output.exit_code)
+        })
+        .await
+    }
+
+    /// Closes the shared SSH connection to the session's instance, leaving
+    /// the instance running.
+    ///
+    /// Commands that keep a session alive after they exit call this before
+    /// dropping the orchestrator, so no master outlives the syncer's control
+    /// directory. [`RunOrchestrator::stop`] closes the connection itself.
+    pub async fn detach(&self, session: &Session) {
+        self.syncer.close_connection(&session.networking).await;
+    }
+
+    /// Closes the shared SSH connection and destroys the session's instance.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RunError::Teardown`] when the backend fails to destroy the
+    /// instance.
+    pub async fn stop(&self, session: Session) -> Result<(), RunError<B::Error>> {
+        self.syncer.close_connection(&session.networking).await;
+        let handle = session.handle;
+        let run_id = handle.run_id.clone();
+        self.backend
+            .destroy(handle)
+            .await
+            .map_err(|err| RunError::Teardown {
+                message: append_run_note(err.to_string(), run_id.as_deref()),
+                source: err,
+            })
+    }
+
+    async fn exec_once(
+        &self,
+        mut session: Session,
+        source: &Utf8Path,
         remote_command: &str,
     ) -> Result<RemoteCommandOutput, RunError<B::Error>> {
-        match self.syncer.run_remote(networking, remote_command) {
-            Ok(result) => Ok(result),
+        match self.exec(&mut session, source, remote_command).await {
+            Ok(output) => {
+                self.stop(session).await?;
+                Ok(output)
+            }
+            Err(err) => Err(self.destroy_after(session, err).await),
+        }
+    }
+
+    /// Creates an instance and waits for it, destroying it when it does not
+    /// become ready.
+    async fn provision(
+        &self,
+        request: &InstanceRequest,
+    ) -> Result<ProvisionedInstance, FailedAttempt<B::Error>> {
+        let handle = self
+            .backend
+            .create(request)
+            .await
+            .map_err(|err| FailedAttempt {
+                retryable: err.is_transient(),
+                error: RunError::Provision(err),
+            })?;
+        match self.backend.wait_for_ready(&handle).await {
+            Ok(networking) => Ok(ProvisionedInstance {
+                handle,
+                networking,
+                request: request.clone(),
+            }),
             Err(err) => {
-                let message = self.destroy_with_note(handle, &err).await;
-                Err(RunError::Remote {
-                    message,
-                    source: err,
+                let message = append_run_note(err.to_string(), handle.run_id.as_deref());
+                let teardown_error = self.backend.destroy(handle).await.err();
+                // Retrying after a failed teardown could leak one instance
+                // per attempt.
+                Err(FailedAttempt {
+                    retryable: err.is_transient() && teardown_error.is_none(),
+                    error: RunError::Wait {
+                        message: append_teardown_note(message, teardown_error.as_ref()),
+                        source: err,
+                    },
                 })
             }
         }
@@ -240,7 +526,6 @@ where
     /// toolchains can write immediately.
     async fn mount_cache_volume(
         &self,
-        handle: &InstanceHandle,
         networking: &InstanceNetworking,
     ) -> Result<(), RunError<B::Error>> {
         let config = self.syncer.config();
@@ -267,21 +552,18 @@ where
 
         let full_command = format!("{mount_command}{mkdir_cache_dirs}");
 
-        match self.syncer.run_remote_raw(networking, &full_command) {
-            Ok(_) => Ok(()),
-            Err(err) => {
-                let message = self.destroy_with_note(handle, &err).await;
-                Err(RunError::Sync {
-                    message,
-                    source: err,
-                })
-            }
-        }
+        self.syncer
+            .run_remote_raw(networking, &full_command)
+            .await
+            .map(|_| ())
+            .map_err(|err| RunError::Sync {
+                message: err.to_string(),
+                source: err,
+            })
     }
 
     async fn wait_for_cloud_init(
         &self,
-        handle: &InstanceHandle,
         networking: &InstanceNetworking,
     ) -> Result<(), RunError<B::Error>> {
         let deadline = Instant::now() + self.cloud_init_wait_timeout;
@@ -289,12 +571,12 @@ where
         let command = format!("sudo test -f {cloud_init_finished_marker}");
 
         while Instant::now() <= deadline {
-            let finished = match self.syncer.run_remote(networking, &command) {
+            // Raw, so the probe never claims the terminal of a `--tty` run.
+            let finished = match self.syncer.run_remote_raw(networking, &command).await {
                 Ok(output) => matches!(output.exit_code, Some(0)),
                 Err(err) => {
-                    let message = self.destroy_with_note(handle, &err).await;
                     return Err(RunError::Provisioning {
-                        message,
+                        message: err.to_string(),
                         source: err,
                     });
                 }
@@ -307,19 +589,111 @@ where
             sleep(self.cloud_init_poll_interval).await;
         }
 
-        let timeout_message = format!(
-            "cloud-init did not finish within {} seconds",
-            self.cloud_init_wait_timeout.as_secs()
-        );
-        let message_with_teardown = self.destroy_with_note(handle, &timeout_message).await;
         Err(RunError::ProvisioningTimeout {
-            message: message_with_teardown,
+            message: format!(
+                "cloud-init did not finish within {} seconds",
+                self.cloud_init_wait_timeout.as_secs()
+            ),
         })
     }
 
-    async fn destroy_with_note<E: Display>(&self, handle: &InstanceHandle, err: &E) -> String {
-        let teardown_error = self.backend.destroy(handle.clone()).await.err();
-        append_teardown_note(err.to_string(), teardown_error.as_ref())
+    /// Destroys the session's instance after `err` and notes any teardown
+    /// failure in the error message.
+    async fn destroy_after(&self, session: Session, err: RunError<B::Error>) -> RunError<B::Error> {
+        self.syncer.close_connection(&session.networking).await;
+        let teardown_error = self.backend.destroy(session.handle).await.err();
+        err.with_teardown_note(teardown_error.as_ref())
+    }
+}
+
+/// A provisioning attempt that failed.
+struct FailedAttempt<E>
+where
+    E: std::error::Error + 'static,
+{
+    error: RunError<E>,
+    /// Whether the failure is transient and left no instance behind.
+    retryable: bool,
+}
+
+impl<E> RunError<E>
+where
+    E: std::error::Error + 'static,
+{
+    fn with_teardown_note<T: Display>(self, teardown_error: Option<&T>) -> Self {
+        let note = |message| append_teardown_note(message, teardown_error);
+        match self {
+            Self::Wait { message, source } => Self::Wait {
+                message: note(message),
+                source,
+            },
+            Self::Provisioning { message, source } => Self::Provisioning {
+                message: note(message),
+                source,
+            },
+            Self::ProvisioningTimeout { message } => Self::ProvisioningTimeout {
+                message: note(message),
+            },
+            Self::Sync { message, source } => Self::Sync {
+                message: note(message),
+                source,
+            },
+            Self::Remote { message, source } => Self::Remote {
+                message: note(message),
+                source,
+            },
+            Self::BeforeHook(failure) => Self::BeforeHook(failure.with_note(note)),
+            Self::AfterHook(failure) => Self::AfterHook(failure.with_note(note)),
+            other @ (Self::Provision(_)
+            | Self::LocalBeforeHook(_)
+            | Self::LocalAfterHook(_)
+            | Self::Teardown { .. }) => other,
+        }
+    }
+}
+
+/// Maps an error from [`Syncer::sync_and_run`] onto the step that failed.
+///
+/// Every command other than `ssh` (`rsync`, and `git` or `tar` in the git sync
+/// modes) runs during the sync.
+fn classify_sync_and_run_error<E>(err: SyncError, ssh_bin: &str) -> RunError<E>
+where
+    E: std::error::Error + 'static,
+{
+    let message = err.to_string();
+    let failed_in_sync = match &err {
+        SyncError::Spawn { program, .. } | SyncError::CommandFailure { program, .. } => {
+            program != ssh_bin
+        }
+        SyncError::InvalidConfig { .. }
+        | SyncError::MissingSource { .. }
+        | SyncError::IgnoreFile { .. }
+        | SyncError::Staging { .. }
+        | SyncError::ControlSocket { .. }
+        | SyncError::SizeLimit { .. }
+        | SyncError::SecretPattern { .. }
+        | SyncError::SecretScan { .. }
+        | SyncError::InvalidEnv { .. } => true,
+    };
+    if failed_in_sync {
+        RunError::Sync {
+            message,
+            source: err,
+        }
+    } else {
+        RunError::Remote {
+            message,
+            source: err,
+        }
+    }
+}
+
+/// Names the run that created the instance, so the message can be matched
+/// with the instance's `mriya-run-<id>` tag.
+fn append_run_note(message: String, run_id: Option<&str>) -> String {
+    match run_id {
+        Some(id) => format!("{message} (run {id})"),
+        None => message,
     }
 }
 
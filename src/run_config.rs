@@ -0,0 +1,123 @@
+//! Settings for the remote command of `mriya run`, read from the `[run]`
+//! configuration section.
+
+use std::env;
+use std::ffi::OsString;
+use std::time::Duration;
+
+use ortho_config::OrthoConfig;
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::hooks::Hooks;
+use crate::retry::{DEFAULT_ATTEMPTS, DEFAULT_BACKOFF, RetryPolicy};
+use crate::sync::{RemoteEnv, SyncError};
+
+/// Run configuration layered via `OrthoConfig`.
+#[derive(Clone, Debug, Default, Deserialize, OrthoConfig, PartialEq, Eq)]
+#[ortho_config(
+    prefix = "MRIYA_RUN",
+    discovery(
+        app_name = "mriya",
+        env_var = "MRIYA_CONFIG_PATH",
+        config_file_name = "mriya.toml",
+        dotfile_name = ".mriya.toml",
+        project_file_name = "mriya.toml"
+    )
+)]
+pub struct RunConfig {
+    /// Variables set for the remote command, as `KEY=VALUE` or as a bare
+    /// `KEY` copied from the local environment.
+    #[serde(default)]
+    pub env: Vec<String>,
+    /// Names of local variables copied to the remote command when set.
+    #[serde(default)]
+    pub pass_env: Vec<String>,
+    /// Names of forwarded variables whose values are masked in output, in
+    /// addition to names that look secret.
+    #[serde(default)]
+    pub secret_env: Vec<String>,
+    /// Commands run on the instance after the sync and before the command.
+    #[serde(default)]
+    pub before: Vec<String>,
+    /// Commands run on the instance after the command, whatever its exit
+    /// code.
+    #[serde(default)]
+    pub after: Vec<String>,
+    /// Commands run on this machine before provisioning.
+    #[serde(default)]
+    pub local_before: Vec<String>,
+    /// Commands run on this machine after teardown.
+    #[serde(default)]
+    pub local_after: Vec<String>,
+    /// Attempts made to provision an instance when failures are transient;
+    /// `1` disables retries.
+    pub provision_attempts: Option<u32>,
+    /// Seconds waited before the first provisioning retry, doubling for each
+    /// later one.
+    pub provision_backoff_secs: Option<u64>,
+}
+
+impl RunConfig {
+    /// Loads run configuration without parsing CLI arguments.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RunConfigError::Parse`] when merging sources fails.
+    pub fn load_without_cli_args() -> Result<Self, RunConfigError> {
+        Self::load_from_iter([OsString::from("mriya")])
+            .map_err(|err| RunConfigError::Parse(err.to_string()))
+    }
+
+    /// Resolves the environment forwarded to the remote command.
+    ///
+    /// `pass_env` is applied first, then `env`, then `overrides` (the
+    /// `--env` flags), so later definitions of a name win. Values of bare
+    /// names come from this process's environment.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SyncError::InvalidEnv`] when a name is not a valid shell
+    /// variable name.
+    pub fn remote_env(&self, overrides: &[String]) -> Result<RemoteEnv, SyncError> {
+        let entries = [self.env.as_slice(), overrides].concat();
+        RemoteEnv::resolve(&self.pass_env, &entries, |name| env::var(name).ok())
+    }
+
+    /// Returns the hooks run on the instance around the command.
+    #[must_use]
+    pub fn remote_hooks(&self) -> Hooks {
+        Hooks {
+            before: self.before.clone(),
+            after: self.after.clone(),
+        }
+    }
+
+    /// Returns the hooks run on this machine around the whole run.
+    #[must_use]
+    pub fn local_hooks(&self) -> Hooks {
+        Hooks {
+            before: self.local_before.clone(),
+            after: self.local_after.clone(),
+        }
+    }
+
+    /// Returns the policy for retrying transient provisioning failures,
+    /// falling back to [`DEFAULT_ATTEMPTS`] and [`DEFAULT_BACKOFF`].
+    #[must_use]
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy::new(
+            self.provision_attempts.unwrap_or(DEFAULT_ATTEMPTS),
+            self.provision_backoff_secs
+                .map_or(DEFAULT_BACKOFF, Duration::from_secs),
+        )
+    }
+}
+
+/// Errors raised while loading run configuration.
+#[derive(Clone, Debug, Error, Eq, PartialEq)]
+pub enum RunConfigError {
+    /// Raised when configuration sources cannot be merged.
+    #[error("failed to load run configuration: {0}")]
+    Parse(String),
+}
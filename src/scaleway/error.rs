@@ -1,6 +1,6 @@
 //! Error types for the Scaleway backend.
 
-use crate::backend::BackendError;
+use crate::backend::{BackendError, TransientError};
 use crate::config::ConfigError;
 use scaleway_rs::ScalewayError;
 use thiserror::Error;
@@ -60,6 +60,13 @@ pub enum ScalewayBackendError {
         /// Current state reported by the provider.
         state: String,
     },
+    /// Raised when the provider reports a temporary failure, such as a
+    /// server error, rate limiting, or no stock of the instance type.
+    #[error("provider temporarily unavailable: {message}")]
+    Unavailable {
+        /// Message returned by the provider.
+        message: String,
+    },
     /// Wrapper for provider level failures.
     #[error("provider error: {message}")]
     Provider {
@@ -86,6 +93,16 @@ pub enum ScalewayBackendError {
         /// Error message from the provider.
         message: String,
     },
+    /// Raised when a public IP attached to an instance cannot be tagged.
+    #[error("failed to tag IP {ip_id} of instance {instance_id}: {message}")]
+    IpTaggingFailed {
+        /// Flexible IP identifier.
+        ip_id: String,
+        /// Instance identifier.
+        instance_id: String,
+        /// Error message from the provider.
+        message: String,
+    },
     /// Raised when a volume cannot be created.
     #[error("failed to create volume {name} in zone {zone}: {message}")]
     VolumeCreateFailed {
@@ -106,6 +123,14 @@ pub enum ScalewayBackendError {
     },
 }
 
+impl TransientError for ScalewayBackendError {
+    /// Temporary provider failures and readiness timeouts are transient; a
+    /// readiness timeout usually means a bad host rather than a bad request.
+    fn is_transient(&self) -> bool {
+        matches!(self, Self::Unavailable { .. } | Self::Timeout { .. })
+    }
+}
+
 impl From<ScalewayError> for ScalewayBackendError {
     fn from(value: ScalewayError) -> Self {
         Self::Provider {
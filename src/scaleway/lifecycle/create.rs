@@ -4,6 +4,7 @@
 //! The creation request sets `stopped: true` so the payload is available when
 //! the instance is powered on.
 
+use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -68,8 +69,9 @@ impl ScalewayBackend {
     /// - `request`: Desired instance configuration (zone, type, project, optional cloud-init).
     /// - `image_id`: Provider image identifier to boot from.
     /// - Returns: `Result<ScalewayInstance, ScalewayBackendError>` containing the created instance.
-    /// - Errors: `Provider`/network failures, and `InstanceTypeUnavailable` when the requested
-    ///   instance type is not available in the selected zone.
+    /// - Errors: `Provider`/network failures, `Unavailable` for server errors, rate limiting,
+    ///   exhausted stock, and connection failures, and `InstanceTypeUnavailable` when the
+    ///   requested instance type is not available in the selected zone.
     pub(in crate::scaleway) async fn create_instance_stopped(
         &self,
         request: &InstanceRequest,
@@ -81,7 +83,7 @@ impl ScalewayBackend {
             request.zone
         );
         let name = format!("mriya-{}", Uuid::new_v4().simple());
-        let tags = Self::instance_tags(self.test_run_id.as_deref());
+        let tags = self.instance_tags();
         let payload = CreateServerRequest {
             name,
             commercial_type: request.instance_type.clone(),
@@ -101,8 +103,13 @@ impl ScalewayBackend {
             .json(&payload)
             .send()
             .await
-            .map_err(|err| ScalewayBackendError::Provider {
-                message: err.to_string(),
+            .map_err(|err| {
+                let message = err.to_string();
+                if err.is_connect() || err.is_timeout() {
+                    ScalewayBackendError::Unavailable { message }
+                } else {
+                    ScalewayBackendError::Provider { message }
+                }
             })?;
 
         let status = response.status();
@@ -121,16 +128,32 @@ impl ScalewayBackend {
             return Ok(parsed.server);
         }
 
-        let message = String::from_utf8_lossy(&body).into_owned();
-        if let Ok(api_err) = serde_json::from_slice::<scaleway_rs::ScalewayApiError>(&body)
-            && Self::is_instance_type_error(&api_err, request)
-        {
-            return Err(ScalewayBackendError::InstanceTypeUnavailable {
-                instance_type: request.instance_type.clone(),
-                zone: request.zone.clone(),
-            });
-        }
+        Err(create_error(status, &body, request))
+    }
+}
 
-        Err(ScalewayBackendError::Provider { message })
+/// Classifies a failed create response.
+///
+/// Server errors, rate limiting, and exhausted stock are transient, so they
+/// are checked before the instance type errors that describe a bad request.
+pub(super) fn create_error(
+    status: StatusCode,
+    body: &[u8],
+    request: &InstanceRequest,
+) -> ScalewayBackendError {
+    let message = String::from_utf8_lossy(body).into_owned();
+    let api_err = serde_json::from_slice::<scaleway_rs::ScalewayApiError>(body).ok();
+    let out_of_stock = api_err
+        .as_ref()
+        .is_some_and(|err| err.etype == "out_of_stock");
+    if status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS || out_of_stock {
+        return ScalewayBackendError::Unavailable { message };
+    }
+    if api_err.is_some_and(|err| ScalewayBackend::is_instance_type_error(&err, request)) {
+        return ScalewayBackendError::InstanceTypeUnavailable {
+            instance_type: request.instance_type.clone(),
+            zone: request.zone.clone(),
+        };
     }
+    ScalewayBackendError::Provider { message }
 }
@@ -0,0 +1,83 @@
+//! Flexible IP tagging helpers for the Scaleway backend.
+//!
+//! Servers created with `dynamic_ip_required` receive their public IP when
+//! they start, so the IP cannot be tagged in the creation request. Tagging it
+//! once the address appears lets `mriya-janitor` sweep IPs left behind when a
+//! server deletion fails part-way.
+
+use serde::Serialize;
+
+use crate::backend::InstanceHandle;
+use crate::janitor::ResourceType;
+
+use super::super::{ScalewayBackend, ScalewayBackendError};
+
+#[derive(Serialize)]
+struct UpdateIpRequest {
+    tags: Vec<String>,
+}
+
+impl ScalewayBackend {
+    /// Applies the IP tags to every public IP attached to the instance.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ScalewayBackendError::IpTaggingFailed`] when the API rejects
+    /// the update, or [`ScalewayBackendError::Provider`] when the request
+    /// cannot be sent.
+    pub(in crate::scaleway) async fn tag_public_ips(
+        &self,
+        handle: &InstanceHandle,
+    ) -> Result<(), ScalewayBackendError> {
+        let servers = self
+            .api
+            .list_instances(&handle.zone)
+            .servers(&handle.id)
+            .per_page(1)
+            .run_async()
+            .await?;
+        let request = UpdateIpRequest {
+            tags: self.tags_for(ResourceType::Ips),
+        };
+
+        for ip in servers.iter().flat_map(|server| server.public_ips.iter()) {
+            self.patch_ip(handle, &ip.id, &request).await?;
+        }
+        Ok(())
+    }
+
+    async fn patch_ip(
+        &self,
+        handle: &InstanceHandle,
+        ip_id: &str,
+        request: &UpdateIpRequest,
+    ) -> Result<(), ScalewayBackendError> {
+        let url = format!(
+            "{}/zones/{}/ips/{ip_id}",
+            super::SCALEWAY_INSTANCE_API_BASE,
+            handle.zone
+        );
+
+        let response = super::HTTP_CLIENT
+            .patch(&url)
+            .header("X-Auth-Token", &self.config.secret_key)
+            .json(request)
+            .timeout(super::HTTP_TIMEOUT)
+            .send()
+            .await
+            .map_err(|err| ScalewayBackendError::Provider {
+                message: err.to_string(),
+            })?;
+
+        if response.status().is_success() {
+            return Ok(());
+        }
+
+        let message = response.text().await.unwrap_or_default();
+        Err(ScalewayBackendError::IpTaggingFailed {
+            ip_id: ip_id.to_owned(),
+            instance_id: handle.id.clone(),
+            message,
+        })
+    }
+}
@@ -5,6 +5,7 @@ use std::time::Duration;
 
 mod create;
 mod image;
+mod ip_tags;
 mod volume_attach;
 mod volume_create;
 mod volume_detach;
@@ -0,0 +1,71 @@
+//! Tests for classifying failed instance creation.
+
+use reqwest::StatusCode;
+use rstest::rstest;
+
+use super::super::create::create_error;
+use crate::backend::{InstanceRequest, TransientError};
+use crate::scaleway::ScalewayBackendError;
+
+fn request() -> InstanceRequest {
+    InstanceRequest {
+        image_label: "label".to_owned(),
+        instance_type: "DEV1-S".to_owned(),
+        zone: "fr-par-1".to_owned(),
+        project_id: "proj".to_owned(),
+        organisation_id: None,
+        architecture: "x86_64".to_owned(),
+        volume_id: None,
+        cloud_init_user_data: None,
+    }
+}
+
+#[rstest]
+#[case(
+    StatusCode::INTERNAL_SERVER_ERROR,
+    r#"{"message":"boom","type":"internal"}"#
+)]
+#[case(StatusCode::SERVICE_UNAVAILABLE, "upstream connect error")]
+#[case(
+    StatusCode::TOO_MANY_REQUESTS,
+    r#"{"message":"slow down","type":"quotas_exceeded"}"#
+)]
+#[case(
+    StatusCode::BAD_REQUEST,
+    r#"{"message":"no capacity","type":"out_of_stock","resource":"commercial_type"}"#
+)]
+fn transient_create_failures_are_unavailable(#[case] status: StatusCode, #[case] body: &str) {
+    let err = create_error(status, body.as_bytes(), &request());
+
+    assert!(
+        matches!(err, ScalewayBackendError::Unavailable { .. }),
+        "expected Unavailable, got {err:?}"
+    );
+    assert!(err.is_transient());
+}
+
+#[rstest]
+fn unknown_instance_types_are_not_transient() {
+    let body = r#"{"message":"invalid","type":"invalid_arguments","resource":"commercial_type"}"#;
+
+    let err = create_error(StatusCode::BAD_REQUEST, body.as_bytes(), &request());
+
+    assert!(
+        matches!(err, ScalewayBackendError::InstanceTypeUnavailable { .. }),
+        "expected InstanceTypeUnavailable, got {err:?}"
+    );
+    assert!(!err.is_transient());
+}
+
+#[rstest]
+fn other_client_errors_are_not_transient() {
+    let body = r#"{"message":"quota exceeded","type":"permissions_denied"}"#;
+
+    let err = create_error(StatusCode::FORBIDDEN, body.as_bytes(), &request());
+
+    assert!(
+        matches!(err, ScalewayBackendError::Provider { .. }),
+        "expected Provider, got {err:?}"
+    );
+    assert!(!err.is_transient());
+}
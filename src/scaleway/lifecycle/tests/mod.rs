@@ -99,6 +99,7 @@ fn backend_fixture() -> ScalewayBackend {
         api: ScalewayApi::new("dummy"),
         config: dummy_config(),
         test_run_id: None,
+        provenance: None,
         ssh_port: DEFAULT_SSH_PORT,
         poll_interval: Duration::from_millis(1),
         wait_timeout: Duration::from_millis(5),
@@ -129,5 +130,37 @@ async fn power_on_if_needed_errors_when_not_allowed(backend_fixture: ScalewayBac
     );
 }
 
+mod create;
 mod image;
 mod wait;
+
+#[rstest]
+fn tags_for_appends_run_provenance(backend_fixture: ScalewayBackend) {
+    let provenance = crate::provenance::RunProvenance {
+        run_id: String::from("abc123"),
+        user: String::from("ada"),
+        hostname: String::from("box"),
+        project: String::from("mriya"),
+        git_commit: None,
+        created_at: 42,
+    };
+    let backend = ScalewayBackend {
+        test_run_id: Some(String::from("run-1")),
+        ..backend_fixture
+    }
+    .with_provenance(provenance);
+
+    assert_eq!(
+        backend.tags_for(crate::janitor::ResourceType::Servers),
+        vec![
+            String::from("mriya"),
+            String::from("ephemeral"),
+            String::from("mriya-test-run-run-1"),
+            String::from("mriya-run-abc123"),
+            String::from("mriya-user-ada"),
+            String::from("mriya-host-box"),
+            String::from("mriya-project-mriya"),
+            String::from("mriya-created-42"),
+        ]
+    );
+}
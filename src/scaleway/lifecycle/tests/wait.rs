@@ -103,6 +103,7 @@ async fn wait_for_public_ip_returns_missing_ip() {
     let handle = InstanceHandle {
         id: "id".to_owned(),
         zone: "zone".to_owned(),
+        run_id: None,
     };
     let result = fake.wait_for_public_ip(&handle).await;
     assert!(
@@ -125,6 +126,7 @@ async fn wait_for_ssh_ready_succeeds_when_port_listens() {
         api: ScalewayApi::new("dummy"),
         config: super::dummy_config(),
         test_run_id: None,
+        provenance: None,
         ssh_port: DEFAULT_SSH_PORT,
         poll_interval: Duration::from_millis(1),
         wait_timeout: Duration::from_millis(200),
@@ -133,6 +135,7 @@ async fn wait_for_ssh_ready_succeeds_when_port_listens() {
     let handle = InstanceHandle {
         id: String::from("id"),
         zone: String::from("zone"),
+        run_id: None,
     };
     let networking = InstanceNetworking {
         public_ip: addr.ip(),
@@ -158,6 +161,7 @@ async fn wait_for_ssh_ready_times_out_when_port_closed() {
         api: ScalewayApi::new("dummy"),
         config: super::dummy_config(),
         test_run_id: None,
+        provenance: None,
         ssh_port: DEFAULT_SSH_PORT,
         poll_interval: Duration::from_millis(1),
         wait_timeout: Duration::from_millis(50),
@@ -166,6 +170,7 @@ async fn wait_for_ssh_ready_times_out_when_port_closed() {
     let handle = InstanceHandle {
         id: String::from("id"),
         zone: String::from("zone"),
+        run_id: None,
     };
     let networking = InstanceNetworking {
         public_ip: IpAddr::from_str("127.0.0.1").expect("loopback address literal should parse"),
@@ -194,6 +199,7 @@ async fn wait_until_gone_times_out_on_residual() {
     let handle = InstanceHandle {
         id: "id".to_owned(),
         zone: "zone".to_owned(),
+        run_id: None,
     };
     let result = fake.wait_until_gone(&handle).await;
     assert!(matches!(
@@ -14,7 +14,8 @@ use lifecycle::InstanceSnapshot;
 use scaleway_rs::ScalewayApi;
 use types::{Action, Zone};
 
-use crate::janitor::{TEST_RUN_ID_ENV, TEST_RUN_TAG_PREFIX};
+use crate::janitor::{ResourceType, TEST_RUN_ID_ENV, TEST_RUN_TAG_PREFIX};
+use crate::provenance::RunProvenance;
 
 const DEFAULT_SSH_PORT: u16 = 22;
 const POLL_INTERVAL: Duration = Duration::from_secs(5);
@@ -28,6 +29,7 @@ pub struct ScalewayBackend {
     api: ScalewayApi,
     config: ScalewayConfig,
     test_run_id: Option<String>,
+    provenance: Option<RunProvenance>,
     ssh_port: u16,
     poll_interval: Duration,
     wait_timeout: Duration,
@@ -73,12 +75,26 @@ impl ScalewayBackend {
             api: ScalewayApi::new(&config.secret_key),
             config,
             test_run_id,
+            provenance: None,
             ssh_port: DEFAULT_SSH_PORT,
             poll_interval: POLL_INTERVAL,
             wait_timeout: WAIT_TIMEOUT,
         })
     }
 
+    /// Tags every resource the backend creates with run provenance.
+    #[must_use]
+    pub fn with_provenance(mut self, provenance: RunProvenance) -> Self {
+        self.provenance = Some(provenance);
+        self
+    }
+
+    /// Returns credentials that must never appear in output, for masking
+    /// with a [`Redactor`](crate::sync::Redactor).
+    pub fn secret_values(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.config.secret_key.as_str())
+    }
+
     /// Builds an instance request using the backend's defaults.
     ///
     /// # Errors
@@ -118,18 +134,46 @@ impl ScalewayBackend {
         tags
     }
 
-    fn instance_tags(test_run_id: Option<&str>) -> Vec<String> {
-        Self::build_tags(
-            vec![String::from("mriya"), String::from("ephemeral")],
-            test_run_id,
-        )
+    /// Returns the tags applied to a resource of the given type.
+    ///
+    /// Every resource type Mriya can create carries the test-run tag when a
+    /// test run ID is configured, so `mriya-janitor` can sweep it, followed by
+    /// the run provenance tags when provenance is configured.
+    #[must_use]
+    pub fn tags_for(&self, resource: ResourceType) -> Vec<String> {
+        let mut tags = Self::resource_tags(resource, self.test_run_id.as_deref());
+        if let Some(provenance) = self.provenance.as_ref() {
+            tags.extend(provenance.tags());
+        }
+        tags
     }
 
-    fn volume_tags(test_run_id: Option<&str>) -> Vec<String> {
-        Self::build_tags(
-            vec![String::from("mriya"), String::from("cache")],
-            test_run_id,
-        )
+    /// Returns `true` when public IPs need tags beyond the defaults: the
+    /// test-run tag for the janitor, or the run provenance.
+    fn tags_ips(&self) -> bool {
+        self.provenance.is_some()
+            || self
+                .test_run_id
+                .as_deref()
+                .is_some_and(|id| !id.trim().is_empty())
+    }
+
+    fn resource_tags(resource: ResourceType, test_run_id: Option<&str>) -> Vec<String> {
+        let role = match resource {
+            ResourceType::Servers | ResourceType::Ips | ResourceType::SecurityGroups => "ephemeral",
+            ResourceType::Volumes => "cache",
+            ResourceType::Images => "image",
+            ResourceType::Snapshots => "snapshot",
+        };
+        Self::build_tags(vec![String::from("mriya"), String::from(role)], test_run_id)
+    }
+
+    fn instance_tags(&self) -> Vec<String> {
+        self.tags_for(ResourceType::Servers)
+    }
+
+    fn volume_tags(&self) -> Vec<String> {
+        self.tags_for(ResourceType::Volumes)
     }
 }
 
@@ -149,6 +193,7 @@ impl Backend for ScalewayBackend {
             let handle = InstanceHandle {
                 id: server.id.clone(),
                 zone: request.zone.clone(),
+                run_id: self.provenance.as_ref().map(|prov| prov.run_id.clone()),
             };
 
             // Attach cache volume before powering on (instance is stopped)
@@ -191,6 +236,9 @@ impl Backend for ScalewayBackend {
     ) -> BackendFuture<'a, InstanceNetworking, Self::Error> {
         Box::pin(async move {
             let networking = self.wait_for_public_ip(handle).await?;
+            if self.tags_ips() {
+                self.tag_public_ips(handle).await?;
+            }
             self.wait_for_ssh_ready(handle, &networking).await?;
             Ok(networking)
         })
@@ -227,16 +275,18 @@ impl VolumeBackend for ScalewayBackend {
 mod tests {
     //! Unit tests for Scaleway backend tagging.
     use super::ScalewayBackend;
+    use crate::janitor::ResourceType;
+    use rstest::rstest;
 
     #[test]
     fn instance_tags_omits_test_tag_when_unset() {
-        let tags = ScalewayBackend::instance_tags(None);
+        let tags = ScalewayBackend::resource_tags(ResourceType::Servers, None);
         assert_eq!(tags, vec![String::from("mriya"), String::from("ephemeral")]);
     }
 
     #[test]
     fn instance_tags_adds_test_run_tag() {
-        let tags = ScalewayBackend::instance_tags(Some("run-123"));
+        let tags = ScalewayBackend::resource_tags(ResourceType::Servers, Some("run-123"));
         assert_eq!(
             tags,
             vec![
@@ -249,13 +299,13 @@ mod tests {
 
     #[test]
     fn volume_tags_omits_test_tag_when_unset() {
-        let tags = ScalewayBackend::volume_tags(None);
+        let tags = ScalewayBackend::resource_tags(ResourceType::Volumes, None);
         assert_eq!(tags, vec![String::from("mriya"), String::from("cache")]);
     }
 
     #[test]
     fn volume_tags_adds_test_run_tag() {
-        let tags = ScalewayBackend::volume_tags(Some("run-123"));
+        let tags = ScalewayBackend::resource_tags(ResourceType::Volumes, Some("run-123"));
         assert_eq!(
             tags,
             vec![
@@ -265,4 +315,21 @@ mod tests {
             ]
         );
     }
+
+    #[rstest]
+    #[case(ResourceType::Ips, "ephemeral")]
+    #[case(ResourceType::Images, "image")]
+    #[case(ResourceType::Snapshots, "snapshot")]
+    #[case(ResourceType::SecurityGroups, "ephemeral")]
+    fn resource_tags_add_test_run_tag(#[case] resource: ResourceType, #[case] role: &str) {
+        let tags = ScalewayBackend::resource_tags(resource, Some("run-123"));
+        assert_eq!(
+            tags,
+            vec![
+                String::from("mriya"),
+                String::from(role),
+                String::from("mriya-test-run-run-123"),
+            ]
+        );
+    }
 }
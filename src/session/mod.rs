@@ -0,0 +1,234 @@
+//! Long-lived sessions on a single provisioned instance.
+//!
+//! A [`Session`] wraps the handle and networking of an instance that stays up
+//! between commands, so an edit/test loop can re-sync incrementally and run
+//! again without paying for provisioning each time. The orchestration lives on
+//! [`RunOrchestrator`]: [`start`] provisions, [`exec`] syncs and runs, and
+//! [`stop`] tears down. A one-shot `mriya run` is a session that runs a single
+//! command.
+//!
+//! The CLI keeps sessions across invocations by recording them in a JSON file
+//! per session name under the local state directory.
+//!
+//! [`RunOrchestrator`]: crate::run::RunOrchestrator
+//! [`start`]: crate::run::RunOrchestrator::start
+//! [`exec`]: crate::run::RunOrchestrator::exec
+//! [`stop`]: crate::run::RunOrchestrator::stop
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::backend::{InstanceHandle, InstanceNetworking, ProvisionedInstance};
+use crate::pool::{PoolStore, STATE_DIR_ENV};
+use crate::sync::Redactor;
+
+/// Name used when no session name is given.
+pub const DEFAULT_SESSION_NAME: &str = "default";
+
+/// Subdirectory of the state directory holding session files.
+const SESSION_DIR: &str = "sessions";
+
+/// An instance kept alive so commands can run on it repeatedly.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Session {
+    /// Backend handle used for teardown.
+    pub handle: InstanceHandle,
+    /// Connection details for SSH and rsync.
+    pub networking: InstanceNetworking,
+    /// `true` until cloud-init has been confirmed finished on the instance.
+    ///
+    /// The check runs once, after the first sync, so later commands skip it.
+    pub awaiting_cloud_init: bool,
+}
+
+impl Session {
+    /// Wraps a provisioned instance in a session.
+    #[must_use]
+    pub fn new(instance: ProvisionedInstance) -> Self {
+        Self {
+            awaiting_cloud_init: instance.request.cloud_init_user_data.is_some(),
+            handle: instance.handle,
+            networking: instance.networking,
+        }
+    }
+}
+
+/// Errors raised while recording or reading sessions.
+#[derive(Debug, Error)]
+pub enum SessionError {
+    /// Raised when no state directory can be determined.
+    #[error("cannot determine state directory: set {STATE_DIR_ENV}, XDG_STATE_HOME, or HOME")]
+    StateDirUnavailable,
+    /// Raised when a session name is empty or contains unsupported characters.
+    #[error("invalid session name '{0}': use letters, digits, '-' or '_'")]
+    InvalidName(String),
+    /// Raised when starting a session whose name is already in use.
+    #[error("session '{0}' already exists: stop it with `mriya session stop` first")]
+    AlreadyExists(String),
+    /// Raised when no session with the given name has been started.
+    #[error("no session named '{0}': start one with `mriya session start`")]
+    NotFound(String),
+    /// Raised when file system operations fail.
+    #[error("failed to access {path}: {message}")]
+    Io {
+        /// Path that could not be accessed.
+        path: Utf8PathBuf,
+        /// Human-readable error message.
+        message: String,
+    },
+    /// Raised when a session file cannot be parsed.
+    #[error("failed to parse {path}: {message}")]
+    Parse {
+        /// Path that could not be parsed.
+        path: Utf8PathBuf,
+        /// Human-readable error message.
+        message: String,
+    },
+}
+
+impl SessionError {
+    /// Masks the secrets of `redactor` in the error's message.
+    #[must_use]
+    pub fn redact(mut self, redactor: &Redactor) -> Self {
+        if let Self::Io { message, .. } | Self::Parse { message, .. } = &mut self {
+            *message = redactor.redact(message);
+        }
+        self
+    }
+}
+
+/// Records sessions as JSON files under the local state directory.
+#[derive(Clone, Debug)]
+pub struct SessionStore {
+    dir: Utf8PathBuf,
+}
+
+impl SessionStore {
+    /// Creates a store rooted at `state_dir`.
+    #[must_use]
+    pub fn new(state_dir: &Utf8Path) -> Self {
+        Self {
+            dir: state_dir.join(SESSION_DIR),
+        }
+    }
+
+    /// Resolves the local state directory shared with the warm pool.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SessionError::StateDirUnavailable`] when none can be found.
+    pub fn default_state_dir() -> Result<Utf8PathBuf, SessionError> {
+        PoolStore::default_state_dir().map_err(|_| SessionError::StateDirUnavailable)
+    }
+
+    /// Path of the file recording session `name`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SessionError::InvalidName`] when `name` is not a valid
+    /// session name.
+    pub fn session_path(&self, name: &str) -> Result<Utf8PathBuf, SessionError> {
+        let valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_'));
+        if !valid {
+            return Err(SessionError::InvalidName(name.to_owned()));
+        }
+        Ok(self.dir.join(format!("{name}.json")))
+    }
+
+    /// Records a new session, refusing to replace an existing one.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SessionError::AlreadyExists`] when `name` is in use, or
+    /// [`SessionError::Io`] when the file cannot be written.
+    pub fn create(&self, name: &str, session: &Session) -> Result<(), SessionError> {
+        let path = self.session_path(name)?;
+        fs::create_dir_all(&self.dir).map_err(|err| io_error(&self.dir, &err))?;
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .map_err(|err| match err.kind() {
+                io::ErrorKind::AlreadyExists => SessionError::AlreadyExists(name.to_owned()),
+                _ => io_error(&path, &err),
+            })?;
+        file.write_all(&encode(&path, session)?)
+            .and_then(|()| file.sync_all())
+            .map_err(|err| io_error(&path, &err))
+    }
+
+    /// Replaces the record of an existing session atomically.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SessionError`] when the file cannot be written.
+    pub fn update(&self, name: &str, session: &Session) -> Result<(), SessionError> {
+        let path = self.session_path(name)?;
+        let tmp_path = self.dir.join(format!("{name}.json.tmp"));
+        let mut tmp = File::create(&tmp_path).map_err(|err| io_error(&tmp_path, &err))?;
+        tmp.write_all(&encode(&path, session)?)
+            .and_then(|()| tmp.sync_all())
+            .map_err(|err| io_error(&tmp_path, &err))?;
+        fs::rename(&tmp_path, &path).map_err(|err| io_error(&path, &err))
+    }
+
+    /// Reads session `name`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SessionError::NotFound`] when no such session exists, or
+    /// [`SessionError`] when the file cannot be read or parsed.
+    pub fn load(&self, name: &str) -> Result<Session, SessionError> {
+        let path = self.session_path(name)?;
+        match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).map_err(|err| SessionError::Parse {
+                path,
+                message: err.to_string(),
+            }),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                Err(SessionError::NotFound(name.to_owned()))
+            }
+            Err(err) => Err(io_error(&path, &err)),
+        }
+    }
+
+    /// Forgets session `name` once its instance has been destroyed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SessionError`] when the file cannot be removed.
+    pub fn remove(&self, name: &str) -> Result<(), SessionError> {
+        let path = self.session_path(name)?;
+        match fs::remove_file(&path) {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                Err(SessionError::NotFound(name.to_owned()))
+            }
+            Err(err) => Err(io_error(&path, &err)),
+        }
+    }
+}
+
+fn encode(path: &Utf8Path, session: &Session) -> Result<Vec<u8>, SessionError> {
+    serde_json::to_vec_pretty(session).map_err(|err| SessionError::Parse {
+        path: path.to_path_buf(),
+        message: err.to_string(),
+    })
+}
+
+fn io_error(path: &Utf8Path, err: &io::Error) -> SessionError {
+    SessionError::Io {
+        path: path.to_path_buf(),
+        message: err.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,99 @@
+//! Unit tests for session records.
+
+use std::net::{IpAddr, Ipv4Addr};
+
+use camino::Utf8PathBuf;
+use rstest::{fixture, rstest};
+use tempfile::TempDir;
+
+use super::*;
+use crate::backend::InstanceRequest;
+
+struct StoreFixture {
+    store: SessionStore,
+    _dir: TempDir,
+}
+
+#[fixture]
+fn fixture() -> StoreFixture {
+    let dir = TempDir::new().expect("temp dir");
+    let root = Utf8PathBuf::from_path_buf(dir.path().to_path_buf()).expect("utf-8 path");
+    StoreFixture {
+        store: SessionStore::new(&root),
+        _dir: dir,
+    }
+}
+
+fn instance(cloud_init: Option<&str>) -> ProvisionedInstance {
+    ProvisionedInstance {
+        handle: InstanceHandle {
+            id: String::from("instance-1"),
+            zone: String::from("fr-par-1"),
+            run_id: None,
+        },
+        networking: InstanceNetworking {
+            public_ip: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            ssh_port: 22,
+        },
+        request: InstanceRequest::builder()
+            .image_label("Ubuntu 24.04 Noble Numbat")
+            .instance_type("DEV1-S")
+            .zone("fr-par-1")
+            .project_id("project")
+            .architecture("x86_64")
+            .cloud_init_user_data(cloud_init.map(str::to_owned))
+            .build()
+            .expect("request should be valid"),
+    }
+}
+
+#[rstest]
+#[case(None, false)]
+#[case(Some("#cloud-config\n"), true)]
+fn new_waits_for_cloud_init_only_when_requested(
+    #[case] cloud_init: Option<&str>,
+    #[case] expected: bool,
+) {
+    assert_eq!(
+        Session::new(instance(cloud_init)).awaiting_cloud_init,
+        expected
+    );
+}
+
+#[rstest]
+fn create_load_update_remove_round_trip(fixture: StoreFixture) {
+    let mut session = Session::new(instance(Some("#cloud-config\n")));
+    fixture.store.create("dev", &session).expect("create");
+    assert_eq!(fixture.store.load("dev").expect("load"), session);
+
+    session.awaiting_cloud_init = false;
+    fixture.store.update("dev", &session).expect("update");
+    assert_eq!(fixture.store.load("dev").expect("load"), session);
+
+    fixture.store.remove("dev").expect("remove");
+    assert!(matches!(
+        fixture.store.load("dev"),
+        Err(SessionError::NotFound(name)) if name == "dev"
+    ));
+}
+
+#[rstest]
+fn create_refuses_to_replace_a_running_session(fixture: StoreFixture) {
+    let session = Session::new(instance(None));
+    fixture.store.create("dev", &session).expect("create");
+    assert!(matches!(
+        fixture.store.create("dev", &session),
+        Err(SessionError::AlreadyExists(_))
+    ));
+}
+
+#[rstest]
+#[case("")]
+#[case("../escape")]
+#[case("with space")]
+fn session_path_rejects_invalid_names(fixture: StoreFixture, #[case] name: &str) {
+    assert!(matches!(
+        fixture.store.session_path(name),
+        Err(SessionError::InvalidName(_))
+    ));
+}
@@ -0,0 +1,103 @@
+//! `mriya session` subcommands.
+//!
+//! `mriya session start` provisions an instance and records it under the
+//! local state directory, `mriya session exec` re-syncs the workspace and runs
+//! a command on that instance, and `mriya session stop` destroys it. Sessions
+//! are named so several can run side by side; the name defaults to `default`.
+
+use std::io::{self, Write};
+
+use mriya::{
+    InstanceRequest, RunOrchestrator, RunProvenance, ScalewayBackend, SessionError, SessionStore,
+    StreamingCommandRunner, SyncConfig, Syncer,
+};
+
+use crate::CliError;
+use crate::cli::{SessionCommand, SessionExecCommand, SessionStartCommand};
+
+pub(crate) type Orchestrator = RunOrchestrator<ScalewayBackend, StreamingCommandRunner>;
+
+/// Executes a `mriya session` subcommand.
+pub(crate) async fn exec_session(command: SessionCommand) -> Result<i32, CliError> {
+    let store = SessionStore::new(&SessionStore::default_state_dir()?);
+    match command {
+        SessionCommand::Start(args) => start_session(&store, &args).await,
+        SessionCommand::Exec(args) => exec_in_session(&store, &args).await,
+        SessionCommand::Stop(args) => {
+            let session = store.load(&args.name)?;
+            let (orchestrator, _) = orchestrator()?;
+            orchestrator.stop(session).await?;
+            store.remove(&args.name)?;
+            writeln!(io::stderr(), "session {}: stopped", args.name).ok();
+            Ok(0)
+        }
+    }
+}
+
+async fn start_session(store: &SessionStore, args: &SessionStartCommand) -> Result<i32, CliError> {
+    // Refuse before provisioning so a clash never costs an instance.
+    match store.load(&args.name) {
+        Err(SessionError::NotFound(_)) => {}
+        Ok(_) => return Err(SessionError::AlreadyExists(args.name.clone()).into()),
+        Err(err) => return Err(err.into()),
+    }
+
+    let (orchestrator, mut request) = orchestrator()?;
+    if let Some(instance_type) = args.instance_type.as_deref() {
+        request.instance_type = crate::parse_override("--instance-type", instance_type)?;
+    }
+    if let Some(image) = args.image.as_deref() {
+        request.image_label = crate::parse_override("--image", image)?;
+    }
+
+    let checks = crate::Preflight {
+        strict_secrets: args.strict_secrets,
+        ..crate::Preflight::default()
+    };
+    crate::preflight(orchestrator.syncer(), checks).await?;
+    let session = orchestrator.start(&request).await?;
+    if let Err(err) = store.create(&args.name, &session) {
+        orchestrator.stop(session).await?;
+        return Err(err.into());
+    }
+    orchestrator.detach(&session).await;
+    writeln!(
+        io::stderr(),
+        "session {}: instance {} ready at {}",
+        args.name,
+        session.handle.id,
+        session.networking.public_ip
+    )
+    .ok();
+    Ok(0)
+}
+
+async fn exec_in_session(store: &SessionStore, args: &SessionExecCommand) -> Result<i32, CliError> {
+    crate::validate_command_args(&args.command)?;
+    let remote_command = crate::render_remote_command(&args.command);
+    let mut session = store.load(&args.name)?;
+    let awaiting_cloud_init = session.awaiting_cloud_init;
+
+    let (orchestrator, _) = orchestrator()?;
+    let source = crate::current_dir()?;
+    let result = orchestrator
+        .exec(&mut session, &source, &remote_command)
+        .await;
+    orchestrator.detach(&session).await;
+    let output = result?;
+    if session.awaiting_cloud_init != awaiting_cloud_init {
+        store.update(&args.name, &session)?;
+    }
+    output.exit_code.ok_or(CliError::MissingExitCode)
+}
+
+/// Builds an orchestrator and the default instance request from configuration.
+pub(crate) fn orchestrator() -> Result<(Orchestrator, InstanceRequest), CliError> {
+    let (base_backend, request) = crate::default_backend_and_request()?;
+    let backend = base_backend.with_provenance(RunProvenance::detect(&crate::current_dir()?));
+    let sync_config =
+        SyncConfig::load_without_cli_args().map_err(|err| CliError::Config(err.to_string()))?;
+    let syncer = Syncer::new(sync_config, StreamingCommandRunner::default())
+        .map_err(|err| CliError::Sync(err.to_string()))?;
+    Ok((RunOrchestrator::new(backend, syncer), request))
+}
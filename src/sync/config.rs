@@ -4,9 +4,11 @@
 //! associated error types. Configuration is loaded via `ortho-config` which
 //! merges defaults, configuration files, and environment variables.
 
+use std::str::FromStr;
+
 use camino::Utf8PathBuf;
 use ortho_config::OrthoConfig;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::backend::InstanceNetworking;
@@ -19,6 +21,55 @@ pub const DEFAULT_REMOTE_PATH: &str = "/home/ubuntu/project";
 /// Default mount path for the persistent cache volume.
 pub const DEFAULT_VOLUME_MOUNT_PATH: &str = "/mriya";
 
+/// How the workspace is selected for transfer.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SyncMode {
+    /// Sync the working tree, skipping paths matched by `.gitignore`.
+    #[default]
+    RsyncFilter,
+    /// Sync only the files `git ls-files` reports.
+    GitFiles,
+    /// Sync a snapshot of the `HEAD` commit.
+    GitCommit,
+    /// Have the remote fetch `HEAD` from the git remote and send only the
+    /// uncommitted changes, falling back to `rsync-filter`.
+    GitFetch,
+}
+
+impl SyncMode {
+    /// Returns the name used in configuration and on the command line.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::RsyncFilter => "rsync-filter",
+            Self::GitFiles => "git-files",
+            Self::GitCommit => "git-commit",
+            Self::GitFetch => "git-fetch",
+        }
+    }
+}
+
+impl FromStr for SyncMode {
+    type Err = SyncConfigLoadError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        [
+            Self::RsyncFilter,
+            Self::GitFiles,
+            Self::GitCommit,
+            Self::GitFetch,
+        ]
+        .into_iter()
+            .find(|mode| mode.as_str() == value.trim())
+            .ok_or_else(|| {
+                SyncConfigLoadError::Parse(format!(
+                    "unknown sync mode `{value}`: expected rsync-filter, git-files, git-commit, or git-fetch"
+                ))
+            })
+    }
+}
+
 /// Synchronization and SSH settings loaded via `ortho-config`.
 #[derive(Clone, Debug, Deserialize, OrthoConfig, PartialEq, Eq)]
 #[ortho_config(
@@ -58,6 +109,10 @@ pub struct SyncConfig {
     /// Known hosts file override; defaults to `/dev/null` for ephemeral hosts.
     #[ortho_config(default = "/dev/null".to_owned())]
     pub ssh_known_hosts_file: String,
+    /// Whether the phases of a run share one SSH connection per instance
+    /// through `ControlMaster`.
+    #[ortho_config(default = true)]
+    pub ssh_multiplex: bool,
     /// Path to the SSH private key file for remote authentication. Supports
     /// tilde expansion (`~/.ssh/id_ed25519`). Optional; when not provided, SSH
     /// falls back to default key locations (`~/.ssh/id_rsa`, `~/.ssh/id_ed25519`,
@@ -74,6 +129,50 @@ pub struct SyncConfig {
     /// mounting. Defaults to true so toolchains can write immediately.
     #[ortho_config(default = true)]
     pub create_cache_directories: bool,
+    /// Patterns synced even when `.gitignore` excludes them, in rsync
+    /// filter syntax.
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// Patterns never synced, even when git tracks them. Excludes take
+    /// precedence over includes.
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// How the workspace is selected for transfer.
+    #[serde(default)]
+    pub sync_mode: SyncMode,
+    /// Path to the `git` executable used by the git sync modes.
+    #[ortho_config(default = "git".to_owned())]
+    pub git_bin: String,
+    /// Whether the `git-files` mode also syncs untracked files that are not
+    /// ignored.
+    #[ortho_config(default = false)]
+    pub git_include_untracked: bool,
+    /// Whether to run `git lfs pull` locally before syncing the working tree
+    /// when files tracked by Git LFS are still pointers. Off by default
+    /// because the pull changes the local checkout.
+    #[ortho_config(default = false)]
+    pub git_lfs_pull: bool,
+    /// Git remote the instance fetches from in the `git-fetch` mode.
+    #[ortho_config(default = "origin".to_owned())]
+    pub git_remote: String,
+    /// Streams a `tar | zstd` archive instead of running rsync when the
+    /// destination is empty, such as on a fresh instance.
+    #[ortho_config(default = true)]
+    pub tar_stream: bool,
+    /// Largest number of bytes a sync may send. Commands that provision check a
+    /// dry run against it first and abort when it is exceeded.
+    pub max_sync_bytes: Option<u64>,
+    /// File name patterns, in gitignore syntax, flagged as likely secrets in
+    /// addition to the built-in list.
+    #[serde(default)]
+    pub secret_patterns: Vec<String>,
+    /// Text fragments flagged as likely secrets when found near the start of
+    /// a file, in addition to the built-in list.
+    #[serde(default)]
+    pub secret_signatures: Vec<String>,
+    /// Patterns, in gitignore syntax, for files never flagged as secrets.
+    #[serde(default)]
+    pub secret_allowlist: Vec<String>,
 }
 
 /// Errors raised when loading the sync configuration from layered sources.
@@ -97,6 +196,8 @@ impl SyncConfig {
         Self::require_value(&self.remote_path, "remote_path")?;
         Self::require_optional_value(self.ssh_identity_file.as_deref(), "ssh_identity_file")?;
         Self::require_value(&self.volume_mount_path, "volume_mount_path")?;
+        Self::require_value(&self.git_bin, "git_bin")?;
+        Self::require_value(&self.git_remote, "git_remote")?;
         Ok(())
     }
 
@@ -172,7 +273,60 @@ pub enum SyncError {
         /// Operating system error string.
         message: String,
     },
-    /// Raised when `rsync` completes with a non-zero exit code.
+    /// Raised when the workspace's `.mriyaignore` file cannot be read.
+    #[error("failed to read {path}: {message}")]
+    IgnoreFile {
+        /// Path of the ignore file.
+        path: Utf8PathBuf,
+        /// Operating system error string.
+        message: String,
+    },
+    /// Raised when the local staging directory for a git sync mode cannot be
+    /// prepared.
+    #[error("failed to prepare sync staging directory: {message}")]
+    Staging {
+        /// Operating system error string.
+        message: String,
+    },
+    /// Raised when the private directory for shared SSH connections cannot
+    /// be created.
+    #[error("failed to prepare SSH control directory: {message}")]
+    ControlSocket {
+        /// Operating system error string.
+        message: String,
+    },
+    /// Raised when a sync would send more than `max_sync_bytes`.
+    #[error("sync would transfer {bytes} bytes, above max_sync_bytes ({limit})")]
+    SizeLimit {
+        /// Bytes the sync would send.
+        bytes: u64,
+        /// Configured limit.
+        limit: u64,
+    },
+    /// Raised when a secret name pattern or allowlist entry is invalid.
+    #[error("invalid secret pattern `{pattern}`: {message}")]
+    SecretPattern {
+        /// Pattern that failed to compile.
+        pattern: String,
+        /// Parser error string.
+        message: String,
+    },
+    /// Raised when a file cannot be read while scanning for secrets.
+    #[error("failed to scan {path} for secrets: {message}")]
+    SecretScan {
+        /// Path of the unreadable file.
+        path: Utf8PathBuf,
+        /// Operating system error string.
+        message: String,
+    },
+    /// Raised when a forwarded environment variable has an invalid name.
+    #[error("invalid environment variable name `{name}`")]
+    InvalidEnv {
+        /// Offending name; the value is never reported.
+        name: String,
+    },
+    /// Raised when a sync or remote command completes with a non-zero exit
+    /// code.
     #[error("{program} exited with status {status_text}: {stderr}")]
     CommandFailure {
         /// Command name used for the attempted operation.
@@ -0,0 +1,73 @@
+//! Shared SSH connections for the phases of a run.
+//!
+//! With `ssh_multiplex` enabled, every `ssh` invocation, including rsync's
+//! remote shell, passes `ControlMaster=auto` and a `ControlPath` inside a
+//! private temporary directory owned by the [`Syncer`](super::Syncer). The
+//! first connection to an instance becomes the master and later ones reuse
+//! its authenticated session. `%C` gives each destination its own socket, so
+//! one syncer can serve several instances. Masters are stopped with
+//! `ssh -O exit` when a run tears its instance down or a session command
+//! leaves it running, and the directory is removed when the last clone of
+//! the syncer is dropped.
+
+use std::ffi::OsString;
+use std::fs;
+
+use camino::{Utf8Path, Utf8PathBuf};
+use tempfile::TempDir;
+
+use super::config::SyncError;
+
+/// Seconds an idle master stays up, bounding how long one can outlive a run
+/// that never reached teardown.
+const CONTROL_PERSIST_SECS: u32 = 60;
+
+/// Mode of the control directory: other users must not reach the sockets,
+/// which grant access to the authenticated sessions.
+#[cfg(unix)]
+const PRIVATE_MODE: u32 = 0o700;
+
+/// Private directory holding the control sockets of one run.
+#[derive(Debug)]
+pub(super) struct ControlDir {
+    root: Utf8PathBuf,
+    _dir: TempDir,
+}
+
+impl ControlDir {
+    /// Creates the directory, readable only by the current user.
+    pub(super) fn new() -> Result<Self, SyncError> {
+        let control_error = |message: String| SyncError::ControlSocket { message };
+        // Unix sockets have short path limits, so keep the prefix brief.
+        let dir = tempfile::Builder::new()
+            .prefix("mriya-ssh-")
+            .tempdir()
+            .map_err(|err| control_error(err.to_string()))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(dir.path(), fs::Permissions::from_mode(PRIVATE_MODE))
+                .map_err(|err| control_error(err.to_string()))?;
+        }
+        let root = Utf8PathBuf::from_path_buf(dir.path().to_path_buf())
+            .map_err(|path| control_error(format!("non UTF-8 path {}", path.display())))?;
+        Ok(Self { root, _dir: dir })
+    }
+
+    /// Returns the directory's path.
+    pub(super) fn path(&self) -> &Utf8Path {
+        &self.root
+    }
+
+    /// `ssh` options that share one connection per destination.
+    pub(super) fn ssh_options(&self) -> [OsString; 6] {
+        [
+            OsString::from("-o"),
+            OsString::from("ControlMaster=auto"),
+            OsString::from("-o"),
+            OsString::from(format!("ControlPath={}/%C", self.root)),
+            OsString::from("-o"),
+            OsString::from(format!("ControlPersist={CONTROL_PERSIST_SECS}")),
+        ]
+    }
+}
@@ -0,0 +1,273 @@
+//! Pull-based sync for the `git-fetch` mode.
+//!
+//! Instead of uploading the workspace, Mriya uploads the uncommitted changes
+//! as a patch and has the destination fetch `HEAD` from the git remote into a
+//! bare clone kept under `volume_mount_path`, so later runs only fetch new
+//! objects. The clone checks `HEAD` out into `remote_path` with a private
+//! index, removes files the commit does not track except ignored ones, and
+//! applies the patch.
+//!
+//! [`prepare`] returns `None` when the commit cannot be fetched that way, and
+//! the caller falls back to `rsync-filter`.
+
+use std::borrow::Cow;
+use std::ffi::OsString;
+use std::fs;
+
+use camino::{Utf8Path, Utf8PathBuf};
+use shell_escape::unix::escape;
+
+use super::config::{SyncConfig, SyncError};
+use super::git::{git_args, has_submodules};
+use super::lfs::uses_lfs;
+use super::staging::Staging;
+use super::stats::{SyncStats, SyncedFile};
+use super::types::AsyncCommandRunner;
+use super::util::{command_failure, run_checked};
+
+/// Directory below `volume_mount_path` holding the bare clones.
+const CACHE_DIR: &str = "git-cache";
+
+/// Staging directory uploaded to the destination.
+const UPLOAD_DIR: &str = "upload";
+
+/// Name of the patch holding the uncommitted changes.
+const PATCH_FILE: &str = "changes.patch";
+
+/// Index file, inside the bare clone, tracking the checked-out work tree.
+const INDEX_FILE: &str = "mriya-index";
+
+/// Shell variable holding the upload directory in the fetch script.
+const UPLOAD_VAR: &str = "MRIYA_UPLOAD";
+
+/// Exit code of `git diff --no-index` when the files differ.
+const DIFF_FOUND: i32 = 1;
+
+/// A commit the destination can fetch, plus the local changes on top of it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FetchPlan {
+    /// Commit checked out on the destination.
+    pub commit: String,
+    /// URL of the git remote the commit is fetched from.
+    pub url: String,
+    /// Local directory holding the patch, uploaded before the script runs.
+    pub upload: Utf8PathBuf,
+}
+
+/// Prepares a pull-based sync of `source`, writing the patch into `staging`.
+///
+/// Returns `None` when `source` has no usable `git_remote`, `HEAD` has not
+/// been pushed to it, or the workspace uses submodules or Git LFS, which the
+/// destination cannot fetch without credentials.
+///
+/// # Errors
+///
+/// Returns [`SyncError::Spawn`] when `git` cannot be started,
+/// [`SyncError::CommandFailure`] when `git diff` fails, or
+/// [`SyncError::Staging`] when the patch cannot be written.
+pub(super) async fn prepare<R: AsyncCommandRunner>(
+    runner: &R,
+    config: &SyncConfig,
+    source: &Utf8Path,
+    staging: &Staging,
+) -> Result<Option<FetchPlan>, SyncError> {
+    if has_submodules(source) || uses_lfs(runner, config, source).await? {
+        return Ok(None);
+    }
+    let git = &config.git_bin;
+    let remote_args = git_args(source, ["remote", "get-url", &config.git_remote]);
+    let Some(url) = query(runner, git, &remote_args).await? else {
+        return Ok(None);
+    };
+    let Some(commit) = query(runner, git, &git_args(source, ["rev-parse", "HEAD"])).await? else {
+        return Ok(None);
+    };
+    let branches_args = git_args(source, ["branch", "-r", "--contains", &commit]);
+    let branches = query(runner, git, &branches_args).await?;
+    let tracking_prefix = format!("{}/", config.git_remote);
+    let pushed = branches.is_some_and(|listing| {
+        listing
+            .lines()
+            .any(|branch| branch.trim().starts_with(&tracking_prefix))
+    });
+    if !pushed {
+        return Ok(None);
+    }
+
+    let upload = staging.path().join(UPLOAD_DIR);
+    fs::create_dir(&upload).map_err(|err| SyncError::Staging {
+        message: format!("{upload}: {err}"),
+    })?;
+    let patch = changes_patch(runner, config, source).await?;
+    staging.write(&format!("{UPLOAD_DIR}/{PATCH_FILE}"), &patch)?;
+    Ok(Some(FetchPlan {
+        commit,
+        url,
+        upload,
+    }))
+}
+
+/// Reports what a sync with `plan` sends: the files the patch changes, at
+/// their working-tree sizes, and the size of the patch itself as the total.
+///
+/// Deleted files are left out, since the patch only names them.
+///
+/// # Errors
+///
+/// Returns [`SyncError::Spawn`] or [`SyncError::CommandFailure`] when `git`
+/// fails, or [`SyncError::Staging`] when the patch cannot be read back.
+pub(super) async fn payload_stats<R: AsyncCommandRunner>(
+    runner: &R,
+    config: &SyncConfig,
+    source: &Utf8Path,
+    plan: &FetchPlan,
+) -> Result<SyncStats, SyncError> {
+    let changed = run_checked(
+        runner,
+        &config.git_bin,
+        &git_args(
+            source,
+            ["diff", "--name-only", "-z", "--diff-filter=d", "HEAD"],
+        ),
+    )
+    .await?;
+    let mut names = vec![changed.stdout];
+    if config.git_include_untracked {
+        let untracked = run_checked(
+            runner,
+            &config.git_bin,
+            &git_args(source, ["ls-files", "-z", "--others", "--exclude-standard"]),
+        )
+        .await?;
+        names.push(untracked.stdout);
+    }
+    let files = names
+        .iter()
+        .flat_map(|listing| listing.split('\0'))
+        .filter(|name| !name.is_empty())
+        .map(|name| SyncedFile {
+            path: Utf8PathBuf::from(name),
+            bytes: fs::metadata(source.join(name)).map_or(0, |meta| meta.len()),
+        })
+        .collect();
+    let patch = plan.upload.join(PATCH_FILE);
+    let total_bytes = fs::metadata(&patch)
+        .map_err(|err| SyncError::Staging {
+            message: format!("{patch}: {err}"),
+        })?
+        .len();
+    Ok(SyncStats { files, total_bytes })
+}
+
+/// Renders the shell script that brings `work_tree` to the planned commit
+/// plus the patch uploaded to `upload`.
+///
+/// A relative `upload` is resolved against the remote `$HOME`. The upload is
+/// removed when the script exits, whether or not it succeeds.
+#[must_use]
+pub fn fetch_script(
+    config: &SyncConfig,
+    plan: &FetchPlan,
+    work_tree: &Utf8Path,
+    upload: &Utf8Path,
+) -> String {
+    let cache_dir = Utf8Path::new(&config.volume_mount_path).join(CACHE_DIR);
+    let cache = cache_dir.join(cache_name(&plan.url));
+    let index = cache.join(INDEX_FILE);
+    let (cache_q, work_q) = (quote(cache.as_str()), quote(work_tree.as_str()));
+    let (url_q, commit_q) = (quote(&plan.url), quote(&plan.commit));
+    let upload_q = if upload.is_absolute() {
+        quote(upload.as_str())
+    } else {
+        format!("\"$HOME\"/{}", quote(upload.as_str()))
+    };
+    let patch = format!("\"${UPLOAD_VAR}\"/{PATCH_FILE}");
+    [
+        String::from("set -e"),
+        format!("{UPLOAD_VAR}={upload_q}"),
+        format!("trap 'rm -rf \"${UPLOAD_VAR}\"' EXIT"),
+        format!("mkdir -p {} {work_q}", quote(cache_dir.as_str())),
+        format!("[ -d {cache_q} ] || git init --bare --quiet {cache_q}"),
+        format!("git --git-dir={cache_q} fetch --quiet {url_q} {commit_q}"),
+        format!(
+            "export GIT_DIR={cache_q} GIT_WORK_TREE={work_q} GIT_INDEX_FILE={}",
+            quote(index.as_str())
+        ),
+        format!("git reset --quiet --hard {commit_q}"),
+        String::from("git clean -fdq"),
+        format!("cd {work_q}"),
+        format!("if [ -s {patch} ]; then git apply --whitespace=nowarn {patch}; fi"),
+    ]
+    .join("\n")
+}
+
+/// Quotes `value` for the remote shell.
+fn quote(value: &str) -> String {
+    escape(Cow::Owned(value.to_owned())).into_owned()
+}
+
+/// Names the bare clone after the remote URL, so repositories sharing a
+/// volume keep separate clones.
+fn cache_name(url: &str) -> String {
+    let mut name = String::with_capacity(url.len());
+    for ch in url.chars() {
+        if ch.is_ascii_alphanumeric() || matches!(ch, '.' | '_') {
+            name.push(ch);
+        } else if !name.ends_with('-') {
+            name.push('-');
+        }
+    }
+    let trimmed = name.trim_matches(|ch| matches!(ch, '-' | '.'));
+    if Utf8Path::new(trimmed).extension() == Some("git") {
+        trimmed.to_owned()
+    } else {
+        format!("{trimmed}.git")
+    }
+}
+
+/// Diffs the working tree against `HEAD`, adding untracked files when
+/// `git_include_untracked` is set.
+async fn changes_patch<R: AsyncCommandRunner>(
+    runner: &R,
+    config: &SyncConfig,
+    source: &Utf8Path,
+) -> Result<String, SyncError> {
+    let tracked = run_checked(
+        runner,
+        &config.git_bin,
+        &git_args(source, ["diff", "--binary", "HEAD"]),
+    )
+    .await?;
+    let mut patches = vec![tracked.stdout];
+    if config.git_include_untracked {
+        let listing = run_checked(
+            runner,
+            &config.git_bin,
+            &git_args(source, ["ls-files", "-z", "--others", "--exclude-standard"]),
+        )
+        .await?;
+        for file in listing.stdout.split('\0').filter(|file| !file.is_empty()) {
+            let args = git_args(
+                source,
+                ["diff", "--binary", "--no-index", "--", "/dev/null", file],
+            );
+            let output = runner.run_quiet(&config.git_bin, &args).await?;
+            if !matches!(output.code, Some(0 | DIFF_FOUND)) {
+                return Err(command_failure(&config.git_bin, output));
+            }
+            patches.push(output.stdout);
+        }
+    }
+    Ok(patches.concat())
+}
+
+/// Runs a git query, returning its trimmed output or `None` when it fails.
+async fn query<R: AsyncCommandRunner>(
+    runner: &R,
+    program: &str,
+    args: &[OsString],
+) -> Result<Option<String>, SyncError> {
+    let output = runner.run_quiet(program, args).await?;
+    let value = output.stdout.trim();
+    Ok((output.is_success() && !value.is_empty()).then(|| value.to_owned()))
+}
@@ -0,0 +1,123 @@
+//! Include and exclude rules layered ahead of the `.gitignore` filter.
+//!
+//! rsync applies the first rule that matches a path, so rules passed before
+//! `--filter=:- .gitignore` take precedence over it. Rules come from three
+//! sources, highest precedence first: `[sync] exclude`, `[sync] include`, and
+//! the workspace's `.mriyaignore` file.
+
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+
+use camino::Utf8Path;
+
+use super::config::{SyncConfig, SyncError};
+
+/// Name of the workspace file holding extra sync rules in gitignore syntax.
+pub const MRIYA_IGNORE_FILE: &str = ".mriyaignore";
+
+/// A single rsync include or exclude rule.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FilterRule {
+    /// Sync matching paths even when `.gitignore` excludes them.
+    Include(String),
+    /// Skip matching paths even when git tracks them.
+    Exclude(String),
+}
+
+impl FilterRule {
+    /// Renders the rule as an rsync argument.
+    #[must_use]
+    pub fn to_arg(&self) -> OsString {
+        match self {
+            Self::Include(pattern) => OsString::from(format!("--include={pattern}")),
+            Self::Exclude(pattern) => OsString::from(format!("--exclude={pattern}")),
+        }
+    }
+
+    /// Pattern the rule matches, in rsync and gitignore syntax.
+    #[must_use]
+    pub fn pattern(&self) -> &str {
+        match self {
+            Self::Include(pattern) | Self::Exclude(pattern) => pattern,
+        }
+    }
+}
+
+/// Rules from `[sync] exclude` and `[sync] include`, in precedence order.
+#[must_use]
+pub fn config_rules(config: &SyncConfig) -> Vec<FilterRule> {
+    let non_empty = |pattern: &&String| !pattern.trim().is_empty();
+    config
+        .exclude
+        .iter()
+        .filter(non_empty)
+        .map(|pattern| FilterRule::Exclude(pattern.trim().to_owned()))
+        .chain(
+            config
+                .include
+                .iter()
+                .filter(non_empty)
+                .map(|pattern| FilterRule::Include(pattern.trim().to_owned())),
+        )
+        .collect()
+}
+
+/// Reads `.mriyaignore` from the workspace root, if present.
+///
+/// # Errors
+///
+/// Returns [`SyncError::IgnoreFile`] when the file exists but cannot be read.
+pub fn mriyaignore_rules(source: &Utf8Path) -> Result<Vec<FilterRule>, SyncError> {
+    let path = source.join(MRIYA_IGNORE_FILE);
+    match fs::read_to_string(&path) {
+        Ok(contents) => Ok(parse_mriyaignore(&contents)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(err) => Err(SyncError::IgnoreFile {
+            path,
+            message: err.to_string(),
+        }),
+    }
+}
+
+/// Every rule layered ahead of `.gitignore`, in precedence order.
+///
+/// # Errors
+///
+/// Returns [`SyncError::IgnoreFile`] when `.mriyaignore` cannot be read.
+pub fn workspace_rules(
+    config: &SyncConfig,
+    source: &Utf8Path,
+) -> Result<Vec<FilterRule>, SyncError> {
+    let mut rules = config_rules(config);
+    rules.extend(mriyaignore_rules(source)?);
+    Ok(rules)
+}
+
+/// Translates gitignore syntax into rsync rules.
+///
+/// The last matching gitignore line wins while rsync stops at the first
+/// match, so the rules are returned in reverse file order. `!pattern` lines
+/// become includes.
+#[must_use]
+pub fn parse_mriyaignore(contents: &str) -> Vec<FilterRule> {
+    let mut rules = contents
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(parse_line)
+        .collect::<Vec<_>>();
+    rules.reverse();
+    rules
+}
+
+fn parse_line(line: &str) -> FilterRule {
+    if let Some(pattern) = line.strip_prefix('!') {
+        return FilterRule::Include(pattern.to_owned());
+    }
+    let unescaped = line
+        .strip_prefix("\\#")
+        .map(|rest| format!("#{rest}"))
+        .or_else(|| line.strip_prefix("\\!").map(|rest| format!("!{rest}")));
+    FilterRule::Exclude(unescaped.unwrap_or_else(|| line.to_owned()))
+}
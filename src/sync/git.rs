@@ -0,0 +1,250 @@
+//! Git-native workspace selection for the `git-files` and `git-commit` sync
+//! modes.
+//!
+//! `git-files` asks `git ls-files` which files to send and hands rsync the
+//! answer as include rules followed by a sender-side hide-everything rule, so
+//! rsync deletes untracked files on the remote while ignored caches stay
+//! protected by the `.gitignore` filter. `git-commit` exports `HEAD` into a
+//! staging directory with `git archive` and syncs that snapshot instead of the
+//! working tree.
+//!
+//! Both modes descend into initialised submodules, sending their files rather
+//! than the bare submodule directory.
+
+use std::collections::BTreeSet;
+use std::ffi::OsString;
+use std::fs;
+
+use camino::{Utf8Path, Utf8PathBuf};
+
+use super::config::{SyncConfig, SyncError};
+use super::staging::Staging;
+use super::types::AsyncCommandRunner;
+use super::util::run_checked;
+
+/// Program used to unpack `git archive` output.
+const TAR_BIN: &str = "tar";
+
+/// Name of the rsync merge file listing the files git reports.
+const LISTING_FILE: &str = "git-files.rules";
+
+/// Name of the archive written by `git archive`.
+const SNAPSHOT_ARCHIVE: &str = "snapshot.tar";
+
+/// Directory the snapshot is unpacked into.
+const SNAPSHOT_TREE: &str = "tree";
+
+/// File declaring a repository's submodules.
+const GITMODULES: &str = ".gitmodules";
+
+/// `git ls-tree` mode of a submodule entry.
+const GITLINK_MODE: &str = "160000";
+
+/// Characters that make rsync treat a pattern as a wildcard match.
+const WILDCARDS: [char; 3] = ['*', '?', '['];
+
+/// Lists the files git reports for `source` and writes them as an rsync
+/// merge file, returning its path.
+///
+/// Files inside submodules are listed in place of the submodule entries.
+///
+/// # Errors
+///
+/// Returns [`SyncError::Spawn`] or [`SyncError::CommandFailure`] when `git`
+/// fails, or [`SyncError::Staging`] when the merge file cannot be written.
+pub(super) async fn write_listing<R: AsyncCommandRunner>(
+    runner: &R,
+    config: &SyncConfig,
+    source: &Utf8Path,
+    staging: &Staging,
+) -> Result<Utf8PathBuf, SyncError> {
+    let mut tracked = vec!["--cached"];
+    let untracked = ["--others", "--exclude-standard"];
+    let mut listing = String::new();
+    if has_submodules(source) {
+        // `--recurse-submodules` only supports tracked files, so untracked
+        // files need a listing of their own.
+        tracked.push("--recurse-submodules");
+        if config.git_include_untracked {
+            listing = ls_files(runner, config, source, &untracked).await?;
+        }
+    } else if config.git_include_untracked {
+        tracked.extend(untracked);
+    }
+    listing.push_str(&ls_files(runner, config, source, &tracked).await?);
+    let files: Vec<&str> = listing.split('\0').collect();
+    staging.write(LISTING_FILE, &listing_rules(&files))
+}
+
+/// Exports the `HEAD` commit of `source`, and of its initialised submodules,
+/// into `staging`, returning the directory holding the snapshot.
+///
+/// Each submodule is exported at the commit `HEAD` records for it.
+/// Submodules that are not checked out locally stay empty, as in a fresh
+/// clone.
+///
+/// # Errors
+///
+/// Returns [`SyncError::Spawn`] or [`SyncError::CommandFailure`] when `git` or
+/// `tar` fails, or [`SyncError::Staging`] when the snapshot directory cannot
+/// be created.
+pub(super) async fn export_head<R: AsyncCommandRunner>(
+    runner: &R,
+    config: &SyncConfig,
+    source: &Utf8Path,
+    staging: &Staging,
+) -> Result<Utf8PathBuf, SyncError> {
+    let tree = staging.path().join(SNAPSHOT_TREE);
+    let mut export = SnapshotExport {
+        runner,
+        config,
+        staging,
+        archives: 0,
+    };
+    export.commit(source, "HEAD", &tree).await?;
+    Ok(tree)
+}
+
+/// Unpacks commits into the staging tree, one archive per repository.
+struct SnapshotExport<'a, R: AsyncCommandRunner> {
+    runner: &'a R,
+    config: &'a SyncConfig,
+    staging: &'a Staging,
+    archives: usize,
+}
+
+impl<R: AsyncCommandRunner> SnapshotExport<'_, R> {
+    /// Exports `commit` of the repository at `repo` into `into`, then each of
+    /// its submodules below it.
+    async fn commit(
+        &mut self,
+        repo: &Utf8Path,
+        commit: &str,
+        into: &Utf8Path,
+    ) -> Result<(), SyncError> {
+        fs::create_dir_all(into).map_err(|err| SyncError::Staging {
+            message: format!("{into}: {err}"),
+        })?;
+        let archive = self.next_archive();
+
+        let mut archive_args = git_args(repo, ["archive", "--format=tar", "-o"]);
+        archive_args.extend([OsString::from(&archive), OsString::from(commit)]);
+        run_checked(self.runner, &self.config.git_bin, &archive_args).await?;
+
+        let tar_args = [
+            OsString::from("-xf"),
+            OsString::from(&archive),
+            OsString::from("-C"),
+            OsString::from(into),
+        ];
+        run_checked(self.runner, TAR_BIN, &tar_args).await?;
+
+        if !has_submodules(repo) {
+            return Ok(());
+        }
+        let mut tree_args = git_args(repo, ["ls-tree", "-r", "-z"]);
+        tree_args.push(OsString::from(commit));
+        let listing = run_checked(self.runner, &self.config.git_bin, &tree_args).await?;
+        for (path, submodule_commit) in gitlinks(&listing.stdout) {
+            let submodule = repo.join(path);
+            if submodule.join(".git").exists() {
+                // Recursion needs the nested future boxed.
+                Box::pin(self.commit(&submodule, submodule_commit, &into.join(path))).await?;
+            }
+        }
+        Ok(())
+    }
+
+    fn next_archive(&mut self) -> Utf8PathBuf {
+        let name = if self.archives == 0 {
+            SNAPSHOT_ARCHIVE.to_owned()
+        } else {
+            format!("submodule-{}.tar", self.archives)
+        };
+        self.archives = self.archives.saturating_add(1);
+        self.staging.path().join(name)
+    }
+}
+
+/// Parses `git ls-tree -z` output into the path and commit of each
+/// submodule.
+pub(super) fn gitlinks(listing: &str) -> Vec<(&str, &str)> {
+    listing
+        .split('\0')
+        .filter_map(|entry| {
+            let (meta, path) = entry.split_once('\t')?;
+            let mut fields = meta.split(' ');
+            let mode = fields.next()?;
+            let object = fields.nth(1)?;
+            (mode == GITLINK_MODE).then_some((path, object))
+        })
+        .collect()
+}
+
+pub(super) fn has_submodules(repo: &Utf8Path) -> bool {
+    repo.join(GITMODULES).is_file()
+}
+
+async fn ls_files<R: AsyncCommandRunner>(
+    runner: &R,
+    config: &SyncConfig,
+    source: &Utf8Path,
+    flags: &[&str],
+) -> Result<String, SyncError> {
+    let mut args = git_args(source, ["ls-files", "-z"]);
+    args.extend(flags.iter().map(OsString::from));
+    Ok(run_checked(runner, &config.git_bin, &args).await?.stdout)
+}
+
+/// Renders `files` as rsync include rules anchored at the transfer root.
+///
+/// Every parent directory is included too so rsync descends into it. rsync
+/// merge files hold one rule per line, so names containing a newline cannot
+/// be expressed and are skipped.
+#[must_use]
+pub fn listing_rules(files: &[&str]) -> String {
+    let listed: Vec<&str> = files
+        .iter()
+        .copied()
+        .filter(|file| !file.is_empty() && !file.contains('\n'))
+        .collect();
+    let directories: BTreeSet<&str> = listed
+        .iter()
+        .flat_map(|file| Utf8Path::new(file).ancestors().skip(1))
+        .map(Utf8Path::as_str)
+        .filter(|dir| !dir.is_empty())
+        .collect();
+    let mut rules: Vec<String> = directories
+        .iter()
+        .map(|dir| format!("+ /{}/\n", escape_pattern(dir)))
+        .collect();
+    rules.extend(
+        listed
+            .iter()
+            .map(|file| format!("+ /{}\n", escape_pattern(file))),
+    );
+    rules.concat()
+}
+
+/// Escapes wildcard characters so rsync matches `name` literally.
+///
+/// rsync only honours backslash escapes in patterns that contain a wildcard,
+/// so names without one are returned unchanged.
+fn escape_pattern(name: &str) -> String {
+    if !name.contains(WILDCARDS) {
+        return name.to_owned();
+    }
+    name.chars().fold(String::new(), |mut escaped, ch| {
+        if ch == '\\' || WILDCARDS.contains(&ch) {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+        escaped
+    })
+}
+
+pub(super) fn git_args<const N: usize>(source: &Utf8Path, args: [&str; N]) -> Vec<OsString> {
+    let mut all = vec![OsString::from("-C"), OsString::from(source)];
+    all.extend(args.map(OsString::from));
+    all
+}
@@ -0,0 +1,85 @@
+//! Git LFS content for the working-tree sync modes.
+//!
+//! A checkout made with LFS smudging skipped holds small pointer files in
+//! place of the real content, and rsync would ship those pointers. With
+//! `git_lfs_pull` enabled and files routed through the LFS filter by any
+//! `.gitattributes` in the workspace, the missing content is fetched locally
+//! with `git lfs pull` before the sync, so the local Git credentials are used
+//! and the instance needs neither `git` nor LFS access.
+
+use camino::Utf8Path;
+
+use super::config::{SyncConfig, SyncError};
+use super::git::git_args;
+use super::types::AsyncCommandRunner;
+use super::util::run_checked;
+
+/// Pathspec matching the tracked files git assigns to the LFS filter.
+const LFS_PATHSPEC: &str = ":(attr:filter=lfs)";
+
+/// `git lfs ls-files` marker for a file whose content is not checked out.
+const POINTER_MARKER: &str = "-";
+
+/// Replaces LFS pointer files in `source` with their content.
+///
+/// Does nothing when `git_lfs_pull` is disabled, the workspace does not use
+/// LFS, or every LFS file is already checked out. This changes the working
+/// tree, which is why `git_lfs_pull` is opt-in.
+///
+/// # Errors
+///
+/// Returns [`SyncError::Spawn`] or [`SyncError::CommandFailure`] when
+/// `git lfs` is missing or fails.
+pub(super) async fn pull_missing<R: AsyncCommandRunner>(
+    runner: &R,
+    config: &SyncConfig,
+    source: &Utf8Path,
+) -> Result<(), SyncError> {
+    if !config.git_lfs_pull || !uses_lfs(runner, config, source).await? {
+        return Ok(());
+    }
+    let listing = run_checked(
+        runner,
+        &config.git_bin,
+        &git_args(source, ["lfs", "ls-files"]),
+    )
+    .await?;
+    if pointer_files(&listing.stdout).is_empty() {
+        return Ok(());
+    }
+    run_checked(runner, &config.git_bin, &git_args(source, ["lfs", "pull"])).await?;
+    Ok(())
+}
+
+/// Parses `git lfs ls-files` output into the paths still holding pointers.
+pub(super) fn pointer_files(listing: &str) -> Vec<&str> {
+    listing
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, ' ');
+            let _oid = fields.next()?;
+            let marker = fields.next()?;
+            let path = fields.next()?;
+            (marker == POINTER_MARKER).then_some(path)
+        })
+        .collect()
+}
+
+/// Reports whether git assigns any tracked file in `source` to the LFS
+/// filter.
+///
+/// Asking git honours `.gitattributes` files in subdirectories and needs no
+/// `git-lfs` install. A `source` that is not a repository does not use LFS.
+///
+/// # Errors
+///
+/// Returns [`SyncError::Spawn`] when `git` cannot be started.
+pub(super) async fn uses_lfs<R: AsyncCommandRunner>(
+    runner: &R,
+    config: &SyncConfig,
+    source: &Utf8Path,
+) -> Result<bool, SyncError> {
+    let args = git_args(source, ["ls-files", "-z", "--", LFS_PATHSPEC]);
+    let output = runner.run_quiet(&config.git_bin, &args).await?;
+    Ok(output.is_success() && !output.stdout.is_empty())
+}
@@ -1,34 +1,76 @@
 //! Git-aware rsync synchronization and remote command execution applying
 //! `.gitignore` filters and wrapping SSH commands while preserving remote
 //! exit codes.
+//!
+//! [`SyncMode`] chooses what is sent: the filtered working tree, the files
+//! `git ls-files` reports, a snapshot of `HEAD`, or, in the pull-based
+//! `git-fetch` mode, only the uncommitted changes. Empty destinations receive
+//! a streamed `tar | zstd` archive instead of an rsync transfer.
 
 use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::sync::Arc;
 
 use camino::Utf8Path;
 
 use crate::backend::InstanceNetworking;
 
 mod config;
+mod control;
+mod fetch;
+mod filters;
+mod git;
+mod lfs;
+mod prefixed;
+mod redact;
 mod remote_command;
+mod remote_env;
+mod secrets;
+mod sink;
+mod staging;
+mod stats;
+mod stream;
 mod types;
+mod upload;
 mod util;
 
 pub use camino::Utf8PathBuf;
 pub use config::{
     DEFAULT_REMOTE_PATH, DEFAULT_VOLUME_MOUNT_PATH, SyncConfig, SyncConfigLoadError, SyncError,
+    SyncMode,
 };
+pub use fetch::{FetchPlan, fetch_script};
+pub use filters::{
+    FilterRule, MRIYA_IGNORE_FILE, config_rules, mriyaignore_rules, parse_mriyaignore,
+    workspace_rules,
+};
+pub use git::listing_rules;
+pub use prefixed::PrefixedCommandRunner;
+pub use redact::{MASK, MIN_SECRET_LEN, Redactor};
 pub use remote_command::{CACHE_SUBDIRECTORIES, create_cache_directories_command};
+pub use remote_env::{RemoteEnv, SECRET_NAME_MARKERS};
+pub use secrets::{
+    DEFAULT_SECRET_PATTERNS, DEFAULT_SECRET_SIGNATURES, SCAN_LIMIT, SecretFinding, SecretMatch,
+};
+pub use sink::{Capture, DEFAULT_CAPTURE_BYTES, OutputCallback, OutputSink, OutputStream};
+pub use stats::{DirectoryTotal, ITEMIZE_FORMAT, SyncStats, SyncedFile};
 pub use types::{
-    CommandOutput, CommandRunner, ProcessCommandRunner, RemoteCommandOutput,
+    AsyncCommandRunner, CommandOutput, CommandRunner, ProcessCommandRunner, RemoteCommandOutput,
     StreamingCommandRunner, SyncDestination,
 };
+pub use upload::{SCRIPT_VAR, script_command};
 pub use util::expand_tilde;
 
 /// Orchestrates rsync plus remote execution.
 #[derive(Clone, Debug)]
-pub struct Syncer<R: CommandRunner> {
+pub struct Syncer<R: AsyncCommandRunner> {
     config: SyncConfig,
     runner: R,
+    control: Option<Arc<control::ControlDir>>,
+    tty: bool,
+    env: RemoteEnv,
+    script: Option<Vec<u8>>,
 }
 
 impl Syncer<ProcessCommandRunner> {
@@ -42,16 +84,94 @@ impl Syncer<ProcessCommandRunner> {
     }
 }
 
-impl<R: CommandRunner> Syncer<R> {
+impl<R: AsyncCommandRunner> Syncer<R> {
     /// Creates a new syncer using the provided runner and configuration.
     ///
     /// # Errors
     ///
     /// Returns [`SyncError::InvalidConfig`] when configuration validation
-    /// fails.
+    /// fails, or [`SyncError::ControlSocket`] when `ssh_multiplex` is set and
+    /// the directory for shared SSH connections cannot be created.
     pub fn new(config: SyncConfig, runner: R) -> Result<Self, SyncError> {
         config.validate()?;
-        Ok(Self { config, runner })
+        let control = if config.ssh_multiplex {
+            Some(Arc::new(control::ControlDir::new()?))
+        } else {
+            None
+        };
+        Ok(Self {
+            config,
+            runner,
+            control,
+            tty: false,
+            env: RemoteEnv::default(),
+            script: None,
+        })
+    }
+
+    /// Uploads `script` before the command of [`Self::run_remote`], which
+    /// runs it through [`script_command`].
+    #[must_use]
+    pub fn with_script(mut self, script: Vec<u8>) -> Self {
+        self.script = Some(script);
+        self
+    }
+
+    /// Forwards `env` to the command of [`Self::run_remote`] without placing
+    /// the values on any command line.
+    #[must_use]
+    pub fn with_env(mut self, env: RemoteEnv) -> Self {
+        self.env = env;
+        self
+    }
+
+    /// Runs the command of [`Self::run_remote`] on a remote pseudo-terminal
+    /// attached to this process's terminal.
+    ///
+    /// `ssh` then forwards local stdin and window-size changes itself, so
+    /// colours, progress bars, and interactive programs work. The output goes
+    /// straight to the terminal and is not captured; only the exit code is
+    /// returned.
+    #[must_use]
+    pub const fn with_tty(mut self, tty: bool) -> Self {
+        self.tty = tty;
+        self
+    }
+
+    /// Returns whether [`Self::run_remote`] allocates a remote
+    /// pseudo-terminal.
+    #[must_use]
+    pub const fn tty(&self) -> bool {
+        self.tty
+    }
+
+    /// Returns the directory holding shared SSH connection sockets, when
+    /// `ssh_multiplex` is enabled.
+    #[must_use]
+    pub fn control_dir(&self) -> Option<&Utf8Path> {
+        self.control.as_deref().map(control::ControlDir::path)
+    }
+
+    /// Stops the shared SSH connection to `networking`, if one is open.
+    ///
+    /// Called when a run tears its instance down or detaches from it.
+    /// Failures are ignored: the master exits on its own once idle or when
+    /// the instance goes away.
+    pub async fn close_connection(&self, networking: &InstanceNetworking) {
+        if self.control.is_none() {
+            return;
+        }
+        let mut args = self.common_ssh_options(networking.ssh_port);
+        args.push(OsString::from("-O"));
+        args.push(OsString::from("exit"));
+        args.push(OsString::from(format!(
+            "{}@{}",
+            self.config.ssh_user, networking.public_ip
+        )));
+        self.runner
+            .run_quiet(&self.config.ssh_bin, &args)
+            .await
+            .ok();
     }
 
     /// Returns a reference to the underlying configuration.
@@ -60,29 +180,321 @@ impl<R: CommandRunner> Syncer<R> {
         &self.config
     }
 
-    /// Runs git-aware rsync from `source` to the chosen destination.
+    /// Runs git-aware rsync from `source` to the chosen destination, selecting
+    /// files according to the configured [`SyncMode`]. With `tar_stream`
+    /// set, an empty destination receives a `tar | zstd` stream of the same
+    /// files instead.
     ///
     /// # Errors
     ///
     /// Returns [`SyncError::MissingSource`] when the source directory is
-    /// absent, or [`SyncError::CommandFailure`] if `rsync` returns a non-zero
-    /// exit code.
-    pub fn sync(&self, source: &Utf8Path, destination: &SyncDestination) -> Result<(), SyncError> {
-        let args = self.build_rsync_args(source, destination)?;
-        let output = self.runner.run(&self.config.rsync_bin, &args)?;
-        if output.is_success() {
+    /// absent, [`SyncError::Staging`] when a git mode cannot prepare its
+    /// staging directory, or [`SyncError::CommandFailure`] if `git`, `tar`,
+    /// `rsync`, or the stream's shell returns a non-zero exit code.
+    pub async fn sync(
+        &self,
+        source: &Utf8Path,
+        destination: &SyncDestination,
+    ) -> Result<(), SyncError> {
+        self.transfer(source, destination, Transfer::Apply)
+            .await
+            .map(|_| ())
+    }
+
+    /// Reports what [`Syncer::sync`] would send to an empty destination,
+    /// such as a freshly provisioned instance, without transferring anything.
+    ///
+    /// In the `git-fetch` mode the report lists the files the patch changes
+    /// and counts the patch's size, unless the commit is not on the remote
+    /// and the sync would fall back to sending the working tree. Missing LFS
+    /// content is pulled first, as [`Syncer::sync`] would, so the report
+    /// counts the content rather than the pointer files.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`Syncer::sync`].
+    pub async fn dry_run(&self, source: &Utf8Path) -> Result<SyncStats, SyncError> {
+        require_source(source)?;
+        self.pull_lfs(source).await?;
+        if self.config.sync_mode == SyncMode::GitFetch {
+            let staging = staging::Staging::new()?;
+            if let Some(plan) = fetch::prepare(&self.runner, &self.config, source, &staging).await?
+            {
+                return fetch::payload_stats(&self.runner, &self.config, source, &plan).await;
+            }
+        }
+        let empty = staging::Staging::new()?;
+        let destination = SyncDestination::Local {
+            path: empty.path().to_path_buf(),
+        };
+        let output = self
+            .transfer(source, &destination, Transfer::DryRun)
+            .await?;
+        Ok(SyncStats::parse(&output.stdout))
+    }
+
+    /// Fails when `stats` exceed the configured `max_sync_bytes`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SyncError::SizeLimit`] when the transfer is too large.
+    pub const fn check_size_limit(&self, stats: &SyncStats) -> Result<(), SyncError> {
+        match self.config.max_sync_bytes {
+            Some(limit) if stats.total_bytes > limit => Err(SyncError::SizeLimit {
+                bytes: stats.total_bytes,
+                limit,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Lists the files in `stats` that look like secrets, reading contents
+    /// from `source`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SyncError::SecretPattern`] when a configured pattern is
+    /// invalid, or [`SyncError::SecretScan`] when a file cannot be read.
+    pub fn scan_secrets(
+        &self,
+        source: &Utf8Path,
+        stats: &SyncStats,
+    ) -> Result<Vec<SecretFinding>, SyncError> {
+        secrets::scan(&self.config, source, &stats.files)
+    }
+
+    async fn transfer(
+        &self,
+        source: &Utf8Path,
+        destination: &SyncDestination,
+        transfer: Transfer,
+    ) -> Result<CommandOutput, SyncError> {
+        require_source(source)?;
+        let staging = staging::Staging::new()?;
+        if matches!(transfer, Transfer::Apply)
+            && let Some(output) = self.prepare_apply(source, destination, &staging).await?
+        {
+            return Ok(output);
+        }
+        let (tree, listing) = self.select(source, &staging).await?;
+        if self.streams_to(destination, transfer).await? {
+            return self.stream(&tree, listing.as_deref(), destination).await;
+        }
+        let args = self.build_rsync_args(&tree, destination, listing.as_deref())?;
+        self.rsync(args, transfer).await
+    }
+
+    /// Readies a transfer that changes `destination`: pulls missing LFS
+    /// content, then, in the `git-fetch` mode, has `destination` fetch `HEAD`
+    /// and apply the uncommitted changes.
+    ///
+    /// Returns `None` in other modes, or when the commit is not on the remote
+    /// or the destination could not fetch it, so the working tree is sent
+    /// instead.
+    async fn prepare_apply(
+        &self,
+        source: &Utf8Path,
+        destination: &SyncDestination,
+        staging: &staging::Staging,
+    ) -> Result<Option<CommandOutput>, SyncError> {
+        self.pull_lfs(source).await?;
+        if self.config.sync_mode != SyncMode::GitFetch {
+            return Ok(None);
+        }
+        match fetch::prepare(&self.runner, &self.config, source, staging).await? {
+            Some(plan) => self.fetch(&plan, destination).await,
+            None => Ok(None),
+        }
+    }
+
+    /// Pulls missing LFS content for the modes that send the working tree.
+    /// `git-commit` relies on `git archive` applying the LFS filter instead.
+    async fn pull_lfs(&self, source: &Utf8Path) -> Result<(), SyncError> {
+        if self.config.sync_mode == SyncMode::GitCommit {
             return Ok(());
         }
+        lfs::pull_missing(&self.runner, &self.config, source).await
+    }
+
+    /// Returns the tree to send and, in the `git-files` mode, the merge file
+    /// listing the files to include.
+    async fn select(
+        &self,
+        source: &Utf8Path,
+        staging: &staging::Staging,
+    ) -> Result<(Utf8PathBuf, Option<Utf8PathBuf>), SyncError> {
+        match self.config.sync_mode {
+            SyncMode::RsyncFilter | SyncMode::GitFetch => Ok((source.to_path_buf(), None)),
+            SyncMode::GitFiles => {
+                let listing =
+                    git::write_listing(&self.runner, &self.config, source, staging).await?;
+                Ok((source.to_path_buf(), Some(listing)))
+            }
+            SyncMode::GitCommit => {
+                let tree = git::export_head(&self.runner, &self.config, source, staging).await?;
+                Ok((tree, None))
+            }
+        }
+    }
 
-        let status_text = output
-            .code
-            .map_or_else(|| String::from("unknown"), |code| code.to_string());
-        Err(SyncError::CommandFailure {
-            program: self.config.rsync_bin.clone(),
-            status: output.code,
-            status_text,
-            stderr: output.stderr,
-        })
+    /// Reports whether the transfer sends a tarball stream instead of
+    /// running rsync.
+    async fn streams_to(
+        &self,
+        destination: &SyncDestination,
+        transfer: Transfer,
+    ) -> Result<bool, SyncError> {
+        Ok(matches!(transfer, Transfer::Apply)
+            && self.config.tar_stream
+            && self.destination_is_empty(destination).await?)
+    }
+
+    /// Reports whether `destination` is missing or empty, so the tarball
+    /// stream can replace rsync.
+    async fn destination_is_empty(&self, destination: &SyncDestination) -> Result<bool, SyncError> {
+        match destination {
+            SyncDestination::Remote {
+                user,
+                host,
+                port,
+                path,
+            } => {
+                let mut args = self.common_ssh_options(*port);
+                args.push(OsString::from(format!("{user}@{host}")));
+                args.push(OsString::from(stream::probe_command(path)));
+                let output = self.runner.run_quiet(&self.config.ssh_bin, &args).await?;
+                Ok(output.is_success())
+            }
+            SyncDestination::Local { path } => Ok(match fs::read_dir(path) {
+                Ok(mut entries) => entries.next().is_none(),
+                Err(err) => err.kind() == io::ErrorKind::NotFound,
+            }),
+        }
+    }
+
+    /// Sends the files a dry run of `tree` reports as a `tar | zstd` stream,
+    /// unpacked by one shell on `destination`.
+    async fn stream(
+        &self,
+        tree: &Utf8Path,
+        listing: Option<&Utf8Path>,
+        destination: &SyncDestination,
+    ) -> Result<CommandOutput, SyncError> {
+        let scratch = staging::Staging::new()?;
+        let empty = SyncDestination::Local {
+            path: scratch.path().join("empty"),
+        };
+        let dry_run_args = self.build_rsync_args(tree, &empty, listing)?;
+        let dry_run = self.rsync(dry_run_args, Transfer::DryRun).await?;
+        let list = scratch.write(stream::LIST_FILE, &stream::archive_list(&dry_run.stdout))?;
+
+        let sink = match destination {
+            SyncDestination::Remote {
+                user,
+                host,
+                port,
+                path,
+            } => {
+                let mut ssh = vec![stream::quote(&self.config.ssh_bin)];
+                ssh.extend(
+                    self.common_ssh_options(*port)
+                        .iter()
+                        .map(|arg| stream::quote(&arg.to_string_lossy())),
+                );
+                ssh.push(stream::quote(&format!("{user}@{host}")));
+                ssh.push(stream::quote(&stream::extract_command(path)));
+                ssh.join(" ")
+            }
+            SyncDestination::Local { path } => format!(
+                "{} -c {}",
+                stream::SHELL,
+                stream::quote(&stream::extract_command(path))
+            ),
+        };
+        let failures = scratch.path().join(stream::FAILURES_FILE);
+        let script = stream::pipeline(tree, &list, &failures, &sink);
+        let args = [OsString::from("-c"), OsString::from(script)];
+        let output = self.runner.run(stream::SHELL, &args).await?;
+        if output.is_success() {
+            return Ok(output);
+        }
+        Err(util::command_failure(stream::SHELL, output))
+    }
+
+    /// Uploads the patch and has `destination` fetch the planned commit.
+    ///
+    /// Returns `None` when the destination fails to fetch or apply it.
+    async fn fetch(
+        &self,
+        plan: &fetch::FetchPlan,
+        destination: &SyncDestination,
+    ) -> Result<Option<CommandOutput>, SyncError> {
+        let (program, args) = match destination {
+            SyncDestination::Remote {
+                user,
+                host,
+                port,
+                path,
+            } => {
+                // A fresh name in the SSH user's home keeps concurrent runs
+                // and other users on the host away from the patch.
+                let upload = Utf8PathBuf::from(upload::private_file("sync"));
+                let upload_args = [
+                    OsString::from("-az"),
+                    OsString::from("--rsh"),
+                    OsString::from(self.build_remote_shell(*port)),
+                    OsString::from(format!("{}/", plan.upload)),
+                    OsString::from(format!("{user}@{host}:{upload}")),
+                ];
+                util::run_checked(&self.runner, &self.config.rsync_bin, &upload_args).await?;
+                let mut ssh_args = self.common_ssh_options(*port);
+                ssh_args.push(OsString::from(format!("{user}@{host}")));
+                ssh_args.push(OsString::from(fetch_script(
+                    &self.config,
+                    plan,
+                    path,
+                    &upload,
+                )));
+                (self.config.ssh_bin.as_str(), ssh_args)
+            }
+            SyncDestination::Local { path } => {
+                let script = fetch_script(&self.config, plan, path, &plan.upload);
+                (
+                    stream::SHELL,
+                    vec![OsString::from("-c"), OsString::from(script)],
+                )
+            }
+        };
+        let output = self.runner.run_quiet(program, &args).await?;
+        Ok(output.is_success().then_some(output))
+    }
+
+    async fn rsync(
+        &self,
+        mut args: Vec<OsString>,
+        transfer: Transfer,
+    ) -> Result<CommandOutput, SyncError> {
+        match transfer {
+            Transfer::Apply => {
+                let output = self.runner.run(&self.config.rsync_bin, &args).await?;
+                if output.is_success() {
+                    return Ok(output);
+                }
+                Err(util::command_failure(&self.config.rsync_bin, output))
+            }
+            Transfer::DryRun => {
+                args.splice(
+                    ..0,
+                    [
+                        "--dry-run".to_owned(),
+                        "--stats".to_owned(),
+                        format!("--out-format={ITEMIZE_FORMAT}"),
+                    ]
+                    .map(OsString::from),
+                );
+                util::run_checked(&self.runner, &self.config.rsync_bin, &args).await
+            }
+        }
     }
 
     /// Performs a sync followed by execution of `remote_command` via SSH.
@@ -96,15 +508,15 @@ impl<R: CommandRunner> Syncer<R> {
     /// `remote_command` is passed verbatim to the SSH client after the working
     /// directory prefix; callers must ensure any untrusted input is sanitized
     /// before invoking this method.
-    pub fn sync_and_run(
+    pub async fn sync_and_run(
         &self,
         source: &Utf8Path,
         networking: &InstanceNetworking,
         remote_command: &str,
     ) -> Result<RemoteCommandOutput, SyncError> {
         let destination = self.config.remote_destination(networking);
-        self.sync(source, &destination)?;
-        self.run_remote(networking, remote_command)
+        self.sync(source, &destination).await?;
+        self.run_remote(networking, remote_command).await
     }
 
     /// Builds a sync destination using the configured SSH settings.
@@ -115,23 +527,90 @@ impl<R: CommandRunner> Syncer<R> {
 
     /// Executes `remote_command` over SSH and returns the remote exit code.
     ///
+    /// Allocates a remote pseudo-terminal when built [`Self::with_tty`], and
+    /// first uploads the variables given to [`Self::with_env`] and the script
+    /// given to [`Self::with_script`].
+    ///
     /// # Errors
     ///
     /// Propagates any failure to spawn or execute the SSH command from the
-    /// configured [`CommandRunner`].
+    /// configured [`AsyncCommandRunner`].
     ///
     /// # Security
     ///
     /// `remote_command` is not escaped; only the working directory component is
     /// shell-escaped. Ensure any caller-provided arguments are validated or
     /// quoted upstream.
-    pub fn run_remote(
+    pub async fn run_remote(
         &self,
         networking: &InstanceNetworking,
         remote_command: &str,
     ) -> Result<RemoteCommandOutput, SyncError> {
-        let remote_cmd_wrapped = self.build_remote_command(remote_command);
-        self.execute_ssh(networking, &remote_cmd_wrapped)
+        let preamble = self.upload_files(networking).await?;
+        let remote_cmd_wrapped =
+            remote_command::build_remote_command(&self.config, &preamble, remote_command);
+        self.execute_ssh(networking, &remote_cmd_wrapped, self.tty)
+            .await
+    }
+
+    /// Executes a hook `command` over SSH like [`Self::run_remote`], with the
+    /// forwarded environment but without the script or a pseudo-terminal.
+    ///
+    /// # Errors
+    ///
+    /// Propagates any failure to spawn or execute the SSH command from the
+    /// configured [`AsyncCommandRunner`].
+    pub async fn run_hook(
+        &self,
+        networking: &InstanceNetworking,
+        command: &str,
+    ) -> Result<RemoteCommandOutput, SyncError> {
+        let preamble = self.upload_env(networking).await?;
+        let wrapped = remote_command::build_remote_command(&self.config, &preamble, command);
+        self.execute_ssh(networking, &wrapped, false).await
+    }
+
+    /// Uploads the forwarded environment and script, returning the fragment
+    /// that loads them; empty when there is nothing to upload.
+    async fn upload_files(&self, networking: &InstanceNetworking) -> Result<String, SyncError> {
+        let mut preamble = self.upload_env(networking).await?;
+        if let Some(script) = &self.script {
+            let file = upload::private_file("script");
+            self.upload(networking, &file, script).await?;
+            preamble.push_str(&upload::script_setup(&file));
+        }
+        Ok(preamble)
+    }
+
+    /// Uploads the forwarded environment, returning the fragment that loads
+    /// it; empty when nothing is forwarded.
+    async fn upload_env(&self, networking: &InstanceNetworking) -> Result<String, SyncError> {
+        if self.env.is_empty() {
+            return Ok(String::new());
+        }
+        let file = upload::private_file("env");
+        self.upload(networking, &file, self.env.script().as_bytes())
+            .await?;
+        Ok(remote_env::source_command(&file))
+    }
+
+    /// Writes `contents` to `file` in the remote home directory, readable
+    /// only by the SSH user.
+    async fn upload(
+        &self,
+        networking: &InstanceNetworking,
+        file: &str,
+        contents: &[u8],
+    ) -> Result<(), SyncError> {
+        let args = self.build_ssh_args(networking, &upload::upload_command(file));
+        let output = self
+            .runner
+            .run_with_input(&self.config.ssh_bin, &args, contents)
+            .await?;
+        if output.is_success() {
+            return Ok(());
+        }
+        Err(util::command_failure(&self.config.ssh_bin, output))
     }
 
     /// Executes `remote_command` over SSH without applying the working
@@ -140,27 +619,37 @@ impl<R: CommandRunner> Syncer<R> {
     /// # Errors
     ///
     /// Propagates any failure to spawn or execute the SSH command from the
-    /// configured [`CommandRunner`].
+    /// configured [`AsyncCommandRunner`].
     ///
     /// # Security
     ///
     /// `remote_command` is passed verbatim to the SSH client. Ensure any
     /// caller-provided arguments are validated or quoted upstream.
-    pub fn run_remote_raw(
+    pub async fn run_remote_raw(
         &self,
         networking: &InstanceNetworking,
         remote_command: &str,
     ) -> Result<RemoteCommandOutput, SyncError> {
-        self.execute_ssh(networking, remote_command)
+        self.execute_ssh(networking, remote_command, false).await
     }
 
-    fn execute_ssh(
+    async fn execute_ssh(
         &self,
         networking: &InstanceNetworking,
         command: &str,
+        tty: bool,
     ) -> Result<RemoteCommandOutput, SyncError> {
-        let args = self.build_ssh_args(networking, command);
-        let output = self.runner.run(&self.config.ssh_bin, &args)?;
+        let mut args = self.build_ssh_args(networking, command);
+        let output = if tty {
+            // `-tt` forces a pseudo-terminal even when an explicit `--tty`
+            // runs without a local one.
+            args.insert(0, OsString::from("-tt"));
+            self.runner
+                .run_interactive(&self.config.ssh_bin, &args)
+                .await?
+        } else {
+            self.runner.run(&self.config.ssh_bin, &args).await?
+        };
 
         Ok(RemoteCommandOutput {
             exit_code: output.code,
@@ -169,24 +658,40 @@ impl<R: CommandRunner> Syncer<R> {
         })
     }
 
+    /// Builds the rsync arguments; `listing` is the merge file of files to
+    /// send in the `git-files` mode.
     fn build_rsync_args(
         &self,
         source: &Utf8Path,
         destination: &SyncDestination,
+        listing: Option<&Utf8Path>,
     ) -> Result<Vec<OsString>, SyncError> {
-        if !source.is_dir() {
-            return Err(SyncError::MissingSource {
-                path: source.to_path_buf(),
-            });
-        }
+        require_source(source)?;
 
         let mut args = vec![
             OsString::from("-az"),
             OsString::from("--delete"),
-            OsString::from("--filter=:- .gitignore"),
             OsString::from("--exclude"),
-            OsString::from(".git/"),
+            OsString::from(".git"),
         ];
+        // rsync applies the first matching rule, so configured rules and
+        // `.mriyaignore` override `.gitignore` but never re-include `.git`.
+        // Submodules hold a `.git` file pointing into the parent's `.git`
+        // directory, which is never sent, so those files are skipped too.
+        args.extend(
+            workspace_rules(&self.config, source)?
+                .iter()
+                .map(FilterRule::to_arg),
+        );
+        if let Some(rules) = listing {
+            args.push(OsString::from(format!("--filter=merge {rules}")));
+        }
+        args.push(OsString::from("--filter=:- .gitignore"));
+        if listing.is_some() {
+            // Hide rules apply only to the sender, so unlisted files are not
+            // sent but are still deleted from the remote.
+            args.push(OsString::from("--filter=H *"));
+        }
 
         match destination {
             SyncDestination::Remote {
@@ -251,6 +756,10 @@ impl<R: CommandRunner> Syncer<R> {
             )));
         }
 
+        if let Some(control) = &self.control {
+            args.extend(control.ssh_options());
+        }
+
         args
     }
 
@@ -264,8 +773,26 @@ impl<R: CommandRunner> Syncer<R> {
         format!("{} {}", self.config.ssh_bin, opts)
     }
 
+    #[cfg(test)]
     fn build_remote_command(&self, remote_command: &str) -> String {
-        remote_command::build_remote_command(&self.config, remote_command)
+        remote_command::build_remote_command(&self.config, "", remote_command)
+    }
+}
+
+/// Whether rsync changes the destination or only reports what it would send.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Transfer {
+    Apply,
+    DryRun,
+}
+
+fn require_source(source: &Utf8Path) -> Result<(), SyncError> {
+    if source.is_dir() {
+        Ok(())
+    } else {
+        Err(SyncError::MissingSource {
+            path: source.to_path_buf(),
+        })
     }
 }
 
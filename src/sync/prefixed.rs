@@ -0,0 +1,84 @@
+//! Line-prefixed output streaming for concurrent runs.
+//!
+//! When several runs share one terminal, raw chunk forwarding interleaves
+//! partial lines from different instances. [`PrefixedCommandRunner`] forwards
+//! whole lines instead, each tagged with a caller-supplied prefix such as
+//! `[shard 2/4] `, while still capturing the unprefixed output.
+
+use std::ffi::OsString;
+
+use super::SyncError;
+use super::redact::Redactor;
+use super::sink::{Capture, OutputSink};
+use super::types::{AsyncCommandRunner, CommandOutput, StreamingCommandRunner};
+
+/// Command runner that streams subprocess output line by line behind a
+/// prefix while capturing it.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PrefixedCommandRunner {
+    prefix: String,
+    capture: Capture,
+    redactor: Redactor,
+}
+
+impl PrefixedCommandRunner {
+    /// Creates a runner that writes `prefix` before every forwarded line.
+    #[must_use]
+    pub fn new(prefix: impl Into<String>) -> Self {
+        Self {
+            prefix: prefix.into(),
+            capture: Capture::default(),
+            redactor: Redactor::default(),
+        }
+    }
+
+    /// Masks the secrets of `redactor` in forwarded and captured output.
+    #[must_use]
+    pub fn with_redactor(mut self, redactor: Redactor) -> Self {
+        self.redactor = redactor;
+        self
+    }
+
+    /// Sets how much of each stream is returned in the command output.
+    #[must_use]
+    pub const fn with_capture(mut self, capture: Capture) -> Self {
+        self.capture = capture;
+        self
+    }
+
+    /// Returns the prefix written before every forwarded line.
+    #[must_use]
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    fn streaming(&self) -> StreamingCommandRunner {
+        StreamingCommandRunner::default()
+            .with_sink(OutputSink::Prefixed(self.prefix.clone()))
+            .with_capture(self.capture)
+            .with_redactor(self.redactor.clone())
+    }
+}
+
+impl AsyncCommandRunner for PrefixedCommandRunner {
+    async fn run_quiet(
+        &self,
+        program: &str,
+        args: &[OsString],
+    ) -> Result<CommandOutput, SyncError> {
+        self.streaming().run_quiet(program, args).await
+    }
+
+    async fn run_with_input(
+        &self,
+        program: &str,
+        args: &[OsString],
+        input: &[u8],
+    ) -> Result<CommandOutput, SyncError> {
+        self.streaming().run_with_input(program, args, input).await
+    }
+
+    async fn run(&self, program: &str, args: &[OsString]) -> Result<CommandOutput, SyncError> {
+        self.streaming().run(program, args).await
+    }
+}
@@ -0,0 +1,160 @@
+//! Masking of secret values in command output and error messages.
+//!
+//! A [`Redactor`] replaces every occurrence of a configured secret, such as a
+//! forwarded API token or the Scaleway secret key, with [`MASK`]. Streamed
+//! output arrives in arbitrary chunks, so [`StreamRedaction`] holds back the
+//! tail of a chunk while it could still be the start of a secret, and only
+//! releases it once the next chunk settles the question.
+
+use std::fmt;
+use std::sync::Arc;
+
+use super::SyncError;
+
+/// Text written in place of a secret.
+pub const MASK: &str = "***";
+
+/// Shortest value treated as a secret; shorter values would mask ordinary
+/// words and numbers throughout the output.
+pub const MIN_SECRET_LEN: usize = 4;
+
+/// Masks configured secret values.
+#[derive(Clone, Default, Eq, PartialEq)]
+pub struct Redactor {
+    /// Secrets, longest first so overlapping secrets mask as much as possible.
+    secrets: Arc<[Vec<u8>]>,
+}
+
+impl Redactor {
+    /// Creates a redactor for `secrets`, ignoring values shorter than
+    /// [`MIN_SECRET_LEN`].
+    #[must_use]
+    pub fn new<I, S>(secrets: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut values: Vec<Vec<u8>> = secrets
+            .into_iter()
+            .map(|secret| secret.as_ref().as_bytes().to_vec())
+            .filter(|secret| secret.len() >= MIN_SECRET_LEN)
+            .collect();
+        values.sort_by(|left, right| right.len().cmp(&left.len()).then(left.cmp(right)));
+        values.dedup();
+        Self {
+            secrets: values.into(),
+        }
+    }
+
+    /// Returns `true` when there is nothing to mask.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.secrets.is_empty()
+    }
+
+    /// Returns `text` with every secret masked.
+    #[must_use]
+    pub fn redact(&self, text: &str) -> String {
+        if self.is_empty() {
+            return text.to_owned();
+        }
+        String::from_utf8_lossy(&self.redact_bytes(text.as_bytes())).into_owned()
+    }
+
+    /// Returns `bytes` with every secret masked.
+    pub(crate) fn redact_bytes(&self, bytes: &[u8]) -> Vec<u8> {
+        let mut masked = Vec::with_capacity(bytes.len());
+        self.scan(bytes, &mut masked, true);
+        masked
+    }
+
+    /// Appends `input` to `out` with secrets masked and returns how many
+    /// bytes were consumed.
+    ///
+    /// Unless `at_end` is set, scanning stops where the rest of `input` is a
+    /// proper prefix of a secret, so the caller can retry once more bytes
+    /// arrive.
+    fn scan(&self, input: &[u8], out: &mut Vec<u8>, at_end: bool) -> usize {
+        let mut index = 0;
+        while let Some(rest) = input.get(index..)
+            && let Some(&byte) = rest.first()
+        {
+            let incomplete =
+                |secret: &Vec<u8>| secret.len() > rest.len() && secret.starts_with(rest);
+            if !at_end && self.secrets.iter().any(incomplete) {
+                break;
+            }
+            if let Some(secret) = self.secrets.iter().find(|secret| rest.starts_with(secret)) {
+                out.extend_from_slice(MASK.as_bytes());
+                index += secret.len();
+            } else {
+                out.push(byte);
+                index += 1;
+            }
+        }
+        index
+    }
+}
+
+impl fmt::Debug for Redactor {
+    /// Reports how many secrets are masked, never the secrets themselves.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Redactor")
+            .field("secrets", &self.secrets.len())
+            .finish()
+    }
+}
+
+impl SyncError {
+    /// Masks the secrets of `redactor` in the error's message or captured
+    /// stderr.
+    #[must_use]
+    pub fn redact(mut self, redactor: &Redactor) -> Self {
+        if let Self::Spawn { message, .. }
+        | Self::IgnoreFile { message, .. }
+        | Self::Staging { message }
+        | Self::ControlSocket { message }
+        | Self::SecretScan { message, .. }
+        | Self::CommandFailure {
+            stderr: message, ..
+        } = &mut self
+        {
+            *message = redactor.redact(message);
+        }
+        self
+    }
+}
+
+/// Redacts a stream delivered in chunks.
+pub(crate) struct StreamRedaction<'a> {
+    redactor: &'a Redactor,
+    pending: Vec<u8>,
+}
+
+impl<'a> StreamRedaction<'a> {
+    pub(crate) const fn new(redactor: &'a Redactor) -> Self {
+        Self {
+            redactor,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Returns the masked output that `chunk` settles, holding back a tail
+    /// that may still turn into a secret.
+    pub(crate) fn push(&mut self, chunk: &[u8]) -> Vec<u8> {
+        if self.redactor.is_empty() {
+            return chunk.to_vec();
+        }
+        self.pending.extend_from_slice(chunk);
+        let mut masked = Vec::with_capacity(self.pending.len());
+        let consumed = self.redactor.scan(&self.pending, &mut masked, false);
+        self.pending.drain(..consumed);
+        masked
+    }
+
+    /// Returns whatever was held back, masked, once the stream has ended.
+    pub(crate) fn finish(&mut self) -> Vec<u8> {
+        let rest = std::mem::take(&mut self.pending);
+        self.redactor.redact_bytes(&rest)
+    }
+}
@@ -30,15 +30,17 @@ pub const CACHE_SUBDIRECTORIES: &[&str] = &[
 /// Builds a remote command string with an optional cache routing preamble.
 ///
 /// The remote path is shell-escaped, cache exports are prepended when enabled,
-/// and the user command is wrapped with a directory change.
-pub(crate) fn build_remote_command(config: &SyncConfig, remote_command: &str) -> String {
+/// `uploads` (which loads forwarded variables and any uploaded script)
+/// follows them so forwarded values win, and the user command is wrapped with
+/// a directory change.
+pub(crate) fn build_remote_command(
+    config: &SyncConfig,
+    uploads: &str,
+    remote_command: &str,
+) -> String {
     let escaped_path = escape(config.remote_path.as_str().into());
     let cache_preamble = cache_routing_preamble(config);
-    if cache_preamble.is_empty() {
-        format!("cd {escaped_path} && {remote_command}")
-    } else {
-        format!("{cache_preamble}cd {escaped_path} && {remote_command}")
-    }
+    format!("{cache_preamble}{uploads}cd {escaped_path} && {remote_command}")
 }
 
 fn cache_routing_preamble(config: &SyncConfig) -> String {
@@ -0,0 +1,144 @@
+//! Environment forwarded to the remote command.
+//!
+//! Values such as `DATABASE_URL` or API tokens must not appear in the `ssh`
+//! command line, where `ps` on either host and shell history would expose
+//! them. The [`Syncer`](super::Syncer) instead uploads them into a file only
+//! the SSH user can read (see [`super::upload`]), and the remote command
+//! sources and deletes that file before it starts. The file is sourced
+//! after the cache routing preamble, so forwarded values win over routed
+//! cache paths.
+
+use std::borrow::Cow;
+use std::fmt::{self, Write as _};
+
+use shell_escape::unix::escape;
+
+use super::SyncError;
+
+/// Ordered set of variables exported to the remote command.
+#[derive(Clone, Default, Eq, PartialEq)]
+pub struct RemoteEnv {
+    vars: Vec<(String, String)>,
+}
+
+impl RemoteEnv {
+    /// Resolves variables forwarded to the remote command.
+    ///
+    /// `pass` names variables copied from the local environment through
+    /// `lookup`, skipping those that are unset. `entries` follow as
+    /// `KEY=VALUE`, or as a bare `KEY` copied like `pass`. Later definitions
+    /// of a name replace earlier ones.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SyncError::InvalidEnv`] when a name is not a valid shell
+    /// variable name.
+    pub fn resolve(
+        pass: &[String],
+        entries: &[String],
+        lookup: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, SyncError> {
+        let mut env = Self::default();
+        let passed = pass.iter().map(|name| (name.as_str(), None));
+        let listed = entries.iter().map(|entry| match entry.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (entry.as_str(), None),
+        });
+        for (name, value) in passed.chain(listed) {
+            if !is_valid_name(name) {
+                return Err(SyncError::InvalidEnv {
+                    name: name.to_owned(),
+                });
+            }
+            let resolved = value.map(str::to_owned).or_else(|| lookup(name));
+            if let Some(found) = resolved {
+                env.set(name, found);
+            }
+        }
+        Ok(env)
+    }
+
+    /// Sets `name` to `value`, replacing any earlier definition.
+    pub fn set(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        let key = name.into();
+        self.vars.retain(|(existing, _)| *existing != key);
+        self.vars.push((key, value.into()));
+    }
+
+    /// Returns `true` when nothing is forwarded.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.vars.is_empty()
+    }
+
+    /// Returns the forwarded names in export order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.vars.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Returns the values to mask in output: those of variables whose names
+    /// look secret (see [`SECRET_NAME_MARKERS`]) or appear in `names`.
+    #[must_use]
+    pub fn secret_values(&self, names: &[String]) -> Vec<&str> {
+        self.vars
+            .iter()
+            .filter(|(name, _)| is_secret_name(name) || names.contains(name))
+            .map(|(_, value)| value.as_str())
+            .collect()
+    }
+
+    /// Renders the file sourced by the remote shell.
+    pub(super) fn script(&self) -> String {
+        self.vars
+            .iter()
+            .fold(String::new(), |mut script, (name, value)| {
+                let quoted = escape(Cow::Borrowed(value.as_str()));
+                writeln!(script, "export {name}={quoted}").ok();
+                script
+            })
+    }
+}
+
+impl fmt::Debug for RemoteEnv {
+    /// Lists names only, so values never reach logs or error messages.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RemoteEnv")
+            .field("names", &self.names().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// Fragments of variable names, matched case-insensitively, whose values are
+/// treated as secrets.
+pub const SECRET_NAME_MARKERS: &[&str] = &[
+    "TOKEN",
+    "SECRET",
+    "PASSWORD",
+    "PASSWD",
+    "KEY",
+    "CREDENTIAL",
+    "AUTH",
+    "DATABASE_URL",
+    "DSN",
+];
+
+fn is_secret_name(name: &str) -> bool {
+    let upper = name.to_ascii_uppercase();
+    SECRET_NAME_MARKERS
+        .iter()
+        .any(|marker| upper.contains(marker))
+}
+
+/// Returns whether `name` is a portable shell variable name.
+fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|first| first == '_' || first.is_ascii_alphabetic())
+        && chars.all(|rest| rest == '_' || rest.is_ascii_alphanumeric())
+}
+
+/// Shell fragment that exports the variables in `file` and deletes it.
+pub(super) fn source_command(file: &str) -> String {
+    format!(". \"$HOME\"/{file}; rm -f \"$HOME\"/{file}; ")
+}
@@ -0,0 +1,186 @@
+//! Pre-sync scan for files that look like credentials.
+//!
+//! The scan runs over the files a dry run reports, so it sees exactly what
+//! the sync would send. A file is flagged when its path matches a secret name
+//! pattern or when its first [`SCAN_LIMIT`] bytes contain a secret signature.
+//! Files matching `[sync] secret_allowlist` are never flagged.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use ignore::Match;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+
+use super::config::{SyncConfig, SyncError};
+use super::stats::SyncedFile;
+
+/// File name patterns, in gitignore syntax, that usually hold credentials.
+pub const DEFAULT_SECRET_PATTERNS: [&str; 18] = [
+    ".env",
+    ".env.*",
+    "!.env.example",
+    "!.env.sample",
+    "!.env.template",
+    "*.pem",
+    "*.key",
+    "*.p12",
+    "*.pfx",
+    "*.jks",
+    "id_rsa",
+    "id_dsa",
+    "id_ecdsa",
+    "id_ed25519",
+    "credentials.json",
+    ".netrc",
+    ".pgpass",
+    ".git-credentials",
+];
+
+/// Text fragments that usually only appear in credentials.
+pub const DEFAULT_SECRET_SIGNATURES: [&str; 6] = [
+    "PRIVATE KEY-----",
+    "PGP PRIVATE KEY BLOCK",
+    "aws_secret_access_key",
+    "ghp_",
+    "github_pat_",
+    "xoxb-",
+];
+
+/// Bytes read from the start of each file when looking for signatures.
+pub const SCAN_LIMIT: u64 = 64 * 1024;
+
+/// Why a file was flagged.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SecretMatch {
+    /// The path matches a secret name pattern.
+    Name(String),
+    /// The file contains a secret signature.
+    Content(String),
+}
+
+impl fmt::Display for SecretMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Name(pattern) => write!(f, "name matches `{pattern}`"),
+            Self::Content(signature) => write!(f, "contains `{signature}`"),
+        }
+    }
+}
+
+/// A file the sync would send that looks like a secret.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SecretFinding {
+    /// Path relative to the workspace root.
+    pub path: Utf8PathBuf,
+    /// Why the file was flagged.
+    pub reason: SecretMatch,
+}
+
+/// Scans `files`, relative to `source`, for likely secrets.
+///
+/// Contents are read from `source`; files missing there, such as those only
+/// present in a `git-commit` snapshot, are checked by name alone.
+///
+/// # Errors
+///
+/// Returns [`SyncError::SecretPattern`] when a configured pattern is invalid,
+/// or [`SyncError::SecretScan`] when a file cannot be read.
+pub(super) fn scan(
+    config: &SyncConfig,
+    source: &Utf8Path,
+    files: &[SyncedFile],
+) -> Result<Vec<SecretFinding>, SyncError> {
+    let names = compile(
+        source,
+        DEFAULT_SECRET_PATTERNS
+            .iter()
+            .copied()
+            .chain(config.secret_patterns.iter().map(String::as_str)),
+    )?;
+    let allowlist = compile(source, config.secret_allowlist.iter().map(String::as_str))?;
+    let signatures: Vec<&str> = DEFAULT_SECRET_SIGNATURES
+        .iter()
+        .copied()
+        .chain(config.secret_signatures.iter().map(String::as_str))
+        .filter(|signature| !signature.is_empty())
+        .collect();
+
+    let mut findings = Vec::new();
+    for file in files {
+        if allowlist
+            .matched_path_or_any_parents(&file.path, false)
+            .is_ignore()
+        {
+            continue;
+        }
+        let reason = match names.matched_path_or_any_parents(&file.path, false) {
+            Match::Ignore(glob) => Some(SecretMatch::Name(glob.original().to_owned())),
+            Match::None | Match::Whitelist(_) => {
+                find_signature(&source.join(&file.path), &signatures)?
+                    .map(|signature| SecretMatch::Content(signature.to_owned()))
+            }
+        };
+        if let Some(found) = reason {
+            findings.push(SecretFinding {
+                path: file.path.clone(),
+                reason: found,
+            });
+        }
+    }
+    Ok(findings)
+}
+
+fn compile<'a>(
+    source: &Utf8Path,
+    patterns: impl Iterator<Item = &'a str>,
+) -> Result<Gitignore, SyncError> {
+    let mut builder = GitignoreBuilder::new(source);
+    builder.case_insensitive(true).map_err(pattern_error(""))?;
+    for pattern in patterns
+        .map(str::trim)
+        .filter(|pattern| !pattern.is_empty())
+    {
+        builder
+            .add_line(None, pattern)
+            .map_err(pattern_error(pattern))?;
+    }
+    builder.build().map_err(pattern_error(""))
+}
+
+fn pattern_error(pattern: &str) -> impl Fn(ignore::Error) -> SyncError + '_ {
+    move |err| SyncError::SecretPattern {
+        pattern: pattern.to_owned(),
+        message: err.to_string(),
+    }
+}
+
+/// Returns the first signature found in the start of `path`.
+fn find_signature<'a>(
+    path: &Utf8Path,
+    signatures: &[&'a str],
+) -> Result<Option<&'a str>, SyncError> {
+    let mut head = Vec::new();
+    let read = File::open(path).and_then(|file| file.take(SCAN_LIMIT).read_to_end(&mut head));
+    match read {
+        Ok(_) => {}
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => {
+            return Err(SyncError::SecretScan {
+                path: path.to_path_buf(),
+                message: err.to_string(),
+            });
+        }
+    }
+    Ok(signatures
+        .iter()
+        .copied()
+        .find(|signature| contains(&head, signature.as_bytes())))
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack
+        .windows(needle.len())
+        .any(|window| window == needle)
+}
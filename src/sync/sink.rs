@@ -0,0 +1,232 @@
+//! Destinations for the output of streamed commands.
+//!
+//! [`StreamingCommandRunner`](super::StreamingCommandRunner) forwards a
+//! command's stdout and stderr to an [`OutputSink`] as they arrive and keeps
+//! only what its [`Capture`] policy asks for. The default keeps the last
+//! [`DEFAULT_CAPTURE_BYTES`] of each stream, enough for error messages, so a
+//! long test run no longer holds its whole log in memory. Secrets known to
+//! the runner's [`Redactor`](super::Redactor) are masked before either sees
+//! the output.
+
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::Arc;
+
+use camino::Utf8PathBuf;
+use tokio::fs::{File, OpenOptions};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWriteExt, BufReader, Stderr, Stdout};
+
+use super::StreamingCommandRunner;
+use super::redact::StreamRedaction;
+
+/// Bytes of each stream kept by [`Capture::default`].
+pub const DEFAULT_CAPTURE_BYTES: usize = 64 * 1024;
+
+/// Stream of a command that produced a chunk of output.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OutputStream {
+    /// Standard output.
+    Stdout,
+    /// Standard error.
+    Stderr,
+}
+
+/// Callback receiving every chunk of output as it arrives.
+pub type OutputCallback = Arc<dyn Fn(OutputStream, &[u8]) + Send + Sync>;
+
+/// Where streamed output is written while a command runs.
+#[derive(Clone, Default)]
+pub enum OutputSink {
+    /// Copies each stream to the matching stream of this process.
+    #[default]
+    Terminal,
+    /// Writes whole lines to the terminal behind a prefix, so lines from
+    /// concurrent runs never interleave.
+    Prefixed(String),
+    /// Appends both streams to a log file, creating it when missing.
+    LogFile(Utf8PathBuf),
+    /// Hands every chunk to a callback.
+    Callback(OutputCallback),
+    /// Drops the output; only the capture remains.
+    Discard,
+}
+
+impl fmt::Debug for OutputSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Terminal => f.write_str("Terminal"),
+            Self::Prefixed(prefix) => f.debug_tuple("Prefixed").field(prefix).finish(),
+            Self::LogFile(path) => f.debug_tuple("LogFile").field(path).finish(),
+            Self::Callback(_) => f.write_str("Callback(..)"),
+            Self::Discard => f.write_str("Discard"),
+        }
+    }
+}
+
+/// How much of each stream is kept in the returned output.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Capture {
+    /// Keeps everything; memory grows with the output.
+    Full,
+    /// Keeps at most this many trailing bytes; `Tail(0)` keeps nothing.
+    Tail(usize),
+}
+
+impl Default for Capture {
+    fn default() -> Self {
+        Self::Tail(DEFAULT_CAPTURE_BYTES)
+    }
+}
+
+/// Bounded buffer implementing a [`Capture`] policy.
+struct CaptureBuffer {
+    limit: Option<usize>,
+    bytes: Vec<u8>,
+}
+
+impl CaptureBuffer {
+    const fn new(capture: Capture) -> Self {
+        let limit = match capture {
+            Capture::Full => None,
+            Capture::Tail(bytes) => Some(bytes),
+        };
+        Self {
+            limit,
+            bytes: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, chunk: &[u8]) {
+        self.bytes.extend_from_slice(chunk);
+        if let Some(limit) = self.limit {
+            let excess = self.bytes.len().saturating_sub(limit);
+            self.bytes.drain(..excess);
+        }
+    }
+
+    fn into_string(self) -> String {
+        String::from_utf8_lossy(&self.bytes).into_owned()
+    }
+}
+
+/// Open writer for one stream of a sink.
+enum SinkWriter<'a> {
+    Stdout(Stdout),
+    Stderr(Stderr),
+    Prefixed {
+        prefix: &'a str,
+        stream: OutputStream,
+    },
+    File(File),
+    Callback(&'a OutputCallback, OutputStream),
+    Discard,
+}
+
+impl<'a> SinkWriter<'a> {
+    async fn open(sink: &'a OutputSink, stream: OutputStream) -> io::Result<Self> {
+        Ok(match sink {
+            OutputSink::Terminal => match stream {
+                OutputStream::Stdout => Self::Stdout(tokio::io::stdout()),
+                OutputStream::Stderr => Self::Stderr(tokio::io::stderr()),
+            },
+            OutputSink::Prefixed(prefix) => Self::Prefixed { prefix, stream },
+            OutputSink::LogFile(path) => Self::File(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .await?,
+            ),
+            OutputSink::Callback(callback) => Self::Callback(callback, stream),
+            OutputSink::Discard => Self::Discard,
+        })
+    }
+
+    /// Whether chunks must be whole lines.
+    const fn is_line_based(&self) -> bool {
+        matches!(self, Self::Prefixed { .. })
+    }
+
+    async fn write(&mut self, chunk: &[u8]) -> io::Result<()> {
+        match self {
+            Self::Stdout(writer) => write_flushed(writer, chunk).await,
+            Self::Stderr(writer) => write_flushed(writer, chunk).await,
+            Self::File(writer) => write_flushed(writer, chunk).await,
+            Self::Prefixed { prefix, stream } => {
+                write_prefixed_line(*stream, prefix.as_bytes(), chunk)
+            }
+            Self::Callback(callback, stream) => {
+                callback(*stream, chunk);
+                Ok(())
+            }
+            Self::Discard => Ok(()),
+        }
+    }
+}
+
+async fn write_flushed(
+    writer: &mut (impl tokio::io::AsyncWrite + Unpin),
+    chunk: &[u8],
+) -> io::Result<()> {
+    writer.write_all(chunk).await?;
+    writer.flush().await
+}
+
+/// Writes a single prefixed line while holding the stream lock so lines from
+/// concurrent runs never interleave.
+fn write_prefixed_line(stream: OutputStream, prefix: &[u8], line: &[u8]) -> io::Result<()> {
+    let mut writer: Box<dyn Write> = match stream {
+        OutputStream::Stdout => Box::new(io::stdout().lock()),
+        OutputStream::Stderr => Box::new(io::stderr().lock()),
+    };
+    writer.write_all(prefix)?;
+    writer.write_all(line)?;
+    if !line.ends_with(b"\n") {
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()
+}
+
+/// Forwards `reader` to the sink of `runner` and returns what its capture
+/// policy keeps, with the runner's secrets masked in both.
+///
+/// Returns an empty string when the stream was not piped.
+pub(super) async fn forward(
+    reader: Option<impl AsyncRead + Unpin>,
+    stream: OutputStream,
+    runner: &StreamingCommandRunner,
+) -> io::Result<String> {
+    let Some(source) = reader else {
+        return Ok(String::new());
+    };
+    let mut writer = SinkWriter::open(runner.sink(), stream).await?;
+    let mut captured = CaptureBuffer::new(runner.capture());
+    let mut redaction = StreamRedaction::new(runner.redactor());
+    let mut lines = BufReader::new(source);
+    let mut chunk = Vec::new();
+    loop {
+        chunk.clear();
+        let masked = if writer.is_line_based() {
+            // Whole lines are written at once, so nothing is held back.
+            lines.read_until(b'\n', &mut chunk).await?;
+            runner.redactor().redact_bytes(&chunk)
+        } else {
+            let available = lines.fill_buf().await?;
+            chunk.extend_from_slice(available);
+            lines.consume(chunk.len());
+            if chunk.is_empty() {
+                redaction.finish()
+            } else {
+                redaction.push(&chunk)
+            }
+        };
+        if !masked.is_empty() {
+            writer.write(&masked).await?;
+            captured.push(&masked);
+        }
+        if chunk.is_empty() {
+            break;
+        }
+    }
+    Ok(captured.into_string())
+}
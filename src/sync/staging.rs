@@ -0,0 +1,45 @@
+//! Temporary local directories for sync modes that need scratch space, such
+//! as the `git-files` listing, the `git-commit` snapshot, and the empty
+//! destination of a dry run.
+
+use std::fs;
+
+use camino::{Utf8Path, Utf8PathBuf};
+use tempfile::TempDir;
+
+use super::config::SyncError;
+
+/// Temporary local directory used by a single sync.
+///
+/// The directory is removed when the value is dropped.
+#[derive(Debug)]
+pub(super) struct Staging {
+    root: Utf8PathBuf,
+    _dir: TempDir,
+}
+
+impl Staging {
+    pub(super) fn new() -> Result<Self, SyncError> {
+        let staging_error = |message: String| SyncError::Staging { message };
+        let dir = tempfile::Builder::new()
+            .prefix("mriya-sync-")
+            .tempdir()
+            .map_err(|err| staging_error(err.to_string()))?;
+        let root = Utf8PathBuf::from_path_buf(dir.path().to_path_buf())
+            .map_err(|path| staging_error(format!("non UTF-8 path {}", path.display())))?;
+        Ok(Self { root, _dir: dir })
+    }
+
+    /// Returns the directory's path.
+    pub(super) fn path(&self) -> &Utf8Path {
+        &self.root
+    }
+
+    pub(super) fn write(&self, name: &str, contents: &str) -> Result<Utf8PathBuf, SyncError> {
+        let path = self.root.join(name);
+        fs::write(&path, contents).map_err(|err| SyncError::Staging {
+            message: format!("{path}: {err}"),
+        })?;
+        Ok(path)
+    }
+}
@@ -0,0 +1,151 @@
+//! Typed summary of what a sync would transfer.
+//!
+//! [`Syncer::dry_run`](super::Syncer::dry_run) runs rsync with `--dry-run`,
+//! `--stats`, and an itemized output format that adds each file's size, and
+//! [`SyncStats::parse`] turns that output into per-file sizes and totals.
+
+use std::collections::HashMap;
+
+use camino::{Utf8Path, Utf8PathBuf};
+
+/// rsync `--out-format` used by dry runs: the itemized change code, the file
+/// length in bytes, and the path.
+pub const ITEMIZE_FORMAT: &str = "%i %l %n";
+
+/// Length of rsync's itemized change code, such as `>f+++++++++`.
+const ITEMIZE_CODE_LEN: usize = 11;
+
+/// `--stats` line reporting the bytes of file data that would be sent.
+const TRANSFERRED_SIZE_LABEL: &str = "Total transferred file size:";
+
+/// A file rsync would send.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SyncedFile {
+    /// Path relative to the workspace root.
+    pub path: Utf8PathBuf,
+    /// File size in bytes.
+    pub bytes: u64,
+}
+
+/// Files rsync would send below one top-level directory.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DirectoryTotal {
+    /// Top-level directory, or `.` for files at the workspace root.
+    pub path: Utf8PathBuf,
+    /// Number of files sent from the directory.
+    pub files: usize,
+    /// Bytes sent from the directory.
+    pub bytes: u64,
+}
+
+/// What a sync would transfer.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SyncStats {
+    /// Every file rsync would send, in rsync's order.
+    pub files: Vec<SyncedFile>,
+    /// Bytes of file data rsync would send.
+    pub total_bytes: u64,
+}
+
+impl SyncStats {
+    /// Parses rsync output produced with `--out-format` set to
+    /// [`ITEMIZE_FORMAT`] and `--stats`.
+    ///
+    /// Only regular files that would be sent are listed. The total comes from
+    /// the `--stats` summary and falls back to the sum of the listed files
+    /// when the summary is missing.
+    #[must_use]
+    pub fn parse(output: &str) -> Self {
+        let files: Vec<SyncedFile> = output.lines().filter_map(parse_itemized).collect();
+        let total_bytes = output
+            .lines()
+            .find_map(parse_transferred_size)
+            .unwrap_or_else(|| files.iter().map(|file| file.bytes).sum());
+        Self { files, total_bytes }
+    }
+
+    /// Number of files rsync would send.
+    #[must_use]
+    pub const fn file_count(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Returns up to `limit` files, largest first.
+    #[must_use]
+    pub fn largest_files(&self, limit: usize) -> Vec<&SyncedFile> {
+        let mut sorted: Vec<&SyncedFile> = self.files.iter().collect();
+        sorted.sort_by(|left, right| {
+            right
+                .bytes
+                .cmp(&left.bytes)
+                .then_with(|| left.path.cmp(&right.path))
+        });
+        sorted.truncate(limit);
+        sorted
+    }
+
+    /// Returns up to `limit` top-level directories, largest first.
+    #[must_use]
+    pub fn top_directories(&self, limit: usize) -> Vec<DirectoryTotal> {
+        let mut totals: HashMap<Utf8PathBuf, DirectoryTotal> = HashMap::new();
+        for file in &self.files {
+            let dir = top_level_directory(&file.path);
+            let total = totals.entry(dir.clone()).or_insert(DirectoryTotal {
+                path: dir,
+                files: 0,
+                bytes: 0,
+            });
+            total.files = total.files.saturating_add(1);
+            total.bytes = total.bytes.saturating_add(file.bytes);
+        }
+        let mut sorted: Vec<DirectoryTotal> = totals.into_values().collect();
+        sorted.sort_by(|left, right| {
+            right
+                .bytes
+                .cmp(&left.bytes)
+                .then_with(|| left.path.cmp(&right.path))
+        });
+        sorted.truncate(limit);
+        sorted
+    }
+}
+
+/// Parses one itemized line such as `>f+++++++++ 1,024 src/lib.rs`.
+fn parse_itemized(line: &str) -> Option<SyncedFile> {
+    let mut parts = line.splitn(3, ' ');
+    let code = parts.next()?;
+    let bytes = parse_count(parts.next()?)?;
+    let path = parts.next()?;
+    let mut flags = code.chars();
+    let update = flags.next()?;
+    let kind = flags.next()?;
+    let sends_file = code.chars().count() == ITEMIZE_CODE_LEN
+        && matches!(update, '<' | '>' | 'c')
+        && kind == 'f';
+    sends_file.then(|| SyncedFile {
+        path: Utf8PathBuf::from(path),
+        bytes,
+    })
+}
+
+fn parse_transferred_size(line: &str) -> Option<u64> {
+    let value = line.trim().strip_prefix(TRANSFERRED_SIZE_LABEL)?;
+    parse_count(value.trim().trim_end_matches("bytes").trim())
+}
+
+/// Parses an rsync count, which may use `,` or `.` as thousands separators.
+fn parse_count(value: &str) -> Option<u64> {
+    let digits: String = value
+        .chars()
+        .filter(|ch| !matches!(ch, ',' | '.'))
+        .collect();
+    digits.parse().ok()
+}
+
+fn top_level_directory(path: &Utf8Path) -> Utf8PathBuf {
+    let mut components = path.components();
+    match (components.next(), components.next()) {
+        (Some(first), Some(_)) => Utf8PathBuf::from(first.as_str()),
+        _ => Utf8PathBuf::from("."),
+    }
+}
@@ -0,0 +1,92 @@
+//! Streamed `tar | zstd` transfer for empty destinations.
+//!
+//! A freshly provisioned instance has an empty `remote_path`, so rsync's delta
+//! algorithm saves nothing while its per-file round trips dominate for trees
+//! with many small files. When the destination is empty, the files a dry run
+//! reports are archived with `tar`, compressed with `zstd`, and unpacked by a
+//! single remote shell, so the selection matches rsync's exactly.
+
+use std::borrow::Cow;
+
+use camino::Utf8Path;
+use shell_escape::unix::escape;
+
+/// Shell used to run the pipeline and the local or remote helpers.
+pub(super) const SHELL: &str = "sh";
+
+/// `tar` binary used on both ends of the stream.
+const TAR_BIN: &str = "tar";
+
+/// `zstd` binary used on both ends of the stream.
+const ZSTD_BIN: &str = "zstd";
+
+/// Name of the staged NUL-separated list of paths to archive.
+pub(super) const LIST_FILE: &str = "stream-files";
+
+/// Name of the staged file the archiving stages report failures to.
+pub(super) const FAILURES_FILE: &str = "stream-failures";
+
+/// Quotes `value` for a POSIX shell.
+pub(super) fn quote(value: &str) -> String {
+    escape(Cow::Borrowed(value)).into_owned()
+}
+
+/// Turns dry-run output produced with
+/// [`ITEMIZE_FORMAT`](super::ITEMIZE_FORMAT) into the NUL-separated list of
+/// directories, regular files, and symlinks to archive, in rsync's order.
+pub(super) fn archive_list(dry_run: &str) -> String {
+    dry_run
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, ' ');
+            let code = parts.next()?;
+            let _bytes = parts.next()?;
+            let path = parts.next()?.trim_end_matches('/');
+            let kind = code.chars().nth(1)?;
+            (matches!(kind, 'f' | 'd' | 'L') && !path.is_empty() && path != ".")
+                .then(|| format!("{path}\0"))
+        })
+        .collect::<Vec<_>>()
+        .concat()
+}
+
+/// Shell command that succeeds only when `path` is missing or empty.
+pub(super) fn probe_command(path: &Utf8Path) -> String {
+    let path_q = quote(path.as_str());
+    format!("[ ! -d {path_q} ] || [ -z \"$(ls -A {path_q})\" ]")
+}
+
+/// Shell command that unpacks the stream from standard input into `path`.
+pub(super) fn extract_command(path: &Utf8Path) -> String {
+    let path_q = quote(path.as_str());
+    format!("mkdir -p {path_q} && {ZSTD_BIN} -dq | {TAR_BIN} -C {path_q} -xpf -")
+}
+
+/// Pipeline archiving the paths in `list`, relative to `tree`, into `sink`.
+///
+/// A shell pipeline only reports the sink's status, so `tar` and `zstd`
+/// append their failures to `failures`, and the pipeline fails, echoing
+/// them, when the file is not empty after the sink succeeds.
+pub(super) fn pipeline(
+    tree: &Utf8Path,
+    list: &Utf8Path,
+    failures: &Utf8Path,
+    sink: &str,
+) -> String {
+    let failures_q = quote(failures.as_str());
+    let stage = |command: String, name: &str| {
+        format!("{{ {command} || echo \"{name} exited with status $?\" >> {failures_q}; }}")
+    };
+    let tar = stage(
+        format!(
+            "{TAR_BIN} -C {} --no-recursion --null -T {} -cf -",
+            quote(tree.as_str()),
+            quote(list.as_str()),
+        ),
+        TAR_BIN,
+    );
+    let zstd = stage(format!("{ZSTD_BIN} -q -T0"), ZSTD_BIN);
+    format!(
+        "{tar} | {zstd} | {sink} && if [ -s {failures_q} ]; then cat {failures_q} >&2; exit 1; fi"
+    )
+}
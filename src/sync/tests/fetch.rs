@@ -0,0 +1,285 @@
+//! Tests for the pull-based `git-fetch` sync mode.
+
+use std::fs;
+
+use super::super::*;
+use crate::test_support::{CommandInvocation, ScriptedRunner};
+use rstest::rstest;
+use tempfile::TempDir;
+
+use super::fixtures::base_config;
+
+const COMMIT: &str = "0123456789abcdef0123456789abcdef01234567";
+
+fn temp_source() -> (TempDir, Utf8PathBuf) {
+    let dir = TempDir::new().expect("temp dir");
+    let source = Utf8PathBuf::from_path_buf(dir.path().to_path_buf()).expect("utf8 path");
+    (dir, source)
+}
+
+fn remote_destination() -> SyncDestination {
+    SyncDestination::Remote {
+        user: String::from("ubuntu"),
+        host: String::from("1.2.3.4"),
+        port: 2222,
+        path: Utf8PathBuf::from("/remote"),
+    }
+}
+
+fn fetch_config(mut config: SyncConfig) -> SyncConfig {
+    config.sync_mode = SyncMode::GitFetch;
+    config
+}
+
+fn commands(runner: &ScriptedRunner) -> Vec<String> {
+    runner
+        .invocations()
+        .iter()
+        .map(CommandInvocation::command_string)
+        .collect()
+}
+
+/// Scripts the queries made for a commit pushed to `origin`.
+fn push_pushed_queries(runner: &ScriptedRunner) {
+    runner.push_success(); // no LFS files
+    runner.push_output(Some(0), "git@github.com:org/repo.git\n", ""); // remote get-url
+    runner.push_output(Some(0), format!("{COMMIT}\n"), ""); // rev-parse HEAD
+    runner.push_output(Some(0), "  origin/HEAD -> origin/main\n  origin/main\n", "");
+    runner.push_output(Some(0), "diff --git a/src/lib.rs b/src/lib.rs\n", ""); // git diff
+}
+
+#[rstest]
+#[case("git@github.com:org/repo.git", "git-github.com-org-repo.git")]
+#[case("https://github.com/org/repo", "https-github.com-org-repo.git")]
+#[case("/srv/git/repo.git/", "srv-git-repo.git")]
+fn fetch_script_names_the_clone_after_the_url(
+    base_config: SyncConfig,
+    #[case] url: &str,
+    #[case] clone: &str,
+) {
+    let plan = FetchPlan {
+        commit: String::from(COMMIT),
+        url: String::from(url),
+        upload: Utf8PathBuf::from("/tmp/upload"),
+    };
+
+    let script = fetch_script(
+        &base_config,
+        &plan,
+        Utf8Path::new("/remote"),
+        Utf8Path::new("/tmp/upload"),
+    );
+
+    assert!(
+        script.contains(&format!("--git-dir=/mriya/git-cache/{clone} fetch")),
+        "unexpected script: {script}"
+    );
+}
+
+#[rstest]
+fn fetch_script_quotes_paths_and_applies_the_patch(base_config: SyncConfig) {
+    let plan = FetchPlan {
+        commit: String::from(COMMIT),
+        url: String::from("https://example.com/repo.git"),
+        upload: Utf8PathBuf::from("/tmp/upload"),
+    };
+
+    let script = fetch_script(
+        &base_config,
+        &plan,
+        Utf8Path::new("/home/ubuntu/my project"),
+        Utf8Path::new("/tmp/mriya sync"),
+    );
+
+    let lines: Vec<&str> = script.lines().collect();
+    assert_eq!(lines.first(), Some(&"set -e"));
+    assert!(lines.contains(&"mkdir -p /mriya/git-cache '/home/ubuntu/my project'"));
+    assert!(lines.contains(&format!("git reset --quiet --hard {COMMIT}").as_str()));
+    assert!(lines.contains(&"git clean -fdq"));
+    assert!(lines.contains(&"MRIYA_UPLOAD='/tmp/mriya sync'"));
+    assert!(lines.contains(&"trap 'rm -rf \"$MRIYA_UPLOAD\"' EXIT"));
+    assert_eq!(
+        lines.last(),
+        Some(
+            &"if [ -s \"$MRIYA_UPLOAD\"/changes.patch ]; then git apply --whitespace=nowarn \"$MRIYA_UPLOAD\"/changes.patch; fi"
+        )
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn git_fetch_uploads_the_patch_and_runs_the_script(base_config: SyncConfig) {
+    let runner = ScriptedRunner::new();
+    push_pushed_queries(&runner);
+    runner.push_success(); // patch upload
+    runner.push_success(); // fetch script
+    let syncer = Syncer::new(fetch_config(base_config), runner.clone()).expect("config");
+    let (_dir, source) = temp_source();
+
+    syncer
+        .sync(&source, &remote_destination())
+        .await
+        .expect("sync should succeed");
+
+    let invoked = commands(&runner);
+    assert_eq!(invoked.len(), 7, "unexpected commands: {invoked:?}");
+    let upload = invoked.get(5).expect("patch upload");
+    assert!(upload.starts_with("rsync -az --rsh "), "{upload}");
+    assert!(upload.contains(" ubuntu@1.2.3.4:.mriya-sync-"), "{upload}");
+    let upload_dir = upload
+        .rsplit_once(':')
+        .map(|(_, dir)| dir)
+        .expect("upload destination");
+    let script = invoked.get(6).expect("fetch script");
+    assert!(script.starts_with("ssh "), "{script}");
+    assert!(script.contains("ubuntu@1.2.3.4 set -e"), "{script}");
+    assert!(
+        script.contains(&format!("MRIYA_UPLOAD=\"$HOME\"/{upload_dir}")),
+        "{script}"
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn git_fetch_uploads_each_patch_to_a_fresh_directory(base_config: SyncConfig) {
+    let runner = ScriptedRunner::new();
+    for _ in 0..2 {
+        push_pushed_queries(&runner);
+        runner.push_success(); // patch upload
+        runner.push_success(); // fetch script
+    }
+    let syncer = Syncer::new(fetch_config(base_config), runner.clone()).expect("config");
+    let (_dir, source) = temp_source();
+
+    for _ in 0..2 {
+        syncer
+            .sync(&source, &remote_destination())
+            .await
+            .expect("sync should succeed");
+    }
+
+    let uploads: Vec<String> = commands(&runner)
+        .into_iter()
+        .filter(|cmd| cmd.starts_with("rsync -az --rsh "))
+        .collect();
+    assert_eq!(uploads.len(), 2);
+    assert_ne!(uploads.first(), uploads.last());
+}
+
+#[rstest]
+#[case::uses_lfs(&[(Some(0), "models/weights.bin\0")][..])]
+#[case::no_remote(&[(Some(0), ""), (Some(2), "")][..])]
+#[case::not_pushed(&[
+    (Some(0), ""),
+    (Some(0), "git@github.com:org/repo.git\n"),
+    (Some(0), "0123456789abcdef\n"),
+    (Some(0), "  upstream/main\n"),
+][..])]
+#[tokio::test]
+async fn git_fetch_falls_back_to_rsync(
+    base_config: SyncConfig,
+    #[case] queries: &[(Option<i32>, &str)],
+) {
+    let runner = ScriptedRunner::new();
+    for (code, stdout) in queries {
+        runner.push_output(*code, *stdout, "");
+    }
+    runner.push_success(); // rsync
+    let syncer = Syncer::new(fetch_config(base_config), runner.clone()).expect("config");
+    let (_dir, source) = temp_source();
+
+    syncer
+        .sync(&source, &remote_destination())
+        .await
+        .expect("sync should succeed");
+
+    let invoked = commands(&runner);
+    assert_eq!(invoked.len(), queries.len() + 1);
+    let last = invoked.last().expect("rsync");
+    assert!(last.starts_with("rsync -az --delete"), "{last}");
+}
+
+#[rstest]
+#[tokio::test]
+async fn git_fetch_falls_back_to_rsync_when_the_script_fails(base_config: SyncConfig) {
+    let runner = ScriptedRunner::new();
+    push_pushed_queries(&runner);
+    runner.push_success(); // patch upload
+    runner.push_output(Some(128), "", "fatal: could not read from remote");
+    runner.push_success(); // rsync
+    let syncer = Syncer::new(fetch_config(base_config), runner.clone()).expect("config");
+    let (_dir, source) = temp_source();
+
+    syncer
+        .sync(&source, &remote_destination())
+        .await
+        .expect("sync should succeed");
+
+    let invoked = commands(&runner);
+    assert_eq!(invoked.len(), 8);
+    let last = invoked.last().expect("rsync");
+    assert!(last.starts_with("rsync -az --delete"), "{last}");
+}
+
+#[rstest]
+#[tokio::test]
+async fn git_fetch_keeps_git_diff_exit_code_one_for_untracked_files(mut base_config: SyncConfig) {
+    base_config.git_include_untracked = true;
+    let runner = ScriptedRunner::new();
+    runner.push_success(); // no LFS files
+    runner.push_output(Some(0), "https://example.com/repo.git\n", "");
+    runner.push_output(Some(0), format!("{COMMIT}\n"), "");
+    runner.push_output(Some(0), "  origin/main\n", "");
+    runner.push_output(Some(0), "", ""); // git diff HEAD
+    runner.push_output(Some(0), "notes.txt\0", ""); // untracked listing
+    runner.push_output(Some(1), "diff --git a/notes.txt b/notes.txt\n", "");
+    runner.push_success(); // patch upload
+    runner.push_success(); // fetch script
+    let syncer = Syncer::new(fetch_config(base_config), runner.clone()).expect("config");
+    let (_dir, source) = temp_source();
+
+    syncer
+        .sync(&source, &remote_destination())
+        .await
+        .expect("sync should succeed");
+
+    let invoked = commands(&runner);
+    assert!(
+        invoked
+            .get(6)
+            .is_some_and(|cmd| cmd.ends_with("diff --binary --no-index -- /dev/null notes.txt")),
+        "unexpected commands: {invoked:?}"
+    );
+    assert_eq!(invoked.len(), 9);
+}
+
+#[rstest]
+#[tokio::test]
+async fn git_fetch_dry_run_reports_the_patch_instead_of_the_tree(base_config: SyncConfig) {
+    let runner = ScriptedRunner::new();
+    push_pushed_queries(&runner);
+    runner.push_output(Some(0), "src/lib.rs\0", ""); // git diff --name-only
+    let syncer = Syncer::new(fetch_config(base_config), runner.clone()).expect("config");
+    let (_dir, source) = temp_source();
+    fs::create_dir(source.join("src")).expect("create src");
+    fs::write(source.join("src/lib.rs"), "edited\n").expect("write lib.rs");
+    fs::write(source.join("large.bin"), vec![0_u8; 4096]).expect("write large.bin");
+
+    let stats = syncer
+        .dry_run(&source)
+        .await
+        .expect("dry run should succeed");
+
+    let paths: Vec<&str> = stats.files.iter().map(|file| file.path.as_str()).collect();
+    assert_eq!(paths, ["src/lib.rs"]);
+    let patch = "diff --git a/src/lib.rs b/src/lib.rs\n";
+    assert_eq!(
+        stats.total_bytes,
+        u64::try_from(patch.len()).expect("length")
+    );
+    let invoked = commands(&runner);
+    assert!(
+        invoked.iter().all(|cmd| !cmd.starts_with("rsync")),
+        "unexpected commands: {invoked:?}"
+    );
+}
@@ -0,0 +1,91 @@
+//! Tests for include and exclude rules layered ahead of `.gitignore`.
+
+use std::fs;
+
+use super::super::*;
+use crate::test_support::ScriptedRunner;
+use rstest::rstest;
+use tempfile::TempDir;
+
+use super::fixtures::base_config;
+
+fn rsync_args(config: SyncConfig, source: &Utf8Path) -> Vec<String> {
+    let syncer = Syncer::new(config, ScriptedRunner::new()).expect("config should validate");
+    let destination = SyncDestination::Local {
+        path: Utf8PathBuf::from("/tmp/dst"),
+    };
+    syncer
+        .build_rsync_args(source, &destination, None)
+        .expect("args should build")
+        .iter()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect()
+}
+
+fn temp_source() -> (TempDir, Utf8PathBuf) {
+    let dir = TempDir::new().expect("temp dir");
+    let source = Utf8PathBuf::from_path_buf(dir.path().to_path_buf()).expect("utf8 path");
+    (dir, source)
+}
+
+#[rstest]
+fn build_rsync_args_layers_rules_between_git_and_gitignore(mut base_config: SyncConfig) {
+    base_config.include = vec![String::from(".env.test"), String::from("  ")];
+    base_config.exclude = vec![String::from("fixtures/large/")];
+    let (_dir, source) = temp_source();
+    fs::write(source.join(MRIYA_IGNORE_FILE), "docs/\n").expect("write .mriyaignore");
+
+    let args = rsync_args(base_config, &source);
+    let filters: Vec<&str> = args.iter().skip(2).take(6).map(String::as_str).collect();
+    assert_eq!(
+        filters,
+        [
+            "--exclude",
+            ".git",
+            "--exclude=fixtures/large/",
+            "--include=.env.test",
+            "--exclude=docs/",
+            "--filter=:- .gitignore",
+        ]
+    );
+}
+
+#[rstest]
+fn build_rsync_args_without_rules_keeps_default_filters(base_config: SyncConfig) {
+    let (_dir, source) = temp_source();
+    let args = rsync_args(base_config, &source);
+    assert!(
+        !args
+            .iter()
+            .any(|arg| arg.starts_with("--include=") || arg.starts_with("--exclude=")),
+        "unexpected rules: {args:?}"
+    );
+}
+
+#[rstest]
+fn build_rsync_args_reports_unreadable_mriyaignore(base_config: SyncConfig) {
+    let (_dir, source) = temp_source();
+    fs::create_dir(source.join(MRIYA_IGNORE_FILE)).expect("create directory");
+    let syncer = Syncer::new(base_config, ScriptedRunner::new()).expect("config should validate");
+    let destination = SyncDestination::Local {
+        path: Utf8PathBuf::from("/tmp/dst"),
+    };
+    let err = syncer
+        .build_rsync_args(&source, &destination, None)
+        .expect_err("directory should not parse as an ignore file");
+    assert!(matches!(err, SyncError::IgnoreFile { .. }), "got {err:?}");
+}
+
+#[rstest]
+fn parse_mriyaignore_reverses_lines_and_translates_negation() {
+    let rules = parse_mriyaignore("# comment\n\ndocs/\n!docs/README.md\n\\#literal\n\\!bang\n");
+    assert_eq!(
+        rules,
+        [
+            FilterRule::Exclude(String::from("!bang")),
+            FilterRule::Exclude(String::from("#literal")),
+            FilterRule::Include(String::from("docs/README.md")),
+            FilterRule::Exclude(String::from("docs/")),
+        ]
+    );
+}
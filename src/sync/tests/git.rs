@@ -0,0 +1,292 @@
+//! Tests for the `git-files` and `git-commit` sync modes.
+
+use std::fs;
+
+use super::super::*;
+use crate::test_support::{CommandInvocation, ScriptedRunner};
+use rstest::rstest;
+use tempfile::TempDir;
+
+use super::fixtures::base_config;
+
+fn temp_source() -> (TempDir, Utf8PathBuf) {
+    let dir = TempDir::new().expect("temp dir");
+    let source = Utf8PathBuf::from_path_buf(dir.path().to_path_buf()).expect("utf8 path");
+    (dir, source)
+}
+
+fn local_destination() -> SyncDestination {
+    SyncDestination::Local {
+        path: Utf8PathBuf::from("/tmp/dst"),
+    }
+}
+
+fn args_of(runner: &ScriptedRunner, index: usize) -> Vec<String> {
+    runner
+        .invocations()
+        .get(index)
+        .expect("invocation should be recorded")
+        .args
+        .iter()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect()
+}
+
+#[rstest]
+#[case(false, "git -C {src} ls-files -z --cached")]
+#[case(true, "git -C {src} ls-files -z --cached --others --exclude-standard")]
+#[tokio::test]
+async fn git_files_mode_lists_files_with_git(
+    mut base_config: SyncConfig,
+    #[case] include_untracked: bool,
+    #[case] expected: &str,
+) {
+    base_config.sync_mode = SyncMode::GitFiles;
+    base_config.git_include_untracked = include_untracked;
+    let runner = ScriptedRunner::new();
+    runner.push_output(Some(0), "src/lib.rs\0README.md\0", "");
+    runner.push_success();
+    let syncer = Syncer::new(base_config, runner.clone()).expect("config should validate");
+    let (_dir, source) = temp_source();
+
+    syncer
+        .sync(&source, &local_destination())
+        .await
+        .expect("sync should succeed");
+
+    let commands: Vec<String> = runner
+        .invocations()
+        .iter()
+        .map(|invocation| invocation.program.clone())
+        .collect();
+    assert_eq!(commands, ["git", "rsync"]);
+    let listing = runner
+        .invocations()
+        .first()
+        .map(CommandInvocation::command_string);
+    assert_eq!(listing, Some(expected.replace("{src}", source.as_str())));
+}
+
+#[rstest]
+#[tokio::test]
+async fn git_files_mode_hides_unlisted_files_after_gitignore(mut base_config: SyncConfig) {
+    base_config.sync_mode = SyncMode::GitFiles;
+    let runner = ScriptedRunner::new();
+    runner.push_output(Some(0), "src/lib.rs\0", "");
+    runner.push_success();
+    let syncer = Syncer::new(base_config, runner.clone()).expect("config should validate");
+    let (_dir, source) = temp_source();
+
+    syncer
+        .sync(&source, &local_destination())
+        .await
+        .expect("sync should succeed");
+
+    let args = args_of(&runner, 1);
+    let merge = args
+        .iter()
+        .position(|arg| arg.starts_with("--filter=merge "))
+        .expect("merge filter");
+    let gitignore = args
+        .iter()
+        .position(|arg| arg == "--filter=:- .gitignore")
+        .expect("gitignore filter");
+    let hide = args
+        .iter()
+        .position(|arg| arg == "--filter=H *")
+        .expect("hide filter");
+    assert!(merge < gitignore && gitignore < hide, "args: {args:?}");
+    assert_eq!(args.last().map(String::as_str), Some("/tmp/dst"));
+}
+
+#[rstest]
+#[tokio::test]
+async fn git_commit_mode_syncs_a_snapshot_of_head(mut base_config: SyncConfig) {
+    base_config.sync_mode = SyncMode::GitCommit;
+    let runner = ScriptedRunner::new();
+    runner.push_success();
+    runner.push_success();
+    runner.push_success();
+    let syncer = Syncer::new(base_config, runner.clone()).expect("config should validate");
+    let (_dir, source) = temp_source();
+
+    syncer
+        .sync(&source, &local_destination())
+        .await
+        .expect("sync should succeed");
+
+    let invocations = runner.invocations();
+    let programs: Vec<&str> = invocations
+        .iter()
+        .map(|invocation| invocation.program.as_str())
+        .collect();
+    assert_eq!(programs, ["git", "tar", "rsync"]);
+    let archive = args_of(&runner, 0);
+    assert!(
+        archive.starts_with(
+            &["-C", source.as_str(), "archive", "--format=tar", "-o"].map(String::from)
+        ),
+        "archive args: {archive:?}"
+    );
+    assert_eq!(archive.last().map(String::as_str), Some("HEAD"));
+
+    let rsync = args_of(&runner, 2);
+    let snapshot = rsync.iter().rev().nth(1).expect("rsync source argument");
+    assert!(
+        snapshot.ends_with("/tree/") && !snapshot.starts_with(source.as_str()),
+        "rsync should send the snapshot, got {snapshot}"
+    );
+    assert!(!rsync.iter().any(|arg| arg == "--filter=H *"));
+}
+
+#[rstest]
+#[tokio::test]
+async fn git_failure_is_reported_before_rsync_runs(mut base_config: SyncConfig) {
+    base_config.sync_mode = SyncMode::GitFiles;
+    let runner = ScriptedRunner::new();
+    runner.push_output(Some(128), "", "fatal: not a git repository");
+    let syncer = Syncer::new(base_config, runner.clone()).expect("config should validate");
+    let (_dir, source) = temp_source();
+
+    let err = syncer
+        .sync(&source, &local_destination())
+        .await
+        .expect_err("git failure should abort the sync");
+
+    assert!(
+        matches!(
+            &err,
+            SyncError::CommandFailure { program, status: Some(128), .. } if program == "git"
+        ),
+        "got {err:?}"
+    );
+    assert_eq!(runner.invocations().len(), 1);
+}
+
+#[rstest]
+fn listing_rules_include_parents_and_escape_wildcards() {
+    let rules = listing_rules(&["src/bin/main.rs", "README.md", "odd[1].txt", "", "a\nb"]);
+    assert_eq!(
+        rules,
+        "+ /src/\n+ /src/bin/\n+ /src/bin/main.rs\n+ /README.md\n+ /odd\\[1].txt\n"
+    );
+}
+
+#[rstest]
+#[case("rsync-filter", SyncMode::RsyncFilter)]
+#[case("git-files", SyncMode::GitFiles)]
+#[case(" git-commit ", SyncMode::GitCommit)]
+fn sync_mode_parses_names(#[case] name: &str, #[case] expected: SyncMode) {
+    assert_eq!(name.parse::<SyncMode>(), Ok(expected));
+}
+
+#[rstest]
+fn sync_mode_rejects_unknown_names() {
+    let err = "tarball".parse::<SyncMode>().expect_err("unknown mode");
+    assert!(err.to_string().contains("git-commit"), "got {err}");
+}
+
+#[rstest]
+#[case(false, &["git -C {src} ls-files -z --cached --recurse-submodules"][..])]
+#[case(
+    true,
+    &[
+        "git -C {src} ls-files -z --others --exclude-standard",
+        "git -C {src} ls-files -z --cached --recurse-submodules",
+    ][..]
+)]
+#[tokio::test]
+async fn git_files_mode_lists_submodule_files(
+    mut base_config: SyncConfig,
+    #[case] include_untracked: bool,
+    #[case] expected: &[&str],
+) {
+    base_config.sync_mode = SyncMode::GitFiles;
+    base_config.git_include_untracked = include_untracked;
+    let runner = ScriptedRunner::new();
+    for _ in expected {
+        runner.push_output(Some(0), "vendor/lib/lib.rs\0", "");
+    }
+    runner.push_success();
+    let syncer = Syncer::new(base_config, runner.clone()).expect("config should validate");
+    let (_dir, source) = temp_source();
+    fs::write(source.join(".gitmodules"), "").expect("write .gitmodules");
+
+    syncer
+        .sync(&source, &local_destination())
+        .await
+        .expect("sync should succeed");
+
+    let commands: Vec<String> = runner
+        .invocations()
+        .iter()
+        .take(expected.len())
+        .map(CommandInvocation::command_string)
+        .collect();
+    let expected_commands: Vec<String> = expected
+        .iter()
+        .map(|command| command.replace("{src}", source.as_str()))
+        .collect();
+    assert_eq!(commands, expected_commands);
+}
+
+#[rstest]
+#[tokio::test]
+async fn git_commit_mode_exports_initialised_submodules(mut base_config: SyncConfig) {
+    base_config.sync_mode = SyncMode::GitCommit;
+    let runner = ScriptedRunner::new();
+    runner.push_success(); // git archive
+    runner.push_success(); // tar
+    runner.push_output(
+        Some(0),
+        "100644 blob aaaa\t.gitmodules\x00160000 commit 1234abcd\tvendor/lib\x00160000 commit 5678\tvendor/missing\0",
+        "",
+    );
+    runner.push_success(); // git archive of vendor/lib
+    runner.push_success(); // tar
+    runner.push_success(); // rsync
+    let syncer = Syncer::new(base_config, runner.clone()).expect("config should validate");
+    let (_dir, source) = temp_source();
+    fs::write(source.join(".gitmodules"), "").expect("write .gitmodules");
+    fs::create_dir_all(source.join("vendor/lib")).expect("create submodule");
+    fs::write(
+        source.join("vendor/lib/.git"),
+        "gitdir: ../../.git/modules/lib\n",
+    )
+    .expect("write gitfile");
+
+    syncer
+        .sync(&source, &local_destination())
+        .await
+        .expect("sync should succeed");
+
+    let programs: Vec<String> = runner
+        .invocations()
+        .iter()
+        .map(|invocation| invocation.program.clone())
+        .collect();
+    assert_eq!(programs, ["git", "tar", "git", "git", "tar", "rsync"]);
+    let submodule_archive = args_of(&runner, 3);
+    assert_eq!(
+        submodule_archive.get(1).map(String::as_str),
+        Some(source.join("vendor/lib").as_str())
+    );
+    assert_eq!(
+        submodule_archive.last().map(String::as_str),
+        Some("1234abcd")
+    );
+    let unpack = args_of(&runner, 4);
+    assert!(
+        unpack
+            .last()
+            .is_some_and(|dir| dir.ends_with("/tree/vendor/lib")),
+        "tar args: {unpack:?}"
+    );
+}
+
+#[rstest]
+fn gitlinks_parse_submodule_entries() {
+    let listing =
+        "100644 blob aaaa\tREADME.md\x00160000 commit beef\tlibs/a b\x00040000 tree cccc\tdocs\0";
+    assert_eq!(git::gitlinks(listing), [("libs/a b", "beef")]);
+}
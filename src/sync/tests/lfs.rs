@@ -0,0 +1,184 @@
+//! Tests for fetching Git LFS content before a working-tree sync.
+
+use super::super::*;
+use crate::test_support::{CommandInvocation, ScriptedRunner};
+use rstest::rstest;
+use tempfile::TempDir;
+
+use super::fixtures::base_config;
+
+const LFS_LISTING: &str = "\
+4d7a214614 * assets/logo.png
+9e8f1a2b3c - models/weights.bin
+";
+
+fn temp_source() -> (TempDir, Utf8PathBuf) {
+    let dir = TempDir::new().expect("temp dir");
+    let source = Utf8PathBuf::from_path_buf(dir.path().to_path_buf()).expect("utf8 path");
+    (dir, source)
+}
+
+/// Config with `git_lfs_pull` enabled.
+fn pull_config(mut config: SyncConfig) -> SyncConfig {
+    config.git_lfs_pull = true;
+    config
+}
+
+/// Scripts git naming an LFS-tracked file.
+fn push_lfs_tracked(runner: &ScriptedRunner) {
+    runner.push_output(Some(0), "models/weights.bin\0", "");
+}
+
+fn local_destination() -> SyncDestination {
+    SyncDestination::Local {
+        path: Utf8PathBuf::from("/tmp/dst"),
+    }
+}
+
+fn commands(runner: &ScriptedRunner) -> Vec<String> {
+    runner
+        .invocations()
+        .iter()
+        .map(CommandInvocation::command_string)
+        .collect()
+}
+
+fn programs(runner: &ScriptedRunner) -> Vec<String> {
+    runner
+        .invocations()
+        .iter()
+        .map(|invocation| invocation.program.clone())
+        .collect()
+}
+
+#[rstest]
+fn pointer_files_lists_content_that_is_not_checked_out() {
+    assert_eq!(lfs::pointer_files(LFS_LISTING), ["models/weights.bin"]);
+    assert!(lfs::pointer_files("").is_empty());
+}
+
+#[rstest]
+#[case(SyncMode::RsyncFilter)]
+#[case(SyncMode::GitFiles)]
+#[tokio::test]
+async fn sync_pulls_lfs_content_before_rsync(base_config: SyncConfig, #[case] mode: SyncMode) {
+    let mut config = pull_config(base_config);
+    config.sync_mode = mode;
+    let runner = ScriptedRunner::new();
+    push_lfs_tracked(&runner);
+    runner.push_output(Some(0), LFS_LISTING, "");
+    runner.push_success(); // git lfs pull
+    runner.push_output(Some(0), "models/weights.bin\0", ""); // git-files listing
+    runner.push_success(); // rsync
+    let syncer = Syncer::new(config, runner.clone()).expect("config should validate");
+    let (_dir, source) = temp_source();
+
+    syncer
+        .sync(&source, &local_destination())
+        .await
+        .expect("sync should succeed");
+
+    let invoked = commands(&runner);
+    assert_eq!(
+        invoked.get(..3),
+        Some(
+            &[
+                format!("git -C {source} ls-files -z -- :(attr:filter=lfs)"),
+                format!("git -C {source} lfs ls-files"),
+                format!("git -C {source} lfs pull"),
+            ][..]
+        )
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn sync_skips_pull_when_content_is_checked_out(base_config: SyncConfig) {
+    let runner = ScriptedRunner::new();
+    push_lfs_tracked(&runner);
+    runner.push_output(Some(0), "4d7a214614 * assets/logo.png\n", "");
+    runner.push_success();
+    let syncer = Syncer::new(pull_config(base_config), runner.clone()).expect("config");
+    let (_dir, source) = temp_source();
+
+    syncer
+        .sync(&source, &local_destination())
+        .await
+        .expect("sync should succeed");
+
+    assert_eq!(programs(&runner), ["git", "git", "rsync"]);
+}
+
+#[rstest]
+#[tokio::test]
+async fn lfs_is_left_alone_when_disabled_or_unused(base_config: SyncConfig) {
+    let runner = ScriptedRunner::new();
+    runner.push_success(); // rsync
+    runner.push_success(); // no LFS files
+    runner.push_success(); // rsync
+    let disabled = Syncer::new(base_config.clone(), runner.clone()).expect("config");
+    let (_dir, source) = temp_source();
+    disabled
+        .sync(&source, &local_destination())
+        .await
+        .expect("sync should succeed");
+
+    let unused = Syncer::new(pull_config(base_config), runner.clone()).expect("config");
+    unused
+        .sync(&source, &local_destination())
+        .await
+        .expect("sync should succeed");
+
+    assert_eq!(programs(&runner), ["rsync", "git", "rsync"]);
+}
+
+#[rstest]
+#[tokio::test]
+async fn dry_runs_pull_before_counting(base_config: SyncConfig) {
+    let runner = ScriptedRunner::new();
+    push_lfs_tracked(&runner);
+    runner.push_output(Some(0), LFS_LISTING, "");
+    runner.push_success(); // git lfs pull
+    runner.push_success(); // rsync --dry-run
+    let syncer = Syncer::new(pull_config(base_config), runner.clone()).expect("config");
+    let (_dir, source) = temp_source();
+
+    syncer
+        .dry_run(&source)
+        .await
+        .expect("dry run should succeed");
+
+    let invoked = commands(&runner);
+    assert_eq!(
+        invoked.get(2),
+        Some(&format!("git -C {source} lfs pull")),
+        "{invoked:?}"
+    );
+    assert!(
+        invoked
+            .last()
+            .is_some_and(|cmd| cmd.starts_with("rsync --dry-run")),
+        "{invoked:?}"
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn missing_git_lfs_fails_the_sync(base_config: SyncConfig) {
+    let runner = ScriptedRunner::new();
+    push_lfs_tracked(&runner);
+    runner.push_output(Some(1), "", "git: 'lfs' is not a git command");
+    let syncer = Syncer::new(pull_config(base_config), runner.clone()).expect("config");
+    let (_dir, source) = temp_source();
+
+    let err = syncer
+        .sync(&source, &local_destination())
+        .await
+        .expect_err("sync should fail");
+
+    assert!(
+        matches!(&err, SyncError::CommandFailure { program, .. } if program == "git"),
+        "{err:?}"
+    );
+    assert_eq!(runner.invocations().len(), 2);
+}
@@ -4,9 +4,22 @@
 //! below the 400-line guideline while remaining easy to navigate.
 
 mod config;
+mod fetch;
+mod filters;
 mod fixtures;
+mod git;
+mod lfs;
+mod prefixed;
+mod redact;
 mod remote;
+mod remote_env;
 mod rsync;
+mod runner;
+mod secrets;
+mod sink;
 mod ssh;
+mod stats;
+mod stream;
 mod streaming;
+mod upload;
 mod util;
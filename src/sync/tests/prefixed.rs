@@ -0,0 +1,35 @@
+//! Tests for `PrefixedCommandRunner` output capture.
+
+use super::super::{AsyncCommandRunner, PrefixedCommandRunner, SyncError};
+use rstest::rstest;
+use std::ffi::OsString;
+
+#[rstest]
+#[tokio::test]
+async fn prefixed_runner_captures_unprefixed_output() {
+    let runner = PrefixedCommandRunner::new("[shard 1/2] ");
+    let output = runner
+        .run(
+            "sh",
+            &[
+                OsString::from("-c"),
+                OsString::from("printf 'one\\ntwo' && printf 'err\\n' 1>&2; exit 3"),
+            ],
+        )
+        .await
+        .expect("command should execute successfully");
+
+    assert_eq!(output.code, Some(3));
+    assert_eq!(output.stdout, "one\ntwo");
+    assert_eq!(output.stderr, "err\n");
+    assert_eq!(runner.prefix(), "[shard 1/2] ");
+}
+
+#[rstest]
+#[tokio::test]
+async fn prefixed_runner_failed_spawn_returns_spawn_error() {
+    let runner = PrefixedCommandRunner::new("[x] ");
+    let result = runner.run("definitely-not-a-real-binary-xyz", &[]).await;
+
+    assert!(matches!(result, Err(SyncError::Spawn { .. })));
+}
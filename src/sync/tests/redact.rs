@@ -0,0 +1,176 @@
+//! Tests for masking secrets in output and error messages.
+
+use std::ffi::OsString;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use rstest::rstest;
+
+use super::super::redact::StreamRedaction;
+use super::super::{
+    AsyncCommandRunner, Capture, OutputSink, Redactor, RemoteEnv, StreamingCommandRunner, SyncError,
+};
+
+const TOKEN: &str = "tok-5f3a9c";
+
+/// Feeds `text` through a stream redaction split at `at` and returns the
+/// combined output.
+fn redact_split(redactor: &Redactor, text: &str, at: usize) -> String {
+    let (head, tail) = text.as_bytes().split_at(at);
+    let mut redaction = StreamRedaction::new(redactor);
+    let mut out = redaction.push(head);
+    out.extend(redaction.push(tail));
+    out.extend(redaction.finish());
+    String::from_utf8(out).expect("utf-8 output")
+}
+
+#[rstest]
+fn redact_masks_every_occurrence() {
+    let redactor = Redactor::new([TOKEN]);
+
+    assert_eq!(
+        redactor.redact(&format!("a {TOKEN} b {TOKEN}")),
+        "a *** b ***"
+    );
+}
+
+#[rstest]
+fn redact_prefers_the_longest_overlapping_secret() {
+    let redactor = Redactor::new(["abcd", "abcdefgh"]);
+
+    assert_eq!(redactor.redact("xabcdefghx abcdx"), "x***x ***x");
+}
+
+#[rstest]
+fn short_values_are_not_treated_as_secrets() {
+    let redactor = Redactor::new(["1", "abc"]);
+
+    assert!(redactor.is_empty());
+    assert_eq!(redactor.redact("abc 1"), "abc 1");
+}
+
+#[rstest]
+fn stream_masks_secrets_split_at_any_chunk_boundary() {
+    let redactor = Redactor::new([TOKEN]);
+    let text = format!("before {TOKEN} after");
+
+    for at in 0..=text.len() {
+        assert_eq!(
+            redact_split(&redactor, &text, at),
+            "before *** after",
+            "split at {at}"
+        );
+    }
+}
+
+#[rstest]
+fn stream_waits_for_a_longer_secret_before_masking_a_shorter_one() {
+    let redactor = Redactor::new(["abcd", "abcdefgh"]);
+
+    assert_eq!(redactor_split_all(&redactor, "abcdefgh!"), "***!");
+    assert_eq!(redactor_split_all(&redactor, "abcdefg!"), "***efg!");
+}
+
+/// Splits `text` at every position and checks each split agrees.
+fn redactor_split_all(redactor: &Redactor, text: &str) -> String {
+    let whole = redactor.redact(text);
+    for at in 0..=text.len() {
+        assert_eq!(redact_split(redactor, text, at), whole, "split at {at}");
+    }
+    whole
+}
+
+#[rstest]
+fn stream_releases_a_held_prefix_at_the_end() {
+    let redactor = Redactor::new([TOKEN]);
+    let mut redaction = StreamRedaction::new(&redactor);
+
+    assert!(redaction.push(b"tok-5f").is_empty());
+    assert_eq!(redaction.finish(), b"tok-5f");
+}
+
+#[rstest]
+fn sync_errors_mask_captured_stderr() {
+    let redactor = Redactor::new([TOKEN]);
+    let err = SyncError::CommandFailure {
+        program: String::from("ssh"),
+        status: Some(1),
+        status_text: String::from("1"),
+        stderr: format!("auth failed for {TOKEN}"),
+    }
+    .redact(&redactor);
+
+    assert_eq!(
+        err.to_string(),
+        "ssh exited with status 1: auth failed for ***"
+    );
+}
+
+#[rstest]
+fn debug_output_never_shows_secrets() {
+    let debug = format!("{:?}", Redactor::new([TOKEN]));
+
+    assert!(!debug.contains(TOKEN), "{debug}");
+}
+
+#[rstest]
+fn secret_values_follow_name_markers_and_configured_names() {
+    let mut env = RemoteEnv::default();
+    env.set("API_TOKEN", "t0ken-value");
+    env.set("RUST_LOG", "debug");
+    env.set("INTERNAL_ENDPOINT", "https://internal");
+
+    assert_eq!(env.secret_values(&[]), ["t0ken-value"]);
+    assert_eq!(
+        env.secret_values(&[String::from("INTERNAL_ENDPOINT")]),
+        ["t0ken-value", "https://internal"]
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn streaming_runner_masks_secrets_written_across_reads() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let recorder = Arc::clone(&seen);
+    let runner = StreamingCommandRunner::default()
+        .with_sink(OutputSink::Callback(Arc::new(move |_, chunk: &[u8]| {
+            recorder
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .extend_from_slice(chunk);
+        })))
+        .with_capture(Capture::Full)
+        .with_redactor(Redactor::new([TOKEN]));
+    let script = "printf 'token=tok-5f'; sleep 0.2; printf '3a9c ok'; printf 'tok-5f3a9c' 1>&2";
+
+    let output = runner
+        .run("sh", &[OsString::from("-c"), OsString::from(script)])
+        .await
+        .expect("command should run");
+
+    assert_eq!(output.stdout, "token=*** ok");
+    assert_eq!(output.stderr, "***");
+    let forwarded = seen.lock().unwrap_or_else(PoisonError::into_inner).clone();
+    assert!(
+        !String::from_utf8_lossy(&forwarded).contains("3a9c"),
+        "forwarded output leaked the secret"
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn quiet_runs_mask_captured_output() {
+    let runner = StreamingCommandRunner::default().with_redactor(Redactor::new([TOKEN]));
+
+    let output = runner
+        .run_quiet(
+            "sh",
+            &[
+                OsString::from("-c"),
+                OsString::from(format!("printf {TOKEN}")),
+            ],
+        )
+        .await
+        .expect("command should run");
+
+    assert_eq!(output.stdout, "***");
+}
@@ -20,7 +20,7 @@ const CACHE_ROUTING_VARS: &[&str] = &[
     "PNPM_STORE_PATH=",
 ];
 
-fn run_remote_with_fake_output(
+async fn run_remote_with_fake_output(
     cfg: SyncConfig,
     networking: &InstanceNetworking,
     script: impl Fn(&ScriptedRunner),
@@ -28,14 +28,15 @@ fn run_remote_with_fake_output(
     let runner = ScriptedRunner::new();
     script(&runner);
     let syncer = Syncer::new(cfg, runner.clone()).expect("config should validate");
-    let output = syncer.run_remote(networking, "echo ok")?;
+    let output = syncer.run_remote(networking, "echo ok").await?;
     Ok((runner, output))
 }
 
 #[rstest]
 #[case(None, "")]
 #[case(Some(7), "")]
-fn run_remote_propagates_exit_codes(
+#[tokio::test]
+async fn run_remote_propagates_exit_codes(
     base_config: SyncConfig,
     networking: InstanceNetworking,
     #[case] exit_code: Option<i32>,
@@ -46,6 +47,7 @@ fn run_remote_propagates_exit_codes(
             None => runner.push_missing_exit_code(),
             Some(code) => runner.push_exit_code(code),
         })
+        .await
         .expect("run_remote should succeed regardless of exit code presence");
 
     assert_eq!(output.exit_code, exit_code);
@@ -70,7 +72,8 @@ fn run_remote_propagates_exit_codes(
 }
 
 #[rstest]
-fn run_remote_cd_prefixes_remote_path(base_config: SyncConfig) {
+#[tokio::test]
+async fn run_remote_cd_prefixes_remote_path(base_config: SyncConfig) {
     let cfg = SyncConfig {
         route_build_caches: false,
         ..base_config
@@ -80,6 +83,7 @@ fn run_remote_cd_prefixes_remote_path(base_config: SyncConfig) {
     let syncer = Syncer::new(cfg, runner.clone()).expect("config should validate");
     let _ = syncer
         .run_remote(&networking(), "cargo test")
+        .await
         .expect("run_remote should succeed");
 
     let invocations = runner.invocations();
@@ -129,7 +133,8 @@ fn build_remote_command_routes_cargo_caches_when_volume_is_mounted(base_config:
 }
 
 #[rstest]
-fn run_remote_invokes_ssh_with_wrapped_command(
+#[tokio::test]
+async fn run_remote_invokes_ssh_with_wrapped_command(
     base_config: SyncConfig,
     networking: InstanceNetworking,
 ) {
@@ -141,6 +146,7 @@ fn run_remote_invokes_ssh_with_wrapped_command(
     let expected_wrapped = syncer.build_remote_command(remote_command);
     let _ = syncer
         .run_remote(&networking, remote_command)
+        .await
         .expect("run_remote should succeed");
 
     let invocations = runner.invocations();
@@ -220,13 +226,15 @@ fn build_ssh_args_uses_wrapped_command_verbatim(
 }
 
 #[rstest]
-fn run_remote_raw_avoids_wrapping(base_config: SyncConfig, networking: InstanceNetworking) {
+#[tokio::test]
+async fn run_remote_raw_avoids_wrapping(base_config: SyncConfig, networking: InstanceNetworking) {
     let runner = ScriptedRunner::new();
     runner.push_success();
     let syncer = Syncer::new(base_config, runner.clone()).expect("config should validate");
     let command = "sudo mkfs.ext4 -F /dev/vdb";
     let _ = syncer
         .run_remote_raw(&networking, command)
+        .await
         .expect("run_remote_raw should succeed");
 
     let invocations = runner.invocations();
@@ -291,3 +299,215 @@ fn create_cache_directories_command_escapes_special_characters() {
         "expected properly escaped full path, got: {cmd}"
     );
 }
+
+#[rstest]
+#[case(false, false)]
+#[case(true, true)]
+#[tokio::test]
+async fn run_remote_allocates_a_pty_only_when_requested(
+    base_config: SyncConfig,
+    networking: InstanceNetworking,
+    #[case] tty: bool,
+    #[case] expect_flag: bool,
+) {
+    let runner = ScriptedRunner::new();
+    runner.push_exit_code(3);
+    runner.push_success();
+    let syncer = Syncer::new(base_config, runner.clone())
+        .expect("config should validate")
+        .with_tty(tty);
+
+    let output = syncer
+        .run_remote(&networking, "gdb ./app")
+        .await
+        .expect("run_remote should succeed");
+    syncer
+        .run_remote_raw(&networking, "true")
+        .await
+        .expect("run_remote_raw should succeed");
+
+    assert_eq!(output.exit_code, Some(3));
+    let invocations = runner.invocations();
+    let [user, raw] = invocations.as_slice() else {
+        panic!("expected two ssh invocations, got: {invocations:?}");
+    };
+    let forces_pty = |args: &[OsString]| args.iter().any(|arg| arg == "-tt");
+    assert_eq!(forces_pty(&user.args), expect_flag);
+    assert!(
+        !forces_pty(&raw.args),
+        "raw commands never take the terminal"
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn run_remote_uploads_env_over_stdin_and_sources_it_after_cache_routing(
+    base_config: SyncConfig,
+    networking: InstanceNetworking,
+) {
+    let runner = ScriptedRunner::new();
+    runner.push_success();
+    runner.push_success();
+    let mut env = RemoteEnv::default();
+    env.set("DATABASE_URL", "postgres://user:hunter2@db/app");
+    let syncer = Syncer::new(base_config, runner.clone())
+        .expect("config should validate")
+        .with_env(env);
+
+    syncer
+        .run_remote(&networking, "cargo test")
+        .await
+        .expect("run_remote should succeed");
+
+    let invocations = runner.invocations();
+    let [upload, command] = invocations.as_slice() else {
+        panic!("expected upload and command invocations, got: {invocations:?}");
+    };
+    assert_eq!(
+        String::from_utf8_lossy(&upload.input),
+        "export DATABASE_URL='postgres://user:hunter2@db/app'\n"
+    );
+    let upload_line = upload.command_string();
+    assert!(upload_line.contains("umask 077"), "{upload_line}");
+    let file = upload_line
+        .rsplit_once("\"$HOME\"/")
+        .map(|(_, file)| file.to_owned())
+        .expect("upload should name the env file");
+    assert!(file.starts_with(".mriya-env-"), "{file}");
+
+    let command_line = command.command_string();
+    assert!(!upload_line.contains("hunter2") && !command_line.contains("hunter2"));
+    let routed = command_line
+        .find("export CARGO_HOME=")
+        .expect("cache routing should be present");
+    let sourced = command_line
+        .find(&format!(". \"$HOME\"/{file}; rm -f \"$HOME\"/{file}; "))
+        .expect("command should source and delete the env file");
+    let started = command_line
+        .find("cd /remote/path && cargo test")
+        .expect("command should run in the remote path");
+    assert!(routed < sourced && sourced < started, "{command_line}");
+}
+
+#[rstest]
+#[tokio::test]
+async fn run_remote_stops_when_the_env_upload_fails(
+    base_config: SyncConfig,
+    networking: InstanceNetworking,
+) {
+    let runner = ScriptedRunner::new();
+    runner.push_failure(1);
+    let mut env = RemoteEnv::default();
+    env.set("RUST_LOG", "debug");
+    let syncer = Syncer::new(base_config, runner.clone())
+        .expect("config should validate")
+        .with_env(env);
+
+    let err = syncer
+        .run_remote(&networking, "cargo test")
+        .await
+        .expect_err("a failed upload should stop the run");
+
+    assert!(matches!(err, SyncError::CommandFailure { .. }), "{err:?}");
+    assert_eq!(runner.invocations().len(), 1);
+}
+
+#[rstest]
+#[tokio::test]
+async fn run_remote_uploads_the_script_before_running_it(
+    base_config: SyncConfig,
+    networking: InstanceNetworking,
+) {
+    let runner = ScriptedRunner::new();
+    runner.push_success();
+    runner.push_success();
+    let syncer = Syncer::new(base_config, runner.clone())
+        .expect("config should validate")
+        .with_script(b"#!/bin/bash\nmake ci\n".to_vec());
+
+    syncer
+        .run_remote(&networking, &script_command("--fast"))
+        .await
+        .expect("run_remote should succeed");
+
+    let invocations = runner.invocations();
+    let [upload, command] = invocations.as_slice() else {
+        panic!("expected upload and command invocations, got: {invocations:?}");
+    };
+    assert_eq!(upload.input, b"#!/bin/bash\nmake ci\n");
+    let upload_line = upload.command_string();
+    assert!(upload_line.contains(".mriya-script-"), "{upload_line}");
+
+    let command_line = command.command_string();
+    let setup = command_line
+        .find("chmod 700 \"$HOME\"/.mriya-script-")
+        .expect("script should be made executable");
+    let trapped = command_line
+        .find("trap 'rm -f \"$HOME\"/.mriya-script-")
+        .expect("script should be removed on exit");
+    let started = command_line
+        .find("cd /remote/path && \"$MRIYA_SCRIPT\" --fast")
+        .expect("script should run in the remote path");
+    assert!(setup < trapped && trapped < started, "{command_line}");
+    assert!(!command_line.contains("make ci"), "{command_line}");
+}
+
+#[rstest]
+#[tokio::test]
+async fn run_hook_forwards_the_env_without_the_script_or_terminal(
+    base_config: SyncConfig,
+    networking: InstanceNetworking,
+) {
+    let runner = ScriptedRunner::new();
+    runner.push_success();
+    runner.push_exit_code(4);
+    let mut env = RemoteEnv::default();
+    env.set("RUST_LOG", "debug");
+    let syncer = Syncer::new(base_config, runner.clone())
+        .expect("config should validate")
+        .with_env(env)
+        .with_script(b"#!/bin/sh\nmake ci\n".to_vec())
+        .with_tty(true);
+
+    let output = syncer
+        .run_hook(&networking, "cargo fetch")
+        .await
+        .expect("run_hook should succeed");
+
+    assert_eq!(output.exit_code, Some(4));
+    let invocations = runner.invocations();
+    let [upload, hook] = invocations.as_slice() else {
+        panic!("expected env upload and hook invocations, got: {invocations:?}");
+    };
+    assert_eq!(upload.input, b"export RUST_LOG=debug\n");
+    assert!(!hook.args.iter().any(|arg| arg == "-tt"));
+    let command_line = hook.command_string();
+    assert!(
+        command_line.contains(". \"$HOME\"/.mriya-env-"),
+        "{command_line}"
+    );
+    assert!(!command_line.contains("MRIYA_SCRIPT"), "{command_line}");
+    assert!(
+        command_line.ends_with("cd /remote/path && cargo fetch"),
+        "{command_line}"
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn shard_commands_do_not_run_when_the_workspace_is_missing(mut base_config: SyncConfig) {
+    base_config.remote_path = String::from("/nonexistent/mriya-workspace");
+    let syncer = Syncer::new(base_config, ScriptedRunner::new()).expect("config should validate");
+    let command = syncer.build_remote_command(&crate::parallel::shard_command(1, 2, "echo ran"));
+
+    let output = AsyncCommandRunner::run(
+        &ProcessCommandRunner,
+        "sh",
+        &[OsString::from("-c"), OsString::from(command)],
+    )
+    .await
+    .expect("shell should run");
+
+    assert_ne!(output.code, Some(0));
+    assert!(!output.stdout.contains("ran"), "stdout: {}", output.stdout);
+}
@@ -0,0 +1,85 @@
+//! Tests for resolving and rendering the environment forwarded to remote
+//! commands.
+
+use std::ffi::OsString;
+
+use rstest::rstest;
+
+use super::super::{AsyncCommandRunner, ProcessCommandRunner, RemoteEnv, SyncError};
+
+fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| (*value).to_owned()).collect()
+}
+
+fn local(name: &str) -> Option<String> {
+    match name {
+        "RUST_LOG" => Some(String::from("debug")),
+        "TOKEN" => Some(String::from("local-token")),
+        _ => None,
+    }
+}
+
+#[rstest]
+fn resolve_copies_passed_names_and_skips_unset_ones() {
+    let env = RemoteEnv::resolve(&strings(&["RUST_LOG", "UNSET"]), &[], local)
+        .expect("names should be valid");
+
+    assert_eq!(env.names().collect::<Vec<_>>(), ["RUST_LOG"]);
+    assert_eq!(env.script(), "export RUST_LOG=debug\n");
+}
+
+#[rstest]
+fn later_entries_override_earlier_ones() {
+    let env = RemoteEnv::resolve(
+        &strings(&["TOKEN"]),
+        &strings(&["RUST_LOG=info", "TOKEN=override", "RUST_LOG"]),
+        local,
+    )
+    .expect("names should be valid");
+
+    assert_eq!(
+        env.script(),
+        "export TOKEN=override\nexport RUST_LOG=debug\n"
+    );
+}
+
+#[rstest]
+#[case("1ABC=x")]
+#[case("BAD-NAME")]
+#[case("=value")]
+fn resolve_rejects_invalid_names(#[case] entry: &str) {
+    let err = RemoteEnv::resolve(&[], &strings(&[entry]), local)
+        .expect_err("invalid name should be rejected");
+
+    assert!(matches!(err, SyncError::InvalidEnv { .. }), "{err:?}");
+    assert!(!err.to_string().contains('='), "values are never reported");
+}
+
+#[rstest]
+fn debug_output_lists_names_without_values() {
+    let env = RemoteEnv::resolve(&[], &strings(&["API_TOKEN=hunter2"]), local)
+        .expect("names should be valid");
+
+    let debug = format!("{env:?}");
+    assert!(debug.contains("API_TOKEN"), "{debug}");
+    assert!(!debug.contains("hunter2"), "{debug}");
+}
+
+#[rstest]
+#[tokio::test]
+async fn script_round_trips_awkward_values_through_a_shell() {
+    let awkward = "it's $HOME `x` \"quoted\"\nsecond line";
+    let mut env = RemoteEnv::default();
+    env.set("AWKWARD", awkward);
+    let command = format!("{}printf %s \"$AWKWARD\"", env.script());
+
+    let output = AsyncCommandRunner::run(
+        &ProcessCommandRunner,
+        "sh",
+        &[OsString::from("-c"), OsString::from(command)],
+    )
+    .await
+    .expect("shell should run");
+
+    assert_eq!(output.stdout, awkward);
+}
@@ -20,7 +20,7 @@ fn build_rsync_args_remote_includes_gitignore_filter(base_config: SyncConfig) {
     let source_dir = TempDir::new().expect("temp dir");
     let source = Utf8PathBuf::from_path_buf(source_dir.path().to_path_buf()).expect("utf8 path");
     let args = syncer
-        .build_rsync_args(&source, &destination)
+        .build_rsync_args(&source, &destination, None)
         .expect("args should build");
 
     let args_strs: Vec<String> = args
@@ -29,7 +29,7 @@ fn build_rsync_args_remote_includes_gitignore_filter(base_config: SyncConfig) {
         .collect();
     assert!(args_strs.contains(&String::from("--filter=:- .gitignore")));
     assert!(args_strs.contains(&String::from("--exclude")));
-    assert!(args_strs.contains(&String::from(".git/")));
+    assert!(args_strs.contains(&String::from(".git")));
     assert!(
         args_strs.iter().any(|arg| arg.starts_with("--rsh")),
         "expected --rsh wrapper"
@@ -50,7 +50,7 @@ fn build_rsync_args_local_omits_remote_shell(base_config: SyncConfig) {
     let source_dir = TempDir::new().expect("temp dir");
     let source = Utf8PathBuf::from_path_buf(source_dir.path().to_path_buf()).expect("utf8 path");
     let args = syncer
-        .build_rsync_args(&source, &destination)
+        .build_rsync_args(&source, &destination, None)
         .expect("args should build");
     let args_strs: Vec<String> = args
         .iter()
@@ -64,7 +64,8 @@ fn build_rsync_args_local_omits_remote_shell(base_config: SyncConfig) {
 }
 
 #[rstest]
-fn sync_returns_error_on_non_zero_rsync_status(base_config: SyncConfig) {
+#[tokio::test]
+async fn sync_returns_error_on_non_zero_rsync_status(base_config: SyncConfig) {
     let runner = ScriptedRunner::new();
     runner.push_failure(12);
     let syncer = Syncer::new(base_config, runner).expect("config should validate");
@@ -73,6 +74,7 @@ fn sync_returns_error_on_non_zero_rsync_status(base_config: SyncConfig) {
     };
     let err = syncer
         .sync(Utf8Path::new("/"), &destination)
+        .await
         .expect_err("non-zero rsync should error");
     let SyncError::CommandFailure {
         status,
@@ -87,12 +89,13 @@ fn sync_returns_error_on_non_zero_rsync_status(base_config: SyncConfig) {
 }
 
 #[rstest]
-fn sync_succeeds_on_zero_status(base_config: SyncConfig) {
+#[tokio::test]
+async fn sync_succeeds_on_zero_status(base_config: SyncConfig) {
     let runner = ScriptedRunner::new();
     runner.push_success();
     let syncer = Syncer::new(base_config, runner).expect("config should validate");
     let destination = SyncDestination::Local {
         path: Utf8PathBuf::from("/tmp/dst"),
     };
-    assert!(syncer.sync(Utf8Path::new("/"), &destination).is_ok());
+    assert!(syncer.sync(Utf8Path::new("/"), &destination).await.is_ok());
 }
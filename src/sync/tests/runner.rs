@@ -0,0 +1,122 @@
+//! Tests for cancelling asynchronous runs and for the blocking adapter.
+
+use std::ffi::OsString;
+use std::fs;
+use std::time::{Duration, Instant};
+
+use rstest::rstest;
+
+use super::super::{
+    AsyncCommandRunner, CommandRunner, ProcessCommandRunner, StreamingCommandRunner, SyncError,
+};
+
+/// Reports whether `pid` is alive; a killed child that has not been reaped
+/// yet is a zombie and counts as stopped.
+fn is_running(pid: &str) -> bool {
+    fs::read_to_string(format!("/proc/{pid}/stat")).is_ok_and(|stat| {
+        stat.rsplit_once(") ")
+            .is_some_and(|(_, fields)| !fields.starts_with('Z'))
+    })
+}
+
+#[rstest]
+fn blocking_adapter_runs_outside_a_runtime() {
+    let output = CommandRunner::run(
+        &ProcessCommandRunner,
+        "sh",
+        &[OsString::from("-c"), OsString::from("printf out; exit 4")],
+    )
+    .expect("command should execute");
+
+    assert_eq!(output.code, Some(4));
+    assert_eq!(output.stdout, "out");
+}
+
+#[rstest]
+#[tokio::test]
+async fn blocking_adapter_refuses_to_run_inside_a_runtime() {
+    let err = CommandRunner::run(
+        &ProcessCommandRunner,
+        "sh",
+        &[OsString::from("-c"), OsString::from("exit 0")],
+    )
+    .expect_err("nested runtimes should be refused");
+
+    assert!(
+        matches!(&err, SyncError::Spawn { program, message }
+            if program == "sh" && message.contains("inside a tokio runtime")),
+        "{err:?}"
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn interactive_run_reports_the_exit_code_without_capturing() {
+    let output = AsyncCommandRunner::run_interactive(
+        &StreamingCommandRunner::default(),
+        "sh",
+        &[OsString::from("-c"), OsString::from("exit 5")],
+    )
+    .await
+    .expect("command should execute");
+
+    assert_eq!(output.code, Some(5));
+    assert!(output.stdout.is_empty() && output.stderr.is_empty());
+}
+
+#[rstest]
+#[tokio::test]
+async fn input_reaches_standard_input_and_then_closes() {
+    let output = AsyncCommandRunner::run_with_input(
+        &StreamingCommandRunner::default(),
+        "sh",
+        &[OsString::from("-c"), OsString::from("cat; printf done")],
+        b"secret\n",
+    )
+    .await
+    .expect("command should execute");
+
+    assert_eq!(output.code, Some(0));
+    assert_eq!(output.stdout, "secret\ndone");
+}
+
+#[rstest]
+#[tokio::test]
+async fn large_input_is_written_while_output_is_read() {
+    // Well beyond a pipe buffer, and echoed back before the input ends, so
+    // writing everything before reading would stall both processes.
+    let input = vec![b'x'; 1 << 20];
+    let run = AsyncCommandRunner::run_with_input(&ProcessCommandRunner, "cat", &[], &input);
+    let output = tokio::time::timeout(Duration::from_secs(30), run)
+        .await
+        .expect("the run should not stall")
+        .expect("command should execute");
+
+    assert_eq!(output.code, Some(0));
+    assert_eq!(output.stdout.len(), input.len());
+}
+
+#[cfg(target_os = "linux")]
+#[rstest]
+#[tokio::test]
+async fn dropping_a_run_kills_the_process() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let pid_file = dir.path().join("pid");
+    let script = format!("echo $$ > '{}'; exec sleep 30", pid_file.display());
+    let args = [OsString::from("-c"), OsString::from(script)];
+
+    let runner = StreamingCommandRunner::default();
+    let run = AsyncCommandRunner::run(&runner, "sh", &args);
+    let timed_out = tokio::time::timeout(Duration::from_millis(500), run).await;
+    assert!(timed_out.is_err(), "the run should still be going");
+
+    let pid = fs::read_to_string(&pid_file).expect("pid file");
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while is_running(pid.trim()) && Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert!(
+        !is_running(pid.trim()),
+        "dropping the run kills the process"
+    );
+}
@@ -0,0 +1,102 @@
+//! Tests for output sinks and capture policies of streamed commands.
+
+use std::ffi::OsString;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use camino::Utf8PathBuf;
+use rstest::rstest;
+use tempfile::TempDir;
+
+use super::super::{
+    AsyncCommandRunner, Capture, DEFAULT_CAPTURE_BYTES, OutputSink, OutputStream,
+    StreamingCommandRunner,
+};
+
+fn script(body: &str) -> [OsString; 2] {
+    [OsString::from("-c"), OsString::from(body)]
+}
+
+#[rstest]
+fn default_keeps_a_bounded_tail() {
+    assert_eq!(Capture::default(), Capture::Tail(DEFAULT_CAPTURE_BYTES));
+    assert!(matches!(
+        StreamingCommandRunner::default().sink(),
+        OutputSink::Terminal
+    ));
+}
+
+#[rstest]
+#[tokio::test]
+async fn tail_capture_keeps_only_the_last_bytes() {
+    let runner = StreamingCommandRunner::default()
+        .with_sink(OutputSink::Discard)
+        .with_capture(Capture::Tail(4));
+    let output = runner
+        .run("sh", &script("printf 0123456789; printf abcdef 1>&2"))
+        .await
+        .expect("command should run");
+
+    assert_eq!(output.stdout, "6789");
+    assert_eq!(output.stderr, "cdef");
+}
+
+#[rstest]
+#[tokio::test]
+async fn full_capture_keeps_everything_past_the_default_tail() {
+    let runner = StreamingCommandRunner::default()
+        .with_sink(OutputSink::Discard)
+        .with_capture(Capture::Full);
+    let output = runner
+        .run("sh", &script("head -c 100000 /dev/zero | tr '\\0' x"))
+        .await
+        .expect("command should run");
+
+    assert_eq!(output.stdout.len(), 100_000);
+}
+
+#[rstest]
+#[tokio::test]
+async fn log_file_sink_appends_both_streams() {
+    let dir = TempDir::new().expect("temp dir");
+    let log = Utf8PathBuf::from_path_buf(dir.path().join("run.log")).expect("utf-8 path");
+    std::fs::write(&log, "earlier\n").expect("seed log");
+    let runner = StreamingCommandRunner::default().with_sink(OutputSink::LogFile(log.clone()));
+    let output = runner
+        .run("sh", &script("echo out; sleep 0.1; echo err 1>&2"))
+        .await
+        .expect("command should run");
+
+    assert_eq!(output.code, Some(0));
+    let written = std::fs::read_to_string(&log).expect("read log");
+    assert_eq!(written, "earlier\nout\nerr\n");
+}
+
+#[rstest]
+#[tokio::test]
+async fn callback_sink_receives_each_stream() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let recorder = Arc::clone(&seen);
+    let runner = StreamingCommandRunner::default().with_sink(OutputSink::Callback(Arc::new(
+        move |stream, chunk: &[u8]| {
+            recorder
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push((stream, chunk.to_vec()));
+        },
+    )));
+    runner
+        .run("sh", &script("printf out; printf err 1>&2"))
+        .await
+        .expect("command should run");
+
+    let chunks = seen.lock().unwrap_or_else(PoisonError::into_inner).clone();
+    let received = |wanted: OutputStream| -> Vec<u8> {
+        chunks
+            .iter()
+            .filter(|(stream, _)| *stream == wanted)
+            .flat_map(|(_, chunk)| chunk.clone())
+            .collect()
+    };
+    assert_eq!(received(OutputStream::Stdout), b"out");
+    assert_eq!(received(OutputStream::Stderr), b"err");
+}
@@ -53,7 +53,7 @@ fn rsync_remote_shell_includes_identity_flag(base_config: SyncConfig) {
     let source_dir = TempDir::new().expect("temp dir");
     let source = Utf8PathBuf::from_path_buf(source_dir.path().to_path_buf()).expect("utf8 path");
     let args = syncer
-        .build_rsync_args(&source, &destination)
+        .build_rsync_args(&source, &destination, None)
         .expect("args should build");
     let args_strs: Vec<String> = args
         .iter()
@@ -69,3 +69,88 @@ fn rsync_remote_shell_includes_identity_flag(base_config: SyncConfig) {
         "remote shell should include key path: {rsh_arg}"
     );
 }
+
+#[rstest]
+fn multiplexing_shares_a_private_control_socket(
+    base_config: SyncConfig,
+    networking: InstanceNetworking,
+) {
+    use std::os::unix::fs::PermissionsExt;
+
+    let cfg = SyncConfig {
+        ssh_multiplex: true,
+        ..base_config
+    };
+    let syncer = Syncer::new(cfg, ScriptedRunner::new()).expect("config should validate");
+    let dir = syncer.control_dir().expect("control dir").to_path_buf();
+    let args: Vec<String> = syncer
+        .build_ssh_args(&networking, "echo ok")
+        .iter()
+        .map(|a| a.to_string_lossy().into_owned())
+        .collect();
+
+    for option in [
+        String::from("ControlMaster=auto"),
+        format!("ControlPath={dir}/%C"),
+        String::from("ControlPersist=60"),
+    ] {
+        assert!(args.contains(&option), "missing {option}: {args:?}");
+    }
+    let mode = std::fs::metadata(&dir)
+        .expect("control dir")
+        .permissions()
+        .mode();
+    assert_eq!(mode & 0o777, 0o700);
+    assert!(
+        syncer
+            .build_remote_shell(networking.ssh_port)
+            .contains(&format!("ControlPath={dir}/%C")),
+        "rsync should reuse the connection"
+    );
+
+    let clone = syncer.clone();
+    drop(syncer);
+    assert!(dir.exists(), "clones keep the control directory");
+    drop(clone);
+    assert!(
+        !dir.exists(),
+        "the last clone removes the control directory"
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn close_connection_stops_the_master(
+    base_config: SyncConfig,
+    networking: InstanceNetworking,
+) {
+    let runner = ScriptedRunner::new();
+    runner.push_success();
+    let cfg = SyncConfig {
+        ssh_multiplex: true,
+        ..base_config
+    };
+    let syncer = Syncer::new(cfg, runner.clone()).expect("config should validate");
+
+    syncer.close_connection(&networking).await;
+
+    let invocations = runner.invocations();
+    let command = invocations.first().expect("ssh -O exit").command_string();
+    assert!(command.starts_with("ssh -p 2222 "), "{command}");
+    assert!(command.ends_with(" -O exit ubuntu@127.0.0.1"), "{command}");
+}
+
+#[rstest]
+#[tokio::test]
+async fn close_connection_without_multiplexing_runs_nothing(
+    base_config: SyncConfig,
+    networking: InstanceNetworking,
+) {
+    let runner = ScriptedRunner::new();
+    let syncer = Syncer::new(base_config, runner.clone()).expect("config should validate");
+
+    syncer.close_connection(&networking).await;
+
+    assert!(syncer.control_dir().is_none());
+    assert!(runner.invocations().is_empty());
+}
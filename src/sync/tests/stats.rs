@@ -0,0 +1,152 @@
+//! Tests for sync dry runs and the `max_sync_bytes` guard.
+
+use super::super::*;
+use crate::test_support::ScriptedRunner;
+use rstest::rstest;
+use tempfile::TempDir;
+
+use super::fixtures::base_config;
+
+const DRY_RUN_OUTPUT: &str = "\
+cd+++++++++ 4,096 ./
+>f+++++++++ 1,024 Cargo.toml
+cd+++++++++ 4,096 data/
+>f+++++++++ 2,147,483,648 data/big.parquet
+>f+++++++++ 512 data/small.csv
+cd+++++++++ 4,096 src/
+>f+++++++++ 8,192 src/lib.rs
+.f          10 unchanged.txt
+*deleting   0 stale.txt
+
+Number of files: 7 (reg: 4, dir: 3)
+Total transferred file size: 2,147,493,376 bytes
+";
+
+fn temp_source() -> (TempDir, Utf8PathBuf) {
+    let dir = TempDir::new().expect("temp dir");
+    let source = Utf8PathBuf::from_path_buf(dir.path().to_path_buf()).expect("utf8 path");
+    (dir, source)
+}
+
+fn parsed() -> SyncStats {
+    SyncStats::parse(DRY_RUN_OUTPUT)
+}
+
+#[rstest]
+fn parse_lists_only_files_that_would_be_sent() {
+    let stats = parsed();
+    let paths: Vec<&str> = stats.files.iter().map(|file| file.path.as_str()).collect();
+    assert_eq!(
+        paths,
+        [
+            "Cargo.toml",
+            "data/big.parquet",
+            "data/small.csv",
+            "src/lib.rs"
+        ]
+    );
+    assert_eq!(stats.file_count(), 4);
+    assert_eq!(stats.total_bytes, 2_147_493_376);
+}
+
+#[rstest]
+fn parse_sums_files_when_stats_summary_is_missing() {
+    let stats = SyncStats::parse(">f+++++++++ 1,024 a\n>f.st...... 2048 b/c\n");
+    assert_eq!(stats.total_bytes, 3072);
+}
+
+#[rstest]
+fn largest_files_are_sorted_by_size() {
+    let stats = parsed();
+    let largest: Vec<&str> = stats
+        .largest_files(2)
+        .iter()
+        .map(|file| file.path.as_str())
+        .collect();
+    assert_eq!(largest, ["data/big.parquet", "src/lib.rs"]);
+}
+
+#[rstest]
+fn top_directories_group_by_first_component() {
+    let stats = parsed();
+    let top = stats.top_directories(10);
+    let summary: Vec<(&str, usize, u64)> = top
+        .iter()
+        .map(|dir| (dir.path.as_str(), dir.files, dir.bytes))
+        .collect();
+    assert_eq!(
+        summary,
+        [("data", 2, 2_147_484_160), ("src", 1, 8192), (".", 1, 1024)]
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn dry_run_asks_rsync_for_itemized_stats(base_config: SyncConfig) {
+    let runner = ScriptedRunner::new();
+    runner.push_output(Some(0), DRY_RUN_OUTPUT, "");
+    let syncer = Syncer::new(base_config, runner.clone()).expect("config should validate");
+
+    let (_dir, source) = temp_source();
+
+    let stats = syncer
+        .dry_run(&source)
+        .await
+        .expect("dry run should succeed");
+
+    assert_eq!(stats, parsed());
+    let invocations = runner.invocations();
+    let invocation = invocations.first().expect("rsync should run");
+    assert_eq!(invocation.program, "rsync");
+    let args: Vec<String> = invocation
+        .args
+        .iter()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect();
+    assert!(args.starts_with(&[
+        String::from("--dry-run"),
+        String::from("--stats"),
+        format!("--out-format={ITEMIZE_FORMAT}"),
+    ]));
+    assert!(
+        !args.iter().any(|arg| arg.contains("ubuntu@")),
+        "dry runs never contact a remote: {args:?}"
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn dry_run_reports_rsync_failures(base_config: SyncConfig) {
+    let runner = ScriptedRunner::new();
+    runner.push_output(Some(23), "", "partial transfer");
+    let syncer = Syncer::new(base_config, runner).expect("config should validate");
+
+    let (_dir, source) = temp_source();
+
+    let err = syncer
+        .dry_run(&source)
+        .await
+        .expect_err("dry run should fail");
+
+    assert!(matches!(err, SyncError::CommandFailure { .. }), "{err:?}");
+}
+
+#[rstest]
+#[case(None, true)]
+#[case(Some(2_147_493_376), true)]
+#[case(Some(1 << 30), false)]
+fn size_limit_rejects_larger_transfers(
+    mut base_config: SyncConfig,
+    #[case] limit: Option<u64>,
+    #[case] allowed: bool,
+) {
+    base_config.max_sync_bytes = limit;
+    let syncer = Syncer::new(base_config, ScriptedRunner::new()).expect("config should validate");
+
+    let result = syncer.check_size_limit(&parsed());
+
+    assert_eq!(result.is_ok(), allowed, "{result:?}");
+    if let Err(err) = result {
+        assert!(matches!(err, SyncError::SizeLimit { .. }), "{err:?}");
+    }
+}
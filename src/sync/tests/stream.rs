@@ -0,0 +1,202 @@
+//! Tests for the streamed `tar | zstd` transfer to empty destinations.
+
+use super::super::*;
+use crate::ProcessCommandRunner;
+use crate::test_support::{CommandInvocation, ScriptedRunner};
+use rstest::rstest;
+use tempfile::TempDir;
+
+use super::fixtures::base_config;
+
+const DRY_RUN: &str = "\
+created directory /tmp/mriya-sync-x/empty
+cd+++++++++ 4,096 ./
+cd+++++++++ 4,096 src/
+>f+++++++++ 12 src/lib.rs
+cL+++++++++ 6 src/current
+>f+++++++++ 3 notes with spaces.txt
+
+Total transferred file size: 21 bytes
+";
+
+fn temp_source() -> (TempDir, Utf8PathBuf) {
+    let dir = TempDir::new().expect("temp dir");
+    let source = Utf8PathBuf::from_path_buf(dir.path().to_path_buf()).expect("utf8 path");
+    (dir, source)
+}
+
+fn remote_destination() -> SyncDestination {
+    SyncDestination::Remote {
+        user: String::from("ubuntu"),
+        host: String::from("1.2.3.4"),
+        port: 2222,
+        path: Utf8PathBuf::from("/remote"),
+    }
+}
+
+fn stream_config(mut config: SyncConfig) -> SyncConfig {
+    config.tar_stream = true;
+    config
+}
+
+fn commands(runner: &ScriptedRunner) -> Vec<String> {
+    runner
+        .invocations()
+        .iter()
+        .map(CommandInvocation::command_string)
+        .collect()
+}
+
+#[rstest]
+fn archive_list_keeps_directories_files_and_symlinks() {
+    assert_eq!(
+        stream::archive_list(DRY_RUN),
+        "src\0src/lib.rs\0src/current\0notes with spaces.txt\0"
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn empty_remote_receives_a_tarball_stream(base_config: SyncConfig) {
+    let runner = ScriptedRunner::new();
+    runner.push_success(); // emptiness probe
+    runner.push_output(Some(0), DRY_RUN, "");
+    runner.push_success(); // stream
+    let syncer = Syncer::new(stream_config(base_config), runner.clone()).expect("config");
+    let (_dir, source) = temp_source();
+
+    syncer
+        .sync(&source, &remote_destination())
+        .await
+        .expect("sync should succeed");
+
+    let invoked = commands(&runner);
+    assert_eq!(invoked.len(), 3, "unexpected commands: {invoked:?}");
+    let probe = invoked.first().expect("probe");
+    assert!(
+        probe.ends_with("ubuntu@1.2.3.4 [ ! -d /remote ] || [ -z \"$(ls -A /remote)\" ]"),
+        "{probe}"
+    );
+    let dry_run = invoked.get(1).expect("dry run");
+    assert!(dry_run.starts_with("rsync --dry-run --stats"), "{dry_run}");
+    let stream = invoked.get(2).expect("stream");
+    assert!(
+        stream.starts_with(&format!(
+            "sh -c {{ tar -C {source} --no-recursion --null -T "
+        )),
+        "{stream}"
+    );
+    assert!(stream.contains(" | { zstd -q -T0 || echo "), "{stream}");
+    assert!(stream.contains("; } | ssh -p 2222 "), "{stream}");
+    assert!(
+        stream.contains(
+            "'ubuntu@1.2.3.4' 'mkdir -p /remote && zstd -dq | tar -C /remote -xpf -' && if [ -s "
+        ),
+        "{stream}"
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn populated_remote_uses_rsync(base_config: SyncConfig) {
+    let runner = ScriptedRunner::new();
+    runner.push_output(Some(1), "", ""); // emptiness probe
+    runner.push_success(); // rsync
+    let syncer = Syncer::new(stream_config(base_config), runner.clone()).expect("config");
+    let (_dir, source) = temp_source();
+
+    syncer
+        .sync(&source, &remote_destination())
+        .await
+        .expect("sync should succeed");
+
+    let invoked = commands(&runner);
+    assert_eq!(invoked.len(), 2);
+    let last = invoked.last().expect("rsync");
+    assert!(last.starts_with("rsync -az --delete"), "{last}");
+}
+
+#[rstest]
+#[tokio::test]
+async fn populated_local_destination_skips_the_probe(base_config: SyncConfig) {
+    let runner = ScriptedRunner::new();
+    runner.push_success(); // rsync
+    let syncer = Syncer::new(stream_config(base_config), runner.clone()).expect("config");
+    let (_dir, source) = temp_source();
+    let (_dest_dir, dest) = temp_source();
+    std::fs::write(dest.join("existing.txt"), "kept").expect("seed destination");
+
+    syncer
+        .sync(&source, &SyncDestination::Local { path: dest })
+        .await
+        .expect("sync should succeed");
+
+    let invoked = commands(&runner);
+    assert_eq!(invoked.len(), 1);
+    assert!(
+        invoked
+            .iter()
+            .all(|cmd| cmd.starts_with("rsync -az --delete"))
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn failed_stream_is_reported(base_config: SyncConfig) {
+    let runner = ScriptedRunner::new();
+    runner.push_success(); // emptiness probe
+    runner.push_output(Some(0), DRY_RUN, "");
+    runner.push_output(Some(2), "", "zstd: not found");
+    let syncer = Syncer::new(stream_config(base_config), runner).expect("config");
+    let (_dir, source) = temp_source();
+
+    let err = syncer
+        .sync(&source, &remote_destination())
+        .await
+        .expect_err("stream failure should surface");
+
+    assert!(
+        matches!(err, SyncError::CommandFailure { ref program, status: Some(2), .. } if program == "sh"),
+        "{err:?}"
+    );
+}
+
+/// Runs the pipeline for `list` over `tree` with real processes, discarding
+/// the archive.
+async fn run_pipeline(tree: &Utf8Path, list: &str) -> CommandOutput {
+    let scratch = TempDir::new().expect("scratch dir");
+    let scratch_path = Utf8Path::from_path(scratch.path()).expect("utf8 path");
+    let list_path = scratch_path.join(stream::LIST_FILE);
+    std::fs::write(&list_path, list).expect("write list");
+    let failures = scratch_path.join(stream::FAILURES_FILE);
+    let script = stream::pipeline(tree, &list_path, &failures, "cat > /dev/null");
+    let args = [OsString::from("-c"), OsString::from(script)];
+    AsyncCommandRunner::run(&ProcessCommandRunner, stream::SHELL, &args)
+        .await
+        .expect("shell should start")
+}
+
+#[rstest]
+#[tokio::test]
+async fn pipeline_succeeds_when_every_stage_does() {
+    let (_dir, source) = temp_source();
+    std::fs::write(source.join("kept.txt"), "kept").expect("seed source");
+
+    let output = run_pipeline(&source, "kept.txt\0").await;
+
+    assert!(output.is_success(), "{output:?}");
+}
+
+#[rstest]
+#[tokio::test]
+async fn pipeline_fails_when_tar_fails_despite_the_sink() {
+    let (_dir, source) = temp_source();
+
+    let output = run_pipeline(&source, "vanished.txt\0").await;
+
+    assert_eq!(output.code, Some(1), "{output:?}");
+    assert!(
+        output.stderr.contains("tar exited with status"),
+        "{output:?}"
+    );
+}
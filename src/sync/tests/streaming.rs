@@ -1,20 +1,21 @@
 //! Tests for `StreamingCommandRunner` output forwarding and capture.
 
-use super::super::*;
+use super::super::{AsyncCommandRunner, StreamingCommandRunner, SyncError};
 use rstest::rstest;
 use std::ffi::OsString;
 use std::fmt::Write as _;
 
 /// Helper to run a shell script via `StreamingCommandRunner` and assert expected output.
-fn assert_streaming_runner_output(
+async fn assert_streaming_runner_output(
     script: &str,
     expected_code: Option<i32>,
     expected_stdout: &str,
     expected_stderr: &str,
 ) {
-    let runner = StreamingCommandRunner;
+    let runner = StreamingCommandRunner::default();
     let output = runner
         .run("sh", &[OsString::from("-c"), OsString::from(script)])
+        .await
         .expect("command should execute successfully");
 
     assert_eq!(output.code, expected_code);
@@ -23,25 +24,30 @@ fn assert_streaming_runner_output(
 }
 
 #[rstest]
-fn streaming_runner_captures_output() {
-    assert_streaming_runner_output("printf out && printf err 1>&2", Some(0), "out", "err");
+#[tokio::test]
+async fn streaming_runner_captures_output() {
+    assert_streaming_runner_output("printf out && printf err 1>&2", Some(0), "out", "err").await;
 }
 
 #[rstest]
-fn streaming_runner_captures_output_on_failure() {
+#[tokio::test]
+async fn streaming_runner_captures_output_on_failure() {
     assert_streaming_runner_output(
         "printf out && printf err 1>&2; exit 42",
         Some(42),
         "out",
         "err",
-    );
+    )
+    .await;
 }
 
 #[rstest]
-fn streaming_runner_propagates_non_zero_exit_code() {
-    let runner = StreamingCommandRunner;
+#[tokio::test]
+async fn streaming_runner_propagates_non_zero_exit_code() {
+    let runner = StreamingCommandRunner::default();
     let output = runner
         .run("sh", &[OsString::from("-c"), OsString::from("exit 7")])
+        .await
         .expect("command should execute successfully");
 
     assert_eq!(output.code, Some(7));
@@ -50,10 +56,12 @@ fn streaming_runner_propagates_non_zero_exit_code() {
 }
 
 #[rstest]
-fn streaming_runner_handles_no_output() {
-    let runner = StreamingCommandRunner;
+#[tokio::test]
+async fn streaming_runner_handles_no_output() {
+    let runner = StreamingCommandRunner::default();
     let output = runner
         .run("sh", &[OsString::from("-c"), OsString::from("")])
+        .await
         .expect("command should execute successfully");
 
     assert_eq!(output.code, Some(0));
@@ -62,8 +70,9 @@ fn streaming_runner_handles_no_output() {
 }
 
 #[rstest]
-fn streaming_runner_captures_large_interleaved_output() {
-    let runner = StreamingCommandRunner;
+#[tokio::test]
+async fn streaming_runner_captures_large_interleaved_output() {
+    let runner = StreamingCommandRunner::default();
     let output = runner
         .run(
             "sh",
@@ -74,6 +83,7 @@ fn streaming_runner_captures_large_interleaved_output() {
                 ),
             ],
         )
+        .await
         .expect("command should execute successfully");
 
     let mut expected_out = String::new();
@@ -89,9 +99,10 @@ fn streaming_runner_captures_large_interleaved_output() {
 }
 
 #[rstest]
-fn streaming_runner_failed_spawn_returns_spawn_error() {
-    let runner = StreamingCommandRunner;
-    let result = runner.run("definitely-not-a-real-binary-xyz", &[]);
+#[tokio::test]
+async fn streaming_runner_failed_spawn_returns_spawn_error() {
+    let runner = StreamingCommandRunner::default();
+    let result = runner.run("definitely-not-a-real-binary-xyz", &[]).await;
 
     match result {
         Err(SyncError::Spawn { .. }) => {}
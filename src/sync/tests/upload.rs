@@ -0,0 +1,75 @@
+//! Tests for running uploaded scripts in the remote shell.
+
+use std::ffi::OsString;
+use std::fs;
+
+use rstest::rstest;
+use tempfile::TempDir;
+
+use super::super::upload::script_setup;
+use super::super::{AsyncCommandRunner, ProcessCommandRunner, script_command};
+
+/// Runs `command` under `sh` with `HOME` pointing at `home`, as the remote
+/// shell would after the upload.
+async fn run_in_home(home: &TempDir, command: &str) -> (Option<i32>, String) {
+    let home_path = home.path().to_string_lossy();
+    let script = format!("HOME='{home_path}'; {command}");
+    let output = AsyncCommandRunner::run(
+        &ProcessCommandRunner,
+        "sh",
+        &[OsString::from("-c"), OsString::from(script)],
+    )
+    .await
+    .expect("shell should run");
+    (output.code, output.stdout)
+}
+
+#[rstest]
+#[case("", "\"$MRIYA_SCRIPT\"")]
+#[case("'a b' c", "\"$MRIYA_SCRIPT\" 'a b' c")]
+fn script_command_passes_escaped_arguments(#[case] args: &str, #[case] expected: &str) {
+    assert_eq!(script_command(args), expected);
+}
+
+#[rstest]
+#[tokio::test]
+async fn uploaded_script_runs_with_arguments_and_its_exit_code() {
+    let home = TempDir::new().expect("temp dir");
+    fs::write(
+        home.path().join(".mriya-script-test"),
+        "#!/bin/sh\nprintf '%s|' \"$@\"\nexit 3\n",
+    )
+    .expect("write script");
+
+    let command = format!(
+        "{}{}",
+        script_setup(".mriya-script-test"),
+        script_command("'a b' c")
+    );
+    let (code, stdout) = run_in_home(&home, &command).await;
+
+    assert_eq!(code, Some(3));
+    assert_eq!(stdout, "a b|c|");
+    assert!(
+        !home.path().join(".mriya-script-test").exists(),
+        "script should be removed when the shell exits"
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn shebang_selects_the_interpreter() {
+    let home = TempDir::new().expect("temp dir");
+    let body = "#!/bin/cat\nprinted by cat, not run by sh\n";
+    fs::write(home.path().join(".mriya-script-cat"), body).expect("write script");
+
+    let command = format!(
+        "{}{}",
+        script_setup(".mriya-script-cat"),
+        script_command("")
+    );
+    let (code, stdout) = run_in_home(&home, &command).await;
+
+    assert_eq!(code, Some(0));
+    assert_eq!(stdout, body);
+}
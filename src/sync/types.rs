@@ -1,12 +1,16 @@
-//! Core sync types and command runner abstraction.
+//! Core sync types and the asynchronous command runner abstraction.
 
 use std::ffi::OsString;
-use std::io::{BufReader, Read, Write};
-use std::process::{Command, Stdio};
-use std::thread;
+use std::io;
+use std::process::Stdio;
 
 use camino::Utf8PathBuf;
+use tokio::io::AsyncWriteExt;
+use tokio::process::{Child, Command};
+use tokio::runtime::{Builder, Handle};
 
+use super::redact::Redactor;
+use super::sink::{self, Capture, OutputSink, OutputStream};
 use crate::sync::SyncError;
 
 /// Target for rsync either on a remote host or locally (used for tests).
@@ -36,9 +40,11 @@ pub struct RemoteCommandOutput {
     /// Exit code reported by the remote command (`None` when the process exits
     /// without an exit status, for example after being killed by a signal).
     pub exit_code: Option<i32>,
-    /// Captured standard output stream.
+    /// Captured standard output stream, bounded by the runner's
+    /// [`Capture`] policy.
     pub stdout: String,
-    /// Captured standard error stream.
+    /// Captured standard error stream, bounded by the runner's [`Capture`]
+    /// policy.
     pub stderr: String,
 }
 
@@ -61,29 +67,185 @@ impl CommandOutput {
     }
 }
 
-/// Abstraction over command execution to support fakes in tests.
-pub trait CommandRunner {
+/// Asynchronous command execution, so orchestrators running on the tokio
+/// runtime never block a worker thread while a command runs.
+///
+/// Dropping a returned future cancels the command: the process runners kill
+/// the child they spawned, so a cancelled run leaves nothing behind.
+pub trait AsyncCommandRunner: Send + Sync {
     /// Runs `program` with the given arguments, capturing stdout and stderr.
     ///
     /// # Errors
     ///
     /// Returns [`SyncError::Spawn`] if the command cannot be started.
+    fn run(
+        &self,
+        program: &str,
+        args: &[OsString],
+    ) -> impl Future<Output = Result<CommandOutput, SyncError>> + Send;
+
+    /// Runs a local helper whose output is data rather than progress, such as
+    /// a `git` file listing, capturing stdout and stderr without echoing them.
+    ///
+    /// Defaults to [`AsyncCommandRunner::run`], which already captures
+    /// silently for runners that do not stream.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SyncError::Spawn`] if the command cannot be started.
+    fn run_quiet(
+        &self,
+        program: &str,
+        args: &[OsString],
+    ) -> impl Future<Output = Result<CommandOutput, SyncError>> + Send {
+        self.run(program, args)
+    }
+
+    /// Runs `program` quietly with `input` written to its standard input,
+    /// for data that must stay out of the argument list.
+    ///
+    /// Defaults to [`AsyncCommandRunner::run_quiet`] and drops `input`, which
+    /// suits runners that never start a process.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SyncError::Spawn`] if the command cannot be started or
+    /// `input` cannot be written.
+    fn run_with_input(
+        &self,
+        program: &str,
+        args: &[OsString],
+        input: &[u8],
+    ) -> impl Future<Output = Result<CommandOutput, SyncError>> + Send {
+        let _ = input;
+        self.run_quiet(program, args)
+    }
+
+    /// Runs `program` attached to this process's stdin, stdout, and stderr,
+    /// for commands that drive the terminal themselves such as `ssh -tt`.
+    ///
+    /// Output is not captured. Defaults to [`AsyncCommandRunner::run`], so
+    /// runners that never touch a terminal treat it like any other command.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SyncError::Spawn`] if the command cannot be started.
+    fn run_interactive(
+        &self,
+        program: &str,
+        args: &[OsString],
+    ) -> impl Future<Output = Result<CommandOutput, SyncError>> + Send {
+        self.run(program, args)
+    }
+}
+
+/// Blocking command execution for callers outside an async context.
+///
+/// Every [`AsyncCommandRunner`] implements it by driving the command to
+/// completion on a private single-threaded runtime. Tokio refuses to nest
+/// runtimes, so called from within one it returns [`SyncError::Spawn`]
+/// without running anything; use [`AsyncCommandRunner`] there instead.
+pub trait CommandRunner {
+    /// Runs `program` with the given arguments, capturing stdout and stderr.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SyncError::Spawn`] if the command cannot be started or the
+    /// caller is inside a tokio runtime.
     fn run(&self, program: &str, args: &[OsString]) -> Result<CommandOutput, SyncError>;
+
+    /// Runs a local helper quietly, as [`AsyncCommandRunner::run_quiet`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SyncError::Spawn`] if the command cannot be started or the
+    /// caller is inside a tokio runtime.
+    fn run_quiet(&self, program: &str, args: &[OsString]) -> Result<CommandOutput, SyncError>;
+}
+
+impl<R: AsyncCommandRunner> CommandRunner for R {
+    fn run(&self, program: &str, args: &[OsString]) -> Result<CommandOutput, SyncError> {
+        block_on(program, AsyncCommandRunner::run(self, program, args))
+    }
+
+    fn run_quiet(&self, program: &str, args: &[OsString]) -> Result<CommandOutput, SyncError> {
+        block_on(program, AsyncCommandRunner::run_quiet(self, program, args))
+    }
+}
+
+fn block_on(
+    program: &str,
+    command: impl Future<Output = Result<CommandOutput, SyncError>>,
+) -> Result<CommandOutput, SyncError> {
+    if Handle::try_current().is_ok() {
+        return Err(SyncError::Spawn {
+            program: program.to_owned(),
+            message: String::from(
+                "blocking runner called inside a tokio runtime; use AsyncCommandRunner",
+            ),
+        });
+    }
+    Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|err| spawn_error(program, &err))?
+        .block_on(command)
 }
 
 /// Real command runner that shells out to the host operating system.
 #[derive(Clone, Debug, Default)]
 pub struct ProcessCommandRunner;
 
-impl CommandRunner for ProcessCommandRunner {
-    fn run(&self, program: &str, args: &[OsString]) -> Result<CommandOutput, SyncError> {
+impl AsyncCommandRunner for ProcessCommandRunner {
+    async fn run_with_input(
+        &self,
+        program: &str,
+        args: &[OsString],
+        input: &[u8],
+    ) -> Result<CommandOutput, SyncError> {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|err| spawn_error(program, &err))?;
+        let pipe = child.stdin.take();
+        // Output is drained while the input is written, so a program that
+        // fills its output pipe before reading all of its input cannot stall.
+        let feed = async move {
+            if let Some(mut stdin) = pipe {
+                // Dropping the handle closes the pipe, so the program sees EOF.
+                stdin.write_all(input).await?;
+            }
+            Ok::<(), io::Error>(())
+        };
+        let (output, ()) = tokio::try_join!(child.wait_with_output(), feed)
+            .map_err(|err| spawn_error(program, &err))?;
+
+        Ok(CommandOutput {
+            code: output.status.code(),
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        })
+    }
+
+    async fn run_interactive(
+        &self,
+        program: &str,
+        args: &[OsString],
+    ) -> Result<CommandOutput, SyncError> {
+        run_attached(program, args).await
+    }
+
+    async fn run(&self, program: &str, args: &[OsString]) -> Result<CommandOutput, SyncError> {
         let output = Command::new(program)
             .args(args)
+            .kill_on_drop(true)
             .output()
-            .map_err(|err| SyncError::Spawn {
-                program: program.to_owned(),
-                message: err.to_string(),
-            })?;
+            .await
+            .map_err(|err| spawn_error(program, &err))?;
 
         Ok(CommandOutput {
             code: output.status.code(),
@@ -93,101 +255,142 @@ impl CommandRunner for ProcessCommandRunner {
     }
 }
 
-fn forward_stream(
-    reader: impl Read + Send + 'static,
-    is_stderr: bool,
-    program: &str,
-) -> thread::JoinHandle<Result<String, SyncError>> {
-    let program_name = program.to_owned();
-    thread::spawn(move || {
-        let mut buffer = [0_u8; 8192];
-        let mut captured = Vec::new();
-        let mut buffered_reader = BufReader::new(reader);
-        let mut writer: Box<dyn Write> = if is_stderr {
-            Box::new(std::io::stderr().lock())
-        } else {
-            Box::new(std::io::stdout().lock())
-        };
-
-        loop {
-            let read = buffered_reader
-                .read(&mut buffer)
-                .map_err(|err| SyncError::Spawn {
-                    program: program_name.clone(),
-                    message: err.to_string(),
-                })?;
+/// Runs `program` with inherited stdio and returns only its exit code.
+async fn run_attached(program: &str, args: &[OsString]) -> Result<CommandOutput, SyncError> {
+    let status = Command::new(program)
+        .args(args)
+        .kill_on_drop(true)
+        .status()
+        .await
+        .map_err(|err| spawn_error(program, &err))?;
 
-            if read == 0 {
-                break;
-            }
-
-            let (chunk, _) = buffer.split_at(read);
-            writer.write_all(chunk).map_err(|err| SyncError::Spawn {
-                program: program_name.clone(),
-                message: err.to_string(),
-            })?;
-            captured.extend_from_slice(chunk);
-        }
+    Ok(CommandOutput {
+        code: status.code(),
+        stdout: String::new(),
+        stderr: String::new(),
+    })
+}
 
-        writer.flush().map_err(|err| SyncError::Spawn {
-            program: program_name.clone(),
-            message: err.to_string(),
-        })?;
+/// Spawns `program` with piped stdout and stderr, killed when dropped.
+pub(super) fn spawn_piped(program: &str, args: &[OsString]) -> Result<Child, SyncError> {
+    Command::new(program)
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|err| spawn_error(program, &err))
+}
 
-        Ok(String::from_utf8_lossy(&captured).into_owned())
-    })
+/// Builds the error reported when `program` cannot be started or its output
+/// cannot be forwarded.
+pub(super) fn spawn_error(program: &str, err: &io::Error) -> SyncError {
+    SyncError::Spawn {
+        program: program.to_owned(),
+        message: err.to_string(),
+    }
 }
 
-/// Join a forwarder thread, mapping panics and stream errors to
-/// [`SyncError::Spawn`].
+/// Command runner that streams subprocess stdout/stderr to an
+/// [`OutputSink`] while capturing them according to a [`Capture`] policy.
 ///
-/// Returns an empty string when no forwarder was spawned for the stream.
-fn join_forwarder(
-    handle: Option<thread::JoinHandle<Result<String, SyncError>>>,
-    program: &str,
-    stream_name: &str,
-) -> Result<String, SyncError> {
-    match handle {
-        Some(join_handle) => join_handle.join().map_err(|_| SyncError::Spawn {
-            program: program.to_owned(),
-            message: format!("{stream_name} forwarder panicked"),
-        })?,
-        None => Ok(String::new()),
+/// By default output goes to the terminal and only the last
+/// [`DEFAULT_CAPTURE_BYTES`](super::DEFAULT_CAPTURE_BYTES) of each stream are
+/// kept; full capture is opt-in through [`Self::with_capture`].
+#[derive(Clone, Debug, Default)]
+pub struct StreamingCommandRunner {
+    sink: OutputSink,
+    capture: Capture,
+    redactor: Redactor,
+}
+
+impl StreamingCommandRunner {
+    /// Sets where streamed output is written.
+    #[must_use]
+    pub fn with_sink(mut self, sink: OutputSink) -> Self {
+        self.sink = sink;
+        self
+    }
+
+    /// Sets how much of each stream is returned in the command output.
+    #[must_use]
+    pub const fn with_capture(mut self, capture: Capture) -> Self {
+        self.capture = capture;
+        self
+    }
+
+    /// Masks the secrets of `redactor` in streamed and captured output.
+    ///
+    /// Output of [`AsyncCommandRunner::run_interactive`] goes straight to the
+    /// terminal and is not redacted.
+    #[must_use]
+    pub fn with_redactor(mut self, redactor: Redactor) -> Self {
+        self.redactor = redactor;
+        self
+    }
+
+    /// Returns where streamed output is written.
+    #[must_use]
+    pub const fn sink(&self) -> &OutputSink {
+        &self.sink
+    }
+
+    /// Returns how much of each stream is captured.
+    #[must_use]
+    pub const fn capture(&self) -> Capture {
+        self.capture
+    }
+
+    /// Returns the secrets masked in output.
+    #[must_use]
+    pub const fn redactor(&self) -> &Redactor {
+        &self.redactor
+    }
+
+    fn redact(&self, mut output: CommandOutput) -> CommandOutput {
+        output.stdout = self.redactor.redact(&output.stdout);
+        output.stderr = self.redactor.redact(&output.stderr);
+        output
     }
 }
 
-/// Command runner that streams subprocess stdout/stderr while capturing them.
-#[derive(Clone, Debug, Default)]
-pub struct StreamingCommandRunner;
+impl AsyncCommandRunner for StreamingCommandRunner {
+    async fn run_with_input(
+        &self,
+        program: &str,
+        args: &[OsString],
+        input: &[u8],
+    ) -> Result<CommandOutput, SyncError> {
+        AsyncCommandRunner::run_with_input(&ProcessCommandRunner, program, args, input)
+            .await
+            .map(|output| self.redact(output))
+    }
 
-impl CommandRunner for StreamingCommandRunner {
-    fn run(&self, program: &str, args: &[OsString]) -> Result<CommandOutput, SyncError> {
-        let mut child = Command::new(program)
-            .args(args)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-            .map_err(|err| SyncError::Spawn {
-                program: program.to_owned(),
-                message: err.to_string(),
-            })?;
-
-        let stdout_handle = child
-            .stdout
-            .take()
-            .map(|stdout| forward_stream(stdout, false, program));
-        let stderr_handle = child
-            .stderr
-            .take()
-            .map(|stderr| forward_stream(stderr, true, program));
-
-        let status = child.wait().map_err(|err| SyncError::Spawn {
-            program: program.to_owned(),
-            message: err.to_string(),
-        })?;
+    async fn run_interactive(
+        &self,
+        program: &str,
+        args: &[OsString],
+    ) -> Result<CommandOutput, SyncError> {
+        run_attached(program, args).await
+    }
+
+    async fn run_quiet(
+        &self,
+        program: &str,
+        args: &[OsString],
+    ) -> Result<CommandOutput, SyncError> {
+        AsyncCommandRunner::run(&ProcessCommandRunner, program, args)
+            .await
+            .map(|output| self.redact(output))
+    }
 
-        let stdout = join_forwarder(stdout_handle, program, "stdout")?;
-        let stderr = join_forwarder(stderr_handle, program, "stderr")?;
+    async fn run(&self, program: &str, args: &[OsString]) -> Result<CommandOutput, SyncError> {
+        let mut child = spawn_piped(program, args)?;
+        let stdout_forward = sink::forward(child.stdout.take(), OutputStream::Stdout, self);
+        let stderr_forward = sink::forward(child.stderr.take(), OutputStream::Stderr, self);
+        let (status, stdout, stderr) =
+            tokio::try_join!(child.wait(), stdout_forward, stderr_forward)
+                .map_err(|err| spawn_error(program, &err))?;
 
         Ok(CommandOutput {
             code: status.code(),
@@ -0,0 +1,46 @@
+//! Private files written to the instance through `ssh`'s standard input.
+//!
+//! Forwarded environment values and `mriya run --script` files must not pass
+//! through a command line, so the [`Syncer`](super::Syncer) streams them into
+//! randomly named files in the SSH user's home directory before the remote
+//! command starts. The command deletes them again: the environment file once
+//! it is sourced, and the script when the remote shell exits.
+
+use uuid::Uuid;
+
+/// Variable holding the path of the uploaded script in the remote shell.
+pub const SCRIPT_VAR: &str = "MRIYA_SCRIPT";
+
+/// Returns a fresh file name for `kind`, relative to `$HOME`.
+pub(super) fn private_file(kind: &str) -> String {
+    format!(".mriya-{kind}-{}", Uuid::new_v4().simple())
+}
+
+/// Shell command that stores standard input in `file`, readable only by the
+/// SSH user and refusing to follow an existing file or symlink.
+pub(super) fn upload_command(file: &str) -> String {
+    format!("umask 077 && set -C && cat > \"$HOME\"/{file}")
+}
+
+/// Shell fragment that makes the uploaded script `file` executable, removes
+/// it when the remote shell exits, and exposes its path as [`SCRIPT_VAR`].
+pub(super) fn script_setup(file: &str) -> String {
+    format!(
+        "chmod 700 \"$HOME\"/{file}; trap 'rm -f \"$HOME\"/{file}' EXIT; \
+         {SCRIPT_VAR}=\"$HOME\"/{file}; "
+    )
+}
+
+/// Remote command that runs the uploaded script with `args`, already
+/// shell-escaped.
+///
+/// The script is executed directly, so its shebang selects the interpreter;
+/// a script without one runs under the remote `sh`.
+#[must_use]
+pub fn script_command(args: &str) -> String {
+    if args.is_empty() {
+        format!("\"${SCRIPT_VAR}\"")
+    } else {
+        format!("\"${SCRIPT_VAR}\" {args}")
+    }
+}
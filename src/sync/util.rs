@@ -1,5 +1,10 @@
 //! Path manipulation and shell operation utilities.
 
+use std::ffi::OsString;
+
+use super::config::SyncError;
+use super::types::{AsyncCommandRunner, CommandOutput};
+
 /// Expands a leading `~/` prefix to the user's home directory.
 ///
 /// If the `HOME` environment variable is not set, the function returns the
@@ -24,3 +29,30 @@ pub fn expand_tilde(path: &str) -> String {
     }
     path.to_owned()
 }
+
+/// Runs a local helper quietly and maps a non-zero exit into
+/// [`SyncError::CommandFailure`].
+pub(super) async fn run_checked<R: AsyncCommandRunner>(
+    runner: &R,
+    program: &str,
+    args: &[OsString],
+) -> Result<CommandOutput, SyncError> {
+    let output = runner.run_quiet(program, args).await?;
+    if output.is_success() {
+        return Ok(output);
+    }
+    Err(command_failure(program, output))
+}
+
+/// Builds the error reported when `program` exits unsuccessfully.
+pub(super) fn command_failure(program: &str, output: CommandOutput) -> SyncError {
+    let status_text = output
+        .code
+        .map_or_else(|| String::from("unknown"), |code| code.to_string());
+    SyncError::CommandFailure {
+        program: program.to_owned(),
+        status: output.code,
+        status_text,
+        stderr: output.stderr,
+    }
+}
@@ -0,0 +1,175 @@
+//! `mriya sync --dry-run` and the pre-provisioning sync checks.
+//!
+//! Both run rsync in dry-run mode against an empty local directory, which is
+//! what a freshly provisioned instance looks like, and report the files and
+//! bytes it would send; in the `git-fetch` mode they report the patch.
+//! Commands that provision call [`preflight`] so a sync carrying likely
+//! secrets, with `--strict-secrets`, or one larger than `max_sync_bytes` is
+//! refused before an instance is created.
+
+use std::io::{self, Write};
+
+use camino::Utf8Path;
+use mriya::sync::{AsyncCommandRunner, SecretFinding};
+use mriya::{ProcessCommandRunner, SyncConfig, SyncStats, Syncer};
+
+use crate::CliError;
+use crate::cli::SyncCommand;
+
+/// Number of files and directories listed in a report.
+const REPORT_LIMIT: usize = 10;
+
+/// Binary units used when formatting sizes, largest first.
+const UNITS: [(&str, u64); 3] = [("GiB", 1 << 30), ("MiB", 1 << 20), ("KiB", 1 << 10)];
+
+/// Which pre-provisioning checks report or fail.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Preflight {
+    /// Write the dry-run report to stderr.
+    pub(crate) sync_stats: bool,
+    /// Fail, rather than warn, when the sync would send likely secrets.
+    pub(crate) strict_secrets: bool,
+}
+
+/// Executes `mriya sync --dry-run`, printing the report to stdout.
+///
+/// Fails after printing when the sync exceeds `max_sync_bytes`, or carries
+/// likely secrets with `--strict-secrets`.
+pub(crate) async fn exec_sync(args: &SyncCommand) -> Result<i32, CliError> {
+    let mut sync_config =
+        SyncConfig::load_without_cli_args().map_err(|err| CliError::Config(err.to_string()))?;
+    crate::apply_sync_mode(&mut sync_config, args.sync_mode.as_deref())?;
+    let syncer = Syncer::new(sync_config, ProcessCommandRunner)
+        .map_err(|err| CliError::Sync(err.to_string()))?;
+    let source = crate::current_dir()?;
+    let stats = dry_run(&syncer, &source).await?;
+    write_report(&mut io::stdout().lock(), &stats).ok();
+    check_secrets(&syncer, &source, &stats, args.strict_secrets)?;
+    check_size_limit(&syncer, &stats)?;
+    Ok(0)
+}
+
+/// Checks what the sync would send before provisioning.
+///
+/// Writes the dry-run report to stderr when requested, warns about likely
+/// secrets or fails on them in strict mode, and fails when the sync exceeds
+/// `max_sync_bytes`.
+pub(crate) async fn preflight<R: AsyncCommandRunner>(
+    syncer: &Syncer<R>,
+    options: Preflight,
+) -> Result<(), CliError> {
+    let source = crate::current_dir()?;
+    let stats = dry_run(syncer, &source).await?;
+    if options.sync_stats {
+        write_report(&mut io::stderr().lock(), &stats).ok();
+    }
+    check_secrets(syncer, &source, &stats, options.strict_secrets)?;
+    check_size_limit(syncer, &stats)
+}
+
+async fn dry_run<R: AsyncCommandRunner>(
+    syncer: &Syncer<R>,
+    source: &Utf8Path,
+) -> Result<SyncStats, CliError> {
+    syncer
+        .dry_run(source)
+        .await
+        .map_err(|err| CliError::Sync(err.to_string()))
+}
+
+fn check_secrets<R: AsyncCommandRunner>(
+    syncer: &Syncer<R>,
+    source: &Utf8Path,
+    stats: &SyncStats,
+    strict: bool,
+) -> Result<(), CliError> {
+    let findings = syncer
+        .scan_secrets(source, stats)
+        .map_err(|err| CliError::Sync(err.to_string()))?;
+    if findings.is_empty() {
+        return Ok(());
+    }
+    write_secret_warnings(&mut io::stderr().lock(), &findings).ok();
+    if strict {
+        return Err(CliError::Sync(format!(
+            "refusing to sync {} likely secret files (--strict-secrets)",
+            findings.len()
+        )));
+    }
+    Ok(())
+}
+
+fn check_size_limit<R: AsyncCommandRunner>(
+    syncer: &Syncer<R>,
+    stats: &SyncStats,
+) -> Result<(), CliError> {
+    syncer
+        .check_size_limit(stats)
+        .map_err(|err| CliError::Sync(err.to_string()))
+}
+
+/// Writes the file count, total size, largest files, and top directories.
+pub(crate) fn write_report(out: &mut impl Write, stats: &SyncStats) -> io::Result<()> {
+    writeln!(
+        out,
+        "sync would send {} files, {}",
+        stats.file_count(),
+        format_bytes(stats.total_bytes)
+    )?;
+    let largest = stats.largest_files(REPORT_LIMIT);
+    if !largest.is_empty() {
+        writeln!(out, "largest files:")?;
+        for file in largest {
+            writeln!(out, "  {:>10}  {}", format_bytes(file.bytes), file.path)?;
+        }
+    }
+    let directories = stats.top_directories(REPORT_LIMIT);
+    if !directories.is_empty() {
+        writeln!(out, "top directories:")?;
+        for dir in directories {
+            writeln!(
+                out,
+                "  {:>10}  {} ({} files)",
+                format_bytes(dir.bytes),
+                dir.path,
+                dir.files
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes one warning per likely secret, followed by how to silence them.
+pub(crate) fn write_secret_warnings(
+    out: &mut impl Write,
+    findings: &[SecretFinding],
+) -> io::Result<()> {
+    for finding in findings {
+        writeln!(
+            out,
+            "warning: {} looks like a secret ({})",
+            finding.path, finding.reason
+        )?;
+    }
+    writeln!(
+        out,
+        "hint: gitignore or exclude these files, or add them to [sync] secret_allowlist"
+    )
+}
+
+/// Formats `bytes` in the largest binary unit it fills, to one decimal place.
+pub(crate) fn format_bytes(bytes: u64) -> String {
+    UNITS.iter().find(|(_, size)| bytes >= *size).map_or_else(
+        || format!("{bytes} B"),
+        |(unit, size)| {
+            let whole = bytes.checked_div(*size).unwrap_or_default();
+            let tenths = bytes
+                .checked_rem(*size)
+                .unwrap_or_default()
+                .saturating_mul(10)
+                .checked_div(*size)
+                .unwrap_or_default();
+            format!("{whole}.{tenths} {unit}")
+        },
+    )
+}
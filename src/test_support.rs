@@ -12,9 +12,16 @@ use tokio::sync::{Mutex, MutexGuard};
 #[derive(Clone, Debug, Default)]
 pub struct ScriptedRunner {
     responses:
-        std::rc::Rc<std::cell::RefCell<std::collections::VecDeque<crate::sync::CommandOutput>>>,
-    invocations: std::rc::Rc<std::cell::RefCell<Vec<CommandInvocation>>>,
-    next_error: std::rc::Rc<std::cell::RefCell<Option<ScopedError>>>,
+        std::sync::Arc<std::sync::Mutex<std::collections::VecDeque<crate::sync::CommandOutput>>>,
+    invocations: std::sync::Arc<std::sync::Mutex<Vec<CommandInvocation>>>,
+    next_error: std::sync::Arc<std::sync::Mutex<Option<ScopedError>>>,
+}
+
+/// Locks `mutex`, recovering the data if a panicking test poisoned it.
+fn lock<T>(mutex: &std::sync::Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
 }
 
 #[derive(Clone, Debug)]
@@ -30,6 +37,8 @@ pub struct CommandInvocation {
     pub program: String,
     /// Arguments passed to the program.
     pub args: Vec<OsString>,
+    /// Bytes written to the program's standard input, if any.
+    pub input: Vec<u8>,
 }
 
 impl CommandInvocation {
@@ -57,51 +66,43 @@ impl ScriptedRunner {
     /// Returns a snapshot of all invocations recorded so far.
     #[must_use]
     pub fn invocations(&self) -> Vec<CommandInvocation> {
-        self.invocations.borrow().clone()
+        lock(&self.invocations).clone()
     }
 
     /// Pushes a successful exit status.
     pub fn push_success(&self) {
-        self.responses
-            .borrow_mut()
-            .push_back(crate::sync::CommandOutput {
-                code: Some(0),
-                stdout: String::new(),
-                stderr: String::new(),
-            });
+        lock(&self.responses).push_back(crate::sync::CommandOutput {
+            code: Some(0),
+            stdout: String::new(),
+            stderr: String::new(),
+        });
     }
 
     /// Pushes a specific exit code.
     pub fn push_exit_code(&self, code: i32) {
-        self.responses
-            .borrow_mut()
-            .push_back(crate::sync::CommandOutput {
-                code: Some(code),
-                stdout: String::new(),
-                stderr: String::new(),
-            });
+        lock(&self.responses).push_back(crate::sync::CommandOutput {
+            code: Some(code),
+            stdout: String::new(),
+            stderr: String::new(),
+        });
     }
 
     /// Pushes a failing exit code with stderr text.
     pub fn push_failure(&self, code: i32) {
-        self.responses
-            .borrow_mut()
-            .push_back(crate::sync::CommandOutput {
-                code: Some(code),
-                stdout: String::new(),
-                stderr: String::from("simulated failure"),
-            });
+        lock(&self.responses).push_back(crate::sync::CommandOutput {
+            code: Some(code),
+            stdout: String::new(),
+            stderr: String::from("simulated failure"),
+        });
     }
 
     /// Pushes a response with no exit code to simulate abnormal termination.
     pub fn push_missing_exit_code(&self) {
-        self.responses
-            .borrow_mut()
-            .push_back(crate::sync::CommandOutput {
-                code: None,
-                stdout: String::new(),
-                stderr: String::new(),
-            });
+        lock(&self.responses).push_back(crate::sync::CommandOutput {
+            code: None,
+            stdout: String::new(),
+            stderr: String::new(),
+        });
     }
 
     /// Pushes an explicit command output response.
@@ -111,18 +112,16 @@ impl ScriptedRunner {
         stdout: impl Into<String>,
         stderr: impl Into<String>,
     ) {
-        self.responses
-            .borrow_mut()
-            .push_back(crate::sync::CommandOutput {
-                code,
-                stdout: stdout.into(),
-                stderr: stderr.into(),
-            });
+        lock(&self.responses).push_back(crate::sync::CommandOutput {
+            code,
+            stdout: stdout.into(),
+            stderr: stderr.into(),
+        });
     }
 
     /// Forces the next invocation of `program` to return the provided error.
     pub fn fail_next_for(&self, program: &str, error: crate::sync::SyncError) {
-        *self.next_error.borrow_mut() = Some(ScopedError {
+        *lock(&self.next_error) = Some(ScopedError {
             program: program.to_owned(),
             error,
         });
@@ -140,19 +139,21 @@ impl ScriptedRunner {
     }
 }
 
-impl crate::sync::CommandRunner for ScriptedRunner {
-    fn run(
+impl ScriptedRunner {
+    fn respond(
         &self,
         program: &str,
-        args: &[std::ffi::OsString],
+        args: &[OsString],
+        input: &[u8],
     ) -> Result<crate::sync::CommandOutput, crate::sync::SyncError> {
-        self.invocations.borrow_mut().push(CommandInvocation {
+        lock(&self.invocations).push(CommandInvocation {
             program: program.to_owned(),
             args: args.to_vec(),
+            input: input.to_vec(),
         });
 
         let error_to_return = {
-            let mut pending = self.next_error.borrow_mut();
+            let mut pending = lock(&self.next_error);
             match pending.as_ref() {
                 Some(scoped) if scoped.program == program => {
                     pending.take().map(|taken| taken.error)
@@ -165,8 +166,7 @@ impl crate::sync::CommandRunner for ScriptedRunner {
             return Err(error);
         }
 
-        self.responses
-            .borrow_mut()
+        lock(&self.responses)
             .pop_front()
             .ok_or_else(|| crate::sync::SyncError::Spawn {
                 program: program.to_owned(),
@@ -175,6 +175,25 @@ impl crate::sync::CommandRunner for ScriptedRunner {
     }
 }
 
+impl crate::sync::AsyncCommandRunner for ScriptedRunner {
+    async fn run(
+        &self,
+        program: &str,
+        args: &[std::ffi::OsString],
+    ) -> Result<crate::sync::CommandOutput, crate::sync::SyncError> {
+        self.respond(program, args, &[])
+    }
+
+    async fn run_with_input(
+        &self,
+        program: &str,
+        args: &[std::ffi::OsString],
+        input: &[u8],
+    ) -> Result<crate::sync::CommandOutput, crate::sync::SyncError> {
+        self.respond(program, args, input)
+    }
+}
+
 /// Global mutex used to serialize environment mutation in tests.
 pub static ENV_LOCK: Mutex<()> = Mutex::const_new(());
 
@@ -244,22 +263,27 @@ fn json_tagged_resources(items: &[(&str, &str, &[&str])]) -> String {
         .join(",")
 }
 
-/// Produces a minimal JSON payload matching `scw instance server list -o json`.
+/// Produces a minimal JSON payload matching `scw <resource> list -o json`.
+///
+/// `key` is the top-level field Scaleway uses for the resource list, for
+/// example `ips` or `security_groups`.
 #[must_use]
-pub fn json_servers(servers: &[(&str, &str, &[&str])]) -> String {
-    let items = json_tagged_resources(servers);
+pub fn json_resources(key: &str, resources: &[(&str, &str, &[&str])]) -> String {
+    let items = json_tagged_resources(resources);
     format!(
-        "{{\"servers\":[{items}],\"total_count\":{}}}",
-        servers.len()
+        "{{\"{key}\":[{items}],\"total_count\":{}}}",
+        resources.len()
     )
 }
 
+/// Produces a minimal JSON payload matching `scw instance server list -o json`.
+#[must_use]
+pub fn json_servers(servers: &[(&str, &str, &[&str])]) -> String {
+    json_resources("servers", servers)
+}
+
 /// Produces a minimal JSON payload matching `scw block volume list -o json`.
 #[must_use]
 pub fn json_volumes(volumes: &[(&str, &str, &[&str])]) -> String {
-    let items = json_tagged_resources(volumes);
-    format!(
-        "{{\"volumes\":[{items}],\"total_count\":{}}}",
-        volumes.len()
-    )
+    json_resources("volumes", volumes)
 }
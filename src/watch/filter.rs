@@ -0,0 +1,149 @@
+//! Decides which local changes are visible to the workspace sync.
+
+use std::collections::HashMap;
+use std::fs;
+
+use camino::{Utf8Path, Utf8PathBuf};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+
+use crate::sync::{FilterRule, MRIYA_IGNORE_FILE, SyncConfig, config_rules, mriyaignore_rules};
+
+const GITIGNORE: &str = ".gitignore";
+const GIT_DIR: &str = ".git";
+
+/// Matches paths against the rules `rsync --filter=':- .gitignore'
+/// --exclude .git` applies when syncing the workspace.
+///
+/// Each `.gitignore` applies to its own directory and everything below it, a
+/// path is excluded when any of its ancestors is excluded, and `.git`
+/// directories and submodule `.git` files are never synced. rsync reads every
+/// `.gitignore` line as an exclude rule, so `!` negations do not re-include
+/// paths here either.
+///
+/// `[sync] include`/`exclude` rules and `.mriyaignore` are checked first and
+/// the first matching rule decides, as with the rsync arguments they become.
+#[derive(Debug)]
+pub struct WorkspaceFilter {
+    root: Utf8PathBuf,
+    gitignores: HashMap<Utf8PathBuf, Gitignore>,
+    config_rules: Vec<FilterRule>,
+    rules: Option<Vec<(FilterRule, Gitignore)>>,
+}
+
+impl WorkspaceFilter {
+    /// Creates a filter for the workspace rooted at `root`.
+    #[must_use]
+    pub fn new(root: &Utf8Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            gitignores: HashMap::new(),
+            config_rules: Vec::new(),
+            rules: None,
+        }
+    }
+
+    /// Applies the include and exclude rules from `config`.
+    #[must_use]
+    pub fn with_config(mut self, config: &SyncConfig) -> Self {
+        self.config_rules = config_rules(config);
+        self.rules = None;
+        self
+    }
+
+    /// Returns `true` when a change to `path` would not reach the remote
+    /// workspace.
+    ///
+    /// Paths outside the workspace are always ignored. Changing a
+    /// `.gitignore` file discards the cached rules for its directory, and
+    /// changing `.mriyaignore` discards the layered rules.
+    pub fn is_ignored(&mut self, path: &Utf8Path) -> bool {
+        let Ok(relative) = path.strip_prefix(&self.root) else {
+            return true;
+        };
+        if path.file_name() == Some(GITIGNORE)
+            && let Some(parent) = path.parent()
+        {
+            self.gitignores.remove(parent);
+        }
+        if relative.as_str() == MRIYA_IGNORE_FILE {
+            self.rules = None;
+        }
+
+        let components = relative.components().collect::<Vec<_>>();
+        let mut current = self.root.clone();
+        for (index, component) in components.iter().enumerate() {
+            current.push(component);
+            let is_dir = index + 1 < components.len() || current.is_dir();
+            if component.as_str() == GIT_DIR {
+                return true;
+            }
+            match self.first_rule(&current, is_dir) {
+                Some(FilterRule::Exclude(_)) => return true,
+                Some(FilterRule::Include(_)) => continue,
+                None => {}
+            }
+            if self.excluded(&current, is_dir) {
+                return true;
+            }
+        }
+        components.is_empty()
+    }
+
+    fn first_rule(&mut self, path: &Utf8Path, is_dir: bool) -> Option<&FilterRule> {
+        let root = &self.root;
+        let config = &self.config_rules;
+        self.rules
+            .get_or_insert_with(|| compile_rules(root, config))
+            .iter()
+            .find(|(_, matcher)| matcher.matched(path, is_dir).is_ignore())
+            .map(|(rule, _)| rule)
+    }
+
+    fn excluded(&mut self, path: &Utf8Path, is_dir: bool) -> bool {
+        let Some(parent) = path.parent() else {
+            return false;
+        };
+        let root = self.root.clone();
+        parent
+            .ancestors()
+            .take_while(|dir| dir.starts_with(&root))
+            .any(|dir| self.rules_for(dir).matched(path, is_dir).is_ignore())
+    }
+
+    fn rules_for(&mut self, dir: &Utf8Path) -> &Gitignore {
+        self.gitignores
+            .entry(dir.to_path_buf())
+            .or_insert_with(|| load_rules(dir))
+    }
+}
+
+fn load_rules(dir: &Utf8Path) -> Gitignore {
+    let path = dir.join(GITIGNORE);
+    let mut builder = GitignoreBuilder::new(dir);
+    if let Ok(contents) = fs::read_to_string(&path) {
+        for line in contents.lines().filter(|line| !line.starts_with('!')) {
+            // rsync skips patterns it cannot parse, so a bad line must not
+            // discard the rest of the file.
+            builder.add_line(Some(path.clone().into()), line).ok();
+        }
+    }
+    builder.build().unwrap_or_else(|_| Gitignore::empty())
+}
+
+/// Builds one matcher per layered rule so the first match can be found.
+///
+/// An unreadable `.mriyaignore` contributes no rules here; the sync itself
+/// reports the error.
+fn compile_rules(root: &Utf8Path, config: &[FilterRule]) -> Vec<(FilterRule, Gitignore)> {
+    let file_rules = mriyaignore_rules(root).unwrap_or_default();
+    config
+        .iter()
+        .chain(&file_rules)
+        .filter_map(|rule| {
+            let mut builder = GitignoreBuilder::new(root);
+            builder.add_line(None, rule.pattern()).ok()?;
+            let matcher = builder.build().ok()?;
+            Some((rule.clone(), matcher))
+        })
+        .collect()
+}
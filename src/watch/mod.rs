@@ -0,0 +1,346 @@
+//! Re-runs a command on a kept-alive instance whenever the workspace changes.
+//!
+//! [`watch_workspace`] reports every change the workspace sync would carry,
+//! honouring `.gitignore` and the sync's include and exclude rules as the
+//! rsync filter does. [`WatchLoop`] runs
+//! the command once on a [`Session`], then waits for changes, debounces them,
+//! and re-syncs and re-runs. A change that arrives while the command is still
+//! running cancels it first by signalling its remote process group. The
+//! session's instance is destroyed when the loop exits.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use camino::{Utf8Path, Utf8PathBuf};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use thiserror::Error;
+use tokio::sync::mpsc::{UnboundedReceiver, unbounded_channel};
+use tokio::task::JoinHandle;
+use tokio::time::timeout;
+
+use crate::backend::{Backend, InstanceNetworking};
+use crate::run::{RunError, RunOrchestrator};
+use crate::session::Session;
+use crate::sync::{AsyncCommandRunner, Redactor, RemoteCommandOutput, SyncConfig};
+
+mod filter;
+
+pub use filter::WorkspaceFilter;
+
+/// Quiet period that must pass after a change before the command re-runs.
+const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Interval between cancellation attempts while a cancelled run winds down.
+const CANCEL_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// File on the instance recording the process group of the running command.
+const REMOTE_PID_FILE: &str = "/tmp/mriya-watch.pid";
+
+/// Errors raised while watching the workspace.
+#[derive(Debug, Error)]
+pub enum WatchError {
+    /// Raised when the file system watcher cannot be set up.
+    #[error("failed to watch {path}: {message}")]
+    Watch {
+        /// Directory that could not be watched.
+        path: Utf8PathBuf,
+        /// Human-readable error message.
+        message: String,
+    },
+}
+
+impl WatchError {
+    /// Masks the secrets of `redactor` in the error's message.
+    #[must_use]
+    pub fn redact(self, redactor: &Redactor) -> Self {
+        match self {
+            Self::Watch { path, message } => Self::Watch {
+                path,
+                message: redactor.redact(&message),
+            },
+        }
+    }
+}
+
+/// Watches `source` recursively and sends every changed path the workspace
+/// sync would carry.
+///
+/// Changes stop being reported once the returned watcher is dropped.
+///
+/// # Errors
+///
+/// Returns [`WatchError::Watch`] when the watcher cannot be created.
+pub fn watch_workspace(
+    source: &Utf8Path,
+    config: &SyncConfig,
+) -> Result<(RecommendedWatcher, UnboundedReceiver<Utf8PathBuf>), WatchError> {
+    let watch_error = |err: notify::Error| WatchError::Watch {
+        path: source.to_path_buf(),
+        message: err.to_string(),
+    };
+    let (sender, receiver) = unbounded_channel();
+    let mut filter = WorkspaceFilter::new(source).with_config(config);
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+        let Ok(changed) = event else { return };
+        if matches!(changed.kind, EventKind::Access(_)) {
+            return;
+        }
+        for path in changed.paths {
+            if let Ok(utf8) = Utf8PathBuf::from_path_buf(path)
+                && !filter.is_ignored(&utf8)
+            {
+                sender.send(utf8).ok();
+            }
+        }
+    })
+    .map_err(watch_error)?;
+    watcher
+        .watch(source.as_std_path(), RecursiveMode::Recursive)
+        .map_err(watch_error)?;
+    Ok((watcher, receiver))
+}
+
+/// What happened to one run of the watched command.
+#[derive(Debug)]
+pub enum WatchRun<'a, E>
+where
+    E: std::error::Error + 'static,
+{
+    /// The command ran to completion.
+    Completed(&'a RemoteCommandOutput),
+    /// The command was cancelled because the workspace changed.
+    Cancelled,
+    /// Syncing or starting the command failed; the instance is kept.
+    Failed(&'a RunError<E>),
+}
+
+/// Callback receiving the outcome of each run.
+pub type WatchReporter<E> = Box<dyn Fn(&WatchRun<'_, E>) + Send + Sync>;
+
+type ExecTask<E> = JoinHandle<(Session, Result<RemoteCommandOutput, RunError<E>>)>;
+
+/// Re-runs a command on a session whenever the workspace changes.
+pub struct WatchLoop<B, R>
+where
+    B: Backend,
+    B::Error: std::error::Error + 'static,
+    R: AsyncCommandRunner,
+{
+    orchestrator: Arc<RunOrchestrator<B, R>>,
+    source: Utf8PathBuf,
+    remote_command: String,
+    debounce: Duration,
+    reporter: WatchReporter<B::Error>,
+}
+
+impl<B, R> WatchLoop<B, R>
+where
+    B: Backend + Send + Sync + 'static,
+    B::Error: std::fmt::Display + Send + Sync + std::error::Error + 'static,
+    R: AsyncCommandRunner + 'static,
+{
+    /// Creates a loop that syncs `source` and runs `remote_command`.
+    #[must_use]
+    pub fn new(
+        orchestrator: Arc<RunOrchestrator<B, R>>,
+        source: &Utf8Path,
+        remote_command: &str,
+    ) -> Self {
+        Self {
+            orchestrator,
+            source: source.to_path_buf(),
+            remote_command: remote_command.to_owned(),
+            debounce: DEFAULT_DEBOUNCE,
+            reporter: Box::new(|_| {}),
+        }
+    }
+
+    /// Overrides how long the workspace must stay quiet before re-running.
+    #[must_use]
+    pub const fn with_debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
+    /// Sets the callback that receives the outcome of each run.
+    #[must_use]
+    pub fn with_reporter(mut self, reporter: WatchReporter<B::Error>) -> Self {
+        self.reporter = reporter;
+        self
+    }
+
+    /// Runs the command, then re-runs it after each batch of changes until
+    /// `shutdown` resolves or `changes` closes, and finally destroys the
+    /// session's instance.
+    ///
+    /// Failed syncs and runs are reported and the loop keeps watching.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RunError::Teardown`] when the instance cannot be destroyed.
+    ///
+    /// # Panics
+    ///
+    /// Propagates a panic raised while running the command.
+    pub async fn run(
+        &self,
+        session: Session,
+        mut changes: UnboundedReceiver<Utf8PathBuf>,
+        shutdown: impl Future<Output = ()>,
+    ) -> Result<(), RunError<B::Error>> {
+        tokio::pin!(shutdown);
+        let mut current = session;
+        loop {
+            let (next_session, next) = self.run_once(current, &mut changes, &mut shutdown).await;
+            current = next_session;
+            if next == Next::Stop {
+                break;
+            }
+            self.settle(&mut changes).await;
+        }
+        self.orchestrator.stop(current).await
+    }
+
+    /// Runs the command once and decides what to do next.
+    ///
+    /// A change or shutdown that arrives mid-run cancels the command; once it
+    /// has finished, this waits for the next change or shutdown instead.
+    #[expect(
+        clippy::integer_division_remainder_used,
+        reason = "tokio::select! expands to a modulo when picking a branch"
+    )]
+    async fn run_once<S>(
+        &self,
+        session: Session,
+        changes: &mut UnboundedReceiver<Utf8PathBuf>,
+        shutdown: &mut Pin<&mut S>,
+    ) -> (Session, Next)
+    where
+        S: Future<Output = ()>,
+    {
+        let networking = session.networking.clone();
+        let mut task = self.spawn_exec(session);
+        let interrupted = tokio::select! {
+            biased;
+            () = shutdown.as_mut() => Next::Stop,
+            change = changes.recv() => Next::after(change.as_ref()),
+            joined = &mut task => {
+                let (finished, result) = join(joined);
+                self.report(&result);
+                return (finished, next_change(changes, shutdown).await);
+            }
+        };
+        let cancelled = self.cancel(task, &networking).await;
+        (self.reporter)(&WatchRun::Cancelled);
+        (cancelled, interrupted)
+    }
+
+    fn spawn_exec(&self, mut session: Session) -> ExecTask<B::Error> {
+        let orchestrator = Arc::clone(&self.orchestrator);
+        let source = self.source.clone();
+        let command = cancellable_command(&self.remote_command);
+        tokio::spawn(async move {
+            let result = orchestrator.exec(&mut session, &source, &command).await;
+            (session, result)
+        })
+    }
+
+    /// Signals the remote process group until the run finishes.
+    ///
+    /// The run may still be syncing when the first signal is sent, so the
+    /// signal is repeated until the task returns.
+    async fn cancel(
+        &self,
+        mut task: ExecTask<B::Error>,
+        networking: &InstanceNetworking,
+    ) -> Session {
+        loop {
+            // A failed signal is retried on the next pass.
+            self.orchestrator
+                .syncer()
+                .run_remote_raw(networking, &cancel_command())
+                .await
+                .ok();
+            if let Ok(joined) = timeout(CANCEL_RETRY_INTERVAL, &mut task).await {
+                return join(joined).0;
+            }
+        }
+    }
+
+    /// Waits until no change has arrived for the debounce period.
+    async fn settle(&self, changes: &mut UnboundedReceiver<Utf8PathBuf>) {
+        while let Ok(Some(_)) = timeout(self.debounce, changes.recv()).await {}
+    }
+
+    fn report(&self, result: &Result<RemoteCommandOutput, RunError<B::Error>>) {
+        match result {
+            Ok(output) => (self.reporter)(&WatchRun::Completed(output)),
+            Err(err) => (self.reporter)(&WatchRun::Failed(err)),
+        }
+    }
+}
+
+/// Whether the loop re-runs the command or exits.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Next {
+    Rerun,
+    Stop,
+}
+
+impl Next {
+    const fn after(change: Option<&Utf8PathBuf>) -> Self {
+        match change {
+            Some(_) => Self::Rerun,
+            None => Self::Stop,
+        }
+    }
+}
+
+#[expect(
+    clippy::integer_division_remainder_used,
+    reason = "tokio::select! expands to a modulo when picking a branch"
+)]
+async fn next_change<S>(
+    changes: &mut UnboundedReceiver<Utf8PathBuf>,
+    shutdown: &mut Pin<&mut S>,
+) -> Next
+where
+    S: Future<Output = ()>,
+{
+    tokio::select! {
+        biased;
+        () = shutdown.as_mut() => Next::Stop,
+        change = changes.recv() => Next::after(change.as_ref()),
+    }
+}
+
+fn join<T>(joined: Result<T, tokio::task::JoinError>) -> T {
+    joined.unwrap_or_else(|err| std::panic::resume_unwind(err.into_panic()))
+}
+
+/// Wraps `remote_command` so its process group can be signalled.
+///
+/// SSH starts each remote command in a new session, so the shell's PID is
+/// also the process group of everything the command spawns.
+#[must_use]
+pub fn cancellable_command(remote_command: &str) -> String {
+    format!(
+        "echo $$ > {REMOTE_PID_FILE} && {{ {remote_command}; }}; \
+         mriya_status=$?; rm -f {REMOTE_PID_FILE}; exit $mriya_status"
+    )
+}
+
+/// Command that terminates the process group recorded by
+/// [`cancellable_command`].
+#[must_use]
+pub fn cancel_command() -> String {
+    format!(
+        "kill -TERM -- -\"$(cat {REMOTE_PID_FILE} 2>/dev/null)\" 2>/dev/null; \
+         rm -f {REMOTE_PID_FILE}; true"
+    )
+}
+
+#[cfg(test)]
+mod tests;
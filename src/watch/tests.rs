@@ -0,0 +1,146 @@
+//! Unit tests for workspace change filtering.
+
+use std::fs;
+
+use camino::{Utf8Path, Utf8PathBuf};
+use rstest::{fixture, rstest};
+use tempfile::TempDir;
+
+use super::*;
+use crate::sync::{MRIYA_IGNORE_FILE, SyncConfig, SyncMode};
+
+struct Workspace {
+    root: Utf8PathBuf,
+    _dir: TempDir,
+}
+
+impl Workspace {
+    fn write(&self, relative: &str, contents: &str) -> Utf8PathBuf {
+        let path = self.root.join(relative);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).expect("create parent");
+        }
+        fs::write(&path, contents).expect("write file");
+        path
+    }
+}
+
+#[fixture]
+fn workspace() -> Workspace {
+    let dir = TempDir::new().expect("temp dir");
+    let root = Utf8PathBuf::from_path_buf(dir.path().to_path_buf()).expect("utf-8 path");
+    let workspace = Workspace { root, _dir: dir };
+    workspace.write(".gitignore", "target/\n*.log\n!keep.log\n");
+    workspace.write("nested/.gitignore", "generated.rs\n");
+    workspace
+}
+
+#[rstest]
+#[case("src/main.rs", false)]
+#[case("nested/lib.rs", false)]
+#[case("target/debug/app", true)]
+#[case("build.log", true)]
+#[case("keep.log", true)]
+#[case("nested/generated.rs", true)]
+#[case("generated.rs", false)]
+#[case(".git/index", true)]
+#[case("nested/.git/HEAD", true)]
+#[case("vendor/lib/.git", true)]
+fn filter_matches_rsync_gitignore_rules(
+    workspace: Workspace,
+    #[case] relative: &str,
+    #[case] ignored: bool,
+) {
+    let path = workspace.write(relative, "");
+    let mut filter = WorkspaceFilter::new(&workspace.root);
+    assert_eq!(filter.is_ignored(&path), ignored, "path: {relative}");
+}
+
+#[rstest]
+fn filter_ignores_paths_outside_the_workspace(workspace: Workspace) {
+    let mut filter = WorkspaceFilter::new(&workspace.root);
+    assert!(filter.is_ignored(Utf8Path::new("/elsewhere/file.rs")));
+    assert!(filter.is_ignored(&workspace.root));
+}
+
+#[rstest]
+fn filter_reloads_rules_when_gitignore_changes(workspace: Workspace) {
+    let notes = workspace.write("notes.md", "");
+    let mut filter = WorkspaceFilter::new(&workspace.root);
+    assert!(!filter.is_ignored(&notes));
+
+    let gitignore = workspace.write(".gitignore", "notes.md\n");
+    assert!(!filter.is_ignored(&gitignore));
+    assert!(filter.is_ignored(&notes));
+}
+
+#[rstest]
+#[case(".env.test", false)]
+#[case("target/debug/app", false)]
+#[case("build.log", true)]
+#[case("docs/guide.md", true)]
+#[case("fixtures/large.bin", true)]
+#[case("fixtures/small.json", false)]
+#[case("src/main.rs", false)]
+fn filter_layers_sync_rules_over_gitignore(
+    workspace: Workspace,
+    #[case] relative: &str,
+    #[case] ignored: bool,
+) {
+    workspace.write(".gitignore", "target/\n*.log\n.env.test\n");
+    workspace.write(MRIYA_IGNORE_FILE, "fixtures/*\n!fixtures/small.json\n");
+    let path = workspace.write(relative, "");
+    let config = SyncConfig {
+        rsync_bin: String::from("rsync"),
+        ssh_bin: String::from("ssh"),
+        ssh_user: String::from("ubuntu"),
+        remote_path: String::from("/remote"),
+        ssh_batch_mode: true,
+        ssh_strict_host_key_checking: false,
+        ssh_known_hosts_file: String::from("/dev/null"),
+        ssh_multiplex: false,
+        ssh_identity_file: None,
+        volume_mount_path: String::from("/mriya"),
+        route_build_caches: true,
+        create_cache_directories: true,
+        include: vec![String::from(".env.test"), String::from("target/")],
+        exclude: vec![String::from("docs/")],
+        sync_mode: SyncMode::RsyncFilter,
+        git_bin: String::from("git"),
+        git_include_untracked: false,
+        git_lfs_pull: false,
+        git_remote: String::from("origin"),
+        tar_stream: false,
+        max_sync_bytes: None,
+        secret_patterns: Vec::new(),
+        secret_signatures: Vec::new(),
+        secret_allowlist: Vec::new(),
+    };
+    let mut filter = WorkspaceFilter::new(&workspace.root).with_config(&config);
+    assert_eq!(filter.is_ignored(&path), ignored, "path: {relative}");
+}
+
+#[rstest]
+fn filter_reloads_rules_when_mriyaignore_changes(workspace: Workspace) {
+    let notes = workspace.write("notes.md", "");
+    let mut filter = WorkspaceFilter::new(&workspace.root);
+    assert!(!filter.is_ignored(&notes));
+
+    let ignore_file = workspace.write(MRIYA_IGNORE_FILE, "notes.md\n");
+    assert!(!filter.is_ignored(&ignore_file));
+    assert!(filter.is_ignored(&notes));
+}
+
+#[rstest]
+fn cancellable_command_records_and_clears_the_process_group() {
+    let wrapped = cancellable_command("cargo test");
+    assert!(
+        wrapped.starts_with("echo $$ > /tmp/mriya-watch.pid && { cargo test; };"),
+        "wrapped: {wrapped}"
+    );
+    assert!(
+        wrapped.ends_with("exit $mriya_status"),
+        "wrapped: {wrapped}"
+    );
+    assert!(cancel_command().contains("kill -TERM -- -"));
+}
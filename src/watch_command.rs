@@ -0,0 +1,76 @@
+//! `mriya watch`: a remote `cargo watch`.
+//!
+//! Provisions one instance, runs the command, and re-syncs and re-runs it
+//! whenever a file the sync would carry changes. The instance is destroyed
+//! when the watch receives `SIGINT` or `SIGTERM`.
+
+use std::io::{self, Write};
+use std::sync::Arc;
+use std::time::Duration;
+
+use mriya::{ScalewayBackendError, WatchLoop, WatchRun, watch_workspace};
+use tokio::signal::unix::{SignalKind, signal};
+
+use crate::CliError;
+use crate::cli::WatchCommand;
+use crate::session_command::orchestrator;
+
+/// Executes `mriya watch`.
+#[expect(
+    clippy::integer_division_remainder_used,
+    reason = "tokio::select! expands to a modulo when picking a branch"
+)]
+pub(crate) async fn exec_watch(args: WatchCommand) -> Result<i32, CliError> {
+    crate::validate_command_args(&args.command)?;
+    let remote_command = crate::render_remote_command(&args.command);
+    let source = crate::current_dir()?;
+
+    let signal_error = |err: io::Error| CliError::Config(format!("signal handler: {err}"));
+    let mut interrupt = signal(SignalKind::interrupt()).map_err(signal_error)?;
+    let mut terminate = signal(SignalKind::terminate()).map_err(signal_error)?;
+    let (run_orchestrator, request) = orchestrator()?;
+    // Watch before provisioning so a watcher failure never costs an instance.
+    let (_watcher, changes) = watch_workspace(&source, run_orchestrator.syncer().config())?;
+    let checks = crate::Preflight {
+        strict_secrets: args.strict_secrets,
+        ..crate::Preflight::default()
+    };
+    crate::preflight(run_orchestrator.syncer(), checks).await?;
+
+    let session = run_orchestrator.start(&request).await?;
+    writeln!(
+        io::stderr(),
+        "watch: instance {} ready; press Ctrl-C to stop",
+        session.handle.id
+    )
+    .ok();
+
+    let watch_loop = WatchLoop::new(Arc::new(run_orchestrator), &source, &remote_command)
+        .with_debounce(Duration::from_millis(args.debounce_ms))
+        .with_reporter(Box::new(report));
+    let shutdown = async {
+        tokio::select! {
+            _ = interrupt.recv() => {}
+            _ = terminate.recv() => {}
+        }
+    };
+    watch_loop.run(session, changes, shutdown).await?;
+    writeln!(io::stderr(), "watch: instance destroyed").ok();
+    Ok(0)
+}
+
+fn report(run: &WatchRun<'_, ScalewayBackendError>) {
+    let mut stderr = io::stderr().lock();
+    let line = match run {
+        WatchRun::Completed(output) => output.exit_code.map_or_else(
+            || String::from("command terminated without an exit status"),
+            |code| format!("command exited with status {code}"),
+        ),
+        WatchRun::Cancelled => {
+            writeln!(stderr, "watch: changes detected, cancelled run").ok();
+            return;
+        }
+        WatchRun::Failed(err) => err.to_string(),
+    };
+    writeln!(stderr, "watch: {line}; waiting for changes").ok();
+}
@@ -8,6 +8,7 @@ use std::sync::LazyLock;
 
 use escargot::CargoBuild;
 use predicates::str::contains;
+use rstest::rstest;
 
 /// Lazily builds the binary once with the test-backdoors feature enabled.
 ///
@@ -53,6 +54,77 @@ fn cli_run_reports_missing_exit_code() {
 
     cmd.assert()
         .failure()
-        .code(1)
+        .code(248)
         .stderr(contains("remote command terminated without an exit status"));
 }
+
+#[rstest]
+#[case("config", 240)]
+#[case("backend", 241)]
+#[case("sync", 244)]
+#[case("run", 241)]
+fn cli_run_exits_with_a_code_per_infrastructure_failure(#[case] prefail: &str, #[case] code: i32) {
+    let mut cmd = mriya_cmd();
+    cmd.env("MRIYA_FAKE_RUN_ENABLE", "1");
+    cmd.env("MRIYA_FAKE_RUN_PREFAIL", prefail);
+    cmd.args(["run", "--", "echo", "ok"]);
+
+    cmd.assert().failure().code(code);
+}
+
+#[test]
+fn cli_run_infra_exit_code_overrides_every_infrastructure_failure() {
+    for prefail in ["config", "sync"] {
+        let mut cmd = mriya_cmd();
+        cmd.env("MRIYA_FAKE_RUN_ENABLE", "1");
+        cmd.env("MRIYA_FAKE_RUN_PREFAIL", prefail);
+        cmd.args(["run", "--infra-exit-code", "125", "--", "echo", "ok"]);
+
+        cmd.assert().failure().code(125);
+    }
+}
+
+#[test]
+fn cli_run_infra_exit_code_leaves_command_exit_codes_alone() {
+    let mut cmd = mriya_cmd();
+    cmd.env("MRIYA_FAKE_RUN_ENABLE", "1");
+    cmd.env("MRIYA_FAKE_RUN_MODE", "exit-7");
+    cmd.args(["run", "--infra-exit-code", "125", "--", "echo", "ok"]);
+
+    cmd.assert().code(7);
+}
+
+#[test]
+fn cli_run_rejects_a_zero_infra_exit_code() {
+    let mut cmd = mriya_cmd();
+    cmd.args(["run", "--infra-exit-code", "0", "--", "true"]);
+
+    cmd.assert().failure().code(240);
+}
+
+#[test]
+fn cli_run_rejects_matrix_with_shards() {
+    let mut cmd = mriya_cmd();
+    cmd.args([
+        "run",
+        "--shards",
+        "2",
+        "--matrix",
+        "arch=arm64",
+        "--",
+        "true",
+    ]);
+
+    cmd.assert()
+        .failure()
+        .code(240)
+        .stderr(contains("cannot be used with"));
+}
+
+#[test]
+fn cli_help_exits_successfully() {
+    let mut cmd = mriya_cmd();
+    cmd.args(["run", "--help"]);
+
+    cmd.assert().success().stdout(contains("--infra-exit-code"));
+}
@@ -1,6 +1,6 @@
 //! Shared sync configuration fixture for behavioural tests.
 
-use mriya::sync::SyncConfig;
+use mriya::sync::{SyncConfig, SyncMode};
 
 pub fn sync_config() -> SyncConfig {
     SyncConfig {
@@ -11,9 +11,22 @@ pub fn sync_config() -> SyncConfig {
         ssh_batch_mode: true,
         ssh_strict_host_key_checking: false,
         ssh_known_hosts_file: String::from("/dev/null"),
+        ssh_multiplex: false,
         ssh_identity_file: Some(String::from("~/.ssh/id_ed25519")),
         volume_mount_path: String::from("/mriya"),
         route_build_caches: true,
         create_cache_directories: true,
+        include: Vec::new(),
+        exclude: Vec::new(),
+        sync_mode: SyncMode::RsyncFilter,
+        git_bin: String::from("git"),
+        git_include_untracked: false,
+        git_lfs_pull: false,
+        git_remote: String::from("origin"),
+        tar_stream: false,
+        max_sync_bytes: None,
+        secret_patterns: Vec::new(),
+        secret_signatures: Vec::new(),
+        secret_allowlist: Vec::new(),
     }
 }
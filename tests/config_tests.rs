@@ -289,3 +289,74 @@ async fn config_expands_tilde_for_cloud_init_user_data_file() {
         Some(String::from("tilde-user-data"))
     );
 }
+
+#[tokio::test]
+async fn run_config_forwards_passed_configured_and_cli_variables() {
+    let _guard = mriya::test_support::EnvGuard::set_vars(&[
+        ("MRIYA_TEST_PASSED", "from-shell"),
+        ("MRIYA_TEST_CLI", "cli-local"),
+    ])
+    .await;
+    let cfg = mriya::RunConfig {
+        env: vec![String::from("MRIYA_TEST_CONFIGURED=configured")],
+        pass_env: vec![
+            String::from("MRIYA_TEST_PASSED"),
+            String::from("MRIYA_TEST_UNSET"),
+        ],
+        ..mriya::RunConfig::default()
+    };
+
+    let env = cfg
+        .remote_env(&[
+            String::from("MRIYA_TEST_CLI"),
+            String::from("MRIYA_TEST_PASSED=overridden"),
+        ])
+        .expect("names should be valid");
+
+    assert_eq!(
+        env.names().collect::<Vec<_>>(),
+        [
+            "MRIYA_TEST_CONFIGURED",
+            "MRIYA_TEST_CLI",
+            "MRIYA_TEST_PASSED"
+        ]
+    );
+}
+
+#[test]
+fn run_config_splits_remote_and_local_hooks() {
+    let cfg = mriya::RunConfig {
+        before: vec![String::from("cargo fetch")],
+        after: vec![String::from("dmesg")],
+        local_before: vec![String::from("make assets")],
+        local_after: vec![String::from("notify-send done")],
+        ..mriya::RunConfig::default()
+    };
+
+    let remote = cfg.remote_hooks();
+    let local = cfg.local_hooks();
+
+    assert_eq!(remote.before, ["cargo fetch"]);
+    assert_eq!(remote.after, ["dmesg"]);
+    assert_eq!(local.before, ["make assets"]);
+    assert_eq!(local.after, ["notify-send done"]);
+}
+
+#[test]
+fn run_config_sets_the_provisioning_retry_policy() {
+    let cfg = mriya::RunConfig {
+        provision_attempts: Some(5),
+        provision_backoff_secs: Some(2),
+        ..mriya::RunConfig::default()
+    };
+
+    let policy = cfg.retry_policy();
+
+    assert_eq!(policy.attempts(), 5);
+    assert_eq!(policy.backoff(1), std::time::Duration::from_secs(2));
+    assert_eq!(policy.backoff(3), std::time::Duration::from_secs(8));
+    assert_eq!(
+        mriya::RunConfig::default().retry_policy().attempts(),
+        mriya::retry::DEFAULT_ATTEMPTS
+    );
+}
@@ -13,3 +13,11 @@ Feature: Scaleway test-run janitor
     When I run the janitor sweep
     Then the janitor reports a not-clean error
 
+
+  Scenario: List tagged resources without deleting them in dry-run mode
+    Given a configured janitor for project "project" and test run "run-1"
+    And the janitor runs in dry-run mode
+    And scw lists one tagged server and one tagged volume without deletions
+    When I run the janitor sweep
+    Then the janitor reports 2 resources that would be deleted
+    And the janitor issues no delete commands
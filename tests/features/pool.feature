@@ -0,0 +1,38 @@
+Feature: Warm instance pool
+
+  Scenario: Fill the pool to its target size
+    Given a started pool of size "2"
+    When the pool manager reconciles
+    Then "2" pooled instances are ready
+    And no pooled instance is destroyed
+
+  Scenario: Replenish the pool after a run claims an instance
+    Given a started pool of size "2"
+    And the pool manager has reconciled
+    When a run claims a pooled instance
+    And the pool manager reconciles
+    Then the run received a pooled instance
+    And "2" pooled instances are ready
+    And "3" instances were created
+
+  Scenario: Drain the pool once it has been idle too long
+    Given a started pool of size "2"
+    And the pool manager has reconciled
+    When the pool manager reconciles after the idle timeout
+    Then "0" pooled instances are ready
+    And "2" pooled instances are destroyed
+    And the pool manager has finished
+
+  Scenario: Destroy instances that never become ready
+    Given a started pool of size "1"
+    And instances never become ready
+    When the pool manager reconciles
+    Then "0" pooled instances are ready
+    And "1" pooled instances are destroyed
+    And the pool reports a failure mentioning "did not become ready"
+
+  Scenario: Refuse several pooled instances sharing a cache volume
+    Given the pool serves cache volume "vol-cache"
+    When a pool of size "2" is started
+    Then the pool refuses to share cache volume "vol-cache"
+    And "0" instances were created
@@ -19,6 +19,7 @@ Feature: Remote execution via mriya run
     And the scripted runner returns exit code "0"
     When I orchestrate a remote run for "echo ok"
     Then teardown failure is reported
+    And the run error names run "scripted-run"
 
   Scenario: Mount cache volume before syncing when volume ID is configured
     Given a ready backend and sync pipeline
@@ -37,6 +38,17 @@ Feature: Remote execution via mriya run
     Then the run result exit code is "0"
     And the instance is destroyed
 
+  Scenario: Share one SSH connection across the phases of a run
+    Given a ready backend and sync pipeline
+    And SSH multiplexing is enabled
+    And a volume ID "vol-12345" is configured
+    And the scripted runner returns exit code "0"
+    When I orchestrate a remote run for "cargo build"
+    Then the run result exit code is "0"
+    And every SSH phase shares one control socket
+    And the shared connection is closed before teardown
+    And the instance is destroyed
+
   Scenario: Route Cargo caches to the mounted cache volume
     Given a ready backend and sync pipeline
     And a volume ID "vol-12345" is configured
@@ -101,3 +113,156 @@ Feature: Remote execution via mriya run
     When I orchestrate a remote run for "cargo build"
     Then the mount command does not create cache subdirectories
     And the instance is destroyed
+
+  Scenario: Run shards concurrently and aggregate their exit codes
+    Given a ready backend and sync pipeline
+    And shard "2" returns exit code "4"
+    When I orchestrate "3" shards for "cargo test"
+    Then the aggregate exit code is "4"
+    And every shard sees its index out of "3"
+    And "3" instances are destroyed
+
+  Scenario: Run several commands on one instance in a session
+    Given a ready backend and sync pipeline
+    And cloud-init user data is configured
+    And the rsync step succeeds
+    And cloud-init is already finished
+    And the remote command returns exit code "0"
+    And the scripted runner returns exit code "3"
+    When I run "cargo check" then "cargo test" in one session
+    Then the session commands end with "0" then "3"
+    And the workspace is synced "2" times
+    And cloud-init readiness is checked once
+    And "1" instances are destroyed
+
+  Scenario: Keep the session instance after a failed sync
+    Given a ready backend and sync pipeline
+    And sync fails with status "12"
+    And the scripted runner returns exit code "0"
+    When I run "cargo check" then "cargo test" in one session
+    Then the session commands end with "sync-error" then "0"
+    And "1" instances are destroyed
+
+  Scenario: Close the shared connection when a session outlives its command
+    Given a ready backend and sync pipeline
+    And SSH multiplexing is enabled
+    And the scripted runner returns exit code "0"
+    When I run "cargo check" in a session and leave it running
+    Then the shared connection is closed last
+    And "0" instances are destroyed
+
+  Scenario: Run after hooks whatever the command's exit code
+    Given a ready backend and sync pipeline
+    And a remote before hook "cargo fetch"
+    And a remote after hook "dmesg"
+    And the rsync step succeeds
+    And the remote command returns exit code "0"
+    And the remote command returns exit code "3"
+    And the remote command returns exit code "0"
+    When I orchestrate a remote run for "cargo test"
+    Then the run result exit code is "3"
+    And the remote commands run in the order "cargo fetch; cargo test; dmesg"
+    And the instance is destroyed
+
+  Scenario: Skip the command but run after hooks when a before hook fails
+    Given a ready backend and sync pipeline
+    And a remote before hook "cargo fetch"
+    And a remote after hook "dmesg"
+    And the rsync step succeeds
+    And the remote command returns exit code "2"
+    And the remote command returns exit code "1"
+    When I orchestrate a remote run for "cargo test"
+    Then the run error is a before hook failure for "`cargo fetch` exited with status 2"
+    And no remote command runs "cargo test"
+    And the remote commands run in the order "cargo fetch; dmesg"
+    And the instance is destroyed
+
+  Scenario: Run after hooks when the sync fails
+    Given a ready backend and sync pipeline
+    And a remote before hook "cargo fetch"
+    And a remote after hook "dmesg"
+    And sync fails with status "12"
+    And the remote command returns exit code "0"
+    When I orchestrate a remote run for "cargo test"
+    Then the run error mentions sync failure
+    And no remote command runs "cargo fetch"
+    And no remote command runs "cargo test"
+    And the remote commands run in the order "dmesg"
+    And the instance is destroyed
+
+  Scenario: Report a failed after hook once the command has run
+    Given a ready backend and sync pipeline
+    And a remote after hook "dmesg"
+    And the rsync step succeeds
+    And the remote command returns exit code "0"
+    And the remote command returns exit code "1"
+    When I orchestrate a remote run for "cargo test"
+    Then the run error is an after hook failure for "`dmesg` exited with status 1"
+    And the instance is destroyed
+
+  Scenario: Retry transient create failures
+    Given a ready backend and sync pipeline
+    And instance creation fails transiently "2" times
+    And the scripted runner returns exit code "0"
+    When I orchestrate a remote run for "cargo test"
+    Then the run result exit code is "0"
+    And instance creation is attempted "3" times
+    And "1" instances are destroyed
+
+  Scenario: Give up after the configured provisioning attempts
+    Given a ready backend and sync pipeline
+    And provisioning is attempted at most "2" times
+    And instance creation fails transiently "2" times
+    When I orchestrate a remote run for "cargo test"
+    Then the run error is a create failure for "temporarily unavailable"
+    And instance creation is attempted "2" times
+
+  Scenario: Do not retry a rejected create
+    Given a ready backend and sync pipeline
+    And instance creation is rejected
+    When I orchestrate a remote run for "cargo test"
+    Then the run error is a create failure for "request rejected"
+    And instance creation is attempted "1" times
+
+  Scenario: Destroy an instance that never became ready before retrying
+    Given a ready backend and sync pipeline
+    And the instance never becomes ready once
+    And the scripted runner returns exit code "0"
+    When I orchestrate a remote run for "cargo test"
+    Then the run result exit code is "0"
+    And instance creation is attempted "2" times
+    And "2" instances are destroyed
+
+  Scenario: Do not retry when the failed instance could not be destroyed
+    Given a ready backend and sync pipeline
+    And a backend that fails during teardown
+    And the instance never becomes ready once
+    When I orchestrate a remote run for "cargo test"
+    Then the run error includes a teardown failure note
+    And the run error names run "scripted-run"
+    And instance creation is attempted "1" times
+
+  Scenario: Do not reprovision once the command has started
+    Given a ready backend and sync pipeline
+    And the scripted runner returns exit code "255"
+    When I orchestrate a remote run for "cargo test"
+    Then the run result exit code is "255"
+    And instance creation is attempted "1" times
+
+  Scenario: Run on a claimed pooled instance that still answers
+    Given a ready backend and sync pipeline
+    And a claimed pooled instance that answers
+    And the scripted runner returns exit code "0"
+    When I orchestrate a remote run for "cargo test"
+    Then the run result exit code is "0"
+    And instance creation is attempted "0" times
+    And "1" instances are destroyed
+
+  Scenario: Replace a claimed pooled instance that no longer answers
+    Given a ready backend and sync pipeline
+    And a claimed pooled instance that no longer answers
+    And the scripted runner returns exit code "0"
+    When I orchestrate a remote run for "cargo test"
+    Then the run result exit code is "0"
+    And instance creation is attempted "1" times
+    And "2" instances are destroyed
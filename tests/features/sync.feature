@@ -6,6 +6,88 @@ Feature: Git-aware file sync
     Then the gitignored cache directory remains after sync
     And tracked files are mirrored to the remote
 
+  Scenario: Layer include and exclude rules over gitignore
+    Given a workspace with gitignored and bulky files
+    When I sync including ".env.test,generated/" and excluding "docs/"
+    Then the remote contains "src/lib.rs,.env.test,generated/api.rs"
+    And the remote lacks "docs/guide.md,fixtures/large.bin"
+
+  Scenario: Sync only the files git reports
+    Given a committed git workspace with local changes
+    When I sync in "git-files" mode
+    Then the remote contains "src/lib.rs,.gitignore,target/cache.txt"
+    And the remote lacks "scratch.txt,stale.txt,.git"
+    And the remote file "src/lib.rs" contains "edited"
+
+  Scenario: Sync a snapshot of the last commit
+    Given a committed git workspace with local changes
+    When I sync in "git-commit" mode
+    Then the remote contains "src/lib.rs,.gitignore,target/cache.txt"
+    And the remote lacks "scratch.txt,stale.txt,.git"
+    And the remote file "src/lib.rs" contains "committed"
+
+  Scenario: Fetch the pushed commit and apply local changes
+    Given a pushed git workspace with local changes
+    When I sync in "git-fetch" mode
+    Then the remote contains "src/lib.rs,.gitignore,target/cache.txt"
+    And the remote lacks "scratch.txt,stale.txt,.git"
+    And the remote file "src/lib.rs" contains "edited"
+    And the cache volume holds a bare clone
+
+  Scenario: Fall back to rsync when the commit is not pushed
+    Given a git workspace with an unpushed commit
+    When I sync in "git-fetch" mode
+    Then the remote contains "src/lib.rs,src/new.rs,scratch.txt,target/cache.txt"
+    And the remote lacks "stale.txt,.git"
+    And the remote file "src/lib.rs" contains "edited"
+    And the cache volume lacks a bare clone
+
+  Scenario: Sync submodule content from the working tree
+    Given a git workspace with a submodule
+    When I sync in "rsync-filter" mode
+    Then the remote contains "src/lib.rs,.gitmodules,vendor/lib/lib.rs"
+    And the remote lacks "vendor/lib/.git,.git"
+
+  Scenario: Sync submodule files git reports
+    Given a git workspace with a submodule
+    When I sync in "git-files" mode
+    Then the remote contains "src/lib.rs,.gitmodules,vendor/lib/lib.rs"
+    And the remote lacks "vendor/lib/.git,.git"
+
+  Scenario: Sync a snapshot of submodule commits
+    Given a git workspace with a submodule
+    When I sync in "git-commit" mode
+    Then the remote contains "src/lib.rs,.gitmodules,vendor/lib/lib.rs"
+    And the remote lacks "vendor/lib/.git,.git"
+
+  Scenario: Sync Git LFS content instead of pointers
+    Given a git workspace whose LFS file is still a pointer
+    When I sync in "rsync-filter" mode
+    Then the remote file "models/weights.bin" contains "real model weights"
+
+  Scenario: Sync Git LFS content routed by a nested .gitattributes
+    Given a git workspace whose LFS file in "assets" is still a pointer
+    When I sync in "rsync-filter" mode
+    Then the remote file "assets/models/weights.bin" contains "real model weights"
+
+  Scenario: Stream a tarball to an empty remote
+    Given a workspace with many small files
+    When I sync with tar streaming
+    Then every small file arrives on the remote
+    And the remote contains ".gitignore"
+    And the remote lacks "target,.git"
+
+  Scenario: Use rsync when the remote already holds files
+    Given a workspace with a gitignored cache on the remote
+    When I sync with tar streaming
+    Then tracked files are mirrored to the remote
+    And the gitignored cache directory remains after sync
+
+  Scenario: Refuse a sync above max_sync_bytes
+    Given a dry run that would send a large dataset
+    When I check the sync against a limit of "1073741824" bytes
+    Then the sync is refused before provisioning
+
   Scenario: Propagate remote exit codes
     Given a scripted runner that succeeds at sync
     When the remote command exits with "7"
@@ -0,0 +1,24 @@
+Feature: Watch mode
+
+  Scenario: Re-run the command after the workspace changes
+    Given a watch loop for "cargo test"
+    When the workspace changes after the first run completes
+    Then the watch reports "completed 0, completed 0"
+    And the workspace is synced "2" times
+    And the watched instance is destroyed once
+
+  Scenario: Cancel an in-flight run when the workspace changes
+    Given a watch loop for "cargo test"
+    And the first run hangs until cancelled
+    When the workspace changes during the first run
+    Then the watch reports "cancelled, completed 0"
+    And the remote process group is signalled
+    And the watched instance is destroyed once
+
+  Scenario: Close the shared connection when the watch stops
+    Given a watch loop for "cargo test"
+    And SSH multiplexing is enabled
+    When the workspace changes after the first run completes
+    Then the watch reports "completed 0, completed 0"
+    And the shared connection is closed
+    And the watched instance is destroyed once
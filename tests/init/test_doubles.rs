@@ -7,7 +7,7 @@ use camino::Utf8PathBuf;
 use mriya::backend::BackendFuture;
 use mriya::{
     Backend, ConfigStoreError, ConfigWriter, InstanceHandle, InstanceNetworking, InstanceRequest,
-    VolumeBackend, VolumeHandle, VolumeRequest,
+    TransientError, VolumeBackend, VolumeHandle, VolumeRequest,
 };
 use thiserror::Error;
 
@@ -179,6 +179,8 @@ pub enum ScriptedVolumeBackendError {
     Lock(String),
 }
 
+impl TransientError for ScriptedVolumeBackendError {}
+
 impl Backend for ScriptedVolumeBackend {
     type Error = ScriptedVolumeBackendError;
 
@@ -196,6 +198,7 @@ impl Backend for ScriptedVolumeBackend {
             || InstanceHandle {
                 id: String::from("instance-123"),
                 zone: String::from("test-zone"),
+                run_id: None,
             },
         )
     }
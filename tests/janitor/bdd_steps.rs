@@ -1,8 +1,9 @@
 //! BDD step definitions for janitor behaviour.
 
-use mriya::janitor::Janitor;
-use mriya::test_support::{json_servers, json_volumes};
+use mriya::janitor::{Janitor, SweepAction};
+use mriya::test_support::{ScriptedRunner, json_resources, json_servers, json_volumes};
 use rstest_bdd_macros::{given, then, when};
+use tokio::runtime::Runtime;
 
 use super::test_helpers::{JanitorContext, SweepOutcome, build_config};
 
@@ -12,6 +13,18 @@ pub enum StepError {
     Assertion(String),
 }
 
+/// Queues empty list responses for IPs, images, and snapshots.
+fn push_empty_between_servers_and_volumes(runner: &ScriptedRunner) {
+    for key in ["ips", "images", "snapshots"] {
+        runner.push_output(Some(0), json_resources(key, &[]), "");
+    }
+}
+
+/// Queues an empty security group list response.
+fn push_empty_security_groups(runner: &ScriptedRunner) {
+    runner.push_output(Some(0), json_resources("security_groups", &[]), "");
+}
+
 #[given("a configured janitor for project \"{project}\" and test run \"{run_id}\"")]
 fn configured_janitor(
     mut janitor_context: JanitorContext,
@@ -38,6 +51,7 @@ fn scw_lists_tagged_resources(janitor_context: JanitorContext) -> JanitorContext
         "",
     );
     janitor_context.runner.push_success(); // delete server
+    push_empty_between_servers_and_volumes(&janitor_context.runner);
     janitor_context.runner.push_output(
         Some(0),
         json_volumes(&[
@@ -47,13 +61,47 @@ fn scw_lists_tagged_resources(janitor_context: JanitorContext) -> JanitorContext
         "",
     );
     janitor_context.runner.push_success(); // delete volume
+    push_empty_security_groups(&janitor_context.runner);
     janitor_context
         .runner
         .push_output(Some(0), json_servers(&[("srv-b", "fr-par-1", &[])]), "");
+    push_empty_between_servers_and_volumes(&janitor_context.runner);
     janitor_context
         .runner
         .push_output(Some(0), json_volumes(&[("vol-b", "fr-par-1", &[])]), "");
+    push_empty_security_groups(&janitor_context.runner);
+
+    janitor_context
+}
 
+#[given("the janitor runs in dry-run mode")]
+fn janitor_dry_run(mut janitor_context: JanitorContext) -> JanitorContext {
+    let Some(config) = janitor_context.config.take() else {
+        panic!("test setup requires configured janitor");
+    };
+    janitor_context.config = Some(config.with_dry_run(true));
+    janitor_context
+}
+
+#[given("scw lists one tagged server and one tagged volume without deletions")]
+fn scw_lists_tagged_resources_only(janitor_context: JanitorContext) -> JanitorContext {
+    let Some(config) = janitor_context.config.as_ref() else {
+        panic!("test setup requires configured janitor");
+    };
+    let tag = config.test_run_tag();
+
+    janitor_context.runner.push_output(
+        Some(0),
+        json_servers(&[("srv-a", "fr-par-1", &[tag.as_str()])]),
+        "",
+    );
+    push_empty_between_servers_and_volumes(&janitor_context.runner);
+    janitor_context.runner.push_output(
+        Some(0),
+        json_volumes(&[("vol-a", "fr-par-1", &[tag.as_str()])]),
+        "",
+    );
+    push_empty_security_groups(&janitor_context.runner);
     janitor_context
 }
 
@@ -70,18 +118,22 @@ fn scw_lists_remaining_server(janitor_context: JanitorContext) -> JanitorContext
         "",
     );
     janitor_context.runner.push_success(); // delete server
+    push_empty_between_servers_and_volumes(&janitor_context.runner);
     janitor_context
         .runner
         .push_output(Some(0), json_volumes(&[]), "");
+    push_empty_security_groups(&janitor_context.runner);
     // post: server still present
     janitor_context.runner.push_output(
         Some(0),
         json_servers(&[("srv-a", "fr-par-1", &[tag.as_str()])]),
         "",
     );
+    push_empty_between_servers_and_volumes(&janitor_context.runner);
     janitor_context
         .runner
         .push_output(Some(0), json_volumes(&[]), "");
+    push_empty_security_groups(&janitor_context.runner);
 
     janitor_context
 }
@@ -91,8 +143,11 @@ fn run_sweep(mut janitor_context: JanitorContext) -> JanitorContext {
     let Some(config) = janitor_context.config.clone() else {
         panic!("test setup requires configured janitor");
     };
+    let Ok(runtime) = Runtime::new() else {
+        panic!("tokio runtime should start for janitor scenarios");
+    };
     let janitor = Janitor::new(config, janitor_context.runner.clone());
-    janitor_context.outcome = Some(match janitor.sweep() {
+    janitor_context.outcome = Some(match runtime.block_on(janitor.sweep()) {
         Ok(summary) => SweepOutcome::Success(summary),
         Err(err) => SweepOutcome::Failure(err.to_string()),
     });
@@ -113,7 +168,9 @@ fn reports_deletions(
             "expected success, got: {outcome:?}"
         )));
     };
-    if summary.deleted_servers == servers as usize && summary.deleted_volumes == volumes as usize {
+    if summary.deleted_servers() == servers as usize
+        && summary.deleted_volumes() == volumes as usize
+    {
         Ok(())
     } else {
         Err(StepError::Assertion(format!(
@@ -176,3 +233,40 @@ fn reports_not_clean(janitor_context: &JanitorContext) -> Result<(), StepError>
         )))
     }
 }
+
+#[then("the janitor reports {count:u32} resources that would be deleted")]
+fn reports_would_delete(janitor_context: &JanitorContext, count: u32) -> Result<(), StepError> {
+    let Some(SweepOutcome::Success(summary)) = janitor_context.outcome.as_ref() else {
+        return Err(StepError::Assertion(format!(
+            "expected success, got: {:?}",
+            janitor_context.outcome
+        )));
+    };
+    let would_delete = summary
+        .resources
+        .iter()
+        .filter(|res| res.action == SweepAction::WouldDelete)
+        .count();
+    if summary.dry_run && would_delete == count as usize {
+        Ok(())
+    } else {
+        Err(StepError::Assertion(format!(
+            "expected {count} dry-run entries, got {summary:?}"
+        )))
+    }
+}
+
+#[then("the janitor issues no delete commands")]
+fn issues_no_deletes(janitor_context: &JanitorContext) -> Result<(), StepError> {
+    let invocations = janitor_context.runner.invocations();
+    if invocations.iter().any(|call| {
+        call.args
+            .iter()
+            .any(|arg| arg.to_string_lossy() == "delete")
+    }) {
+        return Err(StepError::Assertion(format!(
+            "expected no delete invocations, got: {invocations:?}"
+        )));
+    }
+    Ok(())
+}
@@ -19,3 +19,11 @@ fn scenario_delete_tagged_resources(janitor_context: JanitorContext) {
 fn scenario_fail_when_not_clean(janitor_context: JanitorContext) {
     let _ = janitor_context;
 }
+
+#[scenario(
+    path = "tests/features/janitor.feature",
+    name = "List tagged resources without deleting them in dry-run mode"
+)]
+fn scenario_dry_run_lists_only(janitor_context: JanitorContext) {
+    let _ = janitor_context;
+}
@@ -0,0 +1,161 @@
+//! BDD step definitions for the warm instance pool.
+
+use rstest_bdd_macros::{given, then, when};
+use tokio::runtime::Runtime;
+
+use super::test_helpers::{IDLE_TIMEOUT, PoolContext, START};
+
+#[derive(Debug, thiserror::Error)]
+pub enum StepError {
+    #[error("pool error: {0}")]
+    Pool(#[from] mriya::PoolError),
+    #[error("assertion failed: {0}")]
+    Assertion(String),
+}
+
+fn reconcile_at(mut pool_context: PoolContext, now: u64) -> Result<PoolContext, StepError> {
+    let runtime = Runtime::new().map_err(|err| StepError::Assertion(err.to_string()))?;
+    let manager = pool_context.manager();
+    let report = runtime.block_on(manager.tick(now))?;
+    pool_context.report = Some(report);
+    Ok(pool_context)
+}
+
+#[given("a started pool of size \"{size}\"")]
+fn started_pool(mut pool_context: PoolContext, size: usize) -> Result<PoolContext, StepError> {
+    pool_context.size = size;
+    pool_context.manager().start(START)?;
+    Ok(pool_context)
+}
+
+#[given("the pool serves cache volume \"{volume_id}\"")]
+fn pool_with_volume(mut pool_context: PoolContext, volume_id: String) -> PoolContext {
+    pool_context.request.volume_id = Some(volume_id);
+    pool_context
+}
+
+#[given("instances never become ready")]
+fn instances_never_ready(pool_context: PoolContext) -> PoolContext {
+    pool_context.backend.fail_wait();
+    pool_context
+}
+
+#[given("the pool manager has reconciled")]
+fn pool_has_reconciled(pool_context: PoolContext) -> Result<PoolContext, StepError> {
+    reconcile_at(pool_context, START)
+}
+
+#[when("the pool manager reconciles")]
+fn pool_reconciles(pool_context: PoolContext) -> Result<PoolContext, StepError> {
+    reconcile_at(pool_context, START)
+}
+
+#[when("the pool manager reconciles after the idle timeout")]
+fn pool_reconciles_when_idle(pool_context: PoolContext) -> Result<PoolContext, StepError> {
+    reconcile_at(pool_context, START + IDLE_TIMEOUT.as_secs())
+}
+
+#[when("a pool of size \"{size}\" is started")]
+fn pool_started(mut pool_context: PoolContext, size: usize) -> PoolContext {
+    pool_context.size = size;
+    pool_context.start_error = pool_context
+        .manager()
+        .start(START)
+        .err()
+        .map(|err| err.to_string());
+    pool_context
+}
+
+#[when("a run claims a pooled instance")]
+fn run_claims(mut pool_context: PoolContext) -> Result<PoolContext, StepError> {
+    let claimed = pool_context.store.claim(&pool_context.request)?;
+    pool_context.claimed = claimed.map(|instance| instance.handle.id);
+    Ok(pool_context)
+}
+
+#[then("the run received a pooled instance")]
+fn run_received_instance(pool_context: &PoolContext) -> Result<(), StepError> {
+    match &pool_context.claimed {
+        Some(id) if id.starts_with("pool-") => Ok(()),
+        other => Err(StepError::Assertion(format!(
+            "expected a pooled instance, got {other:?}"
+        ))),
+    }
+}
+
+#[then("\"{count}\" pooled instances are ready")]
+fn ready_instances(pool_context: &PoolContext, count: usize) -> Result<(), StepError> {
+    let ready = pool_context.store.load()?.ready_count();
+    if ready == count {
+        Ok(())
+    } else {
+        Err(StepError::Assertion(format!(
+            "expected {count} ready instances, got {ready}"
+        )))
+    }
+}
+
+#[then("\"{count}\" instances were created")]
+fn instances_created(pool_context: &PoolContext, count: u32) -> Result<(), StepError> {
+    let created = pool_context.backend.create_calls();
+    if created == count {
+        Ok(())
+    } else {
+        Err(StepError::Assertion(format!(
+            "expected {count} create calls, got {created}"
+        )))
+    }
+}
+
+#[then("no pooled instance is destroyed")]
+fn none_destroyed(pool_context: &PoolContext) -> Result<(), StepError> {
+    destroyed_instances(pool_context, 0)
+}
+
+#[then("\"{count}\" pooled instances are destroyed")]
+fn destroyed_instances(pool_context: &PoolContext, count: usize) -> Result<(), StepError> {
+    let destroyed = pool_context.backend.destroyed();
+    if destroyed.len() == count {
+        Ok(())
+    } else {
+        Err(StepError::Assertion(format!(
+            "expected {count} destroyed instances, got {destroyed:?}"
+        )))
+    }
+}
+
+#[then("the pool refuses to share cache volume \"{volume_id}\"")]
+fn volume_sharing_refused(pool_context: &PoolContext, volume_id: String) -> Result<(), StepError> {
+    match &pool_context.start_error {
+        Some(message) if message.contains(&volume_id) => Ok(()),
+        other => Err(StepError::Assertion(format!(
+            "expected a shared volume error, got {other:?}"
+        ))),
+    }
+}
+
+#[then("the pool manager has finished")]
+fn manager_finished(pool_context: &PoolContext) -> Result<(), StepError> {
+    match &pool_context.report {
+        Some(report) if report.finished => Ok(()),
+        other => Err(StepError::Assertion(format!(
+            "expected a finished report, got {other:?}"
+        ))),
+    }
+}
+
+#[then("the pool reports a failure mentioning \"{text}\"")]
+fn failure_reported(pool_context: &PoolContext, text: String) -> Result<(), StepError> {
+    let failures = pool_context
+        .report
+        .as_ref()
+        .map(|report| report.failures.clone())
+        .unwrap_or_default();
+    if failures.iter().any(|failure| failure.contains(&text)) {
+        Ok(())
+    } else {
+        Err(StepError::Assertion(format!(
+            "expected a failure mentioning {text:?}, got {failures:?}"
+        )))
+    }
+}
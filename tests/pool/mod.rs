@@ -0,0 +1,6 @@
+//! Pool module behavioural test suite.
+
+mod bdd_steps;
+mod scenarios;
+mod test_doubles;
+mod test_helpers;
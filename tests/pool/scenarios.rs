@@ -0,0 +1,45 @@
+//! BDD scenarios for the warm instance pool.
+
+use rstest_bdd_macros::scenario;
+
+use super::test_helpers::{PoolContext, pool_context};
+
+#[scenario(
+    path = "tests/features/pool.feature",
+    name = "Fill the pool to its target size"
+)]
+fn scenario_fill_pool(pool_context: PoolContext) {
+    let _ = pool_context;
+}
+
+#[scenario(
+    path = "tests/features/pool.feature",
+    name = "Replenish the pool after a run claims an instance"
+)]
+fn scenario_replenish_pool(pool_context: PoolContext) {
+    let _ = pool_context;
+}
+
+#[scenario(
+    path = "tests/features/pool.feature",
+    name = "Drain the pool once it has been idle too long"
+)]
+fn scenario_drain_idle_pool(pool_context: PoolContext) {
+    let _ = pool_context;
+}
+
+#[scenario(
+    path = "tests/features/pool.feature",
+    name = "Destroy instances that never become ready"
+)]
+fn scenario_destroy_unready_instances(pool_context: PoolContext) {
+    let _ = pool_context;
+}
+
+#[scenario(
+    path = "tests/features/pool.feature",
+    name = "Refuse several pooled instances sharing a cache volume"
+)]
+fn scenario_refuse_shared_volume(pool_context: PoolContext) {
+    let _ = pool_context;
+}
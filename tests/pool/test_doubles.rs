@@ -0,0 +1,95 @@
+//! Test doubles for warm pool scenarios.
+
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::{Arc, Mutex};
+
+use mriya::{
+    Backend, InstanceHandle, InstanceNetworking, InstanceRequest, TransientError,
+    backend::BackendFuture,
+};
+use thiserror::Error;
+
+/// Backend double that hands out sequential instance IDs and records
+/// lifecycle calls.
+#[derive(Clone, Debug, Default)]
+pub struct PoolBackend {
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    fail_wait: bool,
+    create_calls: u32,
+    destroyed: Vec<String>,
+}
+
+impl PoolBackend {
+    pub fn fail_wait(&self) {
+        self.lock().fail_wait = true;
+    }
+
+    pub fn create_calls(&self) -> u32 {
+        self.lock().create_calls
+    }
+
+    pub fn destroyed(&self) -> Vec<String> {
+        self.lock().destroyed.clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+/// Errors raised by [`PoolBackend`].
+#[derive(Clone, Debug, Error, Eq, PartialEq)]
+pub enum PoolBackendError {
+    /// Raised when the backend is instructed to fail readiness checks.
+    #[error("ssh never came up")]
+    Wait,
+}
+
+impl TransientError for PoolBackendError {}
+
+impl Backend for PoolBackend {
+    type Error = PoolBackendError;
+
+    fn create<'a>(
+        &'a self,
+        _request: &'a InstanceRequest,
+    ) -> BackendFuture<'a, InstanceHandle, Self::Error> {
+        Box::pin(async move {
+            let mut state = self.lock();
+            state.create_calls += 1;
+            Ok(InstanceHandle {
+                id: format!("pool-{}", state.create_calls),
+                zone: String::from("fr-par-1"),
+                run_id: None,
+            })
+        })
+    }
+
+    fn wait_for_ready<'a>(
+        &'a self,
+        _handle: &'a InstanceHandle,
+    ) -> BackendFuture<'a, InstanceNetworking, Self::Error> {
+        Box::pin(async move {
+            if self.lock().fail_wait {
+                return Err(PoolBackendError::Wait);
+            }
+            Ok(InstanceNetworking {
+                public_ip: IpAddr::V4(Ipv4Addr::LOCALHOST),
+                ssh_port: 22,
+            })
+        })
+    }
+
+    fn destroy(&self, handle: InstanceHandle) -> BackendFuture<'_, (), Self::Error> {
+        Box::pin(async move {
+            self.lock().destroyed.push(handle.id);
+            Ok(())
+        })
+    }
+}
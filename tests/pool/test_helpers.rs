@@ -0,0 +1,69 @@
+//! Shared fixtures for warm pool BDD scenarios.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use camino::Utf8PathBuf;
+use mriya::pool::TickReport;
+use mriya::{InstanceRequest, InstanceRequestBuilder, PoolManager, PoolStore};
+use rstest::fixture;
+use tempfile::TempDir;
+
+use super::test_doubles::PoolBackend;
+use crate::test_constants::DEFAULT_INSTANCE_TYPE;
+
+/// Idle timeout used by every scenario.
+pub const IDLE_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Time at which scenarios start the pool.
+pub const START: u64 = 1_000;
+
+#[derive(Clone, Debug)]
+pub struct PoolContext {
+    pub backend: PoolBackend,
+    pub store: PoolStore,
+    pub request: InstanceRequest,
+    pub size: usize,
+    pub report: Option<TickReport>,
+    pub claimed: Option<String>,
+    pub start_error: Option<String>,
+    pub(crate) _state_dir: Arc<TempDir>,
+}
+
+impl PoolContext {
+    pub fn manager(&self) -> PoolManager<PoolBackend> {
+        PoolManager::new(
+            self.backend.clone(),
+            self.store.clone(),
+            self.request.clone(),
+            self.size,
+        )
+        .with_idle_timeout(IDLE_TIMEOUT)
+    }
+}
+
+#[fixture]
+pub fn pool_context() -> PoolContext {
+    let state_dir = TempDir::new().unwrap_or_else(|err| panic!("tempdir: {err}"));
+    let root = Utf8PathBuf::from_path_buf(state_dir.path().to_path_buf())
+        .unwrap_or_else(|path| panic!("non-utf8 tempdir path: {}", path.display()));
+    let request = InstanceRequestBuilder::new()
+        .image_label("ubuntu")
+        .instance_type(DEFAULT_INSTANCE_TYPE)
+        .zone("fr-par-1")
+        .project_id("project")
+        .architecture("x86_64")
+        .build()
+        .unwrap_or_else(|err| panic!("builder fixture should be valid: {err}"));
+
+    PoolContext {
+        backend: PoolBackend::default(),
+        store: PoolStore::new(&root, &request),
+        request,
+        size: 1,
+        report: None,
+        claimed: None,
+        start_error: None,
+        _state_dir: Arc::new(state_dir),
+    }
+}
@@ -0,0 +1,6 @@
+//! Behavioural scenarios for the warm instance pool.
+
+#[path = "common/test_constants.rs"]
+mod test_constants;
+
+mod pool;
@@ -3,9 +3,13 @@
 //! This module contains the core step definitions. Cache-related steps are
 //! in the `cache_steps` submodule.
 
-use mriya::RunOrchestrator;
 use mriya::sync::{RemoteCommandOutput, Syncer};
+use mriya::{
+    InstanceHandle, InstanceNetworking, InstanceRequest, ProvisionedInstance, RetryPolicy,
+    RunOrchestrator,
+};
 use rstest_bdd_macros::{given, then, when};
+use std::net::{IpAddr, Ipv4Addr};
 use std::time::Duration;
 use tokio::runtime::Runtime;
 
@@ -112,6 +116,37 @@ fn mount_command_fails(run_context: RunContext) -> RunContext {
     run_context
 }
 
+#[given("a claimed pooled instance that answers")]
+fn claimed_instance_answers(mut run_context: RunContext) -> RunContext {
+    run_context.claimed = true;
+    run_context.runner.push_success();
+    run_context
+}
+
+#[given("a claimed pooled instance that no longer answers")]
+fn claimed_instance_unreachable(mut run_context: RunContext) -> RunContext {
+    run_context.claimed = true;
+    // SSH exits with 255 when it cannot reach the host.
+    run_context.runner.push_exit_code(255);
+    run_context
+}
+
+/// Builds the instance a run would claim from a warm pool.
+fn pooled_instance(request: InstanceRequest) -> ProvisionedInstance {
+    ProvisionedInstance {
+        handle: InstanceHandle {
+            id: String::from("pooled-id"),
+            zone: request.zone.clone(),
+            run_id: None,
+        },
+        networking: InstanceNetworking {
+            public_ip: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            ssh_port: 22,
+        },
+        request,
+    }
+}
+
 #[when("I orchestrate a remote run for \"{command}\"")]
 fn outcome(run_context: RunContext, command: String) -> Result<RunContext, StepError> {
     let runtime = Runtime::new().map_err(|err| StepError::Assertion(err.to_string()))?;
@@ -123,6 +158,12 @@ fn outcome(run_context: RunContext, command: String) -> Result<RunContext, StepE
         source,
         cloud_init_poll_interval_override,
         cloud_init_wait_timeout_override,
+        hooks,
+        retry_attempts,
+        shard_runner,
+        shard_exit_code,
+        session_results,
+        claimed,
         source_tmp,
         ..
     } = run_context;
@@ -130,7 +171,10 @@ fn outcome(run_context: RunContext, command: String) -> Result<RunContext, StepE
         .map_err(RunTestError::from)
         .map_err(StepError::from)?;
     let mut orchestrator: RunOrchestrator<ScriptedBackend, ScriptedRunner> =
-        RunOrchestrator::new(backend.clone(), syncer);
+        RunOrchestrator::new(backend.clone(), syncer)
+            .with_hooks(hooks.clone())
+            // Scenarios retry without waiting.
+            .with_retry(RetryPolicy::new(retry_attempts, Duration::ZERO));
     if let Some(interval) = cloud_init_poll_interval_override {
         orchestrator = orchestrator.with_cloud_init_poll_interval(interval);
     }
@@ -141,6 +185,12 @@ fn outcome(run_context: RunContext, command: String) -> Result<RunContext, StepE
     let request_clone = request.clone();
     let source_clone = source.clone();
     let result = runtime.block_on(async move {
+        if claimed {
+            let instance = pooled_instance(request_clone);
+            return orchestrator
+                .execute_claimed(instance, &source_clone, command.as_str())
+                .await;
+        }
         orchestrator
             .execute(&request_clone, &source_clone, command.as_str())
             .await
@@ -156,7 +206,11 @@ fn outcome(run_context: RunContext, command: String) -> Result<RunContext, StepE
                 mriya::RunError::ProvisioningTimeout { .. } => RunFailureKind::ProvisioningTimeout,
                 mriya::RunError::Sync { .. } => RunFailureKind::Sync,
                 mriya::RunError::Remote { .. } => RunFailureKind::Remote,
-                mriya::RunError::Teardown(_) => RunFailureKind::Teardown,
+                mriya::RunError::BeforeHook(_) => RunFailureKind::BeforeHook,
+                mriya::RunError::AfterHook(_) => RunFailureKind::AfterHook,
+                mriya::RunError::LocalBeforeHook(_) => RunFailureKind::LocalBeforeHook,
+                mriya::RunError::LocalAfterHook(_) => RunFailureKind::LocalAfterHook,
+                mriya::RunError::Teardown { .. } => RunFailureKind::Teardown,
             };
             RunResult::Failure(RunFailure {
                 kind,
@@ -173,7 +227,13 @@ fn outcome(run_context: RunContext, command: String) -> Result<RunContext, StepE
         source,
         cloud_init_poll_interval_override,
         cloud_init_wait_timeout_override,
+        hooks,
+        retry_attempts,
         outcome: Some(result_enum),
+        shard_runner,
+        shard_exit_code,
+        session_results,
+        claimed,
         source_tmp,
     })
 }
@@ -211,7 +271,7 @@ fn instance_destroyed(run_context: &RunContext) -> Result<(), StepError> {
     }
 }
 
-fn assert_failure_contains(
+pub fn assert_failure_contains(
     run_context: &RunContext,
     expected_substring: &str,
 ) -> Result<(), StepError> {
@@ -253,6 +313,11 @@ fn teardown_failure_note_present(run_context: &RunContext) -> Result<(), StepErr
     assert_failure_contains(run_context, "teardown also failed")
 }
 
+#[then("the run error names run \"{run_id}\"")]
+fn run_error_names_run(run_context: &RunContext, run_id: String) -> Result<(), StepError> {
+    assert_failure_contains(run_context, &format!("(run {run_id})"))
+}
+
 /// Direction for SSH invocation lookup.
 #[derive(Clone, Copy)]
 pub enum SshLookupDirection {
@@ -0,0 +1,87 @@
+//! BDD step definitions for remote hooks run around the command.
+
+use rstest_bdd_macros::{given, then};
+
+use super::bdd_steps::{StepError, assert_failure_contains};
+use super::test_helpers::{RunContext, RunFailureKind, RunResult};
+
+#[given("a remote before hook \"{command}\"")]
+fn remote_before_hook(mut run_context: RunContext, command: String) -> RunContext {
+    run_context.hooks.before.push(command);
+    run_context
+}
+
+#[given("a remote after hook \"{command}\"")]
+fn remote_after_hook(mut run_context: RunContext, command: String) -> RunContext {
+    run_context.hooks.after.push(command);
+    run_context
+}
+
+/// Returns the remote command of every `ssh` invocation.
+fn ssh_commands(run_context: &RunContext) -> Vec<String> {
+    let ssh_bin = run_context.sync_config.ssh_bin.as_str();
+    run_context
+        .runner
+        .invocations()
+        .into_iter()
+        .filter(|invocation| invocation.program == ssh_bin)
+        .filter_map(|invocation| invocation.args.last().cloned())
+        .map(|command| command.to_string_lossy().into_owned())
+        .collect()
+}
+
+#[then("the remote commands run in the order \"{order}\"")]
+fn remote_commands_in_order(run_context: &RunContext, order: String) -> Result<(), StepError> {
+    let commands = ssh_commands(run_context);
+    let positions = order
+        .split("; ")
+        .map(|expected| {
+            commands
+                .iter()
+                .position(|command| command.ends_with(expected))
+                .ok_or_else(|| {
+                    StepError::Assertion(format!("`{expected}` never ran in {commands:?}"))
+                })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if positions.is_sorted() {
+        Ok(())
+    } else {
+        Err(StepError::Assertion(format!(
+            "expected the order {order}, got {commands:?}"
+        )))
+    }
+}
+
+#[then("no remote command runs \"{command}\"")]
+fn remote_command_skipped(run_context: &RunContext, command: String) -> Result<(), StepError> {
+    let commands = ssh_commands(run_context);
+    if commands.iter().any(|remote| remote.ends_with(&command)) {
+        Err(StepError::Assertion(format!(
+            "`{command}` should not run, got {commands:?}"
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+fn assert_failure_kind(run_context: &RunContext, kind: &RunFailureKind) -> Result<(), StepError> {
+    match &run_context.outcome {
+        Some(RunResult::Failure(failure)) if failure.kind == *kind => Ok(()),
+        other => Err(StepError::Assertion(format!(
+            "expected a {kind:?} failure, got {other:?}"
+        ))),
+    }
+}
+
+#[then("the run error is a before hook failure for \"{reason}\"")]
+fn before_hook_failure(run_context: &RunContext, reason: String) -> Result<(), StepError> {
+    assert_failure_kind(run_context, &RunFailureKind::BeforeHook)?;
+    assert_failure_contains(run_context, &reason)
+}
+
+#[then("the run error is an after hook failure for \"{reason}\"")]
+fn after_hook_failure(run_context: &RunContext, reason: String) -> Result<(), StepError> {
+    assert_failure_kind(run_context, &RunFailureKind::AfterHook)?;
+    assert_failure_contains(run_context, &reason)
+}
@@ -2,6 +2,11 @@
 
 mod bdd_steps;
 mod cache_steps;
+mod hook_steps;
+mod retry_steps;
 mod scenarios;
+mod session_steps;
+mod shard_steps;
+mod ssh_steps;
 mod test_doubles;
 mod test_helpers;
@@ -0,0 +1,63 @@
+//! BDD step definitions for retries of transient provisioning failures.
+
+use rstest_bdd_macros::{given, then};
+
+use super::bdd_steps::{StepError, assert_failure_contains};
+use super::test_doubles::ScriptedBackendError;
+use super::test_helpers::{RunContext, RunFailureKind, RunResult};
+
+#[given("provisioning is attempted at most \"{attempts}\" times")]
+fn retry_attempts(mut run_context: RunContext, attempts: u32) -> RunContext {
+    run_context.retry_attempts = attempts;
+    run_context
+}
+
+#[given("instance creation fails transiently \"{count}\" times")]
+fn create_fails_transiently(run_context: RunContext, count: u32) -> RunContext {
+    for _ in 0..count {
+        run_context
+            .backend
+            .fail_next_create(ScriptedBackendError::Unavailable);
+    }
+    run_context
+}
+
+#[given("instance creation is rejected")]
+fn create_rejected(run_context: RunContext) -> RunContext {
+    run_context
+        .backend
+        .fail_next_create(ScriptedBackendError::Rejected);
+    run_context
+}
+
+#[given("the instance never becomes ready once")]
+fn readiness_times_out(run_context: RunContext) -> RunContext {
+    run_context
+        .backend
+        .fail_next_wait(ScriptedBackendError::ReadinessTimeout);
+    run_context
+}
+
+#[then("instance creation is attempted \"{count}\" times")]
+fn create_attempts(run_context: &RunContext, count: u32) -> Result<(), StepError> {
+    let calls = run_context.backend.create_calls();
+    if calls == count {
+        Ok(())
+    } else {
+        Err(StepError::Assertion(format!(
+            "expected {count} create attempts, got {calls}"
+        )))
+    }
+}
+
+#[then("the run error is a create failure for \"{reason}\"")]
+fn create_failure(run_context: &RunContext, reason: String) -> Result<(), StepError> {
+    match &run_context.outcome {
+        Some(RunResult::Failure(failure)) if failure.kind == RunFailureKind::Provision => {
+            assert_failure_contains(run_context, &reason)
+        }
+        other => Err(StepError::Assertion(format!(
+            "expected a create failure, got {other:?}"
+        ))),
+    }
+}
@@ -99,3 +99,139 @@ fn scenario_create_cache_subdirectories(run_context: RunContext) {
 fn scenario_disable_cache_directory_creation(run_context: RunContext) {
     let _ = run_context;
 }
+
+#[scenario(
+    path = "tests/features/run.feature",
+    name = "Run shards concurrently and aggregate their exit codes"
+)]
+fn scenario_run_shards(run_context: RunContext) {
+    let _ = run_context;
+}
+
+#[scenario(
+    path = "tests/features/run.feature",
+    name = "Run several commands on one instance in a session"
+)]
+fn scenario_session_commands(run_context: RunContext) {
+    let _ = run_context;
+}
+
+#[scenario(
+    path = "tests/features/run.feature",
+    name = "Keep the session instance after a failed sync"
+)]
+fn scenario_session_survives_sync_failure(run_context: RunContext) {
+    let _ = run_context;
+}
+
+#[scenario(
+    path = "tests/features/run.feature",
+    name = "Close the shared connection when a session outlives its command"
+)]
+fn scenario_session_closes_connection(run_context: RunContext) {
+    let _ = run_context;
+}
+
+#[scenario(
+    path = "tests/features/run.feature",
+    name = "Share one SSH connection across the phases of a run"
+)]
+fn scenario_share_ssh_connection(run_context: RunContext) {
+    let _ = run_context;
+}
+
+#[scenario(
+    path = "tests/features/run.feature",
+    name = "Run after hooks whatever the command's exit code"
+)]
+fn scenario_after_hooks_always_run(run_context: RunContext) {
+    let _ = run_context;
+}
+
+#[scenario(
+    path = "tests/features/run.feature",
+    name = "Skip the command but run after hooks when a before hook fails"
+)]
+fn scenario_before_hook_failure(run_context: RunContext) {
+    let _ = run_context;
+}
+
+#[scenario(
+    path = "tests/features/run.feature",
+    name = "Run after hooks when the sync fails"
+)]
+fn scenario_after_hooks_on_sync_failure(run_context: RunContext) {
+    let _ = run_context;
+}
+
+#[scenario(
+    path = "tests/features/run.feature",
+    name = "Report a failed after hook once the command has run"
+)]
+fn scenario_after_hook_failure(run_context: RunContext) {
+    let _ = run_context;
+}
+
+#[scenario(
+    path = "tests/features/run.feature",
+    name = "Retry transient create failures"
+)]
+fn scenario_retry_transient_create(run_context: RunContext) {
+    let _ = run_context;
+}
+
+#[scenario(
+    path = "tests/features/run.feature",
+    name = "Give up after the configured provisioning attempts"
+)]
+fn scenario_retry_gives_up(run_context: RunContext) {
+    let _ = run_context;
+}
+
+#[scenario(
+    path = "tests/features/run.feature",
+    name = "Do not retry a rejected create"
+)]
+fn scenario_rejected_create_not_retried(run_context: RunContext) {
+    let _ = run_context;
+}
+
+#[scenario(
+    path = "tests/features/run.feature",
+    name = "Destroy an instance that never became ready before retrying"
+)]
+fn scenario_unready_instance_destroyed_before_retry(run_context: RunContext) {
+    let _ = run_context;
+}
+
+#[scenario(
+    path = "tests/features/run.feature",
+    name = "Do not retry when the failed instance could not be destroyed"
+)]
+fn scenario_no_retry_after_failed_teardown(run_context: RunContext) {
+    let _ = run_context;
+}
+
+#[scenario(
+    path = "tests/features/run.feature",
+    name = "Do not reprovision once the command has started"
+)]
+fn scenario_no_reprovision_after_command(run_context: RunContext) {
+    let _ = run_context;
+}
+
+#[scenario(
+    path = "tests/features/run.feature",
+    name = "Run on a claimed pooled instance that still answers"
+)]
+fn scenario_run_on_claimed_instance(run_context: RunContext) {
+    let _ = run_context;
+}
+
+#[scenario(
+    path = "tests/features/run.feature",
+    name = "Replace a claimed pooled instance that no longer answers"
+)]
+fn scenario_replace_unreachable_claimed_instance(run_context: RunContext) {
+    let _ = run_context;
+}
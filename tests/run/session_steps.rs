@@ -0,0 +1,122 @@
+//! BDD step definitions for sessions that run several commands on one
+//! instance.
+
+use mriya::{RunError, RunOrchestrator, Syncer};
+use rstest_bdd_macros::{then, when};
+use tokio::runtime::Runtime;
+
+use super::bdd_steps::StepError;
+use super::test_helpers::{RunContext, RunTestError};
+
+#[when("I run \"{first}\" then \"{second}\" in one session")]
+fn run_in_session(
+    mut run_context: RunContext,
+    first: String,
+    second: String,
+) -> Result<RunContext, StepError> {
+    let runtime = Runtime::new().map_err(|err| StepError::Assertion(err.to_string()))?;
+    let syncer = Syncer::new(run_context.sync_config.clone(), run_context.runner.clone())
+        .map_err(RunTestError::from)?;
+    let orchestrator = RunOrchestrator::new(run_context.backend.clone(), syncer);
+    let request = run_context.request.clone();
+    let source = run_context.source.clone();
+
+    let results = runtime.block_on(async {
+        let mut session = orchestrator
+            .start(&request)
+            .await
+            .map_err(|err| StepError::Assertion(format!("session start failed: {err}")))?;
+        let mut results = Vec::new();
+        for command in [&first, &second] {
+            let result = orchestrator.exec(&mut session, &source, command).await;
+            results.push(match result {
+                Ok(output) => output
+                    .exit_code
+                    .map_or_else(|| String::from("none"), |code| code.to_string()),
+                Err(RunError::Sync { .. }) => String::from("sync-error"),
+                Err(err) => err.to_string(),
+            });
+        }
+        orchestrator
+            .stop(session)
+            .await
+            .map_err(|err| StepError::Assertion(format!("session stop failed: {err}")))?;
+        Ok::<_, StepError>(results)
+    })?;
+    run_context.session_results = results;
+    Ok(run_context)
+}
+
+#[when("I run \"{command}\" in a session and leave it running")]
+fn run_and_detach(run_context: RunContext, command: String) -> Result<RunContext, StepError> {
+    let runtime = Runtime::new().map_err(|err| StepError::Assertion(err.to_string()))?;
+    let syncer = Syncer::new(run_context.sync_config.clone(), run_context.runner.clone())
+        .map_err(RunTestError::from)?;
+    let orchestrator = RunOrchestrator::new(run_context.backend.clone(), syncer);
+    let request = run_context.request.clone();
+    let source = run_context.source.clone();
+
+    runtime.block_on(async {
+        let mut session = orchestrator
+            .start(&request)
+            .await
+            .map_err(|err| StepError::Assertion(format!("session start failed: {err}")))?;
+        let result = orchestrator.exec(&mut session, &source, &command).await;
+        orchestrator.detach(&session).await;
+        result
+            .map(drop)
+            .map_err(|err| StepError::Assertion(format!("session exec failed: {err}")))
+    })?;
+    Ok(run_context)
+}
+
+#[then("the session commands end with \"{first}\" then \"{second}\"")]
+fn session_results(
+    run_context: &RunContext,
+    first: String,
+    second: String,
+) -> Result<(), StepError> {
+    if run_context.session_results == [first.clone(), second.clone()] {
+        Ok(())
+    } else {
+        Err(StepError::Assertion(format!(
+            "expected [{first}, {second}], got {:?}",
+            run_context.session_results
+        )))
+    }
+}
+
+#[then("the workspace is synced \"{count}\" times")]
+fn workspace_synced(run_context: &RunContext, count: usize) -> Result<(), StepError> {
+    let rsync_bin = run_context.sync_config.rsync_bin.as_str();
+    let syncs = run_context
+        .runner
+        .invocations()
+        .iter()
+        .filter(|invocation| invocation.program == rsync_bin)
+        .count();
+    if syncs == count {
+        Ok(())
+    } else {
+        Err(StepError::Assertion(format!(
+            "expected {count} syncs, got {syncs}"
+        )))
+    }
+}
+
+#[then("cloud-init readiness is checked once")]
+fn cloud_init_checked_once(run_context: &RunContext) -> Result<(), StepError> {
+    let checks = run_context
+        .runner
+        .invocations()
+        .iter()
+        .filter(|invocation| invocation.command_string().contains("boot-finished"))
+        .count();
+    if checks == 1 {
+        Ok(())
+    } else {
+        Err(StepError::Assertion(format!(
+            "expected one cloud-init check, got {checks}"
+        )))
+    }
+}
@@ -0,0 +1,84 @@
+//! BDD step definitions for sharded runs.
+
+use mriya::parallel::shard_command;
+use mriya::{ParallelRun, RunOrchestrator, Syncer, aggregate_exit_code, run_parallel};
+use rstest_bdd_macros::{given, then, when};
+use tokio::runtime::Builder;
+
+use super::bdd_steps::StepError;
+use super::test_helpers::{RunContext, RunTestError};
+
+#[given("shard \"{index}\" returns exit code \"{code}\"")]
+fn shard_fails(run_context: RunContext, index: usize, code: i32) -> RunContext {
+    run_context.shard_runner.fail_shard(index, code);
+    run_context
+}
+
+#[when("I orchestrate \"{count}\" shards for \"{command}\"")]
+fn orchestrate_shards(
+    mut run_context: RunContext,
+    count: usize,
+    command: String,
+) -> Result<RunContext, StepError> {
+    let runs = (1..=count)
+        .map(|index| {
+            let syncer = Syncer::new(
+                run_context.sync_config.clone(),
+                run_context.shard_runner.clone(),
+            )
+            .map_err(RunTestError::from)?;
+            Ok(ParallelRun {
+                label: format!("shard {index}/{count}"),
+                orchestrator: RunOrchestrator::new(run_context.backend.clone(), syncer),
+                request: run_context.request.clone(),
+                remote_command: shard_command(index, count, &command),
+            })
+        })
+        .collect::<Result<Vec<_>, RunTestError>>()
+        .map_err(StepError::from)?;
+
+    let runtime = Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .map_err(|err| StepError::Assertion(err.to_string()))?;
+    let source = run_context.source.clone();
+    let outcomes = runtime.block_on(async move { run_parallel(runs, &source).await });
+    run_context.shard_exit_code = aggregate_exit_code(&outcomes);
+    Ok(run_context)
+}
+
+#[then("the aggregate exit code is \"{code}\"")]
+fn aggregate_exit(run_context: &RunContext, code: i32) -> Result<(), StepError> {
+    match run_context.shard_exit_code {
+        Some(actual) if actual == code => Ok(()),
+        other => Err(StepError::Assertion(format!(
+            "expected aggregate exit code {code}, got {other:?}"
+        ))),
+    }
+}
+
+#[then("every shard sees its index out of \"{count}\"")]
+fn shard_indices_exported(run_context: &RunContext, count: usize) -> Result<(), StepError> {
+    let commands = run_context.shard_runner.remote_commands();
+    for index in 1..=count {
+        let expected = format!("MRIYA_SHARD_INDEX={index} MRIYA_SHARD_COUNT={count} &&");
+        if !commands.iter().any(|command| command.contains(&expected)) {
+            return Err(StepError::Assertion(format!(
+                "no remote command exported `{expected}`: {commands:?}"
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[then("\"{count}\" instances are destroyed")]
+fn instances_destroyed(run_context: &RunContext, count: u32) -> Result<(), StepError> {
+    let calls = run_context.backend.destroy_calls();
+    if calls == count {
+        Ok(())
+    } else {
+        Err(StepError::Assertion(format!(
+            "expected {count} destroy calls, got {calls}"
+        )))
+    }
+}
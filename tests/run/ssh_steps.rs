@@ -0,0 +1,81 @@
+//! BDD step definitions for SSH connection sharing across run phases.
+
+use rstest_bdd_macros::{given, then};
+
+use super::bdd_steps::StepError;
+use super::test_helpers::RunContext;
+
+#[given("SSH multiplexing is enabled")]
+fn ssh_multiplexing_enabled(mut run_context: RunContext) -> RunContext {
+    run_context.sync_config.ssh_multiplex = true;
+    run_context
+}
+
+/// Returns the `ControlPath` option carried by `args`, if any.
+fn control_path(args: &[String]) -> Option<&str> {
+    args.iter()
+        .find_map(|arg| arg.strip_prefix("ControlPath="))
+        .or_else(|| {
+            args.iter()
+                .flat_map(|arg| arg.split_whitespace())
+                .find_map(|arg| arg.strip_prefix("ControlPath="))
+        })
+}
+
+#[then("every SSH phase shares one control socket")]
+fn phases_share_control_socket(run_context: &RunContext) -> Result<(), StepError> {
+    let invocations = run_context.runner.invocations();
+    let mut paths = Vec::new();
+    for invocation in &invocations {
+        let args: Vec<String> = invocation
+            .args
+            .iter()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect();
+        let path = control_path(&args).ok_or_else(|| {
+            StepError::Assertion(format!(
+                "{} should use the shared connection: {args:?}",
+                invocation.program
+            ))
+        })?;
+        paths.push(path.to_owned());
+    }
+    paths.dedup();
+    match paths.as_slice() {
+        [path] if path.ends_with("/%C") => Ok(()),
+        _ => Err(StepError::Assertion(format!(
+            "expected one control path, found {paths:?}"
+        ))),
+    }
+}
+
+#[then("the shared connection is closed before teardown")]
+fn shared_connection_closed(run_context: &RunContext) -> Result<(), StepError> {
+    closed_last(run_context)
+}
+
+#[then("the shared connection is closed last")]
+fn shared_connection_closed_last(run_context: &RunContext) -> Result<(), StepError> {
+    closed_last(run_context)
+}
+
+/// Checks that the last command run was `ssh -O exit`.
+fn closed_last(run_context: &RunContext) -> Result<(), StepError> {
+    let invocations = run_context.runner.invocations();
+    let last = invocations
+        .last()
+        .ok_or_else(|| StepError::Assertion(String::from("no commands were run")))?;
+    let closes = last.program == run_context.sync_config.ssh_bin
+        && last
+            .args
+            .windows(2)
+            .any(|pair| matches!(pair, [flag, action] if flag == "-O" && action == "exit"));
+    if closes {
+        Ok(())
+    } else {
+        Err(StepError::Assertion(format!(
+            "expected `ssh -O exit` last, found {}",
+            last.command_string()
+        )))
+    }
+}
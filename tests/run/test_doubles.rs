@@ -1,12 +1,20 @@
 //! Test doubles for the run orchestrator.
 //!
-//! Provides a scripted backend that records teardown attempts and allows
-//! controlled failure of the destroy phase to exercise teardown handling.
+//! Provides a scripted backend that records create and teardown attempts and
+//! allows controlled failure of the create, readiness, and destroy phases to
+//! exercise retries and teardown handling, plus a thread-safe command runner
+//! for sharded runs.
 
+use std::collections::VecDeque;
+use std::ffi::OsString;
 use std::net::{IpAddr, Ipv4Addr};
 use std::sync::{Arc, Mutex};
 
-use mriya::{Backend, InstanceHandle, InstanceNetworking, InstanceRequest, backend::BackendFuture};
+use mriya::sync::{AsyncCommandRunner, CommandOutput, SyncError};
+use mriya::{
+    Backend, InstanceHandle, InstanceNetworking, InstanceRequest, TransientError,
+    backend::BackendFuture,
+};
 use thiserror::Error;
 
 /// Test double for [`Backend`] that wraps shared [`State`] to record teardown
@@ -21,6 +29,9 @@ pub struct ScriptedBackend {
 struct State {
     fail_on_destroy: bool,
     destroy_calls: u32,
+    create_calls: u32,
+    create_failures: VecDeque<ScriptedBackendError>,
+    wait_failures: VecDeque<ScriptedBackendError>,
 }
 
 impl ScriptedBackend {
@@ -38,10 +49,27 @@ impl ScriptedBackend {
     }
 
     pub fn destroy_calls(&self) -> u32 {
+        self.lock().destroy_calls
+    }
+
+    pub fn create_calls(&self) -> u32 {
+        self.lock().create_calls
+    }
+
+    /// Makes the next create fail with `error`.
+    pub fn fail_next_create(&self, error: ScriptedBackendError) {
+        self.lock().create_failures.push_back(error);
+    }
+
+    /// Makes the next readiness wait fail with `error`.
+    pub fn fail_next_wait(&self, error: ScriptedBackendError) {
+        self.lock().wait_failures.push_back(error);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
         self.state
             .lock()
             .unwrap_or_else(std::sync::PoisonError::into_inner)
-            .destroy_calls
     }
 }
 
@@ -52,6 +80,21 @@ pub enum ScriptedBackendError {
     /// Raised when the scripted backend is instructed to fail teardown.
     #[error("destroy failure")]
     Destroy,
+    /// Transient create failure, such as exhausted stock.
+    #[error("temporarily unavailable")]
+    Unavailable,
+    /// Permanent create failure, such as an invalid request.
+    #[error("request rejected")]
+    Rejected,
+    /// Transient readiness failure.
+    #[error("readiness timed out")]
+    ReadinessTimeout,
+}
+
+impl TransientError for ScriptedBackendError {
+    fn is_transient(&self) -> bool {
+        matches!(self, Self::Unavailable | Self::ReadinessTimeout)
+    }
 }
 
 impl Backend for ScriptedBackend {
@@ -62,9 +105,15 @@ impl Backend for ScriptedBackend {
         _request: &'a InstanceRequest,
     ) -> BackendFuture<'a, InstanceHandle, Self::Error> {
         Box::pin(async move {
+            let mut state = self.lock();
+            state.create_calls += 1;
+            if let Some(error) = state.create_failures.pop_front() {
+                return Err(error);
+            }
             Ok(InstanceHandle {
                 id: String::from("scripted-id"),
                 zone: String::from("test-zone"),
+                run_id: Some(String::from("scripted-run")),
             })
         })
     }
@@ -74,6 +123,9 @@ impl Backend for ScriptedBackend {
         _handle: &'a InstanceHandle,
     ) -> BackendFuture<'a, InstanceNetworking, Self::Error> {
         Box::pin(async move {
+            if let Some(error) = self.lock().wait_failures.pop_front() {
+                return Err(error);
+            }
             Ok(InstanceNetworking {
                 public_ip: IpAddr::V4(Ipv4Addr::LOCALHOST),
                 ssh_port: 22,
@@ -83,10 +135,7 @@ impl Backend for ScriptedBackend {
 
     fn destroy(&self, _handle: InstanceHandle) -> BackendFuture<'_, (), Self::Error> {
         Box::pin(async move {
-            let mut state = self
-                .state
-                .lock()
-                .unwrap_or_else(std::sync::PoisonError::into_inner);
+            let mut state = self.lock();
             state.destroy_calls += 1;
             if state.fail_on_destroy {
                 return Err(ScriptedBackendError::Destroy);
@@ -95,3 +144,62 @@ impl Backend for ScriptedBackend {
         })
     }
 }
+
+/// Thread-safe command runner for sharded runs.
+///
+/// Every invocation succeeds except the SSH command of the shard configured
+/// to fail, which reports the scripted exit code. Remote commands are recorded
+/// so scenarios can check the exported shard variables.
+#[derive(Clone, Debug, Default)]
+pub struct ShardRunner {
+    state: Arc<Mutex<ShardRunnerState>>,
+}
+
+#[derive(Debug, Default)]
+struct ShardRunnerState {
+    failing_shard: Option<(usize, i32)>,
+    remote_commands: Vec<String>,
+}
+
+impl ShardRunner {
+    pub fn fail_shard(&self, index: usize, code: i32) {
+        self.state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .failing_shard = Some((index, code));
+    }
+
+    pub fn remote_commands(&self) -> Vec<String> {
+        self.state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .remote_commands
+            .clone()
+    }
+}
+
+impl AsyncCommandRunner for ShardRunner {
+    async fn run(&self, program: &str, args: &[OsString]) -> Result<CommandOutput, SyncError> {
+        let mut state = self
+            .state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let mut code = 0;
+        if program == "ssh"
+            && let Some(last) = args.last()
+        {
+            let remote = last.to_string_lossy().into_owned();
+            if let Some((index, failure)) = state.failing_shard
+                && remote.contains(&format!("MRIYA_SHARD_INDEX={index} "))
+            {
+                code = failure;
+            }
+            state.remote_commands.push(remote);
+        }
+        Ok(CommandOutput {
+            code: Some(code),
+            stdout: String::new(),
+            stderr: String::new(),
+        })
+    }
+}
@@ -3,13 +3,14 @@
 use std::time::Duration;
 
 use camino::Utf8PathBuf;
+use mriya::retry::DEFAULT_ATTEMPTS;
 use mriya::sync::{RemoteCommandOutput, SyncConfig, SyncError};
-use mriya::{InstanceRequest, InstanceRequestBuilder};
+use mriya::{Hooks, InstanceRequest, InstanceRequestBuilder};
 use rstest::fixture;
 use tempfile::TempDir;
 use thiserror::Error;
 
-use super::test_doubles::ScriptedBackend;
+use super::test_doubles::{ScriptedBackend, ShardRunner};
 use crate::sync_config::sync_config;
 use crate::test_constants::DEFAULT_INSTANCE_TYPE;
 use mriya::test_support::ScriptedRunner;
@@ -23,7 +24,13 @@ pub struct RunContext {
     pub source: Utf8PathBuf,
     pub cloud_init_poll_interval_override: Option<Duration>,
     pub cloud_init_wait_timeout_override: Option<Duration>,
+    pub hooks: Hooks,
+    pub retry_attempts: u32,
     pub outcome: Option<RunResult>,
+    pub shard_runner: ShardRunner,
+    pub shard_exit_code: Option<i32>,
+    pub session_results: Vec<String>,
+    pub claimed: bool,
     pub(crate) source_tmp: std::sync::Arc<TempDir>,
 }
 
@@ -35,6 +42,10 @@ pub enum RunFailureKind {
     ProvisioningTimeout,
     Sync,
     Remote,
+    BeforeHook,
+    AfterHook,
+    LocalBeforeHook,
+    LocalAfterHook,
     Teardown,
 }
 
@@ -86,7 +97,13 @@ pub fn build_run_context() -> Result<RunContext, RunTestError> {
         source,
         cloud_init_poll_interval_override: None,
         cloud_init_wait_timeout_override: None,
+        hooks: Hooks::default(),
+        retry_attempts: DEFAULT_ATTEMPTS,
         outcome: None,
+        shard_runner: ShardRunner::default(),
+        shard_exit_code: None,
+        session_results: Vec::new(),
+        claimed: false,
         source_tmp: std::sync::Arc::new(tmp_dir),
     })
 }
@@ -3,11 +3,11 @@
 
 use camino::Utf8PathBuf;
 use cap_std::{ambient_authority, fs_utf8::Dir};
-use mriya::sync::{SyncConfig, SyncDestination, SyncError, Syncer};
+use mriya::sync::{SyncConfig, SyncDestination, SyncError, SyncMode, Syncer};
 use rstest_bdd_macros::{given, then, when};
 
 use super::test_doubles::LocalCopyRunner;
-use super::test_helpers::{ScriptedContext, StepError, Workspace};
+use super::test_helpers::{ScriptedContext, StepError, Workspace, block_on};
 
 #[given("a workspace with a gitignored cache on the remote")]
 fn workspace_with_cache(workspace: Workspace) -> Result<Workspace, StepError> {
@@ -38,7 +38,95 @@ fn workspace_with_cache(workspace: Workspace) -> Result<Workspace, StepError> {
 
 #[when("I run git-aware rsync sync to the remote path")]
 fn run_git_aware_sync(workspace: Workspace) -> Result<Workspace, StepError> {
-    let config = SyncConfig {
+    sync_workspace(&workspace, Vec::new(), Vec::new(), SyncMode::RsyncFilter)?;
+    Ok(workspace)
+}
+
+#[given("a workspace with gitignored and bulky files")]
+fn workspace_with_rule_targets(workspace: Workspace) -> Result<Workspace, StepError> {
+    let root = &workspace.local_root;
+    super::test_helpers::write_file(
+        root.join(".gitignore").as_path(),
+        "generated/
+.env.test
+",
+    )?;
+    super::test_helpers::write_file(
+        root.join(".mriyaignore").as_path(),
+        "fixtures/
+",
+    )?;
+    super::test_helpers::write_file(root.join("src").join("lib.rs").as_path(), "// lib\n")?;
+    super::test_helpers::write_file(root.join(".env.test").as_path(), "TOKEN=test\n")?;
+    super::test_helpers::write_file(
+        root.join("generated").join("api.rs").as_path(),
+        "// generated\n",
+    )?;
+    super::test_helpers::write_file(root.join("docs").join("guide.md").as_path(), "# Guide\n")?;
+    super::test_helpers::write_file(root.join("fixtures").join("large.bin").as_path(), "bulk")?;
+    Ok(workspace)
+}
+
+#[when("I sync including \"{include}\" and excluding \"{exclude}\"")]
+fn run_rule_aware_sync(
+    workspace: Workspace,
+    include: String,
+    exclude: String,
+) -> Result<Workspace, StepError> {
+    let split = |patterns: &str| patterns.split(',').map(str::to_owned).collect();
+    sync_workspace(
+        &workspace,
+        split(&include),
+        split(&exclude),
+        SyncMode::RsyncFilter,
+    )?;
+    Ok(workspace)
+}
+
+#[then("the remote contains \"{paths}\"")]
+fn remote_contains(workspace: &Workspace, paths: String) -> Result<(), StepError> {
+    check_remote_paths(workspace, &paths, true)
+}
+
+#[then("the remote lacks \"{paths}\"")]
+fn remote_lacks(workspace: &Workspace, paths: String) -> Result<(), StepError> {
+    check_remote_paths(workspace, &paths, false)
+}
+
+pub(super) fn check_remote_paths(
+    workspace: &Workspace,
+    paths: &str,
+    expected: bool,
+) -> Result<(), StepError> {
+    for path in paths.split(',') {
+        if workspace.remote_root.join(path).exists() != expected {
+            return Err(StepError::Assertion(format!(
+                "expected {path} to be {} on the remote",
+                if expected { "present" } else { "absent" }
+            )));
+        }
+    }
+    Ok(())
+}
+
+pub(super) fn sync_workspace(
+    workspace: &Workspace,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    sync_mode: SyncMode,
+) -> Result<(), StepError> {
+    let config = workspace_sync_config(workspace, include, exclude, sync_mode);
+    sync_with_config(workspace, config)
+}
+
+/// Builds the configuration `sync_workspace` uses, for steps that adjust it.
+pub(super) fn workspace_sync_config(
+    workspace: &Workspace,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    sync_mode: SyncMode,
+) -> SyncConfig {
+    SyncConfig {
         rsync_bin: String::from("rsync"),
         ssh_bin: String::from("ssh"),
         ssh_user: String::from("ubuntu"),
@@ -46,19 +134,34 @@ fn run_git_aware_sync(workspace: Workspace) -> Result<Workspace, StepError> {
         ssh_batch_mode: true,
         ssh_strict_host_key_checking: false,
         ssh_known_hosts_file: String::from("/dev/null"),
+        ssh_multiplex: false,
         ssh_identity_file: Some(String::from("~/.ssh/id_ed25519")),
-        volume_mount_path: String::from("/mriya"),
+        volume_mount_path: workspace.volume_root.to_string(),
         route_build_caches: true,
         create_cache_directories: true,
-    };
+        include,
+        exclude,
+        sync_mode,
+        git_bin: String::from("git"),
+        git_include_untracked: false,
+        git_lfs_pull: true,
+        git_remote: String::from("origin"),
+        tar_stream: false,
+        max_sync_bytes: None,
+        secret_patterns: Vec::new(),
+        secret_signatures: Vec::new(),
+        secret_allowlist: Vec::new(),
+    }
+}
 
+/// Syncs the workspace to its simulated remote using `config`.
+pub(super) fn sync_with_config(workspace: &Workspace, config: SyncConfig) -> Result<(), StepError> {
     let syncer = Syncer::new(config, LocalCopyRunner)?;
     let destination = SyncDestination::Local {
         path: workspace.remote_root.clone(),
     };
-    syncer.sync(&workspace.local_root, &destination)?;
-
-    Ok(workspace)
+    block_on(syncer.sync(&workspace.local_root, &destination))??;
+    Ok(())
 }
 
 #[then("the gitignored cache directory remains after sync")]
@@ -119,13 +222,12 @@ fn remote_command_exits(
         scripted_context_val.config.clone(),
         scripted_context_val.runner.clone(),
     )?;
-    syncer
-        .sync_and_run(
-            &scripted_context_val.source,
-            &scripted_context_val.networking,
-            "echo ok",
-        )
-        .map_err(StepError::from)
+    block_on(syncer.sync_and_run(
+        &scripted_context_val.source,
+        &scripted_context_val.networking,
+        "echo ok",
+    ))?
+    .map_err(StepError::from)
 }
 
 #[then("the orchestrator reports exit code \"{code}\"")]
@@ -164,7 +266,7 @@ fn trigger_sync(scripted_context: ScriptedContext) -> Result<SyncError, StepErro
         port: scripted_context_val.networking.ssh_port,
         path: Utf8PathBuf::from(&scripted_context_val.config.remote_path),
     };
-    let result = syncer.sync(&scripted_context_val.source, &destination);
+    let result = block_on(syncer.sync(&scripted_context_val.source, &destination))?;
     match result {
         Ok(()) => Err(StepError::Assertion(
             "ssh command should not run when sync succeeds".to_owned(),
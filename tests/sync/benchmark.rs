@@ -0,0 +1,104 @@
+//! Benchmark comparing the `tar | zstd` stream with rsync for a fresh
+//! destination.
+//!
+//! Both strategies sync the same tree of small files into empty local
+//! directories through the real process runner, and the resulting trees must
+//! match. Timings are reported on stderr; nothing asserts on them, since they
+//! depend on the machine. The benchmark is skipped when `rsync` or `zstd` is
+//! not installed.
+
+use std::collections::BTreeMap;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use mriya::sync::{SyncDestination, SyncMode, Syncer};
+use rstest::rstest;
+
+use super::bdd_steps::workspace_sync_config;
+use super::test_helpers::{StepError, Workspace, block_on, workspace, write_file};
+
+/// Number of small files in the benchmark tree.
+const FILES: usize = 5_000;
+
+fn installed(program: &str) -> bool {
+    Command::new(program)
+        .arg("--version")
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+fn timed_sync(
+    workspace: &Workspace,
+    tar_stream: bool,
+    destination: &Utf8Path,
+) -> Result<Duration, StepError> {
+    let mut config =
+        workspace_sync_config(workspace, Vec::new(), Vec::new(), SyncMode::RsyncFilter);
+    config.tar_stream = tar_stream;
+    let syncer = Syncer::with_process_runner(config)?;
+    let target = SyncDestination::Local {
+        path: destination.to_path_buf(),
+    };
+    let started = Instant::now();
+    block_on(syncer.sync(&workspace.local_root, &target))??;
+    Ok(started.elapsed())
+}
+
+/// Reads every file below `root`, keyed by its relative path.
+fn snapshot(root: &Utf8Path) -> Result<BTreeMap<Utf8PathBuf, String>, StepError> {
+    let mut files = BTreeMap::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries = std::fs::read_dir(&dir)
+            .map_err(|err| StepError::Assertion(format!("failed to list {dir}: {err}")))?;
+        for entry in entries.flatten() {
+            let Ok(path) = Utf8PathBuf::from_path_buf(entry.path()) else {
+                continue;
+            };
+            if path.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            let contents = std::fs::read_to_string(&path)
+                .map_err(|err| StepError::Assertion(format!("failed to read {path}: {err}")))?;
+            let relative = path.strip_prefix(root).unwrap_or(&path).to_path_buf();
+            files.insert(relative, contents);
+        }
+    }
+    Ok(files)
+}
+
+#[rstest]
+#[expect(clippy::print_stderr, reason = "the benchmark reports its timings")]
+fn tar_stream_matches_rsync_on_an_empty_destination(workspace: Workspace) -> Result<(), StepError> {
+    if !installed("rsync") || !installed("zstd") {
+        eprintln!("skipping sync benchmark: rsync and zstd are required");
+        return Ok(());
+    }
+    let root = &workspace.local_root;
+    write_file(root.join(".gitignore").as_path(), "target/\n")?;
+    write_file(root.join("target").join("cache.bin").as_path(), "ignored")?;
+    for index in 0..FILES {
+        let module = index.checked_rem(100).unwrap_or_default();
+        write_file(
+            root.join(format!("src/module_{module}/file_{index}.rs"))
+                .as_path(),
+            &format!("pub const VALUE: usize = {index};\n"),
+        )?;
+    }
+
+    let rsync_root = workspace.remote_root.join("rsync");
+    let tar_root = workspace.remote_root.join("tar");
+    let rsync_time = timed_sync(&workspace, false, &rsync_root)?;
+    let tar_time = timed_sync(&workspace, true, &tar_root)?;
+
+    let expected = snapshot(&rsync_root)?;
+    if expected.len() != FILES.saturating_add(1) || snapshot(&tar_root)? != expected {
+        return Err(StepError::Assertion(
+            "the tar stream and rsync should produce the same tree".to_owned(),
+        ));
+    }
+    eprintln!("sync of {FILES} small files: rsync {rsync_time:?}, tar | zstd {tar_time:?}");
+    Ok(())
+}
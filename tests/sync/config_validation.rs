@@ -8,7 +8,8 @@ use super::test_helpers::{base_sync_config, networking};
 use mriya::sync::Syncer;
 
 #[rstest]
-fn run_remote_reports_missing_exit_code(
+#[tokio::test]
+async fn run_remote_reports_missing_exit_code(
     base_sync_config: mriya::sync::SyncConfig,
     networking: mriya::InstanceNetworking,
 ) {
@@ -19,6 +20,7 @@ fn run_remote_reports_missing_exit_code(
 
     let output = syncer
         .run_remote(&networking, "echo ok")
+        .await
         .expect("missing exit code should now be propagated");
 
     assert!(output.exit_code.is_none());
@@ -0,0 +1,216 @@
+//! BDD step definitions for the git-native sync modes.
+
+use std::process::Command;
+
+use camino::{Utf8Path, Utf8PathBuf};
+use mriya::sync::SyncMode;
+use rstest_bdd_macros::{given, then, when};
+use tempfile::TempDir;
+
+use super::bdd_steps::sync_workspace;
+use super::lfs_simulator::{object_path, pointer};
+use super::test_helpers::{StepError, Workspace, write_file};
+
+fn git(root: &Utf8Path, args: &[&str]) -> Result<(), StepError> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args([
+            "-c",
+            "user.name=mriya",
+            "-c",
+            "user.email=mriya@example.com",
+        ])
+        .args(args)
+        .output()
+        .map_err(|err| StepError::Assertion(format!("failed to run git: {err}")))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(StepError::Assertion(format!(
+            "git {args:?} failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )))
+    }
+}
+
+#[given("a committed git workspace with local changes")]
+fn committed_workspace(workspace: Workspace) -> Result<Workspace, StepError> {
+    commit_with_local_changes(&workspace, |_| Ok(()))?;
+    Ok(workspace)
+}
+
+#[given("a pushed git workspace with local changes")]
+fn pushed_workspace(workspace: Workspace) -> Result<Workspace, StepError> {
+    commit_with_local_changes(&workspace, |root| push_to_origin(&workspace, root))?;
+    Ok(workspace)
+}
+
+#[given("a git workspace with an unpushed commit")]
+fn unpushed_workspace(workspace: Workspace) -> Result<Workspace, StepError> {
+    commit_with_local_changes(&workspace, |root| {
+        push_to_origin(&workspace, root)?;
+        write_file(root.join("src").join("new.rs").as_path(), "// unpushed\n")?;
+        git(root, &["add", "--all"])?;
+        git(root, &["commit", "--quiet", "--message", "unpushed"])
+    })?;
+    Ok(workspace)
+}
+
+/// Creates a bare origin for the workspace and pushes `HEAD` to it.
+///
+/// The origin lives beside the simulated cache volume so it outlives the step.
+fn push_to_origin(workspace: &Workspace, root: &Utf8Path) -> Result<(), StepError> {
+    let origin = workspace.volume_root.join("origin.git");
+    git(
+        &workspace.volume_root,
+        &["init", "--quiet", "--bare", origin.as_str()],
+    )?;
+    git(root, &["remote", "add", "origin", origin.as_str()])?;
+    git(root, &["push", "--quiet", "origin", "HEAD:refs/heads/main"])
+}
+
+/// Commits a small crate, runs `after_commit`, then edits the working tree
+/// and seeds the remote with a cache and a stale file.
+fn commit_with_local_changes(
+    workspace: &Workspace,
+    after_commit: impl FnOnce(&Utf8Path) -> Result<(), StepError>,
+) -> Result<(), StepError> {
+    let root = &workspace.local_root;
+    write_file(root.join(".gitignore").as_path(), "target/\n")?;
+    write_file(root.join("src").join("lib.rs").as_path(), "// committed\n")?;
+    git(root, &["init", "--quiet"])?;
+    git(root, &["add", "--all"])?;
+    git(root, &["commit", "--quiet", "--message", "initial"])?;
+    after_commit(root)?;
+
+    write_file(root.join("src").join("lib.rs").as_path(), "// edited\n")?;
+    write_file(root.join("scratch.txt").as_path(), "untracked notes")?;
+    write_file(
+        workspace
+            .remote_root
+            .join("target")
+            .join("cache.txt")
+            .as_path(),
+        "cached artifact",
+    )?;
+    write_file(
+        workspace.remote_root.join("stale.txt").as_path(),
+        "remove me",
+    )?;
+    Ok(())
+}
+
+/// Object ID of the simulated LFS file.
+const LFS_OID: &str = "4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393";
+
+/// Content stored for [`LFS_OID`].
+const LFS_CONTENT: &str = "real model weights";
+
+#[given("a git workspace with a submodule")]
+fn workspace_with_submodule(workspace: Workspace) -> Result<Workspace, StepError> {
+    let origin_dir = TempDir::new()
+        .map_err(|err| StepError::Assertion(format!("failed to create origin: {err}")))?;
+    let origin = Utf8PathBuf::from_path_buf(origin_dir.path().to_path_buf())
+        .map_err(|_| StepError::Assertion("origin path is not UTF-8".to_owned()))?;
+    write_file(origin.join("lib.rs").as_path(), "// submodule\n")?;
+    git(&origin, &["init", "--quiet"])?;
+    git(&origin, &["add", "--all"])?;
+    git(&origin, &["commit", "--quiet", "--message", "library"])?;
+
+    let root = &workspace.local_root;
+    write_file(root.join("src").join("lib.rs").as_path(), "// committed\n")?;
+    git(root, &["init", "--quiet"])?;
+    git(
+        root,
+        &[
+            "-c",
+            "protocol.file.allow=always",
+            "submodule",
+            "add",
+            "--quiet",
+            origin.as_str(),
+            "vendor/lib",
+        ],
+    )?;
+    git(root, &["add", "--all"])?;
+    git(root, &["commit", "--quiet", "--message", "initial"])?;
+    Ok(workspace)
+}
+
+#[given("a git workspace whose LFS file is still a pointer")]
+fn workspace_with_lfs_pointer(workspace: Workspace) -> Result<Workspace, StepError> {
+    commit_lfs_pointer(&workspace, Utf8Path::new(""))?;
+    Ok(workspace)
+}
+
+#[given("a git workspace whose LFS file in \"{dir}\" is still a pointer")]
+fn workspace_with_nested_lfs_pointer(
+    workspace: Workspace,
+    dir: String,
+) -> Result<Workspace, StepError> {
+    commit_lfs_pointer(&workspace, Utf8Path::new(&dir))?;
+    Ok(workspace)
+}
+
+/// Commits `<dir>/models/weights.bin` as an LFS pointer, routed through the
+/// LFS filter by a `.gitattributes` in `dir`, and stores its content in the
+/// local LFS object store.
+fn commit_lfs_pointer(workspace: &Workspace, dir: &Utf8Path) -> Result<(), StepError> {
+    let root = &workspace.local_root;
+    write_file(
+        root.join(dir).join(".gitattributes").as_path(),
+        "*.bin filter=lfs diff=lfs merge=lfs -text\n",
+    )?;
+    write_file(
+        root.join(dir).join("models").join("weights.bin").as_path(),
+        &pointer(LFS_OID, LFS_CONTENT.len()),
+    )?;
+    git(root, &["init", "--quiet"])?;
+    git(root, &["add", "--all"])?;
+    git(root, &["commit", "--quiet", "--message", "initial"])?;
+    write_file(object_path(root, LFS_OID).as_path(), LFS_CONTENT)?;
+    Ok(())
+}
+
+#[when("I sync in \"{mode}\" mode")]
+fn sync_in_mode(workspace: Workspace, mode: String) -> Result<Workspace, StepError> {
+    let sync_mode: SyncMode = mode
+        .parse()
+        .map_err(|err| StepError::Assertion(format!("{err}")))?;
+    sync_workspace(&workspace, Vec::new(), Vec::new(), sync_mode)?;
+    Ok(workspace)
+}
+
+#[then("the remote file \"{path}\" contains \"{text}\"")]
+fn remote_file_contains(
+    workspace: &Workspace,
+    path: String,
+    text: String,
+) -> Result<(), StepError> {
+    let remote = workspace.remote_root.join(&path);
+    let contents = std::fs::read_to_string(&remote)
+        .map_err(|err| StepError::Assertion(format!("failed to read {remote}: {err}")))?;
+    if contents.contains(&text) {
+        Ok(())
+    } else {
+        Err(StepError::Assertion(format!(
+            "expected {path} to contain {text:?}, found {contents:?}"
+        )))
+    }
+}
+
+#[then("the cache volume {holds} a bare clone")]
+fn cache_volume_clone(workspace: &Workspace, holds: String) -> Result<(), StepError> {
+    let cache = workspace.volume_root.join("git-cache");
+    let clones = std::fs::read_dir(&cache).map_or(0, Iterator::count);
+    let expected = holds == "holds";
+    if (clones == 1) == expected {
+        Ok(())
+    } else {
+        Err(StepError::Assertion(format!(
+            "expected {cache} to {} a bare clone, found {clones} entries",
+            if expected { "hold" } else { "lack" }
+        )))
+    }
+}
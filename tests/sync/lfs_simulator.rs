@@ -0,0 +1,97 @@
+//! Test-only `git lfs` simulator used by sync BDD scenarios.
+//!
+//! git-lfs is not assumed on test machines. `ls-files` reports every file
+//! holding an LFS pointer with the `-` marker git-lfs uses for content that
+//! is not checked out, and `pull` replaces each pointer with the object stored
+//! under `.git/lfs/objects`, following git-lfs's local object layout.
+
+use std::ffi::OsString;
+use std::fs;
+
+use camino::{Utf8Path, Utf8PathBuf};
+use mriya::sync::{CommandOutput, SyncError};
+
+/// First line of every LFS pointer file.
+const POINTER_HEADER: &str = "version https://git-lfs.github.com/spec/v1";
+
+/// Renders the pointer file git-lfs leaves for content that is not fetched.
+pub fn pointer(oid: &str, size: usize) -> String {
+    format!("{POINTER_HEADER}\noid sha256:{oid}\nsize {size}\n")
+}
+
+/// Path git-lfs stores the object `oid` under in `repo`.
+pub fn object_path(repo: &Utf8Path, oid: &str) -> Utf8PathBuf {
+    let mut path = repo.join(".git").join("lfs").join("objects");
+    for fanout in [oid.get(0..2), oid.get(2..4)].into_iter().flatten() {
+        path.push(fanout);
+    }
+    path.join(oid)
+}
+
+/// Runs `git -C <repo> lfs <ls-files|pull>` against the simulated store.
+pub fn simulate_git_lfs(args: &[OsString]) -> Result<CommandOutput, SyncError> {
+    let values: Vec<&str> = args.iter().filter_map(|arg| arg.to_str()).collect();
+    let after = |flag: &str| {
+        values
+            .iter()
+            .position(|value| *value == flag)
+            .and_then(|index| values.get(index.saturating_add(1)))
+            .copied()
+    };
+    let repo = Utf8PathBuf::from(after("-C").ok_or_else(|| lfs_error("missing -C"))?);
+    let pointers = find_pointers(&repo, &repo)?;
+    let stdout = match after("lfs") {
+        Some("ls-files") => pointers
+            .iter()
+            .map(|(path, oid)| format!("{} - {path}\n", oid.get(0..10).unwrap_or(oid)))
+            .collect::<Vec<_>>()
+            .concat(),
+        Some("pull") => {
+            for (path, oid) in &pointers {
+                fs::copy(object_path(&repo, oid), repo.join(path))
+                    .map_err(|err| lfs_error(&format!("missing object for {path}: {err}")))?;
+            }
+            String::new()
+        }
+        other => return Err(lfs_error(&format!("unsupported git lfs command {other:?}"))),
+    };
+    Ok(CommandOutput {
+        code: Some(0),
+        stdout,
+        stderr: String::new(),
+    })
+}
+
+/// Lists pointer files below `dir`, relative to `repo`, with their object IDs.
+fn find_pointers(repo: &Utf8Path, dir: &Utf8Path) -> Result<Vec<(Utf8PathBuf, String)>, SyncError> {
+    let mut pointers = Vec::new();
+    let entries = fs::read_dir(dir).map_err(|err| lfs_error(&err.to_string()))?;
+    for entry in entries {
+        let dir_entry = entry.map_err(|err| lfs_error(&err.to_string()))?;
+        let path = Utf8PathBuf::from_path_buf(dir_entry.path())
+            .map_err(|_| lfs_error("non UTF-8 path"))?;
+        if path.file_name() == Some(".git") {
+            continue;
+        }
+        if path.is_dir() {
+            pointers.extend(find_pointers(repo, &path)?);
+            continue;
+        }
+        let contents = fs::read_to_string(&path).unwrap_or_default();
+        let oid = contents.strip_prefix(POINTER_HEADER).and_then(|rest| {
+            rest.lines()
+                .find_map(|line| line.strip_prefix("oid sha256:"))
+        });
+        if let (Some(found), Ok(relative)) = (oid, path.strip_prefix(repo)) {
+            pointers.push((relative.to_path_buf(), found.to_owned()));
+        }
+    }
+    Ok(pointers)
+}
+
+fn lfs_error(message: &str) -> SyncError {
+    SyncError::Spawn {
+        program: String::from("git"),
+        message: format!("git lfs simulator: {message}"),
+    }
+}
@@ -4,8 +4,13 @@
 //! utilities for the rsync synchronization layer.
 
 mod bdd_steps;
+mod benchmark;
 mod config_validation;
+mod git_steps;
+mod lfs_simulator;
 mod rsync_simulator;
 mod scenarios;
+mod stats_steps;
+mod stream_steps;
 mod test_doubles;
 mod test_helpers;
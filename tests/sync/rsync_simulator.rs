@@ -2,13 +2,18 @@
 //!
 //! Provides a minimal gitignore-aware file copier that preserves ignored cache
 //! directories and prunes destination entries not present in the source.
+//! Include and exclude rules passed ahead of the `.gitignore` filter are
+//! applied first, matching rsync's first-match semantics, and a trailing
+//! sender-side hide rule keeps unmatched paths from being sent without
+//! protecting them from deletion. Dry runs copy into the (empty) destination
+//! and then itemize it, as rsync would report a transfer to a fresh instance.
 
 use std::collections::HashSet;
 
 use camino::{Utf8Path, Utf8PathBuf};
 use cap_std::fs_utf8::{DirEntry, FileType};
 use cap_std::{ambient_authority, fs_utf8::Dir};
-use mriya::sync::SyncError;
+use mriya::sync::{FilterRule, SyncError};
 
 #[derive(Clone, Debug)]
 struct IgnoreRules {
@@ -16,8 +21,29 @@ struct IgnoreRules {
     files: HashSet<String>,
 }
 
+/// Rules passed to rsync alongside the `.gitignore` filter.
+#[derive(Clone, Debug, Default)]
+pub struct SimulatedFilters {
+    /// Include and exclude rules checked before `.gitignore`, in order.
+    pub rules: Vec<FilterRule>,
+    /// Whether `--filter=H *` hides paths that no other rule matched.
+    pub hide_unmatched: bool,
+}
+
+/// What the filters decide for a path.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Verdict {
+    /// Sent, and deleted from the destination when absent from the source.
+    Send,
+    /// Neither sent nor deleted.
+    Excluded,
+    /// Not sent, but deleted from the destination.
+    Hidden,
+}
+
 struct SimulationContext<'a> {
     fs: &'a Dir,
+    filters: &'a SimulatedFilters,
     rules: &'a IgnoreRules,
     source_root: &'a Utf8Path,
     destination_root: &'a Utf8Path,
@@ -25,7 +51,11 @@ struct SimulationContext<'a> {
     ancestors: &'a mut HashSet<Utf8PathBuf>,
 }
 
-pub fn simulate_rsync(source: &Utf8Path, destination: &Utf8Path) -> Result<(), SyncError> {
+pub fn simulate_rsync(
+    source: &Utf8Path,
+    destination: &Utf8Path,
+    filters: &SimulatedFilters,
+) -> Result<(), SyncError> {
     let fs = Dir::open_ambient_dir("/", ambient_authority())
         .map_err(|err| map_io_error(err.to_string()))?;
     let rules = load_ignores(source)?;
@@ -33,6 +63,7 @@ pub fn simulate_rsync(source: &Utf8Path, destination: &Utf8Path) -> Result<(), S
     let mut ancestors: HashSet<Utf8PathBuf> = HashSet::new();
     let mut context = SimulationContext {
         fs: &fs,
+        filters,
         rules: &rules,
         source_root: source,
         destination_root: destination,
@@ -45,6 +76,47 @@ pub fn simulate_rsync(source: &Utf8Path, destination: &Utf8Path) -> Result<(), S
     Ok(())
 }
 
+/// Lists `destination` in rsync's itemized `%i %l %n` format followed by the
+/// `--stats` total.
+pub fn itemize(destination: &Utf8Path) -> Result<String, SyncError> {
+    let mut lines = Vec::new();
+    let mut total: u64 = 0;
+    itemize_dir(destination, destination, &mut lines, &mut total)?;
+    lines.push(format!("Total transferred file size: {total} bytes\n"));
+    Ok(lines.concat())
+}
+
+fn itemize_dir(
+    root: &Utf8Path,
+    dir: &Utf8Path,
+    lines: &mut Vec<String>,
+    total: &mut u64,
+) -> Result<(), SyncError> {
+    if !dir.exists() {
+        return Ok(());
+    }
+    let mut entries: Vec<Utf8PathBuf> = std::fs::read_dir(dir)
+        .map_err(|err| map_io_error(err.to_string()))?
+        .filter_map(|entry| Utf8PathBuf::from_path_buf(entry.ok()?.path()).ok())
+        .collect();
+    entries.sort();
+    for path in entries {
+        let relative = path.strip_prefix(root).unwrap_or(&path);
+        if path.is_dir() {
+            lines.push(format!("cd+++++++++ 0 {relative}/\n"));
+            itemize_dir(root, &path, lines, total)?;
+        } else {
+            let bytes = path
+                .metadata()
+                .map_err(|err| map_io_error(err.to_string()))?
+                .len();
+            *total = total.saturating_add(bytes);
+            lines.push(format!(">f+++++++++ {bytes} {relative}\n"));
+        }
+    }
+    Ok(())
+}
+
 fn compute_ancestors(kept: &HashSet<Utf8PathBuf>, ancestors: &mut HashSet<Utf8PathBuf>) {
     for path in kept {
         let mut current = path.clone();
@@ -93,16 +165,55 @@ fn load_ignores(source: &Utf8Path) -> Result<IgnoreRules, SyncError> {
     Ok(IgnoreRules { dirs, files })
 }
 
-fn should_ignore(relative: &Utf8Path, rules: &IgnoreRules) -> bool {
-    for component in relative.components() {
-        if rules.dirs.contains(component.as_str()) {
-            return true;
+/// Applies `filters` then `.gitignore` to each component of `relative`.
+///
+/// Like the `.gitignore` parser, filter patterns are limited to names, with a
+/// trailing `/` restricting a pattern to directories, and patterns with a
+/// leading or inner `/` that match the relative path exactly. Globbing is not
+/// implemented.
+fn classify(
+    relative: &Utf8Path,
+    is_dir: bool,
+    filters: &SimulatedFilters,
+    rules: &IgnoreRules,
+) -> Verdict {
+    let components: Vec<&str> = relative.components().map(|part| part.as_str()).collect();
+    let last = components.len().saturating_sub(1);
+    let mut prefix = Utf8PathBuf::new();
+    for (index, name) in components.iter().enumerate() {
+        prefix.push(name);
+        let is_last = index == last;
+        match first_matching_filter(&prefix, !is_last || is_dir, &filters.rules) {
+            Some(FilterRule::Exclude(_)) => return Verdict::Excluded,
+            Some(FilterRule::Include(_)) => continue,
+            None => {}
+        }
+        if rules.dirs.contains(*name) || (is_last && rules.files.contains(*name)) {
+            return Verdict::Excluded;
+        }
+        if filters.hide_unmatched {
+            return Verdict::Hidden;
         }
     }
+    Verdict::Send
+}
 
-    relative
-        .file_name()
-        .is_some_and(|name| rules.files.contains(name))
+fn first_matching_filter<'a>(
+    path: &Utf8Path,
+    is_dir: bool,
+    filters: &'a [FilterRule],
+) -> Option<&'a FilterRule> {
+    filters.iter().find(|rule| {
+        let pattern = rule.pattern();
+        let dir_only = pattern.ends_with('/');
+        let trimmed = pattern.trim_matches('/');
+        let matches = if pattern.starts_with('/') || trimmed.contains('/') {
+            path.as_str() == trimmed
+        } else {
+            path.file_name() == Some(trimmed)
+        };
+        matches && (is_dir || !dir_only)
+    })
 }
 
 fn relative_to_root(path: &Utf8Path) -> &Utf8Path {
@@ -129,14 +240,14 @@ fn copy_tree(
             .strip_prefix(context.source_root)
             .map_err(|err| map_io_error(err.to_string()))?;
 
-        if should_ignore(relative, context.rules) {
+        let metadata = entry
+            .file_type()
+            .map_err(|err| map_io_error(err.to_string()))?;
+        if classify(relative, metadata.is_dir(), context.filters, context.rules) != Verdict::Send {
             continue;
         }
 
         let destination_path = destination_dir.join(&name_path);
-        let metadata = entry
-            .file_type()
-            .map_err(|err| map_io_error(err.to_string()))?;
 
         if metadata.is_dir() {
             context
@@ -215,14 +326,13 @@ fn process_destination_entry(
         .strip_prefix(context.destination_root)
         .map_err(|err| map_io_error(err.to_string()))?;
 
-    if should_ignore(relative, context.rules) {
-        return Ok(());
-    }
-
     let file_type = entry
         .file_type()
         .map_err(|err| map_io_error(err.to_string()))?;
     let is_dir = file_type.is_dir();
+    if classify(relative, is_dir, context.filters, context.rules) == Verdict::Excluded {
+        return Ok(());
+    }
 
     if should_keep_entry(relative, file_type, context.kept, context.ancestors) {
         if is_dir {
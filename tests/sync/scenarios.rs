@@ -15,6 +15,62 @@ fn scenario_preserve_caches(workspace: Workspace) {
     let _ = workspace;
 }
 
+#[scenario(
+    path = "tests/features/sync.feature",
+    name = "Layer include and exclude rules over gitignore"
+)]
+fn scenario_layer_sync_rules(workspace: Workspace) {
+    let _ = workspace;
+}
+
+#[scenario(
+    path = "tests/features/sync.feature",
+    name = "Sync only the files git reports"
+)]
+fn scenario_sync_git_files(workspace: Workspace) {
+    let _ = workspace;
+}
+
+#[scenario(
+    path = "tests/features/sync.feature",
+    name = "Sync a snapshot of the last commit"
+)]
+fn scenario_sync_git_commit(workspace: Workspace) {
+    let _ = workspace;
+}
+
+#[scenario(
+    path = "tests/features/sync.feature",
+    name = "Fetch the pushed commit and apply local changes"
+)]
+fn scenario_sync_git_fetch(workspace: Workspace) {
+    let _ = workspace;
+}
+
+#[scenario(
+    path = "tests/features/sync.feature",
+    name = "Fall back to rsync when the commit is not pushed"
+)]
+fn scenario_git_fetch_fallback(workspace: Workspace) {
+    let _ = workspace;
+}
+
+#[scenario(
+    path = "tests/features/sync.feature",
+    name = "Stream a tarball to an empty remote"
+)]
+fn scenario_stream_tarball(workspace: Workspace) {
+    let _ = workspace;
+}
+
+#[scenario(
+    path = "tests/features/sync.feature",
+    name = "Use rsync when the remote already holds files"
+)]
+fn scenario_stream_falls_back_to_rsync(workspace: Workspace) {
+    let _ = workspace;
+}
+
 #[scenario(
     path = "tests/features/sync.feature",
     name = "Propagate remote exit codes"
@@ -30,3 +86,51 @@ fn scenario_propagate_exit_codes(
 fn scenario_surface_failures(scripted_context: ScriptedContext, error: mriya::sync::SyncError) {
     let _ = (scripted_context, error);
 }
+
+#[scenario(
+    path = "tests/features/sync.feature",
+    name = "Sync submodule content from the working tree"
+)]
+fn scenario_sync_submodules_rsync_filter(workspace: Workspace) {
+    let _ = workspace;
+}
+
+#[scenario(
+    path = "tests/features/sync.feature",
+    name = "Sync submodule files git reports"
+)]
+fn scenario_sync_submodules_git_files(workspace: Workspace) {
+    let _ = workspace;
+}
+
+#[scenario(
+    path = "tests/features/sync.feature",
+    name = "Sync a snapshot of submodule commits"
+)]
+fn scenario_sync_submodules_git_commit(workspace: Workspace) {
+    let _ = workspace;
+}
+
+#[scenario(
+    path = "tests/features/sync.feature",
+    name = "Sync Git LFS content instead of pointers"
+)]
+fn scenario_sync_lfs_content(workspace: Workspace) {
+    let _ = workspace;
+}
+
+#[scenario(
+    path = "tests/features/sync.feature",
+    name = "Sync Git LFS content routed by a nested .gitattributes"
+)]
+fn scenario_sync_nested_lfs_content(workspace: Workspace) {
+    let _ = workspace;
+}
+
+#[scenario(
+    path = "tests/features/sync.feature",
+    name = "Refuse a sync above max_sync_bytes"
+)]
+fn scenario_refuse_large_sync(scripted_context: ScriptedContext, error: mriya::sync::SyncError) {
+    let _ = (scripted_context, error);
+}
@@ -0,0 +1,52 @@
+//! BDD steps for sync dry runs and the `max_sync_bytes` guard.
+
+use mriya::sync::{SyncError, Syncer};
+use rstest_bdd_macros::{given, then, when};
+
+use super::test_helpers::{ScriptedContext, StepError, block_on};
+
+/// Dry-run output for a workspace where a large dataset slipped through.
+const LARGE_DRY_RUN: &str = "\
+>f+++++++++ 1,024 src/lib.rs
+>f+++++++++ 2,147,483,648 data/train.parquet
+
+Total transferred file size: 2,147,484,672 bytes
+";
+
+#[given("a dry run that would send a large dataset")]
+fn large_dry_run(scripted_context: ScriptedContext) -> ScriptedContext {
+    scripted_context
+        .runner
+        .push_output(Some(0), LARGE_DRY_RUN, "");
+    scripted_context
+}
+
+#[when("I check the sync against a limit of \"{limit}\" bytes")]
+fn check_sync_limit(scripted_context: ScriptedContext, limit: u64) -> Result<SyncError, StepError> {
+    let mut config = scripted_context.config.clone();
+    config.max_sync_bytes = Some(limit);
+    let syncer = Syncer::new(config, scripted_context.runner.clone())?;
+    let stats = block_on(syncer.dry_run(&scripted_context.source))??;
+    let largest = stats.largest_files(1);
+    if largest.first().map(|file| file.path.as_str()) != Some("data/train.parquet") {
+        return Err(StepError::Assertion(format!(
+            "expected the dataset to be the largest file, got {largest:?}"
+        )));
+    }
+    match syncer.check_size_limit(&stats) {
+        Ok(()) => Err(StepError::Assertion(
+            "a sync above max_sync_bytes should be refused".to_owned(),
+        )),
+        Err(err) => Ok(err),
+    }
+}
+
+#[then("the sync is refused before provisioning")]
+fn sync_refused(error: &SyncError) -> Result<(), StepError> {
+    match error {
+        SyncError::SizeLimit { bytes, .. } if *bytes == 2_147_484_672 => Ok(()),
+        other => Err(StepError::Assertion(format!(
+            "expected a size limit error for 2147484672 bytes, got {other:?}"
+        ))),
+    }
+}
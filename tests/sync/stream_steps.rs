@@ -0,0 +1,53 @@
+//! BDD steps for the streamed `tar | zstd` transfer to empty destinations.
+
+use mriya::sync::SyncMode;
+use rstest_bdd_macros::{given, then, when};
+
+use super::bdd_steps::{sync_with_config, workspace_sync_config};
+use super::test_helpers::{StepError, Workspace, write_file};
+
+/// Number of small source files in the streamed workspace.
+const SMALL_FILES: usize = 200;
+
+fn small_file(index: usize) -> String {
+    let module = index.checked_rem(10).unwrap_or_default();
+    format!("src/module_{module}/file_{index}.rs")
+}
+
+#[given("a workspace with many small files")]
+fn workspace_with_small_files(workspace: Workspace) -> Result<Workspace, StepError> {
+    let root = &workspace.local_root;
+    write_file(root.join(".gitignore").as_path(), "target/\n")?;
+    write_file(root.join("target").join("cache.bin").as_path(), "ignored")?;
+    for index in 0..SMALL_FILES {
+        write_file(
+            root.join(small_file(index)).as_path(),
+            &format!("pub const VALUE: usize = {index};\n"),
+        )?;
+    }
+    Ok(workspace)
+}
+
+#[when("I sync with tar streaming")]
+fn sync_with_tar_streaming(workspace: Workspace) -> Result<Workspace, StepError> {
+    let mut config =
+        workspace_sync_config(&workspace, Vec::new(), Vec::new(), SyncMode::RsyncFilter);
+    config.tar_stream = true;
+    sync_with_config(&workspace, config)?;
+    Ok(workspace)
+}
+
+#[then("every small file arrives on the remote")]
+fn small_files_arrive(workspace: &Workspace) -> Result<(), StepError> {
+    for index in 0..SMALL_FILES {
+        let path = workspace.remote_root.join(small_file(index));
+        let contents = std::fs::read_to_string(&path)
+            .map_err(|err| StepError::Assertion(format!("failed to read {path}: {err}")))?;
+        if contents != format!("pub const VALUE: usize = {index};\n") {
+            return Err(StepError::Assertion(format!(
+                "unexpected contents in {path}: {contents:?}"
+            )));
+        }
+    }
+    Ok(())
+}
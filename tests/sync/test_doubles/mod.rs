@@ -4,14 +4,21 @@
 //! and `LocalCopyRunner`, which simulates rsync behaviour on the local
 //! filesystem. `LocalCopyRunner` assumes `rsync_bin == "rsync"` and that the
 //! final two arguments are UTF-8 source and destination paths; this coupling is
-//! acceptable for test purposes.
+//! acceptable for test purposes. `--include=` and `--exclude=` arguments, rules
+//! from `--filter=merge` files, and the `--filter=H *` hide rule are forwarded
+//! to the simulator in order, and `--dry-run` reports the copied tree. `git`
+//! and `tar`, which the git sync modes run locally, are executed for real,
+//! except `git lfs`, which is simulated. `sh` also runs for real, so the
+//! `git-fetch` script can update a local destination.
 
 use std::ffi::OsString;
+use std::fs;
 
 use camino::{Utf8Path, Utf8PathBuf};
-use mriya::sync::{CommandOutput, CommandRunner, SyncError};
+use mriya::sync::{AsyncCommandRunner, CommandOutput, FilterRule, ProcessCommandRunner, SyncError};
 
-use super::rsync_simulator::simulate_rsync;
+use super::lfs_simulator::simulate_git_lfs;
+use super::rsync_simulator::{SimulatedFilters, itemize, simulate_rsync};
 
 mod shared_scripted_runner;
 pub use shared_scripted_runner::ScriptedRunner;
@@ -44,10 +51,54 @@ impl LocalCopyRunner {
             Utf8PathBuf::from(destination_arg),
         ))
     }
+
+    fn parse_filters(args: &[OsString]) -> Result<SimulatedFilters, SyncError> {
+        let mut filters = SimulatedFilters::default();
+        let mut values = args.iter().filter_map(|value| value.to_str());
+        while let Some(arg) = values.next() {
+            if arg == "--exclude" {
+                let pattern = values.next().unwrap_or_default();
+                filters.rules.push(FilterRule::Exclude(pattern.to_owned()));
+            } else if let Some(pattern) = arg.strip_prefix("--include=") {
+                filters.rules.push(FilterRule::Include(pattern.to_owned()));
+            } else if let Some(pattern) = arg.strip_prefix("--exclude=") {
+                filters.rules.push(FilterRule::Exclude(pattern.to_owned()));
+            } else if let Some(path) = arg.strip_prefix("--filter=merge ") {
+                filters.rules.extend(Self::read_merge_file(path)?);
+            } else if arg == "--filter=H *" {
+                filters.hide_unmatched = true;
+            }
+        }
+        Ok(filters)
+    }
+
+    fn read_merge_file(path: &str) -> Result<Vec<FilterRule>, SyncError> {
+        let contents = fs::read_to_string(path).map_err(|err| SyncError::Spawn {
+            program: String::from("rsync"),
+            message: format!("failed to read merge file {path}: {err}"),
+        })?;
+        Ok(contents
+            .lines()
+            .filter_map(|line| {
+                line.strip_prefix("+ ")
+                    .map(|pattern| FilterRule::Include(pattern.to_owned()))
+                    .or_else(|| {
+                        line.strip_prefix("- ")
+                            .map(|pattern| FilterRule::Exclude(pattern.to_owned()))
+                    })
+            })
+            .collect())
+    }
 }
 
-impl CommandRunner for LocalCopyRunner {
-    fn run(&self, program: &str, args: &[OsString]) -> Result<CommandOutput, SyncError> {
+impl AsyncCommandRunner for LocalCopyRunner {
+    async fn run(&self, program: &str, args: &[OsString]) -> Result<CommandOutput, SyncError> {
+        if program == "git" && args.iter().any(|arg| arg == "lfs") {
+            return simulate_git_lfs(args);
+        }
+        if matches!(program, "git" | "tar" | "sh") {
+            return ProcessCommandRunner.run(program, args).await;
+        }
         if program != "rsync" {
             return Err(SyncError::Spawn {
                 program: program.to_owned(),
@@ -56,11 +107,21 @@ impl CommandRunner for LocalCopyRunner {
         }
 
         let (source, destination) = Self::parse_paths(args)?;
-        simulate_rsync(Utf8Path::new(&source), Utf8Path::new(&destination))?;
+        let filters = Self::parse_filters(args)?;
+        simulate_rsync(
+            Utf8Path::new(&source),
+            Utf8Path::new(&destination),
+            &filters,
+        )?;
+        let stdout = if args.iter().any(|arg| arg == "--dry-run") {
+            itemize(&destination)?
+        } else {
+            String::new()
+        };
 
         Ok(CommandOutput {
             code: Some(0),
-            stdout: String::new(),
+            stdout,
             stderr: String::new(),
         })
     }
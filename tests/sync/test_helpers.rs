@@ -4,22 +4,27 @@
 //! utilities for testing the rsync synchronization layer.
 
 use cap_std::{ambient_authority, fs_utf8::Dir};
+use std::future::Future;
 use std::net::{IpAddr, Ipv4Addr};
 use std::sync::Arc;
 
 use camino::{Utf8Path, Utf8PathBuf};
 use mriya::InstanceNetworking;
-use mriya::sync::{RemoteCommandOutput, SyncConfig, SyncError};
+use mriya::sync::{RemoteCommandOutput, SyncConfig, SyncError, SyncMode};
 use rstest::fixture;
 use tempfile::TempDir;
 use thiserror::Error;
+use tokio::runtime::Runtime;
 
 #[derive(Clone, Debug)]
 pub struct Workspace {
     pub local_root: Utf8PathBuf,
     pub remote_root: Utf8PathBuf,
+    /// Stands in for the instance's cache volume.
+    pub volume_root: Utf8PathBuf,
     _local_tmp: Arc<TempDir>,
     _remote_tmp: Arc<TempDir>,
+    _volume_tmp: Arc<TempDir>,
 }
 
 impl Default for Workspace {
@@ -35,6 +40,7 @@ impl Workspace {
     pub fn new() -> Result<Self, SyncError> {
         let local_tmp = Arc::new(temp_dir("local workspace")?);
         let remote_tmp = Arc::new(temp_dir("remote workspace")?);
+        let volume_tmp = Arc::new(temp_dir("cache volume")?);
 
         let local_root =
             Utf8PathBuf::from_path_buf(local_tmp.path().to_path_buf()).map_err(|err| {
@@ -50,12 +56,15 @@ impl Workspace {
                     message: err.display().to_string(),
                 }
             })?;
+        let volume_root = utf8_path(volume_tmp.path().to_path_buf(), "cache volume")?;
 
         Ok(Self {
             local_root,
             remote_root,
+            volume_root,
             _local_tmp: local_tmp,
             _remote_tmp: remote_tmp,
+            _volume_tmp: volume_tmp,
         })
     }
 }
@@ -122,10 +131,23 @@ pub fn build_scripted_context(
             ssh_batch_mode: true,
             ssh_strict_host_key_checking: false,
             ssh_known_hosts_file: String::from("/dev/null"),
+            ssh_multiplex: false,
             ssh_identity_file: Some(String::from("~/.ssh/id_ed25519")),
             volume_mount_path: String::from("/mriya"),
             route_build_caches: true,
             create_cache_directories: true,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            sync_mode: SyncMode::RsyncFilter,
+            git_bin: String::from("git"),
+            git_include_untracked: false,
+            git_lfs_pull: false,
+            git_remote: String::from("origin"),
+            tar_stream: false,
+            max_sync_bytes: None,
+            secret_patterns: Vec::new(),
+            secret_signatures: Vec::new(),
+            secret_allowlist: Vec::new(),
         },
         networking: InstanceNetworking {
             public_ip: IpAddr::V4(Ipv4Addr::LOCALHOST),
@@ -203,10 +225,23 @@ pub fn base_sync_config() -> SyncConfig {
         ssh_batch_mode: true,
         ssh_strict_host_key_checking: false,
         ssh_known_hosts_file: String::from("/dev/null"),
+        ssh_multiplex: false,
         ssh_identity_file: Some(String::from("~/.ssh/id_ed25519")),
         volume_mount_path: String::from("/mriya"),
         route_build_caches: true,
         create_cache_directories: true,
+        include: Vec::new(),
+        exclude: Vec::new(),
+        sync_mode: SyncMode::RsyncFilter,
+        git_bin: String::from("git"),
+        git_include_untracked: false,
+        git_lfs_pull: false,
+        git_remote: String::from("origin"),
+        tar_stream: false,
+        max_sync_bytes: None,
+        secret_patterns: Vec::new(),
+        secret_signatures: Vec::new(),
+        secret_allowlist: Vec::new(),
     }
 }
 
@@ -217,3 +252,9 @@ pub enum StepError {
     #[error("assertion failed: {0}")]
     Assertion(String),
 }
+
+/// Drives `future` to completion on its own runtime, as steps are synchronous.
+pub fn block_on<F: Future>(future: F) -> Result<F::Output, StepError> {
+    let runtime = Runtime::new().map_err(|err| StepError::Assertion(err.to_string()))?;
+    Ok(runtime.block_on(future))
+}
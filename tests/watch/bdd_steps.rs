@@ -0,0 +1,146 @@
+//! BDD step definitions for watch mode.
+
+use rstest_bdd_macros::{given, then, when};
+use tokio::runtime::Runtime;
+use tokio::sync::{mpsc, oneshot};
+
+use super::test_helpers::{WatchContext, eventually};
+
+#[derive(Debug, thiserror::Error)]
+pub enum StepError {
+    #[error("assertion failed: {0}")]
+    Assertion(String),
+}
+
+#[given("a watch loop for \"{command}\"")]
+fn watch_loop_for(mut watch_context: WatchContext, command: String) -> WatchContext {
+    watch_context.command = command;
+    watch_context
+}
+
+#[given("the first run hangs until cancelled")]
+fn first_run_hangs(watch_context: WatchContext) -> WatchContext {
+    watch_context.runner.hang_first_run();
+    watch_context
+}
+
+#[given("SSH multiplexing is enabled")]
+fn multiplexing_enabled(mut watch_context: WatchContext) -> WatchContext {
+    watch_context.multiplex = true;
+    watch_context
+}
+
+/// Starts the loop, sends one change once `ready` holds, waits for the second
+/// run to be reported, and shuts the loop down.
+fn drive(
+    watch_context: WatchContext,
+    ready: impl Fn(&WatchContext) -> bool,
+) -> Result<WatchContext, StepError> {
+    let runtime = Runtime::new().map_err(|err| StepError::Assertion(err.to_string()))?;
+    runtime.block_on(async {
+        let watch_loop = watch_context.watch_loop();
+        let session = watch_context
+            .orchestrator()
+            .start(&watch_context.request)
+            .await
+            .map_err(|err| StepError::Assertion(err.to_string()))?;
+        let (changes, receiver) = mpsc::unbounded_channel();
+        let (stop, stopped) = oneshot::channel::<()>();
+        let task = tokio::spawn(async move {
+            watch_loop
+                .run(session, receiver, async {
+                    stopped.await.ok();
+                })
+                .await
+        });
+
+        if !eventually(|| ready(&watch_context)).await {
+            return Err(StepError::Assertion(String::from(
+                "watch loop never reached the first run",
+            )));
+        }
+        changes
+            .send(watch_context.source.join("src/lib.rs"))
+            .map_err(|err| StepError::Assertion(err.to_string()))?;
+        let settled = eventually(|| watch_context.reports().len() >= 2).await;
+        stop.send(()).ok();
+        task.await
+            .map_err(|err| StepError::Assertion(err.to_string()))?
+            .map_err(|err| StepError::Assertion(err.to_string()))?;
+        if settled {
+            Ok(())
+        } else {
+            Err(StepError::Assertion(format!(
+                "expected two runs, got {:?}",
+                watch_context.reports()
+            )))
+        }
+    })?;
+    Ok(watch_context)
+}
+
+#[when("the workspace changes after the first run completes")]
+fn change_after_first_run(watch_context: WatchContext) -> Result<WatchContext, StepError> {
+    drive(watch_context, |context| context.reports().len() == 1)
+}
+
+#[when("the workspace changes during the first run")]
+fn change_during_first_run(watch_context: WatchContext) -> Result<WatchContext, StepError> {
+    drive(watch_context, |context| context.runner.runs_started() == 1)
+}
+
+#[then("the watch reports \"{expected}\"")]
+fn watch_reports(watch_context: &WatchContext, expected: String) -> Result<(), StepError> {
+    let actual = watch_context.reports().join(", ");
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(StepError::Assertion(format!(
+            "expected reports `{expected}`, got `{actual}`"
+        )))
+    }
+}
+
+#[then("the workspace is synced \"{count}\" times")]
+fn workspace_synced(watch_context: &WatchContext, count: usize) -> Result<(), StepError> {
+    let syncs = watch_context.runner.syncs();
+    if syncs == count {
+        Ok(())
+    } else {
+        Err(StepError::Assertion(format!(
+            "expected {count} syncs, got {syncs}"
+        )))
+    }
+}
+
+#[then("the remote process group is signalled")]
+fn process_group_signalled(watch_context: &WatchContext) -> Result<(), StepError> {
+    if watch_context.runner.cancels() > 0 {
+        Ok(())
+    } else {
+        Err(StepError::Assertion(String::from(
+            "expected a cancel command over SSH",
+        )))
+    }
+}
+
+#[then("the watched instance is destroyed once")]
+fn destroyed_once(watch_context: &WatchContext) -> Result<(), StepError> {
+    match watch_context.backend.destroy_calls() {
+        1 => Ok(()),
+        calls => Err(StepError::Assertion(format!(
+            "expected one teardown, got {calls}"
+        ))),
+    }
+}
+
+#[then("the shared connection is closed")]
+fn connection_closed(watch_context: &WatchContext) -> Result<(), StepError> {
+    if watch_context.runner.closes() > 0 {
+        Ok(())
+    } else {
+        Err(StepError::Assertion(String::from(
+            "expected `ssh -O exit` when the watch stops",
+        )))
+    }
+}
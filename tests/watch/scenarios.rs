@@ -0,0 +1,29 @@
+//! BDD scenarios for watch mode.
+
+use rstest_bdd_macros::scenario;
+
+use super::test_helpers::{WatchContext, watch_context};
+
+#[scenario(
+    path = "tests/features/watch.feature",
+    name = "Re-run the command after the workspace changes"
+)]
+fn scenario_rerun_on_change(watch_context: WatchContext) {
+    let _ = watch_context;
+}
+
+#[scenario(
+    path = "tests/features/watch.feature",
+    name = "Cancel an in-flight run when the workspace changes"
+)]
+fn scenario_cancel_in_flight_run(watch_context: WatchContext) {
+    let _ = watch_context;
+}
+
+#[scenario(
+    path = "tests/features/watch.feature",
+    name = "Close the shared connection when the watch stops"
+)]
+fn scenario_close_connection_on_stop(watch_context: WatchContext) {
+    let _ = watch_context;
+}
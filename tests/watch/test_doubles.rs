@@ -0,0 +1,165 @@
+//! Test doubles for watch mode scenarios.
+
+use std::ffi::OsString;
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+use mriya::sync::{AsyncCommandRunner, CommandOutput, SyncError};
+use mriya::{
+    Backend, InstanceHandle, InstanceNetworking, InstanceRequest, TransientError,
+    backend::BackendFuture,
+};
+use thiserror::Error;
+
+/// Backend double that records how often instances are destroyed.
+#[derive(Clone, Debug, Default)]
+pub struct WatchBackend {
+    destroyed: Arc<Mutex<u32>>,
+}
+
+impl WatchBackend {
+    pub fn destroy_calls(&self) -> u32 {
+        *self
+            .destroyed
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Errors raised by [`WatchBackend`]; it never fails.
+#[derive(Clone, Debug, Error, Eq, PartialEq)]
+#[error("unreachable")]
+pub struct WatchBackendError;
+
+impl TransientError for WatchBackendError {}
+
+impl Backend for WatchBackend {
+    type Error = WatchBackendError;
+
+    fn create<'a>(
+        &'a self,
+        _request: &'a InstanceRequest,
+    ) -> BackendFuture<'a, InstanceHandle, Self::Error> {
+        Box::pin(async move {
+            Ok(InstanceHandle {
+                id: String::from("watch-1"),
+                zone: String::from("fr-par-1"),
+                run_id: None,
+            })
+        })
+    }
+
+    fn wait_for_ready<'a>(
+        &'a self,
+        _handle: &'a InstanceHandle,
+    ) -> BackendFuture<'a, InstanceNetworking, Self::Error> {
+        Box::pin(async move {
+            Ok(InstanceNetworking {
+                public_ip: IpAddr::V4(Ipv4Addr::LOCALHOST),
+                ssh_port: 22,
+            })
+        })
+    }
+
+    fn destroy(&self, _handle: InstanceHandle) -> BackendFuture<'_, (), Self::Error> {
+        Box::pin(async move {
+            *self
+                .destroyed
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner) += 1;
+            Ok(())
+        })
+    }
+}
+
+/// Interval at which a hanging run checks whether it was cancelled.
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Thread-safe command runner that succeeds, optionally holding the first
+/// remote run until the cancel command arrives.
+#[derive(Clone, Debug, Default)]
+pub struct WatchRunner {
+    state: Arc<Mutex<RunnerState>>,
+}
+
+#[derive(Debug, Default)]
+struct RunnerState {
+    hang_first_run: bool,
+    runs_started: usize,
+    syncs: usize,
+    cancels: usize,
+    closes: usize,
+}
+
+impl WatchRunner {
+    pub fn hang_first_run(&self) {
+        self.lock().hang_first_run = true;
+    }
+
+    pub fn runs_started(&self) -> usize {
+        self.lock().runs_started
+    }
+
+    pub fn syncs(&self) -> usize {
+        self.lock().syncs
+    }
+
+    pub fn cancels(&self) -> usize {
+        self.lock().cancels
+    }
+
+    pub fn closes(&self) -> usize {
+        self.lock().closes
+    }
+
+    fn lock(&self) -> MutexGuard<'_, RunnerState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    async fn remote_run(&self) -> i32 {
+        let cancels_before = {
+            let mut state = self.lock();
+            state.runs_started += 1;
+            if !(state.hang_first_run && state.runs_started == 1) {
+                return 0;
+            }
+            state.cancels
+        };
+        while self.lock().cancels == cancels_before {
+            tokio::time::sleep(CANCEL_POLL_INTERVAL).await;
+        }
+        143
+    }
+}
+
+impl AsyncCommandRunner for WatchRunner {
+    async fn run(&self, program: &str, args: &[OsString]) -> Result<CommandOutput, SyncError> {
+        let command = args
+            .last()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let closes = args
+            .windows(2)
+            .any(|pair| matches!(pair, [flag, action] if flag == "-O" && action == "exit"));
+        let code = if program == "rsync" {
+            self.lock().syncs += 1;
+            0
+        } else if closes {
+            self.lock().closes += 1;
+            0
+        } else if command.contains("kill -TERM") {
+            self.lock().cancels += 1;
+            0
+        } else if command.contains("mriya-watch.pid &&") {
+            self.remote_run().await
+        } else {
+            0
+        };
+        Ok(CommandOutput {
+            code: Some(code),
+            stdout: String::new(),
+            stderr: String::new(),
+        })
+    }
+}
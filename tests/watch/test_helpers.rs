@@ -0,0 +1,104 @@
+//! Shared fixtures for watch mode BDD scenarios.
+
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use camino::Utf8PathBuf;
+use mriya::{
+    InstanceRequest, InstanceRequestBuilder, RunOrchestrator, Syncer, WatchLoop, WatchRun,
+};
+use rstest::fixture;
+use tempfile::TempDir;
+
+use super::test_doubles::{WatchBackend, WatchBackendError, WatchRunner};
+use crate::sync_config::sync_config;
+use crate::test_constants::DEFAULT_INSTANCE_TYPE;
+
+/// How long scenarios wait for the loop to reach an expected state.
+pub const SETTLE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Debug)]
+pub struct WatchContext {
+    pub backend: WatchBackend,
+    pub runner: WatchRunner,
+    pub request: InstanceRequest,
+    pub command: String,
+    pub multiplex: bool,
+    pub source: Utf8PathBuf,
+    pub reports: Arc<Mutex<Vec<String>>>,
+    pub(crate) _source_dir: Arc<TempDir>,
+}
+
+impl WatchContext {
+    pub fn orchestrator(&self) -> RunOrchestrator<WatchBackend, WatchRunner> {
+        let mut config = sync_config();
+        config.ssh_multiplex = self.multiplex;
+        let syncer = Syncer::new(config, self.runner.clone())
+            .unwrap_or_else(|err| panic!("sync config fixture should be valid: {err}"));
+        RunOrchestrator::new(self.backend.clone(), syncer)
+    }
+
+    pub fn watch_loop(&self) -> WatchLoop<WatchBackend, WatchRunner> {
+        let reports = Arc::clone(&self.reports);
+        WatchLoop::new(Arc::new(self.orchestrator()), &self.source, &self.command)
+            .with_debounce(Duration::from_millis(10))
+            .with_reporter(Box::new(move |run: &WatchRun<'_, WatchBackendError>| {
+                let line = match run {
+                    WatchRun::Completed(output) => {
+                        format!("completed {}", output.exit_code.map_or(-1, |code| code))
+                    }
+                    WatchRun::Cancelled => String::from("cancelled"),
+                    WatchRun::Failed(err) => format!("failed: {err}"),
+                };
+                reports
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .push(line);
+            }))
+    }
+
+    pub fn reports(&self) -> Vec<String> {
+        self.reports
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
+
+/// Polls `condition` until it holds or [`SETTLE_TIMEOUT`] passes.
+pub async fn eventually(condition: impl Fn() -> bool) -> bool {
+    let deadline = Instant::now() + SETTLE_TIMEOUT;
+    while !condition() {
+        if Instant::now() > deadline {
+            return false;
+        }
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    true
+}
+
+#[fixture]
+pub fn watch_context() -> WatchContext {
+    let source_dir = TempDir::new().unwrap_or_else(|err| panic!("tempdir: {err}"));
+    let source = Utf8PathBuf::from_path_buf(source_dir.path().to_path_buf())
+        .unwrap_or_else(|path| panic!("non-utf8 tempdir path: {}", path.display()));
+    let request = InstanceRequestBuilder::new()
+        .image_label("ubuntu")
+        .instance_type(DEFAULT_INSTANCE_TYPE)
+        .zone("fr-par-1")
+        .project_id("project")
+        .architecture("x86_64")
+        .build()
+        .unwrap_or_else(|err| panic!("builder fixture should be valid: {err}"));
+
+    WatchContext {
+        backend: WatchBackend::default(),
+        runner: WatchRunner::default(),
+        request,
+        command: String::new(),
+        multiplex: false,
+        source,
+        reports: Arc::new(Mutex::new(Vec::new())),
+        _source_dir: Arc::new(source_dir),
+    }
+}
@@ -0,0 +1,8 @@
+//! Behavioural scenarios for `mriya watch`.
+
+#[path = "common/sync_config.rs"]
+mod sync_config;
+#[path = "common/test_constants.rs"]
+mod test_constants;
+
+mod watch;